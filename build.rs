@@ -0,0 +1,5 @@
+fn main() {
+    // Enables the Web Serial backend on `wasm32`, see the `web-sys` dependency. Declared here
+    // instead of with `[lints.rust]`, which requires a newer Cargo than our MSRV ships with.
+    println!("cargo:rustc-check-cfg=cfg(web_sys_unstable_apis)");
+}
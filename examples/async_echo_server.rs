@@ -0,0 +1,96 @@
+//! Asynchronous echo server example
+//!
+//! This example emulates several devices echoing back everything they receive, served from a
+//! single thread. The device ends of pseudo terminals are switched to non-blocking mode with a
+//! [`Registration`] each and a [`Selector`] waits until any of them received data, which then
+//! gets echoed back without blocking. Clients talk to the other ends from their own threads, just
+//! like they would talk to real serial ports. No hardware is required.
+
+#[cfg(unix)]
+fn main() {
+    use std::io::{self, Read, Write};
+    use std::thread;
+    use std::time::Duration;
+
+    use serialport::{Registration, Selector, SerialPortExt, TTYPort};
+
+    const DEVICES: usize = 3;
+    const MESSAGES: usize = 5;
+
+    let message = |device: usize, x: usize| format!("Device #{} message #{}", device, x);
+
+    let mut devices = Vec::new();
+    let mut clients = Vec::new();
+    for device in 0..DEVICES {
+        let (server_end, mut port) = TTYPort::pair().expect("Failed to create pseudo terminal");
+        devices.push(server_end);
+        clients.push(thread::spawn(move || {
+            for x in 1..=MESSAGES {
+                let msg = message(device, x);
+                port.write_all(msg.as_bytes()).unwrap();
+
+                let mut reply = vec![0u8; msg.len()];
+                port.read_exact_timeout(&mut reply, Duration::from_secs(1))
+                    .expect("No reply from emulated device");
+                println!("Echo: {}", String::from_utf8_lossy(&reply));
+            }
+        }));
+    }
+
+    let registrations = devices
+        .iter()
+        .map(Registration::new)
+        .collect::<serialport::Result<Vec<_>>>()
+        .expect("Failed to register device");
+    let mut selector = Selector::new();
+    let keys = registrations
+        .iter()
+        .map(|registration| selector.add(registration.port()))
+        .collect::<serialport::Result<Vec<_>>>()
+        .expect("Failed to add device");
+
+    // Echo until all messages have been sent back.
+    let total: usize = (0..DEVICES)
+        .flat_map(|device| (1..=MESSAGES).map(move |x| message(device, x).len()))
+        .sum();
+    let mut echoed = 0;
+    while echoed < total {
+        for key in selector
+            .select(Duration::from_secs(1))
+            .expect("No data received")
+        {
+            let index = keys.iter().position(|&k| k == key).unwrap();
+            let mut port = registrations[index].port();
+            let mut buf = [0u8; 256];
+            loop {
+                match port.read(&mut buf) {
+                    Ok(n) => {
+                        port.write_all(&buf[..n]).expect("Failed to echo data");
+                        echoed += n;
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    // The client has closed its end after receiving all replies.
+                    Err(_) => {
+                        selector.remove(key);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    for client in clients {
+        client.join().unwrap();
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn runs_without_hardware() {
+        super::main();
+    }
+}
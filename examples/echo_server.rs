@@ -0,0 +1,55 @@
+//! Echo server example
+//!
+//! This example emulates a device echoing back everything it receives. The device side runs in a
+//! background thread using a [`PipeSerialServer`] while the main thread talks to it through a
+//! [`PipeSerialPort`], just like it would talk to a real serial port. No hardware is required.
+
+#[cfg(unix)]
+fn main() {
+    use std::io::{Read, Write};
+    use std::thread;
+    use std::time::Duration;
+
+    use serialport::{PipeSerialPort, PipeSerialServer, SerialPortExt};
+
+    let path = std::env::temp_dir().join(format!("serialport-echo-{}", std::process::id()));
+    let server = PipeSerialServer::bind(&path).expect("Failed to create emulated device");
+
+    let device = thread::spawn(move || {
+        let mut stream = server.accept().expect("Failed to accept connection");
+        let mut buf = [0u8; 256];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => stream.write_all(&buf[..n]).expect("Failed to echo data"),
+            }
+        }
+    });
+
+    let builder = serialport::new(path.to_string_lossy(), 115_200);
+    let mut port = PipeSerialPort::open(&builder).expect("Failed to connect to emulated device");
+
+    for x in 1..6 {
+        let msg = format!("Message #{}", x);
+        port.write_all(msg.as_bytes()).unwrap();
+
+        let mut reply = vec![0u8; msg.len()];
+        port.read_exact_timeout(&mut reply, Duration::from_secs(1))
+            .expect("No reply from emulated device");
+        println!("Echo: {}", String::from_utf8_lossy(&reply));
+    }
+
+    drop(port);
+    device.join().unwrap();
+}
+
+#[cfg(not(unix))]
+fn main() {}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn runs_without_hardware() {
+        super::main();
+    }
+}
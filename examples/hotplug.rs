@@ -0,0 +1,28 @@
+//! Hotplug example
+//!
+//! This example prints a line whenever a serial port appears or disappears, e.g. when plugging
+//! in or removing a USB serial adapter. It runs until interrupted with Ctrl+C.
+
+use serialport::watcher::{PortEvent, PortWatcher};
+use serialport::SerialPortType;
+
+fn main() {
+    let watcher = PortWatcher::new().expect("Failed to watch for serial ports");
+
+    println!("Waiting for serial ports to be added or removed. Press Ctrl+C to exit.");
+    while let Some(event) = watcher.recv() {
+        match event {
+            PortEvent::Added(port) => match port.port_type {
+                SerialPortType::UsbPort(info) => println!(
+                    "Added:   {} (USB {:04x}:{:04x} {})",
+                    port.port_name,
+                    info.vid,
+                    info.pid,
+                    info.product.as_ref().map_or("", String::as_str)
+                ),
+                _ => println!("Added:   {}", port.port_name),
+            },
+            PortEvent::Removed(port) => println!("Removed: {}", port.port_name),
+        }
+    }
+}
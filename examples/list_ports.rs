@@ -15,12 +15,35 @@ fn main() {
 
             for p in ports {
                 println!("    {}", p.port_name);
+                println!(
+                    "        Friendly Name: {}",
+                    p.friendly_name.as_ref().map_or("", String::as_str)
+                );
+                println!(
+                    "        Description: {}",
+                    p.description.as_ref().map_or("", String::as_str)
+                );
+                println!(
+                    "        Instance ID: {}",
+                    p.instance_id.as_ref().map_or("", String::as_str)
+                );
+                println!(
+                    "        Counterpart: {}",
+                    p.counterpart.as_ref().map_or("", String::as_str)
+                );
+                println!(
+                    "        By ID: {}",
+                    p.by_id.as_ref().map_or("", String::as_str)
+                );
+                println!(
+                    "        By Path: {}",
+                    p.by_path.as_ref().map_or("", String::as_str)
+                );
                 match p.port_type {
                     SerialPortType::UsbPort(info) => {
                         println!("        Type: USB");
                         println!("        VID: {:04x}", info.vid);
                         println!("        PID: {:04x}", info.pid);
-                        #[cfg(feature = "usbportinfo-interface")]
                         println!(
                             "        Interface: {}",
                             info.interface
@@ -39,9 +62,47 @@ fn main() {
                             "        Product: {}",
                             info.product.as_ref().map_or("", String::as_str)
                         );
+                        println!(
+                            "        Location: {}",
+                            info.location.as_ref().map_or("", String::as_str)
+                        );
+                        println!(
+                            "        Device Release: {}",
+                            info.bcd_device
+                                .as_ref()
+                                .map_or("".to_string(), |x| format!("{:04x}", *x))
+                        );
+                        println!(
+                            "        Device Class: {}",
+                            info.device_class
+                                .as_ref()
+                                .map_or("".to_string(), |x| format!(
+                                    "{:02x}/{:02x}/{:02x}",
+                                    x.class, x.subclass, x.protocol
+                                ))
+                        );
+                        println!(
+                            "        Interface Class: {}",
+                            info.interface_class
+                                .as_ref()
+                                .map_or("".to_string(), |x| format!(
+                                    "{:02x}/{:02x}/{:02x}",
+                                    x.class, x.subclass, x.protocol
+                                ))
+                        );
                     }
                     SerialPortType::BluetoothPort => {
                         println!("        Type: Bluetooth");
+                        if let Some(info) = p.bluetooth.as_ref() {
+                            println!(
+                                "        Address: {}",
+                                info.address.as_ref().map_or("", String::as_str)
+                            );
+                            println!(
+                                "           Name: {}",
+                                info.name.as_ref().map_or("", String::as_str)
+                            );
+                        }
                     }
                     SerialPortType::PciPort => {
                         println!("        Type: PCI");
@@ -0,0 +1,59 @@
+//! Record and replay example
+//!
+//! This example records a session with an emulated modem to a file and replays it afterwards
+//! without the modem, like a test would use a recording attached to a bug report. No hardware is
+//! required.
+
+use std::fs;
+use std::io::Write;
+use std::time::Duration;
+
+use serialport::mock::{MockPort, Script};
+use serialport::record::{RecordingPort, ReplayPort};
+use serialport::SerialPortExt;
+
+fn main() {
+    let path = std::env::temp_dir().join(format!("serialport-session-{}.txt", std::process::id()));
+
+    // Record a session with an emulated modem answering a command.
+    let (port, modem) = MockPort::pair();
+    let modem = Script::new()
+        .expect(b"AT\r")
+        .delay(Duration::from_millis(20))
+        .send(b"OK\r\n")
+        .spawn(modem);
+
+    let mut port = RecordingPort::create(port, &path).expect("Failed to create recording");
+    port.write_all(b"AT\r").unwrap();
+    let mut response = [0u8; 4];
+    port.read_exact_timeout(&mut response, Duration::from_secs(1))
+        .expect("No response from emulated modem");
+    println!("Recorded: {:?}", String::from_utf8_lossy(&response));
+    port.finish().expect("Failed to write recording");
+    modem.join().unwrap().expect("Emulated modem failed");
+
+    println!("Recording:");
+    print!(
+        "{}",
+        fs::read_to_string(&path).expect("Failed to read recording")
+    );
+
+    // Replay the session. The response arrives with the same timing as when recording it.
+    let mut port = ReplayPort::open(&path).expect("Failed to open recording");
+    port.write_all(b"AT\r").unwrap();
+    let mut response = [0u8; 4];
+    port.read_exact_timeout(&mut response, Duration::from_secs(1))
+        .expect("No response in recording");
+    println!("Replayed: {:?}", String::from_utf8_lossy(&response));
+    assert!(port.is_finished());
+
+    fs::remove_file(&path).expect("Failed to remove recording");
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn runs_without_hardware() {
+        super::main();
+    }
+}
@@ -0,0 +1,95 @@
+//! RFC 2217 client example
+//!
+//! This example talks to a serial port shared over the network by a terminal server or by
+//! ser2net with the Telnet COM port control option. It sends a line, prints whatever the device
+//! answers and reports the modem control inputs notified by the server.
+//!
+//! With ser2net, a port can be shared for this example with a configuration like
+//!
+//! ```text
+//! connection: &con0
+//!     accepter: telnet(rfc2217),tcp,2217
+//!     connector: serialdev,/dev/ttyUSB0,115200n81,local
+//! ```
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use clap::{Arg, Command};
+use serialport::{Rfc2217Port, SerialPort};
+
+fn main() {
+    let matches = Command::new("Serialport Example - RFC 2217 Client")
+        .about("Sends a line to a serial port shared over the network and prints the response")
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("address")
+                .help("The address of the server, e.g. localhost:2217")
+                .use_value_delimiter(false)
+                .required(true),
+        )
+        .arg(
+            Arg::new("baud")
+                .help("The baud rate to connect at")
+                .use_value_delimiter(false)
+                .required(true)
+                .validator(valid_baud),
+        )
+        .arg(
+            Arg::new("line")
+                .help("The line to send")
+                .use_value_delimiter(false)
+                .default_value("AT"),
+        )
+        .get_matches();
+
+    let address = matches.value_of("address").unwrap();
+    let baud_rate = matches.value_of("baud").unwrap().parse::<u32>().unwrap();
+    let line = matches.value_of("line").unwrap();
+
+    let builder = serialport::new(address, baud_rate).timeout(Duration::from_millis(500));
+    let mut port = match Rfc2217Port::open(&builder) {
+        Ok(port) => port,
+        Err(e) => {
+            eprintln!("Failed to connect to \"{}\". Error: {}", address, e);
+            ::std::process::exit(1);
+        }
+    };
+
+    // The server applies the settings to the actual port and reports the modem control inputs.
+    println!(
+        "Connected to {} at {} baud",
+        address,
+        port.baud_rate().unwrap()
+    );
+    println!("CTS: {}", port.read_clear_to_send().unwrap());
+    println!("DSR: {}", port.read_data_set_ready().unwrap());
+    println!("CD: {}", port.read_carrier_detect().unwrap());
+
+    port.write_data_terminal_ready(true).unwrap();
+    port.write_all(format!("{}\r", line).as_bytes())
+        .expect("Failed to send line");
+
+    // Print the response until the device stays silent for the timeout.
+    let mut buf = [0u8; 256];
+    loop {
+        match io::Read::read(&mut port, &mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                io::stdout().write_all(&buf[..n]).unwrap();
+                io::stdout().flush().unwrap();
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => break,
+            Err(e) => {
+                eprintln!("{:?}", e);
+                break;
+            }
+        }
+    }
+}
+
+fn valid_baud(val: &str) -> Result<(), String> {
+    val.parse::<u32>()
+        .map(|_| ())
+        .map_err(|_| format!("Invalid baud rate '{}' specified", val))
+}
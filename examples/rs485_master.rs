@@ -0,0 +1,130 @@
+//! RS-485 polling master example
+//!
+//! This example polls a number of slave devices on a half-duplex RS-485 bus in turn. The
+//! transceiver's driver is enabled through RTS while sending a request and disabled again before
+//! waiting for the reply. Each request consists of the slave address followed by a status query
+//! command. A slave replies with its address, the length of its payload, the payload itself and
+//! the XOR of all preceding bytes.
+//!
+//! Slaves which don't reply in time are reported and polled again in the next round. If the
+//! adapter gets unplugged, the master waits for it to come back.
+
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+use clap::{Arg, Command};
+use serialport::{ManagedPort, PortState, SerialPort, SerialPortExt};
+
+const STATUS_QUERY: u8 = 0x01;
+const REPLY_TIMEOUT: Duration = Duration::from_millis(100);
+
+fn main() {
+    let matches = Command::new("Serialport Example - RS-485 Polling Master")
+        .about("Polls slave devices on an RS-485 bus")
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("port")
+                .help("The device path to a serial port")
+                .use_value_delimiter(false)
+                .required(true),
+        )
+        .arg(
+            Arg::new("baud")
+                .help("The baud rate to connect at")
+                .use_value_delimiter(false)
+                .required(true)
+                .validator(valid_baud),
+        )
+        .arg(
+            Arg::new("slaves")
+                .help("The addresses of the slaves to poll")
+                .use_value_delimiter(false)
+                .multiple_values(true)
+                .required(true)
+                .validator(valid_address),
+        )
+        .get_matches();
+
+    let port_name = matches.value_of("port").unwrap();
+    let baud_rate = matches.value_of("baud").unwrap().parse::<u32>().unwrap();
+    let slaves: Vec<u8> = matches
+        .values_of("slaves")
+        .unwrap()
+        .map(|address| address.parse().unwrap())
+        .collect();
+
+    let mut port = match ManagedPort::open(serialport::new(port_name, baud_rate)) {
+        Ok(port) => port,
+        Err(e) => {
+            eprintln!("Failed to open \"{}\". Error: {}", port_name, e);
+            ::std::process::exit(1);
+        }
+    };
+
+    loop {
+        for &address in &slaves {
+            match poll(&mut port, address) {
+                Ok(payload) => println!("Slave {}: {:02x?}", address, payload),
+                Err(e) if port.state() == PortState::Disconnected => {
+                    eprintln!("Lost connection to \"{}\": {}", port_name, e);
+                    while port.recover().is_err() {
+                        thread::sleep(Duration::from_secs(1));
+                    }
+                    eprintln!("Reconnected to \"{}\"", port_name);
+                }
+                Err(e) => eprintln!("Slave {}: {}", address, e),
+            }
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Sends a status query to the slave at `address` and returns the payload of its reply
+fn poll(port: &mut ManagedPort, address: u8) -> serialport::Result<Vec<u8>> {
+    // Discard any leftovers from a previous reply which arrived too late.
+    port.clear(serialport::ClearBuffer::Input)?;
+
+    port.write_request_to_send(true)?;
+    port.write_all(&[address, STATUS_QUERY])?;
+    port.flush()?;
+    // Keep the driver enabled until the request has left the transmit buffer.
+    while port.bytes_to_write()? > 0 {
+        thread::sleep(Duration::from_millis(1));
+    }
+    port.write_request_to_send(false)?;
+
+    let mut header = [0u8; 2];
+    port.read_exact_timeout(&mut header, REPLY_TIMEOUT)?;
+    if header[0] != address {
+        return Err(invalid_reply("reply from wrong slave"));
+    }
+
+    let mut payload = vec![0u8; usize::from(header[1])];
+    port.read_exact_timeout(&mut payload, REPLY_TIMEOUT)?;
+    let checksum = port.read_u8_timeout(REPLY_TIMEOUT)?;
+    if header.iter().chain(&payload).fold(0, |acc, b| acc ^ b) != checksum {
+        return Err(invalid_reply("checksum mismatch"));
+    }
+
+    Ok(payload)
+}
+
+fn invalid_reply(description: &str) -> serialport::Error {
+    serialport::Error::new(
+        serialport::ErrorKind::Io(io::ErrorKind::InvalidData),
+        description,
+    )
+}
+
+fn valid_baud(val: &str) -> Result<(), String> {
+    val.parse::<u32>()
+        .map(|_| ())
+        .map_err(|_| format!("Invalid baud rate '{}' specified", val))
+}
+
+fn valid_address(val: &str) -> Result<(), String> {
+    val.parse::<u8>()
+        .map(|_| ())
+        .map_err(|_| format!("Invalid slave address '{}' specified", val))
+}
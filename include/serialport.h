@@ -0,0 +1,105 @@
+/*
+ * C API of the serialport crate
+ *
+ * Build the library with the `ffi` feature, e.g.
+ *
+ *     cargo rustc --release --features ffi --crate-type cdylib
+ *
+ * Functions returning an int return SERIALPORT_OK on success and one of the negative
+ * SERIALPORT_ERROR_* codes on failure. serialport_last_error() describes the last error which
+ * occurred on the calling thread.
+ */
+
+#ifndef SERIALPORT_H
+#define SERIALPORT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SERIALPORT_OK 0
+#define SERIALPORT_ERROR_NO_DEVICE -1
+#define SERIALPORT_ERROR_INVALID_INPUT -2
+#define SERIALPORT_ERROR_UNKNOWN -3
+#define SERIALPORT_ERROR_IO -4
+#define SERIALPORT_ERROR_TIMED_OUT -5
+#define SERIALPORT_ERROR_PERMISSION_DENIED -6
+#define SERIALPORT_ERROR_BUSY -7
+#define SERIALPORT_ERROR_UNSUPPORTED -8
+#define SERIALPORT_ERROR_BROKEN_PIPE -9
+#define SERIALPORT_ERROR_CANCELLED -10
+
+#define SERIALPORT_PARITY_NONE 0
+#define SERIALPORT_PARITY_ODD 1
+#define SERIALPORT_PARITY_EVEN 2
+
+#define SERIALPORT_FLOW_CONTROL_NONE 0
+#define SERIALPORT_FLOW_CONTROL_SOFTWARE 1
+#define SERIALPORT_FLOW_CONTROL_HARDWARE 2
+
+#define SERIALPORT_CLEAR_INPUT 0
+#define SERIALPORT_CLEAR_OUTPUT 1
+#define SERIALPORT_CLEAR_ALL 2
+
+#define SERIALPORT_TYPE_USB 0
+#define SERIALPORT_TYPE_PCI 1
+#define SERIALPORT_TYPE_BLUETOOTH 2
+#define SERIALPORT_TYPE_UNKNOWN 3
+
+/* An open serial port */
+typedef struct serialport serialport_t;
+
+/* A list of the serial ports on the system */
+typedef struct serialport_list serialport_list_t;
+
+/* The settings of a serial port */
+typedef struct serialport_config {
+    uint32_t baud_rate;
+    /* 5 to 8 */
+    uint8_t data_bits;
+    /* SERIALPORT_PARITY_* */
+    int parity;
+    /* 1 or 2 */
+    uint8_t stop_bits;
+    /* SERIALPORT_FLOW_CONTROL_* */
+    int flow_control;
+    uint64_t timeout_ms;
+} serialport_config_t;
+
+/* Opening, configuring and closing ports */
+int serialport_open(const char *path, uint32_t baud_rate, serialport_t **port);
+void serialport_close(serialport_t *port);
+int serialport_get_config(const serialport_t *port, serialport_config_t *config);
+int serialport_set_config(serialport_t *port, const serialport_config_t *config);
+
+/* Transferring data */
+int serialport_read(serialport_t *port, uint8_t *buf, size_t len, size_t *read);
+int serialport_write(serialport_t *port, const uint8_t *buf, size_t len, size_t *written);
+int serialport_flush(serialport_t *port);
+int serialport_bytes_to_read(const serialport_t *port, uint32_t *count);
+/* buffer is one of SERIALPORT_CLEAR_* */
+int serialport_clear(const serialport_t *port, int buffer);
+
+/* Enumerating ports */
+int serialport_list_ports(serialport_list_t **list);
+size_t serialport_list_len(const serialport_list_t *list);
+/* Returns NULL if index is out of range. The name is valid until the list gets freed. */
+const char *serialport_list_name(const serialport_list_t *list, size_t index);
+/* Returns one of SERIALPORT_TYPE_* */
+int serialport_list_type(const serialport_list_t *list, size_t index);
+int serialport_list_usb_ids(const serialport_list_t *list, size_t index, uint16_t *vid,
+                            uint16_t *pid);
+void serialport_list_free(serialport_list_t *list);
+
+/* Errors */
+const char *serialport_last_error(void);
+const char *serialport_error_name(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SERIALPORT_H */
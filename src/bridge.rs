@@ -0,0 +1,232 @@
+//! Bridging serial ports to TCP connections
+//!
+//! A [`Bridge`] pumps data between a serial port and TCP connections in both directions. It can
+//! serve clients connecting to a listener one after another, or keep a connection to a remote
+//! server and reconnect whenever it gets lost.
+//!
+//! ```no_run
+//! use std::net::TcpListener;
+//!
+//! use serialport::bridge::Bridge;
+//!
+//! let port = serialport::new("/dev/ttyUSB0", 115_200).open().expect("Failed to open port");
+//! let listener = TcpListener::bind("0.0.0.0:2000").expect("Failed to listen");
+//! Bridge::new(port)
+//!     .expect("Failed to set up bridge")
+//!     .listen(&listener)
+//!     .expect("Failed to access port");
+//! ```
+//!
+//! Writes block until the other side accepts the data, so a slow receiver throttles reading
+//! from the sender instead of data piling up in memory.
+
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crate::{Error, Result, SerialPort};
+
+/// How often blocked operations check whether the bridge has been stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The default delay between attempts to reconnect to a server
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Pumps data between a serial port and TCP connections
+///
+/// The bridge takes over the port and sets its timeout for regularly checking whether it has
+/// been stopped. Errors of the port end the bridge, while errors of a TCP connection only end
+/// that connection.
+#[derive(Debug)]
+pub struct Bridge {
+    port: Box<dyn SerialPort>,
+    reconnect_delay: Duration,
+    stop: Arc<AtomicBool>,
+}
+
+/// Stops a running [`Bridge`] from another thread
+#[derive(Debug, Clone)]
+pub struct StopHandle(Arc<AtomicBool>);
+
+impl StopHandle {
+    /// Stops the bridge
+    ///
+    /// The bridge closes its current connection and returns shortly after.
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Bridge {
+    /// Creates a bridge for `port`
+    pub fn new(mut port: Box<dyn SerialPort>) -> Result<Self> {
+        port.set_timeout(POLL_INTERVAL)?;
+        Ok(Bridge {
+            port,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            stop: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Sets the delay between attempts to reconnect in [`connect`](Bridge::connect)
+    ///
+    /// The default is one second.
+    #[must_use]
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// Returns a handle for stopping the bridge from another thread
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle(self.stop.clone())
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Pumps data between the port and `stream` until either the connection gets closed or the
+    /// bridge gets stopped
+    ///
+    /// ## Errors
+    ///
+    /// Returns errors from reading from or writing to the port.
+    pub fn pump(&mut self, stream: TcpStream) -> Result<()> {
+        let mut port_writer = self.port.try_clone()?;
+        let mut stream_reader = stream.try_clone()?;
+        stream_reader.set_read_timeout(Some(POLL_INTERVAL))?;
+        stream.set_write_timeout(Some(POLL_INTERVAL))?;
+        let stop = self.stop.clone();
+        let closed = Arc::new(AtomicBool::new(false));
+
+        // Pump data from the connection to the port in the background.
+        let thread_closed = closed.clone();
+        let to_port = thread::spawn(move || -> Result<()> {
+            let is_done = || stop.load(Ordering::Relaxed) || thread_closed.load(Ordering::Relaxed);
+            let mut buf = [0u8; 1024];
+            let result = loop {
+                if is_done() {
+                    break Ok(());
+                }
+                let n = match stream_reader.read(&mut buf) {
+                    Ok(0) => break Ok(()),
+                    Ok(n) => n,
+                    Err(e) if is_retryable(&e) => continue,
+                    Err(_) => break Ok(()),
+                };
+                if let Err(e) = write_all(&mut *port_writer, &buf[..n], is_done) {
+                    break Err(e.into());
+                }
+            };
+            thread_closed.store(true, Ordering::Relaxed);
+            result
+        });
+
+        // Pump data from the port to the connection.
+        let mut buf = [0u8; 1024];
+        let result = loop {
+            if self.is_stopped() || closed.load(Ordering::Relaxed) {
+                break Ok(());
+            }
+            let n = match self.port.read(&mut buf) {
+                Ok(n) => n,
+                Err(e) if is_retryable(&e) => continue,
+                Err(e) => break Err(Error::from(e)),
+            };
+            let is_done = || self.is_stopped() || closed.load(Ordering::Relaxed);
+            if write_all(&mut &stream, &buf[..n], is_done).is_err() {
+                break Ok(());
+            }
+        };
+
+        closed.store(true, Ordering::Relaxed);
+        let _ = stream.shutdown(Shutdown::Both);
+        let to_port_result = to_port.join().unwrap_or(Ok(()));
+        result.and(to_port_result)
+    }
+
+    /// Serves the clients connecting to `listener` one after another until the bridge gets
+    /// stopped
+    ///
+    /// Clients connecting while another one is being served wait until it disconnects.
+    ///
+    /// ## Errors
+    ///
+    /// Returns errors from accepting connections and from reading from or writing to the port.
+    pub fn listen(&mut self, listener: &TcpListener) -> Result<()> {
+        listener.set_nonblocking(true)?;
+        while !self.is_stopped() {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    self.pump(stream)?;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(e) if is_retryable(&e) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Connects to the server at `address` and reconnects whenever the connection gets lost or
+    /// can't be established, until the bridge gets stopped
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` if `address` can't be resolved.
+    /// * Errors from reading from or writing to the port.
+    pub fn connect(&mut self, address: impl ToSocketAddrs) -> Result<()> {
+        let addresses: Vec<_> = address.to_socket_addrs()?.collect();
+        let timeout = self.reconnect_delay.max(POLL_INTERVAL);
+        while !self.is_stopped() {
+            let stream = addresses
+                .iter()
+                .find_map(|address| TcpStream::connect_timeout(address, timeout).ok());
+            match stream {
+                Some(stream) => self.pump(stream)?,
+                None => self.sleep(self.reconnect_delay),
+            }
+        }
+        Ok(())
+    }
+
+    /// Sleeps for `duration` unless the bridge gets stopped in the meantime
+    fn sleep(&self, duration: Duration) {
+        let mut remaining = duration;
+        while remaining > Duration::from_secs(0) && !self.is_stopped() {
+            let step = remaining.min(POLL_INTERVAL);
+            thread::sleep(step);
+            remaining -= step;
+        }
+    }
+}
+
+/// Returns whether an operation failing with `e` should just be retried
+fn is_retryable(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+    )
+}
+
+/// Writes all of `data` to `writer`, retrying after timeouts until `is_done` returns `true`
+fn write_all<W: Write + ?Sized>(
+    writer: &mut W,
+    mut data: &[u8],
+    is_done: impl Fn() -> bool,
+) -> io::Result<()> {
+    while !data.is_empty() && !is_done() {
+        match writer.write(data) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => data = &data[n..],
+            Err(e) if is_retryable(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,221 @@
+//! Cancelling blocking operations from another thread
+//!
+//! Ports wait for their device together with the waitable object of the token: the read end of
+//! a self-pipe on POSIX and an event on Windows. Cancelling makes the object signaled, which
+//! wakes up the waiting thread right away instead of after the timeout.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+
+#[cfg(unix)]
+use nix::fcntl::{fcntl, FcntlArg, FdFlag, OFlag};
+#[cfg(unix)]
+use nix::unistd;
+
+#[cfg(windows)]
+use winapi::shared::minwindef::{FALSE, TRUE};
+#[cfg(windows)]
+use winapi::um::handleapi::CloseHandle;
+#[cfg(windows)]
+use winapi::um::synchapi::{CreateEventW, ResetEvent, SetEvent};
+#[cfg(windows)]
+use winapi::um::winnt::HANDLE;
+
+use crate::{Error, ErrorKind, Result};
+
+/// A handle for aborting blocking reads and writes of a port from another thread
+///
+/// Get the token of a port with [`SerialPort::cancel_token`](crate::SerialPort::cancel_token)
+/// before moving the port to the thread using it. Calling [`cancel`](CancelToken::cancel) makes
+/// reads and writes which are waiting for the device fail with `ErrorKind::Cancelled` right
+/// away, so applications can shut down without waiting for the timeout of the port.
+///
+/// Cancelling is sticky: all following blocking operations fail the same way until the token
+/// gets [`reset`](CancelToken::reset). Tokens are cheap to clone and all clones refer to the
+/// same state.
+///
+/// ```no_run
+/// use std::io::Read;
+/// use std::thread;
+///
+/// let mut port = serialport::new("/dev/ttyUSB0", 115200).open().expect("Failed to open");
+/// let token = port.cancel_token().expect("Failed to get cancel token");
+///
+/// let reader = thread::spawn(move || {
+///     let mut buf = [0u8; 64];
+///     // Returns with `ErrorKind::Cancelled` once cancelled.
+///     port.read(&mut buf)
+/// });
+///
+/// token.cancel();
+/// let _ = reader.join().unwrap();
+/// ```
+#[derive(Clone)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    cancelled: AtomicBool,
+    /// Serializes cancelling and resetting, so the flag and the waitable object agree
+    guard: Mutex<()>,
+    /// The self-pipe, which has data to read while cancelled
+    #[cfg(unix)]
+    pipe: (RawFd, RawFd),
+    /// The manual-reset event, which is signaled while cancelled
+    #[cfg(windows)]
+    event: HANDLE,
+}
+
+// The event handle is only used with thread-safe system calls.
+#[cfg(windows)]
+unsafe impl Send for Inner {}
+#[cfg(windows)]
+unsafe impl Sync for Inner {}
+
+impl CancelToken {
+    /// Creates a new token, which is not cancelled
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the system resources for waking up waiting threads
+    /// could not be created.
+    pub fn new() -> Result<CancelToken> {
+        Ok(CancelToken {
+            inner: Arc::new(Inner {
+                cancelled: AtomicBool::new(false),
+                guard: Mutex::new(()),
+                #[cfg(unix)]
+                pipe: new_pipe()?,
+                #[cfg(windows)]
+                event: new_event()?,
+            }),
+        })
+    }
+
+    /// Cancels the blocking operations of all ports using this token
+    pub fn cancel(&self) {
+        let _guard = self.inner.guard.lock().unwrap_or_else(|e| e.into_inner());
+        if self.inner.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        #[cfg(unix)]
+        let _ = unistd::write(self.inner.pipe.1, &[0]);
+        #[cfg(windows)]
+        unsafe {
+            SetEvent(self.inner.event);
+        }
+    }
+
+    /// Returns whether the token has been cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Makes the token usable again after cancelling it
+    ///
+    /// Operations started after resetting block as usual.
+    pub fn reset(&self) {
+        let _guard = self.inner.guard.lock().unwrap_or_else(|e| e.into_inner());
+        if !self.inner.cancelled.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        #[cfg(unix)]
+        {
+            let mut buf = [0u8; 16];
+            while let Ok(len) = unistd::read(self.inner.pipe.0, &mut buf) {
+                if len == 0 {
+                    break;
+                }
+            }
+        }
+        #[cfg(windows)]
+        unsafe {
+            ResetEvent(self.inner.event);
+        }
+    }
+
+    /// Returns a `Cancelled` error if the token has been cancelled
+    pub(crate) fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(cancelled())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the descriptor which becomes readable when cancelling
+    #[cfg(unix)]
+    pub(crate) fn fd(&self) -> RawFd {
+        self.inner.pipe.0
+    }
+
+    /// Returns the event which becomes signaled when cancelling
+    #[cfg(windows)]
+    pub(crate) fn event(&self) -> HANDLE {
+        self.inner.event
+    }
+}
+
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancelToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        {
+            let _ = unistd::close(self.pipe.0);
+            let _ = unistd::close(self.pipe.1);
+        }
+        #[cfg(windows)]
+        unsafe {
+            CloseHandle(self.event);
+        }
+    }
+}
+
+/// Returns a `Cancelled` error if any of `tokens` has been cancelled
+pub(crate) fn check_all(tokens: &[Option<&CancelToken>]) -> Result<()> {
+    tokens.iter().flatten().try_for_each(|token| token.check())
+}
+
+/// The error reported for operations aborted with a token
+pub(crate) fn cancelled() -> Error {
+    Error::new(ErrorKind::Cancelled, "Operation cancelled")
+}
+
+/// Creates a non-blocking self-pipe, which is not inherited by child processes
+#[cfg(unix)]
+fn new_pipe() -> Result<(RawFd, RawFd)> {
+    // `pipe2()` is not available on macOS, so the flags get set separately.
+    let (read, write) = unistd::pipe()?;
+    for fd in [read, write] {
+        let configured = fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))
+            .and_then(|_| fcntl(fd, FcntlArg::F_SETFL(OFlag::O_NONBLOCK)));
+        if let Err(e) = configured {
+            let _ = unistd::close(read);
+            let _ = unistd::close(write);
+            return Err(e.into());
+        }
+    }
+    Ok((read, write))
+}
+
+/// Creates a manual-reset event, which is not signaled
+#[cfg(windows)]
+fn new_event() -> Result<HANDLE> {
+    let event = unsafe { CreateEventW(std::ptr::null_mut(), TRUE, FALSE, std::ptr::null()) };
+    if event.is_null() {
+        return Err(crate::windows::last_os_error());
+    }
+    Ok(event)
+}
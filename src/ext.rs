@@ -0,0 +1,292 @@
+//! Convenience extensions for the `SerialPort` trait.
+
+use std::io;
+use std::mem::MaybeUninit;
+use std::slice;
+use std::time::{Duration, Instant};
+
+use crate::{Error, ErrorKind, PortSettings, Result, SerialPort};
+
+/// How often [`SerialPortExt::wait_for_carrier`] checks the carrier detect line
+const CARRIER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Extension methods for reading and writing with an overall timeout
+///
+/// These methods are available for every [`SerialPort`] and are especially handy for binary
+/// register protocols, where fixed-size words have to be read within a given time.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use serialport::SerialPortExt;
+///
+/// let mut port = serialport::new("/dev/ttyUSB0", 115_200).open().expect("Failed to open port");
+/// port.write_u16_be(0x0102).unwrap();
+/// let register = port.read_u32_le(Duration::from_millis(100)).unwrap();
+/// # let _ = register;
+/// ```
+pub trait SerialPortExt: SerialPort {
+    /// Reads exactly enough bytes to fill `buf` within `timeout`
+    ///
+    /// In contrast to [`io::Read::read_exact`], `timeout` bounds the whole operation instead of
+    /// every single read. The port's timeout is restored afterwards.
+    ///
+    /// ## Errors
+    ///
+    /// * `Timeout` if `buf` could not be filled within `timeout`. The data read so far is
+    ///   lost in this case.
+    /// * `Io(UnexpectedEof)` if the port reported end of file.
+    /// * Any error from configuring or reading from the port.
+    fn read_exact_timeout(&mut self, mut buf: &mut [u8], timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let original_timeout = self.timeout();
+        let mut port = scopeguard::guard(self, |port| {
+            let _ = port.set_timeout(original_timeout);
+        });
+
+        while !buf.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::ZERO {
+                return Err(Error::new(
+                    ErrorKind::Timeout,
+                    "buffer could not be filled before timeout",
+                ));
+            }
+            port.set_timeout(remaining)?;
+
+            match port.read(buf) {
+                Ok(0) => {
+                    return Err(Error::new(
+                        ErrorKind::Io(io::ErrorKind::UnexpectedEof),
+                        "failed to fill whole buffer",
+                    ))
+                }
+                Ok(n) => buf = &mut buf[n..],
+                Err(ref e)
+                    if e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes all of `buf` within `timeout`
+    ///
+    /// In contrast to [`io::Write::write_all`], `timeout` bounds the whole operation instead of
+    /// every single write. The port's timeout is restored afterwards.
+    ///
+    /// ## Errors
+    ///
+    /// * `Timeout` if `buf` could not be written within `timeout`. Part of it may have been
+    ///   written already in this case.
+    /// * `Io(WriteZero)` if the port did not accept any data.
+    /// * Any error from configuring or writing to the port.
+    fn write_all_timeout(&mut self, mut buf: &[u8], timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let original_timeout = self.timeout();
+        let mut port = scopeguard::guard(self, |port| {
+            let _ = port.set_timeout(original_timeout);
+        });
+
+        while !buf.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::ZERO {
+                return Err(Error::new(
+                    ErrorKind::Timeout,
+                    "buffer could not be written before timeout",
+                ));
+            }
+            port.set_timeout(remaining)?;
+
+            match port.write(buf) {
+                Ok(0) => {
+                    return Err(Error::new(
+                        ErrorKind::Io(io::ErrorKind::WriteZero),
+                        "failed to write whole buffer",
+                    ))
+                }
+                Ok(n) => buf = &buf[n..],
+                Err(ref e)
+                    if e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Waits for the carrier detect (DCD) line to become asserted within `timeout`
+    ///
+    /// This is intended for modem-style devices which signal an established connection through
+    /// DCD. The line is polled in short intervals.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(NotConnected)` if no carrier was detected within `timeout`.
+    /// * Any error from reading the carrier detect line.
+    fn wait_for_carrier(&mut self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.read_carrier_detect()? {
+                return Ok(());
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::ZERO {
+                return Err(Error::new(
+                    ErrorKind::Io(io::ErrorKind::NotConnected),
+                    "no carrier detected before timeout",
+                ));
+            }
+            std::thread::sleep(remaining.min(CARRIER_POLL_INTERVAL));
+        }
+    }
+
+    /// Changes multiple settings at once
+    ///
+    /// `f` receives the current settings of the port and stages its changes on them. The
+    /// changed settings are then applied with a single call to
+    /// [`apply_settings`](SerialPort::apply_settings), so the line does not go through
+    /// intermediate configurations. Nothing is applied if `f` leaves the settings unchanged.
+    ///
+    /// ```no_run
+    /// use serialport::{Parity, SerialPortExt};
+    ///
+    /// let mut port = serialport::new("/dev/ttyUSB0", 9600).open().expect("Failed to open port");
+    /// port.modify(|settings| {
+    ///     settings.baud_rate = 115_200;
+    ///     settings.parity = Parity::Even;
+    /// })
+    /// .expect("Failed to change settings");
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Any error from reading the current settings or from applying the changed ones. In the
+    /// latter case, the settings are left unchanged as described for
+    /// [`apply_settings`](SerialPort::apply_settings).
+    fn modify<F: FnOnce(&mut PortSettings)>(&mut self, f: F) -> Result<()> {
+        let original = self.try_settings()?;
+        let mut settings = original;
+        f(&mut settings);
+        if settings == original {
+            return Ok(());
+        }
+        self.apply_settings(&settings.to_builder(String::new()))
+    }
+
+    /// Reads into the spare capacity of `buf` and appends the data read
+    ///
+    /// This uses [`read_uninit`](SerialPort::read_uninit), so native ports read into the
+    /// capacity without zeroing it first. Reserve the capacity for the data to read beforehand,
+    /// as without any, this reads nothing and returns `Ok(0)`.
+    ///
+    /// ```no_run
+    /// use serialport::SerialPortExt;
+    ///
+    /// let mut port = serialport::new("/dev/ttyUSB0", 3_000_000).open().expect("Failed to open port");
+    /// let mut capture = Vec::with_capacity(1 << 20);
+    /// while capture.len() < capture.capacity() {
+    ///     port.read_to_spare(&mut capture).expect("Failed to read");
+    /// }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Any error from reading from the port. `buf` is left unchanged in this case.
+    fn read_to_spare(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        let len = buf.len();
+        let spare_len = buf.capacity() - len;
+        // `Vec::spare_capacity_mut()` requires Rust 1.60.
+        let spare = unsafe {
+            slice::from_raw_parts_mut(buf.as_mut_ptr().add(len) as *mut MaybeUninit<u8>, spare_len)
+        };
+        let spare_ptr = spare.as_ptr() as *const u8;
+        let read = self.read_uninit(spare)?;
+
+        // Only data read into the start of the spare capacity can be appended.
+        assert!(read.is_empty() || (read.as_ptr() == spare_ptr && read.len() <= spare_len));
+        let read_len = read.len();
+        // The bytes have been initialized by reading into them.
+        unsafe { buf.set_len(len + read_len) };
+        Ok(read_len)
+    }
+
+    /// Reads a single byte within `timeout`
+    fn read_u8_timeout(&mut self, timeout: Duration) -> Result<u8> {
+        let mut buf = [0u8; 1];
+        self.read_exact_timeout(&mut buf, timeout)?;
+        Ok(buf[0])
+    }
+
+    /// Reads a little-endian `u16` within `timeout`
+    fn read_u16_le(&mut self, timeout: Duration) -> Result<u16> {
+        let mut buf = [0u8; 2];
+        self.read_exact_timeout(&mut buf, timeout)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    /// Reads a big-endian `u16` within `timeout`
+    fn read_u16_be(&mut self, timeout: Duration) -> Result<u16> {
+        let mut buf = [0u8; 2];
+        self.read_exact_timeout(&mut buf, timeout)?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    /// Reads a little-endian `u32` within `timeout`
+    fn read_u32_le(&mut self, timeout: Duration) -> Result<u32> {
+        let mut buf = [0u8; 4];
+        self.read_exact_timeout(&mut buf, timeout)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    /// Reads a big-endian `u32` within `timeout`
+    fn read_u32_be(&mut self, timeout: Duration) -> Result<u32> {
+        let mut buf = [0u8; 4];
+        self.read_exact_timeout(&mut buf, timeout)?;
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    /// Writes a little-endian `u16`
+    fn write_u16_le(&mut self, value: u16) -> Result<()> {
+        self.write_all(&value.to_le_bytes()).map_err(Error::from)
+    }
+
+    /// Writes a big-endian `u16`
+    fn write_u16_be(&mut self, value: u16) -> Result<()> {
+        self.write_all(&value.to_be_bytes()).map_err(Error::from)
+    }
+
+    /// Writes a little-endian `u32`
+    fn write_u32_le(&mut self, value: u32) -> Result<()> {
+        self.write_all(&value.to_le_bytes()).map_err(Error::from)
+    }
+
+    /// Writes a big-endian `u32`
+    fn write_u32_be(&mut self, value: u32) -> Result<()> {
+        self.write_all(&value.to_be_bytes()).map_err(Error::from)
+    }
+
+    /// Writes a little-endian `u16` within `timeout`
+    fn write_u16_le_timeout(&mut self, value: u16, timeout: Duration) -> Result<()> {
+        self.write_all_timeout(&value.to_le_bytes(), timeout)
+    }
+
+    /// Writes a big-endian `u16` within `timeout`
+    fn write_u16_be_timeout(&mut self, value: u16, timeout: Duration) -> Result<()> {
+        self.write_all_timeout(&value.to_be_bytes(), timeout)
+    }
+
+    /// Writes a little-endian `u32` within `timeout`
+    fn write_u32_le_timeout(&mut self, value: u32, timeout: Duration) -> Result<()> {
+        self.write_all_timeout(&value.to_le_bytes(), timeout)
+    }
+
+    /// Writes a big-endian `u32` within `timeout`
+    fn write_u32_be_timeout(&mut self, value: u32, timeout: Duration) -> Result<()> {
+        self.write_all_timeout(&value.to_be_bytes(), timeout)
+    }
+}
+
+impl<T: SerialPort + ?Sized> SerialPortExt for T {}
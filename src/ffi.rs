@@ -0,0 +1,540 @@
+//! C API for using the crate from other languages
+//!
+//! With the `ffi` feature, this module exports functions with C linkage. Their declarations for C
+//! and C++ are in `include/serialport.h`. A shared or static library can be built with
+//!
+//! ```sh
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! cargo rustc --release --features ffi --crate-type staticlib
+//! ```
+//!
+//! All functions returning an `int` return [`SERIALPORT_OK`] on success and one of the negative
+//! `SERIALPORT_ERROR_*` codes on failure. The description of the last error which occurred on the
+//! calling thread is available from [`serialport_last_error`]. Panics are caught at the boundary
+//! and reported as [`SERIALPORT_ERROR_UNKNOWN`].
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::io::{Read, Write};
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::time::Duration;
+
+use crate::{
+    ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity, Result, SerialPort,
+    SerialPortType, StopBits,
+};
+
+/// The operation succeeded
+pub const SERIALPORT_OK: c_int = 0;
+/// The device is not available
+pub const SERIALPORT_ERROR_NO_DEVICE: c_int = -1;
+/// A parameter was incorrect
+pub const SERIALPORT_ERROR_INVALID_INPUT: c_int = -2;
+/// An unknown error occurred
+pub const SERIALPORT_ERROR_UNKNOWN: c_int = -3;
+/// An I/O error occurred
+pub const SERIALPORT_ERROR_IO: c_int = -4;
+/// The operation timed out
+pub const SERIALPORT_ERROR_TIMED_OUT: c_int = -5;
+/// The device could not be accessed because of missing permissions
+pub const SERIALPORT_ERROR_PERMISSION_DENIED: c_int = -6;
+/// The device is in use by another process
+pub const SERIALPORT_ERROR_BUSY: c_int = -7;
+/// The operation is not supported
+pub const SERIALPORT_ERROR_UNSUPPORTED: c_int = -8;
+/// The other end of the connection has been closed
+pub const SERIALPORT_ERROR_BROKEN_PIPE: c_int = -9;
+/// The operation was aborted with a cancel token
+pub const SERIALPORT_ERROR_CANCELLED: c_int = -10;
+
+/// No parity bit
+pub const SERIALPORT_PARITY_NONE: c_int = 0;
+/// Odd parity
+pub const SERIALPORT_PARITY_ODD: c_int = 1;
+/// Even parity
+pub const SERIALPORT_PARITY_EVEN: c_int = 2;
+
+/// No flow control
+pub const SERIALPORT_FLOW_CONTROL_NONE: c_int = 0;
+/// Flow control using XON/XOFF bytes
+pub const SERIALPORT_FLOW_CONTROL_SOFTWARE: c_int = 1;
+/// Flow control using RTS/CTS signals
+pub const SERIALPORT_FLOW_CONTROL_HARDWARE: c_int = 2;
+
+/// Data received but not read
+pub const SERIALPORT_CLEAR_INPUT: c_int = 0;
+/// Data written but not yet transmitted
+pub const SERIALPORT_CLEAR_OUTPUT: c_int = 1;
+/// Both of the above
+pub const SERIALPORT_CLEAR_ALL: c_int = 2;
+
+/// The port is connected via USB
+pub const SERIALPORT_TYPE_USB: c_int = 0;
+/// The port is connected via PCI
+pub const SERIALPORT_TYPE_PCI: c_int = 1;
+/// The port is connected via Bluetooth
+pub const SERIALPORT_TYPE_BLUETOOTH: c_int = 2;
+/// It can't be determined how the port is connected
+pub const SERIALPORT_TYPE_UNKNOWN: c_int = 3;
+
+/// An open serial port, `serialport_t` in C
+#[derive(Debug)]
+pub struct SerialPortHandle {
+    port: Box<dyn SerialPort>,
+}
+
+/// A list of the serial ports on the system, `serialport_list_t` in C
+#[derive(Debug)]
+pub struct SerialPortList {
+    ports: Vec<(CString, SerialPortType)>,
+}
+
+/// The settings of a serial port, `serialport_config_t` in C
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialPortConfig {
+    /// The baud rate in symbols-per-second
+    pub baud_rate: u32,
+    /// Number of bits per character, 5 to 8
+    pub data_bits: u8,
+    /// One of the `SERIALPORT_PARITY_*` constants
+    pub parity: c_int,
+    /// Number of stop bits, 1 or 2
+    pub stop_bits: u8,
+    /// One of the `SERIALPORT_FLOW_CONTROL_*` constants
+    pub flow_control: c_int,
+    /// Amount of time to wait for reads and writes, in milliseconds
+    pub timeout_ms: u64,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Runs `f`, records its error for `serialport_last_error` and returns the matching status code
+fn call(f: impl FnOnce() -> Result<()>) -> c_int {
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        Err(Error::new(
+            ErrorKind::Unknown,
+            "panic while calling into serialport",
+        ))
+    });
+    match result {
+        Ok(()) => SERIALPORT_OK,
+        Err(error) => {
+            let code = match error.kind() {
+                ErrorKind::NoDevice => SERIALPORT_ERROR_NO_DEVICE,
+                ErrorKind::InvalidInput => SERIALPORT_ERROR_INVALID_INPUT,
+                ErrorKind::PermissionDenied => SERIALPORT_ERROR_PERMISSION_DENIED,
+                ErrorKind::Busy => SERIALPORT_ERROR_BUSY,
+                ErrorKind::Unsupported => SERIALPORT_ERROR_UNSUPPORTED,
+                ErrorKind::BrokenPipe => SERIALPORT_ERROR_BROKEN_PIPE,
+                ErrorKind::Unknown => SERIALPORT_ERROR_UNKNOWN,
+                ErrorKind::Timeout => SERIALPORT_ERROR_TIMED_OUT,
+                ErrorKind::Cancelled => SERIALPORT_ERROR_CANCELLED,
+                ErrorKind::Io(_) => SERIALPORT_ERROR_IO,
+            };
+            // Descriptions don't contain NUL bytes in practice. Drop them just in case.
+            let description = error.to_string().replace('\0', "");
+            let description = CString::new(description).unwrap_or_default();
+            LAST_ERROR.with(|last| *last.borrow_mut() = description);
+            code
+        }
+    }
+}
+
+fn null_pointer() -> Error {
+    Error::new(ErrorKind::InvalidInput, "unexpected null pointer")
+}
+
+/// Converts a pointer from C into a reference, failing for null pointers
+unsafe fn deref<'a, T>(pointer: *const T) -> Result<&'a T> {
+    pointer.as_ref().ok_or_else(null_pointer)
+}
+
+/// Converts a pointer from C into a mutable reference, failing for null pointers
+unsafe fn deref_mut<'a, T>(pointer: *mut T) -> Result<&'a mut T> {
+    pointer.as_mut().ok_or_else(null_pointer)
+}
+
+impl SerialPortConfig {
+    fn from_port(port: &dyn SerialPort) -> Result<Self> {
+        let settings = port.try_settings()?;
+        Ok(SerialPortConfig {
+            baud_rate: settings.baud_rate,
+            data_bits: settings.data_bits.into(),
+            parity: match settings.parity {
+                Parity::None => SERIALPORT_PARITY_NONE,
+                Parity::Odd => SERIALPORT_PARITY_ODD,
+                Parity::Even => SERIALPORT_PARITY_EVEN,
+            },
+            stop_bits: settings.stop_bits.into(),
+            flow_control: match settings.flow_control {
+                FlowControl::None => SERIALPORT_FLOW_CONTROL_NONE,
+                FlowControl::Software => SERIALPORT_FLOW_CONTROL_SOFTWARE,
+                FlowControl::Hardware => SERIALPORT_FLOW_CONTROL_HARDWARE,
+            },
+            timeout_ms: settings.timeout.as_millis().min(u64::MAX as u128) as u64,
+        })
+    }
+
+    fn apply_to(&self, port: &mut dyn SerialPort) -> Result<()> {
+        let invalid = |what: &str| Error::new(ErrorKind::InvalidInput, format!("invalid {}", what));
+        let data_bits = DataBits::try_from(self.data_bits).map_err(|_| invalid("data bits"))?;
+        let parity = match self.parity {
+            SERIALPORT_PARITY_NONE => Parity::None,
+            SERIALPORT_PARITY_ODD => Parity::Odd,
+            SERIALPORT_PARITY_EVEN => Parity::Even,
+            _ => return Err(invalid("parity")),
+        };
+        let stop_bits = StopBits::try_from(self.stop_bits).map_err(|_| invalid("stop bits"))?;
+        let flow_control = match self.flow_control {
+            SERIALPORT_FLOW_CONTROL_NONE => FlowControl::None,
+            SERIALPORT_FLOW_CONTROL_SOFTWARE => FlowControl::Software,
+            SERIALPORT_FLOW_CONTROL_HARDWARE => FlowControl::Hardware,
+            _ => return Err(invalid("flow control")),
+        };
+
+        let mut settings = crate::new("", self.baud_rate)
+            .data_bits(data_bits)
+            .parity(parity)
+            .stop_bits(stop_bits)
+            .flow_control(flow_control)
+            .timeout(Duration::from_millis(self.timeout_ms));
+        // Keep the inter-byte timeout, which is not covered by the configuration.
+        if let Ok(inter_byte_timeout) = port.inter_byte_timeout() {
+            settings.inter_byte_timeout = inter_byte_timeout;
+        }
+        port.apply_settings(&settings)
+    }
+}
+
+/// Opens the serial port at `path` with the given baud rate and otherwise default settings
+///
+/// On success, the port is stored in `port` and must be closed with [`serialport_close`].
+///
+/// # Safety
+///
+/// `path` must be a NUL-terminated string and `port` must be valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_open(
+    path: *const c_char,
+    baud_rate: u32,
+    port: *mut *mut SerialPortHandle,
+) -> c_int {
+    call(|| {
+        let port = deref_mut(port)?;
+        if path.is_null() {
+            return Err(null_pointer());
+        }
+        let path = CStr::from_ptr(path)
+            .to_str()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "path is not valid UTF-8"))?;
+        let opened = crate::new(path, baud_rate).open()?;
+        *port = Box::into_raw(Box::new(SerialPortHandle { port: opened }));
+        Ok(())
+    })
+}
+
+/// Closes a port opened by [`serialport_open`]
+///
+/// Passing a null pointer does nothing.
+///
+/// # Safety
+///
+/// `port` must be null or a port returned by [`serialport_open`] which has not been closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_close(port: *mut SerialPortHandle) {
+    if !port.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(port))));
+    }
+}
+
+/// Reads the current settings of `port` into `config`
+///
+/// # Safety
+///
+/// `port` must be an open port and `config` must be valid for writing.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_get_config(
+    port: *const SerialPortHandle,
+    config: *mut SerialPortConfig,
+) -> c_int {
+    call(|| {
+        let port = deref(port)?;
+        let config = deref_mut(config)?;
+        *config = SerialPortConfig::from_port(&*port.port)?;
+        Ok(())
+    })
+}
+
+/// Applies all settings from `config` to `port` at once
+///
+/// Either all settings are applied or none of them.
+///
+/// # Safety
+///
+/// `port` must be an open port and `config` must be valid for reading.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_set_config(
+    port: *mut SerialPortHandle,
+    config: *const SerialPortConfig,
+) -> c_int {
+    call(|| {
+        let port = deref_mut(port)?;
+        let config = deref(config)?;
+        config.apply_to(&mut *port.port)
+    })
+}
+
+/// Reads up to `len` bytes into `buf` and stores the number of bytes read in `read`
+///
+/// Waits for data up to the timeout of the port and fails with [`SERIALPORT_ERROR_TIMED_OUT`] if
+/// none arrived.
+///
+/// # Safety
+///
+/// `port` must be an open port, `buf` must be valid for writing `len` bytes and `read` must be
+/// valid for writing.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_read(
+    port: *mut SerialPortHandle,
+    buf: *mut u8,
+    len: usize,
+    read: *mut usize,
+) -> c_int {
+    call(|| {
+        let port = deref_mut(port)?;
+        let read = deref_mut(read)?;
+        if buf.is_null() && len > 0 {
+            return Err(null_pointer());
+        }
+        let buf = if len > 0 {
+            slice::from_raw_parts_mut(buf, len)
+        } else {
+            &mut []
+        };
+        *read = port.port.read(buf)?;
+        Ok(())
+    })
+}
+
+/// Writes up to `len` bytes from `buf` and stores the number of bytes written in `written`
+///
+/// # Safety
+///
+/// `port` must be an open port, `buf` must be valid for reading `len` bytes and `written` must be
+/// valid for writing.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_write(
+    port: *mut SerialPortHandle,
+    buf: *const u8,
+    len: usize,
+    written: *mut usize,
+) -> c_int {
+    call(|| {
+        let port = deref_mut(port)?;
+        let written = deref_mut(written)?;
+        if buf.is_null() && len > 0 {
+            return Err(null_pointer());
+        }
+        let buf = if len > 0 {
+            slice::from_raw_parts(buf, len)
+        } else {
+            &[]
+        };
+        *written = port.port.write(buf)?;
+        Ok(())
+    })
+}
+
+/// Waits until all written data has been handed over to the device
+///
+/// # Safety
+///
+/// `port` must be an open port.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_flush(port: *mut SerialPortHandle) -> c_int {
+    call(|| Ok(deref_mut(port)?.port.flush()?))
+}
+
+/// Stores the number of bytes which can be read without waiting in `count`
+///
+/// # Safety
+///
+/// `port` must be an open port and `count` must be valid for writing.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_bytes_to_read(
+    port: *const SerialPortHandle,
+    count: *mut u32,
+) -> c_int {
+    call(|| {
+        let port = deref(port)?;
+        *deref_mut(count)? = port.port.bytes_to_read()?;
+        Ok(())
+    })
+}
+
+/// Discards the buffered data selected by one of the `SERIALPORT_CLEAR_*` constants
+///
+/// # Safety
+///
+/// `port` must be an open port.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_clear(port: *const SerialPortHandle, buffer: c_int) -> c_int {
+    call(|| {
+        let port = deref(port)?;
+        let buffer = match buffer {
+            SERIALPORT_CLEAR_INPUT => ClearBuffer::Input,
+            SERIALPORT_CLEAR_OUTPUT => ClearBuffer::Output,
+            SERIALPORT_CLEAR_ALL => ClearBuffer::All,
+            _ => return Err(Error::new(ErrorKind::InvalidInput, "invalid buffer")),
+        };
+        port.port.clear(buffer)
+    })
+}
+
+/// Lists the serial ports on the system
+///
+/// On success, the list is stored in `list` and must be freed with [`serialport_list_free`].
+///
+/// # Safety
+///
+/// `list` must be valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_list_ports(list: *mut *mut SerialPortList) -> c_int {
+    call(|| {
+        let list = deref_mut(list)?;
+        let ports = crate::available_ports()?
+            .into_iter()
+            .filter_map(|info| Some((CString::new(info.port_name).ok()?, info.port_type)))
+            .collect();
+        *list = Box::into_raw(Box::new(SerialPortList { ports }));
+        Ok(())
+    })
+}
+
+/// Returns the number of ports in `list`
+///
+/// # Safety
+///
+/// `list` must be a list returned by [`serialport_list_ports`] which has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_list_len(list: *const SerialPortList) -> usize {
+    list.as_ref().map_or(0, |list| list.ports.len())
+}
+
+/// Returns the name of the port at `index` in `list`, or null if `index` is out of range
+///
+/// The name stays valid until the list gets freed.
+///
+/// # Safety
+///
+/// `list` must be a list returned by [`serialport_list_ports`] which has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_list_name(
+    list: *const SerialPortList,
+    index: usize,
+) -> *const c_char {
+    list.as_ref()
+        .and_then(|list| list.ports.get(index))
+        .map_or(ptr::null(), |(name, _)| name.as_ptr())
+}
+
+/// Returns how the port at `index` in `list` is connected as one of the `SERIALPORT_TYPE_*`
+/// constants
+///
+/// Returns [`SERIALPORT_TYPE_UNKNOWN`] if `index` is out of range.
+///
+/// # Safety
+///
+/// `list` must be a list returned by [`serialport_list_ports`] which has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_list_type(list: *const SerialPortList, index: usize) -> c_int {
+    let port_type = list.as_ref().and_then(|list| list.ports.get(index));
+    match port_type.map(|(_, port_type)| port_type) {
+        Some(SerialPortType::UsbPort(_)) => SERIALPORT_TYPE_USB,
+        Some(SerialPortType::PciPort) => SERIALPORT_TYPE_PCI,
+        Some(SerialPortType::BluetoothPort) => SERIALPORT_TYPE_BLUETOOTH,
+        Some(SerialPortType::Unknown) | None => SERIALPORT_TYPE_UNKNOWN,
+    }
+}
+
+/// Stores the USB vendor and product ID of the port at `index` in `list` in `vid` and `pid`
+///
+/// Fails with [`SERIALPORT_ERROR_INVALID_INPUT`] if `index` is out of range or the port is not a
+/// USB port.
+///
+/// # Safety
+///
+/// `list` must be a list returned by [`serialport_list_ports`] which has not been freed yet.
+/// `vid` and `pid` must be valid for writing.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_list_usb_ids(
+    list: *const SerialPortList,
+    index: usize,
+    vid: *mut u16,
+    pid: *mut u16,
+) -> c_int {
+    call(|| {
+        let list = deref(list)?;
+        let vid = deref_mut(vid)?;
+        let pid = deref_mut(pid)?;
+        match list.ports.get(index) {
+            Some((_, SerialPortType::UsbPort(info))) => {
+                *vid = info.vid;
+                *pid = info.pid;
+                Ok(())
+            }
+            Some(_) => Err(Error::new(ErrorKind::InvalidInput, "not a USB port")),
+            None => Err(Error::new(ErrorKind::InvalidInput, "index out of range")),
+        }
+    })
+}
+
+/// Frees a list returned by [`serialport_list_ports`]
+///
+/// Passing a null pointer does nothing.
+///
+/// # Safety
+///
+/// `list` must be null or a list returned by [`serialport_list_ports`] which has not been freed
+/// yet.
+#[no_mangle]
+pub unsafe extern "C" fn serialport_list_free(list: *mut SerialPortList) {
+    if !list.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(list))));
+    }
+}
+
+/// Returns the description of the last error which occurred on the calling thread
+///
+/// The string is empty if no error occurred yet. It stays valid until the next failing call on
+/// the same thread.
+#[no_mangle]
+pub extern "C" fn serialport_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+/// Returns a static description of one of the `SERIALPORT_OK` or `SERIALPORT_ERROR_*` codes
+#[no_mangle]
+pub extern "C" fn serialport_error_name(code: c_int) -> *const c_char {
+    let name: &'static [u8] = match code {
+        SERIALPORT_OK => b"success\0",
+        SERIALPORT_ERROR_NO_DEVICE => b"device not available\0",
+        SERIALPORT_ERROR_INVALID_INPUT => b"invalid input\0",
+        SERIALPORT_ERROR_UNKNOWN => b"unknown error\0",
+        SERIALPORT_ERROR_IO => b"I/O error\0",
+        SERIALPORT_ERROR_TIMED_OUT => b"operation timed out\0",
+        SERIALPORT_ERROR_PERMISSION_DENIED => b"permission denied\0",
+        SERIALPORT_ERROR_BUSY => b"device busy\0",
+        SERIALPORT_ERROR_UNSUPPORTED => b"operation not supported\0",
+        SERIALPORT_ERROR_BROKEN_PIPE => b"broken pipe\0",
+        SERIALPORT_ERROR_CANCELLED => b"operation cancelled\0",
+        _ => b"unrecognized error code\0",
+    };
+    name.as_ptr() as *const c_char
+}
@@ -0,0 +1,245 @@
+//! Selecting serial ports by their USB device information.
+
+use crate::{Error, ErrorKind, Result, SerialPortInfo, SerialPortType, UsbPortInfo};
+
+/// Criteria for selecting USB serial ports
+///
+/// A port matches the filter if it is a USB port and satisfies all criteria which have been set.
+/// An empty filter matches all USB ports.
+///
+/// ```
+/// use serialport::PortFilter;
+///
+/// // Any FTDI FT232R
+/// let filter = PortFilter::new().vid(0x0403).pid(0x6001);
+/// # let _ = filter;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortFilter {
+    vid: Option<u16>,
+    pid: Option<u16>,
+    serial_number: Option<String>,
+    manufacturer: Option<String>,
+    product: Option<String>,
+}
+
+impl PortFilter {
+    /// Creates a filter matching all USB ports
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match ports with the given USB vendor ID
+    #[must_use]
+    pub fn vid(mut self, vid: u16) -> Self {
+        self.vid = Some(vid);
+        self
+    }
+
+    /// Only match ports with the given USB product ID
+    #[must_use]
+    pub fn pid(mut self, pid: u16) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    /// Only match ports with the given USB serial number
+    ///
+    /// Serial numbers are compared ignoring ASCII case as Windows reports them in upper case.
+    /// For FTDI devices, the interface letter which the Windows driver appends to the serial
+    /// number is ignored as well.
+    #[must_use]
+    pub fn serial_number(mut self, serial_number: impl Into<String>) -> Self {
+        self.serial_number = Some(serial_number.into());
+        self
+    }
+
+    /// Only match ports whose manufacturer contains `manufacturer`, ignoring ASCII case
+    #[must_use]
+    pub fn manufacturer(mut self, manufacturer: impl Into<String>) -> Self {
+        self.manufacturer = Some(manufacturer.into());
+        self
+    }
+
+    /// Only match ports whose product name contains `product`, ignoring ASCII case
+    #[must_use]
+    pub fn product(mut self, product: impl Into<String>) -> Self {
+        self.product = Some(product.into());
+        self
+    }
+
+    /// Returns whether `port` satisfies all criteria of this filter
+    pub fn matches(&self, port: &SerialPortInfo) -> bool {
+        match port.port_type {
+            SerialPortType::UsbPort(ref info) => self.matches_usb(info),
+            _ => false,
+        }
+    }
+
+    fn matches_usb(&self, info: &UsbPortInfo) -> bool {
+        self.vid.map_or(true, |vid| vid == info.vid)
+            && self.pid.map_or(true, |pid| pid == info.pid)
+            && self.matches_serial_number(info)
+            && contains_ignore_case(&self.manufacturer, &info.manufacturer)
+            && contains_ignore_case(&self.product, &info.product)
+    }
+
+    fn matches_serial_number(&self, info: &UsbPortInfo) -> bool {
+        let expected = match self.serial_number {
+            Some(ref expected) => expected,
+            None => return true,
+        };
+        let actual = match info.serial_number {
+            Some(ref actual) => actual,
+            None => return false,
+        };
+
+        if actual.eq_ignore_ascii_case(expected) {
+            return true;
+        }
+
+        // The FTDI driver on Windows appends a letter denoting the interface to the serial
+        // number, e.g. `A702TB52A` for the first interface of the device `A702TB52`.
+        info.vid == FTDI_VID
+            && actual.len() == expected.len() + 1
+            && actual.is_char_boundary(expected.len())
+            && actual[..expected.len()].eq_ignore_ascii_case(expected)
+            && actual[expected.len()..]
+                .bytes()
+                .all(|b| b.is_ascii_uppercase())
+    }
+}
+
+const FTDI_VID: u16 = 0x0403;
+
+/// Returns the only port in `ports` or an error naming the candidates
+pub(crate) fn single_port(ports: Vec<SerialPortInfo>, criteria: &str) -> Result<SerialPortInfo> {
+    let mut ports = ports.into_iter();
+    match (ports.next(), ports.next()) {
+        (Some(port), None) => Ok(port),
+        (None, _) => Err(Error::new(
+            ErrorKind::NoDevice,
+            format!("no port found with {}", criteria),
+        )),
+        (Some(first), Some(second)) => {
+            let candidates: Vec<String> = [first, second]
+                .into_iter()
+                .chain(ports)
+                .map(|port| port.port_name)
+                .collect();
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "multiple ports found with {}: {}",
+                    criteria,
+                    candidates.join(", ")
+                ),
+            ))
+        }
+    }
+}
+
+fn contains_ignore_case(needle: &Option<String>, haystack: &Option<String>) -> bool {
+    match (needle, haystack) {
+        (None, _) => true,
+        (Some(needle), Some(haystack)) => haystack
+            .to_ascii_lowercase()
+            .contains(&needle.to_ascii_lowercase()),
+        (Some(_), None) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usb_port(vid: u16, pid: u16, serial_number: Option<&str>) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: "/dev/ttyUSB0".to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid,
+                pid,
+                serial_number: serial_number.map(String::from),
+                manufacturer: Some("FTDI".to_string()),
+                product: Some("FT232R USB UART".to_string()),
+                interface: None,
+                location: None,
+                bcd_device: None,
+                device_class: None,
+                interface_class: None,
+            }),
+            friendly_name: None,
+            description: None,
+            instance_id: None,
+            counterpart: None,
+            by_id: None,
+            by_path: None,
+            bluetooth: None,
+        }
+    }
+
+    #[test]
+    fn empty_filter_matches_usb_ports_only() {
+        let filter = PortFilter::new();
+        assert!(filter.matches(&usb_port(0x0403, 0x6001, None)));
+        assert!(!filter.matches(&SerialPortInfo {
+            port_name: "/dev/ttyS0".to_string(),
+            port_type: SerialPortType::Unknown,
+            friendly_name: None,
+            description: None,
+            instance_id: None,
+            counterpart: None,
+            by_id: None,
+            by_path: None,
+            bluetooth: None,
+        }));
+    }
+
+    #[test]
+    fn filter_matches_ids_and_serial_number() {
+        let port = usb_port(0x0403, 0x6001, Some("A123"));
+        assert!(PortFilter::new().vid(0x0403).pid(0x6001).matches(&port));
+        assert!(!PortFilter::new().vid(0x0403).pid(0x6015).matches(&port));
+        assert!(PortFilter::new().serial_number("A123").matches(&port));
+        assert!(!PortFilter::new().serial_number("A12").matches(&port));
+        assert!(PortFilter::new().serial_number("a123").matches(&port));
+        assert!(!PortFilter::new()
+            .serial_number("A123")
+            .matches(&usb_port(0x0403, 0x6001, None)));
+    }
+
+    #[test]
+    fn filter_matches_manufacturer_and_product_substrings() {
+        let port = usb_port(0x0403, 0x6001, None);
+        assert!(PortFilter::new().manufacturer("ftdi").matches(&port));
+        assert!(PortFilter::new().product("uart").matches(&port));
+        assert!(!PortFilter::new().manufacturer("Prolific").matches(&port));
+    }
+
+    #[test]
+    fn filter_ignores_ftdi_interface_letter() {
+        let port = usb_port(0x0403, 0x6001, Some("A702TB52A"));
+        assert!(PortFilter::new().serial_number("A702TB52").matches(&port));
+        assert!(PortFilter::new().serial_number("A702TB52A").matches(&port));
+
+        let port = usb_port(0x10c4, 0xea60, Some("A702TB52A"));
+        assert!(!PortFilter::new().serial_number("A702TB52").matches(&port));
+    }
+
+    #[test]
+    fn single_port_requires_exactly_one_port() {
+        let port = usb_port(0x0403, 0x6001, None);
+        assert_eq!(single_port(vec![port.clone()], "x").unwrap(), port);
+
+        let error = single_port(vec![], "serial number 42").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NoDevice);
+        assert_eq!(error.to_string(), "no port found with serial number 42");
+
+        let error = single_port(vec![port.clone(), port], "x").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            error.to_string(),
+            "multiple ports found with x: /dev/ttyUSB0, /dev/ttyUSB0"
+        );
+    }
+}
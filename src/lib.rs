@@ -32,21 +32,88 @@
 // doc tests.
 #![doc(test(attr(allow(unused_must_use))))]
 
+use std::cmp::Ordering;
 use std::error::Error as StdError;
 use std::fmt;
 use std::io;
+use std::mem::MaybeUninit;
 use std::str::FromStr;
 use std::time::Duration;
 
 #[cfg(unix)]
 mod posix;
+#[cfg(target_os = "linux")]
+pub use posix::SignalMask;
 #[cfg(unix)]
-pub use posix::{BreakDuration, TTYPort};
+pub use posix::{BreakDuration, PipeSerialPort, PipeSerialServer, TTYPort};
+
+#[cfg(all(target_arch = "wasm32", web_sys_unstable_apis))]
+mod web;
+#[cfg(all(target_arch = "wasm32", web_sys_unstable_apis))]
+pub use web::WebSerialPort;
 
 #[cfg(windows)]
 mod windows;
 #[cfg(windows)]
-pub use windows::COMPort;
+pub use windows::{COMPort, PipeSerialPort, PipeSerialServer};
+
+mod cancel;
+pub use cancel::CancelToken;
+
+mod ext;
+pub use ext::SerialPortExt;
+
+mod filter;
+pub use filter::PortFilter;
+
+mod managed;
+pub use managed::{ManagedPort, PortRenamed, PortState};
+
+mod observer;
+pub use observer::{add_observer, remove_observer, ObserverId, PortObserver};
+
+mod rfc2217;
+pub use rfc2217::Rfc2217Port;
+
+mod registration;
+pub use registration::{Interest, Registration};
+
+mod selector;
+pub use selector::Selector;
+
+mod settings;
+pub use settings::{LossySettings, PortSettings, Setting};
+
+mod shared;
+pub use shared::SharedPort;
+
+mod split;
+pub use split::{ReadHalf, WriteHalf};
+
+mod stats;
+pub use stats::PortStats;
+
+mod support;
+pub use support::{
+    support_bundle, LineErrors, OpenPortReport, PlatformInfo, PortReport, SupportBundle,
+};
+
+mod writer;
+pub use writer::{BackgroundWriter, WritePriority};
+
+pub mod bridge;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "log")]
+pub mod logged;
+
+pub mod mock;
+
+pub mod record;
+
+pub mod watcher;
 
 #[cfg(test)]
 pub(crate) mod tests;
@@ -58,19 +125,43 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 /// Categories of errors that can occur when interacting with serial ports
 ///
-/// This list is intended to grow over time and it is not recommended to
-/// exhaustively match against it.
+/// This list is intended to grow over time, so matching against it requires a
+/// wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ErrorKind {
     /// The device is not available.
     ///
-    /// This could indicate that the device is in use by another process or was
-    /// disconnected while performing I/O.
+    /// This could indicate that the device does not exist or was disconnected while
+    /// performing I/O.
     NoDevice,
 
     /// A parameter was incorrect.
     InvalidInput,
 
+    /// The device could not be accessed because of missing permissions.
+    ///
+    /// Windows reports this as `Busy`, as opening a port in use by another process fails the same
+    /// way.
+    PermissionDenied,
+
+    /// The device is in use by another process or not ready to be opened yet.
+    Busy,
+
+    /// The operation is not supported by the device or the platform.
+    Unsupported,
+
+    /// The other end of the connection has been closed.
+    BrokenPipe,
+
+    /// The operation did not complete within the timeout.
+    ///
+    /// Errors of this kind convert into an `io::Error` of kind `TimedOut`.
+    Timeout,
+
+    /// The operation was aborted with a [`CancelToken`].
+    Cancelled,
+
     /// An unknown error occurred.
     Unknown,
 
@@ -81,12 +172,18 @@ pub enum ErrorKind {
 }
 
 /// An error type for serial port operations
+///
+/// Errors of operations on ports may carry the name of the port and the operation which failed.
+/// Both are included when displaying the error, e.g. as `/dev/ttyUSB0: setting baud rate:
+/// Invalid argument`. As this context is private, errors are created with [`Error::new`].
 #[derive(Debug, Clone)]
 pub struct Error {
     /// The kind of error this is
     pub kind: ErrorKind,
     /// A description of the error suitable for end-users
     pub description: String,
+    operation: Option<String>,
+    port: Option<String>,
 }
 
 impl Error {
@@ -95,6 +192,8 @@ impl Error {
         Error {
             kind,
             description: description.into(),
+            operation: None,
+            port: None,
         }
     }
 
@@ -102,10 +201,54 @@ impl Error {
     pub fn kind(&self) -> ErrorKind {
         self.kind
     }
+
+    /// Returns the operation which failed, e.g. `setting baud rate`, if known
+    pub fn operation(&self) -> Option<&str> {
+        self.operation.as_deref()
+    }
+
+    /// Returns the name of the port the error occurred on, if known
+    pub fn port(&self) -> Option<&str> {
+        self.port.as_deref()
+    }
+
+    /// Adds the operation which failed and the name of the port it failed on
+    ///
+    /// Context which the error already carries is kept, as it is the more specific one.
+    #[must_use]
+    pub fn with_context(mut self, operation: &str, port: Option<&str>) -> Self {
+        if self.operation.is_none() {
+            self.operation = Some(operation.to_string());
+        }
+        if self.port.is_none() {
+            self.port = port.map(str::to_string);
+        }
+        self
+    }
+
+    /// Adds context to an error converted into an `io::Error`
+    ///
+    /// Errors which are expected to be retried right away are passed through as they are.
+    pub(crate) fn io_with_context(
+        error: io::Error,
+        operation: &str,
+        port: Option<&str>,
+    ) -> io::Error {
+        match error.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => error,
+            _ => Error::from(error).with_context(operation, port).into(),
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> std::result::Result<(), fmt::Error> {
+        if let Some(ref port) = self.port {
+            write!(fmt, "{}: ", port)?;
+        }
+        if let Some(ref operation) = self.operation {
+            write!(fmt, "{}: ", operation)?;
+        }
         fmt.write_str(&self.description)
     }
 }
@@ -118,7 +261,19 @@ impl StdError for Error {
 
 impl From<io::Error> for Error {
     fn from(io_error: io::Error) -> Error {
-        Error::new(ErrorKind::Io(io_error.kind()), format!("{}", io_error))
+        // Errors converted into `io::Error` before, e.g. by `io::Read` implementations, keep their
+        // original kind.
+        if let Some(error) = io_error.get_ref().and_then(|e| e.downcast_ref::<Error>()) {
+            return error.clone();
+        }
+        let kind = match io_error.kind() {
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            io::ErrorKind::Unsupported => ErrorKind::Unsupported,
+            io::ErrorKind::BrokenPipe => ErrorKind::BrokenPipe,
+            io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            kind => ErrorKind::Io(kind),
+        };
+        Error::new(kind, format!("{}", io_error))
     }
 }
 
@@ -127,11 +282,18 @@ impl From<Error> for io::Error {
         let kind = match error.kind {
             ErrorKind::NoDevice => io::ErrorKind::NotFound,
             ErrorKind::InvalidInput => io::ErrorKind::InvalidInput,
+            ErrorKind::PermissionDenied => io::ErrorKind::PermissionDenied,
+            ErrorKind::Busy => io::ErrorKind::Other,
+            ErrorKind::Unsupported => io::ErrorKind::Unsupported,
+            ErrorKind::BrokenPipe => io::ErrorKind::BrokenPipe,
+            ErrorKind::Timeout => io::ErrorKind::TimedOut,
+            // Not `Interrupted`, as `read_exact()` and `write_all()` retry those.
+            ErrorKind::Cancelled => io::ErrorKind::Other,
             ErrorKind::Unknown => io::ErrorKind::Other,
             ErrorKind::Io(kind) => kind,
         };
 
-        io::Error::new(kind, error.description)
+        io::Error::new(kind, error)
     }
 }
 
@@ -314,25 +476,178 @@ pub enum ClearBuffer {
     All,
 }
 
+/// The directions a port gets opened for
+///
+/// Opening a port for a single direction lets the operating system enforce it. Monitoring tools
+/// can guarantee that they never transmit and transmit-only loggers can be granted narrower
+/// permissions. Operations in the other direction fail.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AccessMode {
+    /// Open the port for reading and writing
+    ReadWrite,
+    /// Open the port for reading only
+    ReadOnly,
+    /// Open the port for writing only
+    WriteOnly,
+}
+
+/// Whether others may open a port while it is open
+///
+/// Sharing a port enables monitoring setups where a sniffer and an application deliberately
+/// open the same device, but only works with drivers which permit it. Most drivers on Windows
+/// don't.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ShareMode {
+    /// Deny others opening the port
+    Exclusive,
+    /// Allow others to open the port for reading only
+    ///
+    /// POSIX systems can't restrict others to reading, so the port is not opened for exclusive
+    /// access there at all.
+    ShareRead,
+    /// Allow others to open the port for reading and writing
+    Shared,
+}
+
+/// How to handle operations which are not supported by a port
+///
+/// Virtual ports like pseudo terminals or USB gadgets often lack support for some operations,
+/// for example for the modem control lines. Being lenient about this allows code to work with
+/// these ports without special-casing them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Strictness {
+    /// Return an `Unsupported` error for unsupported operations.
+    Strict,
+    /// Return a sensible default for unsupported operations instead of an error.
+    ///
+    /// Setting control lines or sending breaks succeeds without effect. CTS, DSR and CD read as
+    /// asserted, RI as not asserted, and the output buffer as empty.
+    Lenient,
+}
+
+impl Strictness {
+    /// Replaces an `Unsupported` error with `default` when being lenient
+    pub(crate) fn or_default<T>(self, result: Result<T>, default: T) -> Result<T> {
+        match result {
+            Err(ref e) if self == Strictness::Lenient && e.kind == ErrorKind::Unsupported => {
+                Ok(default)
+            }
+            result => result,
+        }
+    }
+}
+
 /// A struct containing all serial port settings
+///
+/// With the `serde` feature, builders can be stored in configuration files. When deserializing,
+/// only `path` and `baud_rate` are required. All other settings default to the values set by
+/// [`new`].
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SerialPortBuilder {
     /// The port name, usually the device path
     path: String,
     /// The baud rate in symbols-per-second
     baud_rate: u32,
     /// Number of bits used to represent a character sent on the line
+    #[cfg_attr(feature = "serde", serde(default = "builder_defaults::data_bits"))]
     data_bits: DataBits,
     /// The type of signalling to use for controlling data transfer
+    #[cfg_attr(feature = "serde", serde(default = "builder_defaults::flow_control"))]
     flow_control: FlowControl,
     /// The type of parity to use for error checking
+    #[cfg_attr(feature = "serde", serde(default = "builder_defaults::parity"))]
     parity: Parity,
     /// Number of bits to use to signal the end of a character
+    #[cfg_attr(feature = "serde", serde(default = "builder_defaults::stop_bits"))]
     stop_bits: StopBits,
     /// Amount of time to wait to receive data before timing out
+    #[cfg_attr(feature = "serde", serde(default))]
     timeout: Duration,
+    /// Amount of time the line may stay idle between two bytes before a read returns
+    #[cfg_attr(feature = "serde", serde(default))]
+    inter_byte_timeout: Option<Duration>,
     /// The state to set DTR to when opening the device
+    #[cfg_attr(feature = "serde", serde(default = "builder_defaults::dtr_on_open"))]
     dtr_on_open: Option<bool>,
+    /// The directions to open the device for
+    #[cfg_attr(feature = "serde", serde(default = "builder_defaults::access_mode"))]
+    access_mode: AccessMode,
+    /// Whether others may open the device at the same time
+    #[cfg_attr(feature = "serde", serde(default = "builder_defaults::share_mode"))]
+    share_mode: ShareMode,
+    /// Whether child processes inherit the port
+    #[cfg_attr(feature = "serde", serde(default))]
+    inheritable: bool,
+    /// Whether to create a UUCP lock file for the device
+    #[cfg(unix)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    uucp_lock: bool,
+    /// Whether to restore the previous terminal settings when closing the port
+    #[cfg(unix)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    restore_settings: bool,
+    /// Whether to open devices which fail the terminal checks anyway
+    #[cfg(unix)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    skip_tty_checks: bool,
+    /// Whether to retry reads, writes and waits interrupted by signals
+    #[cfg(unix)]
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "builder_defaults::retry_interrupted")
+    )]
+    retry_interrupted: bool,
+    /// How to handle operations which are not supported by the port
+    #[cfg_attr(feature = "serde", serde(default = "builder_defaults::strictness"))]
+    strictness: Strictness,
+}
+
+/// The defaults of the optional settings when deserializing a [`SerialPortBuilder`], matching
+/// the ones set by [`new`]
+#[cfg(feature = "serde")]
+mod builder_defaults {
+    use super::{AccessMode, DataBits, FlowControl, Parity, ShareMode, StopBits, Strictness};
+
+    pub fn data_bits() -> DataBits {
+        DataBits::Eight
+    }
+
+    pub fn flow_control() -> FlowControl {
+        FlowControl::None
+    }
+
+    pub fn parity() -> Parity {
+        Parity::None
+    }
+
+    pub fn stop_bits() -> StopBits {
+        StopBits::One
+    }
+
+    pub fn dtr_on_open() -> Option<bool> {
+        Some(true)
+    }
+
+    pub fn access_mode() -> AccessMode {
+        AccessMode::ReadWrite
+    }
+
+    pub fn share_mode() -> ShareMode {
+        ShareMode::Exclusive
+    }
+
+    #[cfg(unix)]
+    pub fn retry_interrupted() -> bool {
+        true
+    }
+
+    pub fn strictness() -> Strictness {
+        Strictness::Strict
+    }
 }
 
 impl SerialPortBuilder {
@@ -380,6 +695,67 @@ impl SerialPortBuilder {
         self
     }
 
+    /// Set the baud rate and framing from a compact mode string like `115200,8N1`
+    ///
+    /// The mode consists of an optional baud rate and an optional framing, separated by a comma.
+    /// At least one of them has to be present. The framing consists of the number of data bits
+    /// (`5` to `8`), the parity (`N`, `O` or `E`, case-insensitive) and the number of stop bits
+    /// (`1` or `2`). Settings not present in the mode are left as they are.
+    ///
+    /// ```
+    /// use serialport::{DataBits, Parity, StopBits};
+    ///
+    /// let builder = serialport::new("/dev/ttyUSB0", 9600).parse_mode("115200,7E2").unwrap();
+    /// assert_eq!(
+    ///     builder,
+    ///     serialport::new("/dev/ttyUSB0", 115_200)
+    ///         .data_bits(DataBits::Seven)
+    ///         .parity(Parity::Even)
+    ///         .stop_bits(StopBits::Two)
+    /// );
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `mode` is not a valid mode string.
+    pub fn parse_mode(mut self, mode: &str) -> Result<Self> {
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid mode string: {:?}", mode),
+            )
+        };
+
+        let (baud_rate, framing) = match mode.split_once(',') {
+            Some((baud_rate, framing)) => (Some(baud_rate), Some(framing)),
+            None if mode.bytes().all(|b| b.is_ascii_digit()) => (Some(mode), None),
+            None => (None, Some(mode)),
+        };
+
+        if let Some(baud_rate) = baud_rate {
+            self.baud_rate = baud_rate.trim().parse().map_err(|_| invalid())?;
+        }
+
+        if let Some(framing) = framing {
+            let framing = framing.trim().as_bytes();
+            if framing.len() != 3 {
+                return Err(invalid());
+            }
+            self.data_bits =
+                DataBits::try_from(framing[0].wrapping_sub(b'0')).map_err(|_| invalid())?;
+            self.parity = match framing[1].to_ascii_uppercase() {
+                b'N' => Parity::None,
+                b'O' => Parity::Odd,
+                b'E' => Parity::Even,
+                _ => return Err(invalid()),
+            };
+            self.stop_bits =
+                StopBits::try_from(framing[2].wrapping_sub(b'0')).map_err(|_| invalid())?;
+        }
+
+        Ok(self)
+    }
+
     /// Set the amount of time to wait to receive data before timing out
     ///
     /// <div class="warning">
@@ -395,6 +771,26 @@ impl SerialPortBuilder {
         self
     }
 
+    /// Set the amount of time the line may stay idle between two received bytes before a read
+    /// returns the data received so far
+    ///
+    /// This allows for reading packets from protocols which delimit them by pauses on the line.
+    /// The inter-byte timer only starts after the first byte has been received. Waiting for this
+    /// first byte is still governed by [`timeout`](SerialPortBuilder::timeout).
+    ///
+    /// <div class="warning">
+    ///
+    /// The accuracy is limited by the underlying platform's capabilities. POSIX systems only
+    /// support a resolution of 100 ms and a maximum of 25.5 s. The value gets rounded up to the
+    /// next supported one.
+    ///
+    /// </div>
+    #[must_use]
+    pub fn inter_byte_timeout(mut self, inter_byte_timeout: Duration) -> Self {
+        self.inter_byte_timeout = Some(inter_byte_timeout);
+        self
+    }
+
     /// Set data terminal ready (DTR) to the given state when opening the device
     #[must_use]
     pub fn dtr_on_open(mut self, state: bool) -> Self {
@@ -411,134 +807,582 @@ impl SerialPortBuilder {
         self
     }
 
-    /// Open a cross-platform interface to the port with the specified settings
-    pub fn open(self) -> Result<Box<dyn SerialPort>> {
-        #[cfg(unix)]
-        return posix::TTYPort::open(&self).map(|p| Box::new(p) as Box<dyn SerialPort>);
-
-        #[cfg(windows)]
-        return windows::COMPort::open(&self).map(|p| Box::new(p) as Box<dyn SerialPort>);
-
-        #[cfg(not(any(unix, windows)))]
-        Err(Error::new(
-            ErrorKind::Unknown,
-            "open() not implemented for platform",
-        ))
+    /// Set how to handle operations which are not supported by the port
+    ///
+    /// Defaults to [`Strictness::Strict`].
+    #[must_use]
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        self.strictness = strictness;
+        self
     }
 
-    /// Open a platform-specific interface to the port with the specified settings
-    #[cfg(unix)]
-    pub fn open_native(self) -> Result<TTYPort> {
-        posix::TTYPort::open(&self)
+    /// Set the directions to open the device for
+    ///
+    /// This uses `O_RDONLY` or `O_WRONLY` on POSIX systems and `GENERIC_READ` or `GENERIC_WRITE`
+    /// on Windows. Defaults to [`AccessMode::ReadWrite`].
+    #[must_use]
+    pub fn access_mode(mut self, access_mode: AccessMode) -> Self {
+        self.access_mode = access_mode;
+        self
     }
 
-    /// Open a platform-specific interface to the port with the specified settings
-    #[cfg(windows)]
-    pub fn open_native(self) -> Result<COMPort> {
-        windows::COMPort::open(&self)
+    /// Set whether others may open the device while it is open
+    ///
+    /// Opening an exclusive port again fails with `Busy`. On POSIX systems, an exclusive port
+    /// uses the `TIOCEXCL` ioctl, which only guards against other processes opening the device
+    /// as well when they lack the `CAP_SYS_ADMIN` capability. On Windows, the share mode maps to
+    /// the `dwShareMode` passed to `CreateFile`, i.e. `0`, `FILE_SHARE_READ` or
+    /// `FILE_SHARE_READ | FILE_SHARE_WRITE`. The other party has to open the device with a
+    /// compatible share mode as well.
+    ///
+    /// Defaults to [`ShareMode::Exclusive`].
+    #[must_use]
+    pub fn share_mode(mut self, share_mode: ShareMode) -> Self {
+        self.share_mode = share_mode;
+        self
     }
-}
-
-/// A trait for serial port devices
-///
-/// This trait is all that's necessary to implement a new serial port driver
-/// for a new platform.
-pub trait SerialPort: Send + io::Read + io::Write {
-    // Port settings getters
 
-    /// Returns the name of this port if it exists.
+    /// Set whether child processes inherit the port
     ///
-    /// This name may not be the canonical device name and instead be shorthand.
-    /// Additionally it may not exist for virtual ports.
-    fn name(&self) -> Option<String>;
-
-    /// Returns the current baud rate.
+    /// Enable this to deliberately hand the open port over to a child process, e.g. an external
+    /// flashing tool. The setting can be changed later on with `set_inheritable()` of `TTYPort`
+    /// and `COMPort`.
     ///
-    /// This may return a value different from the last specified baud rate depending on the
-    /// platform as some will return the actual device baud rate rather than the last specified
-    /// baud rate.
-    fn baud_rate(&self) -> Result<u32>;
-
-    /// Returns the character size.
+    /// On POSIX systems, the file descriptor of the port gets opened with `FD_CLOEXEC` set unless
+    /// this is enabled, so it is closed in child processes when they execute another program. On
+    /// Windows, this sets `bInheritHandle` in the `SECURITY_ATTRIBUTES` the handle gets created
+    /// with, so child processes created with handle inheritance enabled get a copy of it.
     ///
-    /// This function returns `None` if the character size could not be determined. This may occur
-    /// if the hardware is in an uninitialized state or is using a non-standard character size.
-    /// Setting a baud rate with `set_char_size()` should initialize the character size to a
-    /// supported value.
-    fn data_bits(&self) -> Result<DataBits>;
+    /// Defaults to `false`.
+    #[must_use]
+    pub fn inheritable(mut self, inheritable: bool) -> Self {
+        self.inheritable = inheritable;
+        self
+    }
 
-    /// Returns the flow control mode.
+    /// Set whether to lock the device with a UUCP lock file
     ///
-    /// This function returns `None` if the flow control mode could not be determined. This may
-    /// occur if the hardware is in an uninitialized state or is using an unsupported flow control
-    /// mode. Setting a flow control mode with `set_flow_control()` should initialize the flow
-    /// control mode to a supported value.
-    fn flow_control(&self) -> Result<FlowControl>;
-
-    /// Returns the parity-checking mode.
+    /// Legacy software like minicom or pppd marks devices in use with lock files named
+    /// `LCK..<device>` in `/var/lock` (`/var/spool/lock` on BSDs and macOS). With this enabled,
+    /// opening fails with `Busy` if a running process holds such a lock, and the port holds a
+    /// lock itself until it and all its clones are closed. Stale lock files of processes no
+    /// longer running get replaced.
     ///
-    /// This function returns `None` if the parity mode could not be determined. This may occur if
-    /// the hardware is in an uninitialized state or is using a non-standard parity mode. Setting
-    /// a parity mode with `set_parity()` should initialize the parity mode to a supported value.
-    fn parity(&self) -> Result<Parity>;
+    /// Creating the lock file requires write access to the lock directory. Defaults to `false`.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn uucp_lock(mut self, uucp_lock: bool) -> Self {
+        self.uucp_lock = uucp_lock;
+        self
+    }
 
-    /// Returns the number of stop bits.
+    /// Set whether to restore the terminal settings of the device when closing the port
     ///
-    /// This function returns `None` if the number of stop bits could not be determined. This may
-    /// occur if the hardware is in an uninitialized state or is using an unsupported stop bit
-    /// configuration. Setting the number of stop bits with `set_stop-bits()` should initialize the
-    /// stop bits to a supported value.
-    fn stop_bits(&self) -> Result<StopBits>;
-
-    /// Returns the current timeout.
-    fn timeout(&self) -> Duration;
-
-    // Port settings setters
+    /// With this enabled, the `termios` settings of the device are saved when opening it and
+    /// restored once the port and all its clones are closed. This leaves devices shared with
+    /// other software, e.g. consoles also used by getty, as they were found.
+    ///
+    /// Defaults to `false`.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn restore_settings(mut self, restore_settings: bool) -> Self {
+        self.restore_settings = restore_settings;
+        self
+    }
 
-    /// Sets the baud rate.
+    /// Set whether to open devices which reject terminal operations anyway
     ///
-    /// ## Errors
+    /// Some character devices behaving mostly like serial ports, for example some USB CDC
+    /// gadgets, `/dev/rfcomm0` or devices of proprietary drivers, reject some of the `termios`
+    /// calls used for checking and configuring the device when opening it. With this enabled,
+    /// failures of these calls and of setting DTR are ignored, so the device can still be used
+    /// for plain reads and writes. The settings of the builder may not be in effect then, and
+    /// the port is not opened for exclusive access.
     ///
-    /// If the implementation does not support the requested baud rate, this function may return an
-    /// `InvalidInput` error. Even if the baud rate is accepted by `set_baud_rate()`, it may not be
-    /// supported by the underlying hardware.
-    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()>;
-
-    /// Sets the character size.
-    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()>;
-
-    /// Sets the flow control mode.
-    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()>;
-
-    /// Sets the parity-checking mode.
-    fn set_parity(&mut self, parity: Parity) -> Result<()>;
-
-    /// Sets the number of stop bits.
-    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()>;
+    /// Defaults to `false`.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn skip_tty_checks(mut self, skip_tty_checks: bool) -> Self {
+        self.skip_tty_checks = skip_tty_checks;
+        self
+    }
 
-    /// Sets the timeout for future I/O operations.
-    ///
-    /// <div class="warning">
+    /// Set whether to retry reads, writes and waits interrupted by signals
     ///
-    /// The accuracy is limited by the underlying platform's capabilities. Longer timeouts will be
-    /// clamped to the maximum supported value which is expected to be in the magnitude of a few
-    /// days.
+    /// Blocking calls fail with `EINTR` when the thread catches a signal, e.g. from a timer or
+    /// `SIGCHLD`, unless the handler was installed with `SA_RESTART`, and waiting for the device
+    /// never gets restarted automatically. With this enabled, the port retries these calls with
+    /// the remaining part of its timeout instead of reporting `Io(Interrupted)`. Disable it for
+    /// applications which use signals to break out of blocking reads.
     ///
-    /// </div>
-    fn set_timeout(&mut self, timeout: Duration) -> Result<()>;
-
-    // Functions for setting non-data control signal pins
+    /// Defaults to `true`.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn retry_interrupted(mut self, retry_interrupted: bool) -> Self {
+        self.retry_interrupted = retry_interrupted;
+        self
+    }
 
-    /// Sets the state of the RTS (Request To Send) control signal.
+    /// Checks the settings for errors without touching any device
     ///
-    /// Setting a value of `true` asserts the RTS control signal. `false` clears the signal.
+    /// This allows for reporting configuration errors early, e.g. when loading a configuration
+    /// file. The following problems are detected:
     ///
-    /// ## Errors
+    /// * An empty path.
+    /// * A baud rate of zero. Note that `TTYPort::open` accepts this for pseudo terminals, which
+    ///   don't have a baud rate.
+    /// * Five data bits with two stop bits, which UARTs implement as one and a half stop bits
+    ///   if at all.
     ///
-    /// This function returns an error if the RTS control signal could not be set to the desired
-    /// state on the underlying hardware:
+    /// A valid configuration may still be rejected by the device when opening it.
     ///
-    /// * `NoDevice` if the device was disconnected.
+    /// ## Errors
+    ///
+    /// * `InvalidInput` describing the first problem found.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |description: &str| Err(Error::new(ErrorKind::InvalidInput, description));
+        if self.path.is_empty() {
+            return invalid("path is empty");
+        }
+        if self.baud_rate == 0 {
+            return invalid("baud rate is zero");
+        }
+        if self.data_bits == DataBits::Five && self.stop_bits == StopBits::Two {
+            return invalid("two stop bits are not supported with five data bits");
+        }
+        Ok(())
+    }
+
+    /// Returns the path to the serial port
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the baud rate in symbols-per-second
+    pub fn get_baud_rate(&self) -> u32 {
+        self.baud_rate
+    }
+
+    /// Returns the number of bits used to represent a character sent on the line
+    pub fn get_data_bits(&self) -> DataBits {
+        self.data_bits
+    }
+
+    /// Returns the type of signalling to use for controlling data transfer
+    pub fn get_flow_control(&self) -> FlowControl {
+        self.flow_control
+    }
+
+    /// Returns the type of parity to use for error checking
+    pub fn get_parity(&self) -> Parity {
+        self.parity
+    }
+
+    /// Returns the number of bits to use to signal the end of a character
+    pub fn get_stop_bits(&self) -> StopBits {
+        self.stop_bits
+    }
+
+    /// Returns the amount of time to wait to receive data before timing out
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns the inter-byte timeout, if one has been set
+    pub fn get_inter_byte_timeout(&self) -> Option<Duration> {
+        self.inter_byte_timeout
+    }
+
+    /// Returns the state to set data terminal ready (DTR) to when opening the device
+    ///
+    /// `None` means that the state is preserved, see
+    /// [`preserve_dtr_on_open`](SerialPortBuilder::preserve_dtr_on_open).
+    pub fn get_dtr_on_open(&self) -> Option<bool> {
+        self.dtr_on_open
+    }
+
+    /// Returns how to handle operations which are not supported by the port
+    pub fn get_strictness(&self) -> Strictness {
+        self.strictness
+    }
+
+    /// Returns the directions the device gets opened for
+    pub fn get_access_mode(&self) -> AccessMode {
+        self.access_mode
+    }
+
+    /// Returns whether others may open the device while it is open
+    pub fn get_share_mode(&self) -> ShareMode {
+        self.share_mode
+    }
+
+    /// Returns whether child processes inherit the port
+    pub fn get_inheritable(&self) -> bool {
+        self.inheritable
+    }
+
+    /// Returns whether the device gets locked with a UUCP lock file
+    #[cfg(unix)]
+    pub fn get_uucp_lock(&self) -> bool {
+        self.uucp_lock
+    }
+
+    /// Returns whether the terminal settings get restored when closing the port
+    #[cfg(unix)]
+    pub fn get_restore_settings(&self) -> bool {
+        self.restore_settings
+    }
+
+    /// Returns whether devices which reject terminal operations get opened anyway
+    #[cfg(unix)]
+    pub fn get_skip_tty_checks(&self) -> bool {
+        self.skip_tty_checks
+    }
+
+    /// Returns whether calls interrupted by signals get retried
+    #[cfg(unix)]
+    pub fn get_retry_interrupted(&self) -> bool {
+        self.retry_interrupted
+    }
+
+    /// Open a cross-platform interface to the port with the specified settings
+    ///
+    /// Paths referring to an emulated port, i.e. a Unix domain socket or a Windows named pipe
+    /// starting with `\\.\pipe\`, are opened as [`PipeSerialPort`]. See [`PipeSerialServer`]
+    /// for providing the device side.
+    pub fn open(self) -> Result<Box<dyn SerialPort>> {
+        #[cfg(unix)]
+        if posix::is_pipe_path(&self.path) {
+            return posix::PipeSerialPort::open(&self).map(|p| Box::new(p) as Box<dyn SerialPort>);
+        }
+
+        #[cfg(windows)]
+        if windows::is_pipe_path(&self.path) {
+            return windows::PipeSerialPort::open(&self)
+                .map(|p| Box::new(p) as Box<dyn SerialPort>);
+        }
+
+        #[cfg(unix)]
+        return posix::TTYPort::open(&self).map(|p| Box::new(p) as Box<dyn SerialPort>);
+
+        #[cfg(windows)]
+        return windows::COMPort::open(&self).map(|p| Box::new(p) as Box<dyn SerialPort>);
+
+        #[cfg(all(target_arch = "wasm32", web_sys_unstable_apis))]
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            "ports can't be opened synchronously in the browser, use WebSerialPort::open()",
+        ));
+
+        #[cfg(not(any(unix, windows, all(target_arch = "wasm32", web_sys_unstable_apis))))]
+        Err(Error::new(
+            ErrorKind::Unknown,
+            "open() not implemented for platform",
+        ))
+    }
+
+    /// Open a cross-platform interface to the port, retrying while the device is busy
+    ///
+    /// Right after a device has been plugged in, other software like ModemManager or udev rules
+    /// often hold it for a moment, or its permissions have not been set up yet. This makes up to
+    /// `attempts` attempts to open the port. The first retry happens after `backoff`, which
+    /// doubles for every further retry.
+    ///
+    /// Only errors indicating that the device is busy or not accessible yet are retried:
+    /// `EBUSY` and `EACCES` on POSIX systems and `ERROR_ACCESS_DENIED` on Windows. All other
+    /// errors are returned right away.
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// let port = serialport::new("/dev/ttyUSB0", 115_200)
+    ///     .open_with_retry(5, Duration::from_millis(100))
+    ///     .expect("Failed to open port");
+    /// # let _ = port;
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// The error from the last attempt.
+    pub fn open_with_retry(self, attempts: u32, backoff: Duration) -> Result<Box<dyn SerialPort>> {
+        let mut delay = backoff;
+        let mut remaining = attempts.max(1);
+        loop {
+            remaining -= 1;
+            match self.clone().open() {
+                Err(ref e) if remaining > 0 && is_busy(e) => {
+                    std::thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Open a cross-platform interface to the port, giving up after `timeout`
+    ///
+    /// Opening and initially configuring a device may hang for a long time, for example with
+    /// some flaky USB adapters. This opens the port on a separate thread and stops waiting for it
+    /// after `timeout`. If the thread still manages to open the port afterwards, it closes it
+    /// right away.
+    ///
+    /// ## Errors
+    ///
+    /// * `Timeout` if the port could not be opened within `timeout`.
+    /// * Any error from [`open`](SerialPortBuilder::open).
+    ///
+    /// ## Panics
+    ///
+    /// A panic while opening the port within `timeout` is propagated to the caller.
+    pub fn open_timeout(self, timeout: Duration) -> Result<Box<dyn SerialPort>> {
+        use std::sync::mpsc::RecvTimeoutError;
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let opening = std::thread::Builder::new()
+            .name("serialport-open".to_string())
+            .spawn(move || {
+                // The receiver is gone if the timeout has passed, which drops the port.
+                let _ = sender.send(self.open());
+            })?;
+        match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                Err(Error::new(ErrorKind::Timeout, "Operation timed out"))
+            }
+            // The sender only goes away without a result if opening the port panicked.
+            Err(RecvTimeoutError::Disconnected) => match opening.join() {
+                Err(panic) => std::panic::resume_unwind(panic),
+                Ok(()) => unreachable!("the port was opened without sending the result"),
+            },
+        }
+    }
+
+    /// Open a cross-platform interface to a modem-style device once it detects a carrier
+    ///
+    /// The port gets opened like with [`open`](SerialPortBuilder::open). Afterwards, this waits
+    /// up to `timeout` for the carrier detect (DCD) line to become asserted. On POSIX systems,
+    /// the modem control lines are no longer ignored (`CLOCAL` is cleared), so reads fail with
+    /// `NoDevice` once the carrier gets lost.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(NotConnected)` if no carrier was detected within `timeout`. The port gets closed
+    ///   in this case.
+    /// * Any error from opening the port or reading the carrier detect line.
+    pub fn open_wait_for_carrier(self, timeout: Duration) -> Result<Box<dyn SerialPort>> {
+        #[cfg(unix)]
+        return posix::TTYPort::open_wait_for_carrier(&self, timeout)
+            .map(|p| Box::new(p) as Box<dyn SerialPort>);
+
+        #[cfg(windows)]
+        return windows::COMPort::open_wait_for_carrier(&self, timeout)
+            .map(|p| Box::new(p) as Box<dyn SerialPort>);
+
+        #[cfg(not(any(unix, windows)))]
+        Err(Error::new(
+            ErrorKind::Unknown,
+            "open_wait_for_carrier() not implemented for platform",
+        ))
+    }
+
+    /// Open a platform-specific interface to the port with the specified settings
+    #[cfg(unix)]
+    pub fn open_native(self) -> Result<TTYPort> {
+        posix::TTYPort::open(&self)
+    }
+
+    /// Open a platform-specific interface to the port with the specified settings
+    #[cfg(windows)]
+    pub fn open_native(self) -> Result<COMPort> {
+        windows::COMPort::open(&self)
+    }
+}
+
+/// A trait for serial port devices
+///
+/// This trait is all that's necessary to implement a new serial port driver
+/// for a new platform.
+pub trait SerialPort: Send + io::Read + io::Write {
+    // Port settings getters
+
+    /// Returns the name of this port if it exists.
+    ///
+    /// This name may not be the canonical device name and instead be shorthand.
+    /// Additionally it may not exist for virtual ports.
+    fn name(&self) -> Option<String>;
+
+    /// Returns the current baud rate.
+    ///
+    /// This may return a value different from the last specified baud rate depending on the
+    /// platform as some will return the actual device baud rate rather than the last specified
+    /// baud rate.
+    fn baud_rate(&self) -> Result<u32>;
+
+    /// Returns the character size.
+    ///
+    /// This function returns `None` if the character size could not be determined. This may occur
+    /// if the hardware is in an uninitialized state or is using a non-standard character size.
+    /// Setting a baud rate with `set_char_size()` should initialize the character size to a
+    /// supported value.
+    fn data_bits(&self) -> Result<DataBits>;
+
+    /// Returns the flow control mode.
+    ///
+    /// This function returns `None` if the flow control mode could not be determined. This may
+    /// occur if the hardware is in an uninitialized state or is using an unsupported flow control
+    /// mode. Setting a flow control mode with `set_flow_control()` should initialize the flow
+    /// control mode to a supported value.
+    fn flow_control(&self) -> Result<FlowControl>;
+
+    /// Returns the parity-checking mode.
+    ///
+    /// This function returns `None` if the parity mode could not be determined. This may occur if
+    /// the hardware is in an uninitialized state or is using a non-standard parity mode. Setting
+    /// a parity mode with `set_parity()` should initialize the parity mode to a supported value.
+    fn parity(&self) -> Result<Parity>;
+
+    /// Returns the number of stop bits.
+    ///
+    /// This function returns `None` if the number of stop bits could not be determined. This may
+    /// occur if the hardware is in an uninitialized state or is using an unsupported stop bit
+    /// configuration. Setting the number of stop bits with `set_stop-bits()` should initialize the
+    /// stop bits to a supported value.
+    fn stop_bits(&self) -> Result<StopBits>;
+
+    /// Returns the current timeout.
+    fn timeout(&self) -> Duration;
+
+    /// Returns the current inter-byte timeout.
+    ///
+    /// `None` indicates that reads return as soon as any data is available.
+    fn inter_byte_timeout(&self) -> Result<Option<Duration>> {
+        Ok(None)
+    }
+
+    /// Returns all settings of the port at once.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the error from the first setting which could not be read, e.g. a baud rate which
+    /// has been configured by other means and can't be represented.
+    fn try_settings(&self) -> Result<PortSettings> {
+        PortSettings::from_port(self)
+    }
+
+    /// Returns all settings of the port which can be read, along with the ones which can't.
+    ///
+    /// Unlike [`try_settings`](SerialPort::try_settings), this never fails. It is meant for
+    /// ports which have been configured by other applications, possibly with settings which are
+    /// not supported by this library.
+    fn settings_lossy(&self) -> LossySettings {
+        PortSettings::from_port_lossy(self)
+    }
+
+    /// Returns a builder with the current settings of the port.
+    ///
+    /// The builder's path is the name of the port and it covers the same settings as
+    /// [`try_settings`](SerialPort::try_settings). All other settings have the defaults set by
+    /// [`new`]. This allows for reopening a port with the same configuration after it got
+    /// disconnected, or for opening another port with the same configuration. The path is empty
+    /// for ports without a name, like the master of a pseudo terminal pair.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the error from [`try_settings`](SerialPort::try_settings).
+    fn to_builder(&self) -> Result<SerialPortBuilder> {
+        let settings = self.try_settings()?;
+        Ok(settings.to_builder(self.name().unwrap_or_default()))
+    }
+
+    // Port settings setters
+
+    /// Sets the baud rate.
+    ///
+    /// ## Errors
+    ///
+    /// If the implementation does not support the requested baud rate, this function may return an
+    /// `InvalidInput` error. Even if the baud rate is accepted by `set_baud_rate()`, it may not be
+    /// supported by the underlying hardware.
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()>;
+
+    /// Sets the character size.
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()>;
+
+    /// Sets the flow control mode.
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()>;
+
+    /// Sets the parity-checking mode.
+    fn set_parity(&mut self, parity: Parity) -> Result<()>;
+
+    /// Sets the number of stop bits.
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()>;
+
+    /// Sets the timeout for future I/O operations.
+    ///
+    /// <div class="warning">
+    ///
+    /// The accuracy is limited by the underlying platform's capabilities. Longer timeouts will be
+    /// clamped to the maximum supported value which is expected to be in the magnitude of a few
+    /// days.
+    ///
+    /// </div>
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()>;
+
+    /// Sets the inter-byte timeout for future reads.
+    ///
+    /// When set, a read returns once the line stayed idle for the given amount of time after at
+    /// least one byte has been received. Passing `None` restores returning as soon as any data is
+    /// available.
+    ///
+    /// <div class="warning">
+    ///
+    /// The accuracy is limited by the underlying platform's capabilities. POSIX systems only
+    /// support a resolution of 100 ms and a maximum of 25.5 s. The value gets rounded up to the
+    /// next supported one.
+    ///
+    /// </div>
+    ///
+    /// ## Errors
+    ///
+    /// The default implementation returns an `Unsupported` error for ports not supporting an
+    /// inter-byte timeout.
+    fn set_inter_byte_timeout(&mut self, inter_byte_timeout: Option<Duration>) -> Result<()> {
+        let _ = inter_byte_timeout;
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "inter-byte timeout not supported by this port",
+        ))
+    }
+
+    /// Applies all settings from `settings` at once.
+    ///
+    /// This covers the baud rate, data bits, flow control, parity, stop bits, timeout and
+    /// inter-byte timeout. The path and the DTR state from `settings` are ignored.
+    ///
+    /// Either all settings are applied or none of them: if applying any setting fails, the
+    /// previous settings get restored before returning the error.
+    ///
+    /// ## Errors
+    ///
+    /// Returns the error from applying the settings. Its description states whether the port
+    /// settings were left unchanged or whether restoring the previous settings failed too.
+    #[doc(alias = "reconfigure")]
+    fn apply_settings(&mut self, settings: &SerialPortBuilder) -> Result<()> {
+        settings::apply_with_rollback(self, settings)
+    }
+
+    // Functions for setting non-data control signal pins
+
+    /// Sets the state of the RTS (Request To Send) control signal.
+    ///
+    /// Setting a value of `true` asserts the RTS control signal. `false` clears the signal.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the RTS control signal could not be set to the desired
+    /// state on the underlying hardware:
+    ///
+    /// * `NoDevice` if the device was disconnected.
     /// * `Io` for any other type of I/O error.
     fn write_request_to_send(&mut self, level: bool) -> Result<()>;
 
@@ -639,6 +1483,184 @@ pub trait SerialPort: Send + io::Read + io::Write {
     /// * `Io` for any other type of I/O error.
     fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()>;
 
+    /// Returns the counters for errors the serial driver detected on the line.
+    ///
+    /// ## Errors
+    ///
+    /// The default implementation returns an `Unsupported` error for ports not counting
+    /// line errors. Besides that, this function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn line_errors(&self) -> Result<LineErrors> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "line error counters not supported by this port",
+        ))
+    }
+
+    /// Returns and clears the error conditions the serial driver detected on the line since the
+    /// last call.
+    ///
+    /// Like [`TcpStream::take_error`](std::net::TcpStream::take_error), this reports conditions
+    /// such as overruns, framing or parity errors which don't fail any read on their own. The
+    /// first call reports the conditions detected since the port has been opened.
+    ///
+    /// ## Errors
+    ///
+    /// The default implementation returns an `Unsupported` error for ports not detecting
+    /// line errors. Besides that, this function may return the following errors:
+    ///
+    /// * `NoDevice` if the device was disconnected.
+    /// * `Io` for any other type of I/O error.
+    fn take_error(&self) -> Result<Option<Error>> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "line error detection not supported by this port",
+        ))
+    }
+
+    /// Returns statistics about the reads and writes on this port.
+    ///
+    /// This is meant for long-running applications monitoring the throughput and health of their
+    /// connections.
+    ///
+    /// ## Errors
+    ///
+    /// The default implementation returns an `Unsupported` error for ports not counting
+    /// their I/O operations.
+    fn stats(&self) -> Result<PortStats> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "statistics not supported by this port",
+        ))
+    }
+
+    /// Returns whether the device behind this port is still present.
+    ///
+    /// This is a cheap check without any I/O on the line, meant for detecting unplugged
+    /// adapters without waiting for the next read or write to fail.
+    ///
+    /// ## Errors
+    ///
+    /// The default implementation returns an `Unsupported` error for ports not able to check
+    /// their device.
+    fn is_connected(&self) -> Result<bool> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "connection check not supported by this port",
+        ))
+    }
+
+    /// Returns the token for cancelling blocking operations of this port from another thread.
+    ///
+    /// Reads and writes waiting for the device fail with a `Cancelled` error as soon as the
+    /// token gets cancelled, instead of waiting for the timeout. Later calls return the same
+    /// token, which is shared with the clones of the port.
+    ///
+    /// ## Errors
+    ///
+    /// The default implementation returns an `Unsupported` error for ports which can't be
+    /// cancelled.
+    fn cancel_token(&self) -> Result<CancelToken> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "cancelling not supported by this port",
+        ))
+    }
+
+    /// Reads like [`io::Read::read`], but fails with a `Cancelled` error as soon as `cancel` gets
+    /// cancelled.
+    ///
+    /// Unlike the token of [`cancel_token`](SerialPort::cancel_token), `cancel` can be any
+    /// token, e.g. one shared by all ports of an application and cancelled from its Ctrl-C
+    /// handler. The token of the port still applies as well.
+    ///
+    /// The default implementation only checks `cancel` before reading, so the read may still
+    /// block up to the timeout of the port.
+    fn read_cancellable(&mut self, buf: &mut [u8], cancel: &CancelToken) -> io::Result<usize> {
+        cancel.check()?;
+        self.read(buf)
+    }
+
+    /// Writes like [`io::Write::write`], but fails with a `Cancelled` error as soon as `cancel`
+    /// gets cancelled.
+    ///
+    /// See [`read_cancellable`](SerialPort::read_cancellable) for the token. The default
+    /// implementation only checks `cancel` before writing.
+    fn write_cancellable(&mut self, buf: &[u8], cancel: &CancelToken) -> io::Result<usize> {
+        cancel.check()?;
+        self.write(buf)
+    }
+
+    /// Flushes like [`io::Write::flush`], but fails with a `Cancelled` error as soon as `cancel`
+    /// gets cancelled while waiting for the output buffer to drain.
+    ///
+    /// See [`read_cancellable`](SerialPort::read_cancellable) for the token. The default
+    /// implementation only checks `cancel` before flushing.
+    fn flush_cancellable(&mut self, cancel: &CancelToken) -> io::Result<()> {
+        cancel.check()?;
+        self.flush()
+    }
+
+    /// Reads like [`io::Read::read`], but into memory which doesn't have to be initialized, and
+    /// returns the part of `buf` holding the data read.
+    ///
+    /// This stands in for `io::Read::read_buf`, which is not stable yet. It allows reading into
+    /// spare capacity, e.g. of a `Vec` or a `BytesMut`, without zeroing it first. See
+    /// [`SerialPortExt::read_to_spare`] for appending to a `Vec`. The returned slice always
+    /// starts at the beginning of `buf`.
+    ///
+    /// The default implementation zeroes `buf` before reading into it. Native ports read into
+    /// it directly.
+    fn read_uninit<'a>(&mut self, buf: &'a mut [MaybeUninit<u8>]) -> io::Result<&'a mut [u8]> {
+        for byte in buf.iter_mut() {
+            *byte = MaybeUninit::new(0);
+        }
+        // All bytes have been initialized above.
+        let buf = unsafe { &mut *(buf as *mut [MaybeUninit<u8>] as *mut [u8]) };
+        let len = self.read(buf)?;
+        Ok(&mut buf[..len])
+    }
+
+    /// Waits until data can be read from the port without blocking, but doesn't read it.
+    ///
+    /// This allows to wait for the device without consuming any data, e.g. for buffering layers
+    /// deciding how much to read or loops serving several ports. The wait also ends with a
+    /// `Cancelled` error when the token of the port gets cancelled.
+    ///
+    /// ## Errors
+    ///
+    /// * `Timeout` if no data arrived within `timeout`.
+    /// * `NoDevice` if the device has been disconnected.
+    /// * `Unsupported` for ports not able to wait for data, which is the default.
+    fn wait_readable(&self, timeout: Duration) -> Result<()> {
+        let _ = timeout;
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "waiting for readiness not supported by this port",
+        ))
+    }
+
+    /// Waits until data can be written to the port without blocking, but doesn't write any.
+    ///
+    /// This is the case as soon as the output buffer has space left, which allows to hold
+    /// data back while the device is busy, e.g. with hardware flow control. The wait also ends
+    /// with a `Cancelled` error when the token of the port gets cancelled.
+    ///
+    /// ## Errors
+    ///
+    /// * `Timeout` if no space became available within `timeout`.
+    /// * `NoDevice` if the device has been disconnected.
+    /// * `Unsupported` for ports not able to wait for buffer space, which is the default.
+    fn wait_writable(&self, timeout: Duration) -> Result<()> {
+        let _ = timeout;
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "waiting for readiness not supported by this port",
+        ))
+    }
+
     // Misc methods
 
     /// Attempts to clone the `SerialPort`. This allow you to write and read simultaneously from the
@@ -646,9 +1668,11 @@ pub trait SerialPort: Send + io::Read + io::Write {
     /// should look at [mio-serial](https://crates.io/crates/mio-serial) or
     /// [tokio-serial](https://crates.io/crates/tokio-serial).
     ///
-    /// Also, you must be very careful when changing the settings of a cloned `SerialPort` : since
-    /// the settings are cached on a per object basis, trying to modify them from two different
-    /// objects can cause some nasty behavior.
+    /// The native ports share the settings applying to the device with their clones, so changes
+    /// made through one of them are reflected by all others. Settings like the timeout may still
+    /// be kept per clone, depending on the platform. For sharing a single port between threads
+    /// instead, the native ports implement `Sync` and `io::Read` and `io::Write` for shared
+    /// references.
     ///
     /// # Errors
     ///
@@ -691,6 +1715,22 @@ impl<T: SerialPort> SerialPort for &mut T {
         (**self).timeout()
     }
 
+    fn inter_byte_timeout(&self) -> Result<Option<Duration>> {
+        (**self).inter_byte_timeout()
+    }
+
+    fn try_settings(&self) -> Result<PortSettings> {
+        (**self).try_settings()
+    }
+
+    fn settings_lossy(&self) -> LossySettings {
+        (**self).settings_lossy()
+    }
+
+    fn to_builder(&self) -> Result<SerialPortBuilder> {
+        (**self).to_builder()
+    }
+
     fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
         (**self).set_baud_rate(baud_rate)
     }
@@ -715,6 +1755,14 @@ impl<T: SerialPort> SerialPort for &mut T {
         (**self).set_timeout(timeout)
     }
 
+    fn set_inter_byte_timeout(&mut self, inter_byte_timeout: Option<Duration>) -> Result<()> {
+        (**self).set_inter_byte_timeout(inter_byte_timeout)
+    }
+
+    fn apply_settings(&mut self, settings: &SerialPortBuilder) -> Result<()> {
+        (**self).apply_settings(settings)
+    }
+
     fn write_request_to_send(&mut self, level: bool) -> Result<()> {
         (**self).write_request_to_send(level)
     }
@@ -751,6 +1799,50 @@ impl<T: SerialPort> SerialPort for &mut T {
         (**self).clear(buffer_to_clear)
     }
 
+    fn line_errors(&self) -> Result<LineErrors> {
+        (**self).line_errors()
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        (**self).take_error()
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        (**self).stats()
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        (**self).is_connected()
+    }
+
+    fn cancel_token(&self) -> Result<CancelToken> {
+        (**self).cancel_token()
+    }
+
+    fn read_cancellable(&mut self, buf: &mut [u8], cancel: &CancelToken) -> io::Result<usize> {
+        (**self).read_cancellable(buf, cancel)
+    }
+
+    fn write_cancellable(&mut self, buf: &[u8], cancel: &CancelToken) -> io::Result<usize> {
+        (**self).write_cancellable(buf, cancel)
+    }
+
+    fn flush_cancellable(&mut self, cancel: &CancelToken) -> io::Result<()> {
+        (**self).flush_cancellable(cancel)
+    }
+
+    fn read_uninit<'a>(&mut self, buf: &'a mut [MaybeUninit<u8>]) -> io::Result<&'a mut [u8]> {
+        (**self).read_uninit(buf)
+    }
+
+    fn wait_readable(&self, timeout: Duration) -> Result<()> {
+        (**self).wait_readable(timeout)
+    }
+
+    fn wait_writable(&self, timeout: Duration) -> Result<()> {
+        (**self).wait_writable(timeout)
+    }
+
     fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
         (**self).try_clone()
     }
@@ -792,7 +1884,7 @@ impl fmt::Debug for dyn SerialPort {
 }
 
 /// Contains all possible USB information about a `SerialPort`
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UsbPortInfo {
     /// Vendor ID
@@ -808,12 +1900,55 @@ pub struct UsbPortInfo {
     /// The interface index of the USB serial port. This can be either the interface number of
     /// the communication interface (as is the case on Windows and Linux) or the data
     /// interface (as is the case on macOS), so you should recognize both interface numbers.
-    #[cfg(feature = "usbportinfo-interface")]
     pub interface: Option<u8>,
+    /// The physical location of the USB device, identifying the port it is plugged into. This
+    /// is the bus and port path on Linux (e.g. `1-1.4.2`), the location ID on macOS (e.g.
+    /// `0x14100000`) and the location path on Windows (e.g.
+    /// `PCIROOT(0)#PCI(1400)#USBROOT(0)#USB(4)#USB(2)`). The location stays the same as long as
+    /// the device is plugged into the same port, but the format is platform specific.
+    pub location: Option<String>,
+    /// The device release number (`bcdDevice`) in binary-coded decimal, e.g. `0x0600` for
+    /// release 6.00. Chip vendors use it for telling apart revisions sharing the same product ID.
+    pub bcd_device: Option<u16>,
+    /// The class codes of the USB device. Composite devices report a class of zero here and
+    /// declare the actual classes per interface.
+    pub device_class: Option<UsbClass>,
+    /// The class codes of the USB interface providing the serial port
+    pub interface_class: Option<UsbClass>,
+}
+
+/// The class, subclass and protocol codes of a USB device or interface
+///
+/// The codes are assigned by the USB-IF. For example, the communication interface of a CDC-ACM
+/// device has class `0x02` and subclass `0x02`, while vendor specific USB-to-serial bridges
+/// usually report class `0xFF`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UsbClass {
+    /// Class code
+    pub class: u8,
+    /// Subclass code
+    pub subclass: u8,
+    /// Protocol code
+    pub protocol: u8,
+}
+
+/// Contains information about the remote device of a Bluetooth serial port
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BluetoothPortInfo {
+    /// The address of the remote device in upper case, e.g. `00:11:22:AA:BB:CC`
+    ///
+    /// This is available once the port is bound to a device.
+    pub address: Option<String>,
+    /// The name of the remote device, e.g. `HC-05`
+    ///
+    /// This is only available on macOS and Windows.
+    pub name: Option<String>,
 }
 
 /// The physical type of a `SerialPort`
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SerialPortType {
     /// The serial port is connected via USB
@@ -827,6 +1962,9 @@ pub enum SerialPortType {
 }
 
 /// A device-independent implementation of serial port information
+///
+/// Ports are ordered naturally by their names, i.e. numbers within the names are compared by
+/// their value. So `COM2` comes before `COM10` and `/dev/ttyUSB2` comes before `/dev/ttyUSB10`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SerialPortInfo {
@@ -834,6 +1972,120 @@ pub struct SerialPortInfo {
     pub port_name: String,
     /// The hardware device type that exposes this port
     pub port_type: SerialPortType,
+    /// The name of the port as shown in the Device Manager, e.g. `USB Serial Port (COM7)`
+    ///
+    /// This is only available on Windows.
+    pub friendly_name: Option<String>,
+    /// The product description reported by the device on its bus, e.g. `FT232R USB UART`
+    ///
+    /// This is only available on Windows.
+    pub description: Option<String>,
+    /// The device instance ID, e.g. `USB\VID_0403&PID_6001\A5002kMh`
+    ///
+    /// The ID identifies the device towards the Windows device management APIs and stays the
+    /// same as long as the device's serial number, or for devices without one, the port it is
+    /// plugged into stays the same. This is only available on Windows.
+    pub instance_id: Option<String>,
+    /// The other device node for the same port
+    ///
+    /// On macOS every port shows up twice, as a callout device (`/dev/cu.*`) and as a dialin
+    /// device (`/dev/tty.*`). This names the respective other one, e.g. `/dev/cu.usbserial-1410`
+    /// for `/dev/tty.usbserial-1410`. Most applications want to open the callout device, as
+    /// opening the dialin device blocks until the carrier detect line gets asserted. This is only
+    /// available on macOS.
+    pub counterpart: Option<String>,
+    /// The stable symlink udev creates for the port in `/dev/serial/by-id`, e.g.
+    /// `/dev/serial/by-id/usb-FTDI_FT232R_USB_UART_A5002kMh-if00-port0`
+    ///
+    /// The name is derived from the USB device's identity, so it stays the same when the
+    /// kernel assigns another `/dev/ttyUSB{N}` to the device. This is only available on Linux.
+    pub by_id: Option<String>,
+    /// The stable symlink udev creates for the port in `/dev/serial/by-path`, e.g.
+    /// `/dev/serial/by-path/pci-0000:00:14.0-usb-0:2:1.0-port0`
+    ///
+    /// The name is derived from the port the device is plugged into, so it stays the same for
+    /// identical devices without serial numbers. This is only available on Linux.
+    pub by_path: Option<String>,
+    /// The remote device of a Bluetooth serial port
+    ///
+    /// This is available for ports of type [`SerialPortType::BluetoothPort`] on Linux, macOS and
+    /// Windows.
+    pub bluetooth: Option<BluetoothPortInfo>,
+}
+
+impl PartialOrd for SerialPortInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SerialPortInfo {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Fall back to comparing names byte by byte for names differing only in leading zeros
+        // and to the remaining fields for keeping the order consistent with `Eq`.
+        #[allow(unused_mut)]
+        let mut ordering = natural_cmp(&self.port_name, &other.port_name)
+            .then_with(|| self.port_name.cmp(&other.port_name))
+            .then_with(|| self.port_type.cmp(&other.port_type));
+        {
+            ordering = ordering
+                .then_with(|| self.friendly_name.cmp(&other.friendly_name))
+                .then_with(|| self.description.cmp(&other.description));
+        }
+        {
+            ordering = ordering.then_with(|| self.instance_id.cmp(&other.instance_id));
+        }
+        {
+            ordering = ordering.then_with(|| self.counterpart.cmp(&other.counterpart));
+        }
+        {
+            ordering = ordering
+                .then_with(|| self.by_id.cmp(&other.by_id))
+                .then_with(|| self.by_path.cmp(&other.by_path));
+        }
+        {
+            ordering = ordering.then_with(|| self.bluetooth.cmp(&other.bluetooth));
+        }
+        ordering
+    }
+}
+
+/// Compares two strings with runs of digits compared by their numerical value
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (a_digits, b_digits) = (digit_run(a), digit_run(b));
+        if !a_digits.is_empty() && !b_digits.is_empty() {
+            let a_value = a_digits.trim_start_matches('0');
+            let b_value = b_digits.trim_start_matches('0');
+            let ordering = a_value
+                .len()
+                .cmp(&b_value.len())
+                .then_with(|| a_value.cmp(b_value));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            a = &a[a_digits.len()..];
+            b = &b[b_digits.len()..];
+            continue;
+        }
+
+        let mut a_chars = a.chars();
+        let mut b_chars = b.chars();
+        match (a_chars.next(), b_chars.next()) {
+            (Some(a_char), Some(b_char)) if a_char == b_char => {
+                a = a_chars.as_str();
+                b = b_chars.as_str();
+            }
+            (a_char, b_char) => return a_char.cmp(&b_char),
+        }
+    }
+}
+
+/// Returns the leading ASCII digits of `s`
+fn digit_run(s: &str) -> &str {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    &s[..end]
 }
 
 /// Construct a builder of `SerialPort` objects
@@ -855,28 +2107,275 @@ pub fn new<'a>(path: impl Into<std::borrow::Cow<'a, str>>, baud_rate: u32) -> Se
         parity: Parity::None,
         stop_bits: StopBits::One,
         timeout: Duration::from_millis(0),
+        inter_byte_timeout: None,
         // By default, set DTR when opening the device. There are USB devices performing "wait for
         // DTR" before sending any data and users stumbled over this multiple times (see issues #29
         // and #204). We are expecting little to no negative consequences from setting DTR by
         // default but less hassle for users.
         dtr_on_open: Some(true),
+        access_mode: AccessMode::ReadWrite,
+        share_mode: ShareMode::Exclusive,
+        inheritable: false,
+        #[cfg(unix)]
+        uucp_lock: false,
+        #[cfg(unix)]
+        restore_settings: false,
+        #[cfg(unix)]
+        skip_tty_checks: false,
+        #[cfg(unix)]
+        retry_interrupted: true,
+        strictness: Strictness::Strict,
+    }
+}
+
+/// Returns whether opening a port failed because the device is busy or not accessible yet
+fn is_busy(error: &Error) -> bool {
+    #[cfg(unix)]
+    return posix::is_busy(error);
+
+    #[cfg(windows)]
+    return windows::is_busy(error);
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = error;
+        false
+    }
+}
+
+/// Construct a builder from a port path followed by a compact mode string
+///
+/// The path and the mode are separated by the last colon, e.g. `/dev/ttyUSB0:115200,8N1` or
+/// `COM3:9600`. The mode has to contain a baud rate and may contain a framing, see
+/// [`SerialPortBuilder::parse_mode`]. All other settings are the same as with [`new`].
+///
+/// ```
+/// use serialport::Parity;
+///
+/// let builder = serialport::new_from_str("/dev/ttyUSB0:115200,8E1").unwrap();
+/// assert_eq!(
+///     builder,
+///     serialport::new("/dev/ttyUSB0", 115_200).parity(Parity::Even)
+/// );
+/// ```
+///
+/// ## Errors
+///
+/// * `InvalidInput` if `s` does not consist of a path and a valid mode string with a baud rate.
+pub fn new_from_str(s: &str) -> Result<SerialPortBuilder> {
+    let invalid = |reason: &str| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid port specification {:?}: {}", s, reason),
+        )
+    };
+
+    let (path, mode) = s.rsplit_once(':').ok_or_else(|| invalid("missing mode"))?;
+    if path.is_empty() {
+        return Err(invalid("missing path"));
+    }
+    let baud_rate = mode.split(',').next().unwrap_or_default().trim();
+    if baud_rate.is_empty() || !baud_rate.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid("missing baud rate"));
     }
+    new(path, 0).parse_mode(mode)
+}
+
+/// Creates two serial ports connected to each other like a null-modem cable
+///
+/// Data written to one port can be read from the other. This is meant for tests which need a
+/// connected pair of ports without any hardware or driver setup.
+///
+/// On POSIX platforms, this is a pseudo-terminal pair as created by
+/// `TTYPort::pair`. On Windows, where there are no built-in virtual serial
+/// ports, this is an in-process [`MockPort`](crate::mock::MockPort) pair.
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use std::time::Duration;
+///
+/// let (mut first, mut second) = serialport::virtual_pair().expect("Failed to create ports");
+/// second.set_timeout(Duration::from_secs(1)).unwrap();
+///
+/// first.write_all(b"hello").unwrap();
+/// let mut buf = [0u8; 5];
+/// second.read_exact(&mut buf).unwrap();
+/// assert_eq!(&buf, b"hello");
+/// ```
+pub fn virtual_pair() -> Result<(Box<dyn SerialPort>, Box<dyn SerialPort>)> {
+    #[cfg(unix)]
+    let (first, second) = posix::TTYPort::pair()?;
+    #[cfg(not(unix))]
+    let (first, second) = mock::MockPort::pair();
+    Ok((Box::new(first), Box::new(second)))
 }
 
 /// Returns a list of all serial ports on system
 ///
 /// It is not guaranteed that these ports exist or are available even if they're
-/// returned by this function.
+/// returned by this function. The ports are sorted naturally by their names, see
+/// [`SerialPortInfo`].
+///
+/// Devices whose information can't be read completely don't fail the enumeration. They are
+/// reported with as much information as could be read, or left out if not even their name could be
+/// determined. Use [`available_ports_with_warnings`] for learning about them.
 pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+    available_ports_with_warnings().map(|enumeration| enumeration.ports)
+}
+
+/// Returns a list of all serial ports on system without the details which are slow to look up
+///
+/// The ports are reported with their names and types, but without the information which takes a
+/// while to query for every device. On Windows, USB ports only carry the vendor ID, product ID
+/// and serial number contained in their device instance IDs. On macOS, USB ports only carry their
+/// vendor and product ID. On other platforms, all information is cheap to look up, so this is the
+/// same as [`available_ports`].
+///
+/// This is meant for applications polling for ports frequently, e.g. for filling a dropdown.
+pub fn available_ports_lossy() -> Result<Vec<SerialPortInfo>> {
     #[cfg(unix)]
-    return crate::posix::available_ports();
+    let ports = crate::posix::available_ports_lossy(&mut Vec::new());
 
     #[cfg(windows)]
-    return crate::windows::available_ports();
+    let ports = crate::windows::available_ports_lossy(&mut Vec::new());
 
     #[cfg(not(any(unix, windows)))]
-    Err(Error::new(
+    let ports: Result<Vec<SerialPortInfo>> = Err(Error::new(
         ErrorKind::Unknown,
         "available_ports() not implemented for platform",
-    ))
+    ));
+
+    ports.map(|mut ports| {
+        ports.sort();
+        ports
+    })
+}
+
+/// Returns a list of all serial ports on system along with warnings about devices whose
+/// information could not be read
+///
+/// ```no_run
+/// let enumeration = serialport::available_ports_with_warnings().expect("Failed to list ports");
+/// for port in &enumeration.ports {
+///     println!("{}", port.port_name);
+/// }
+/// for warning in &enumeration.warnings {
+///     eprintln!("Warning: {}", warning);
+/// }
+/// ```
+pub fn available_ports_with_warnings() -> Result<PortEnumeration> {
+    let mut warnings = Vec::new();
+
+    #[cfg(unix)]
+    let ports = crate::posix::available_ports(&mut warnings);
+
+    #[cfg(windows)]
+    let ports = crate::windows::available_ports(&mut warnings);
+
+    #[cfg(not(any(unix, windows)))]
+    let ports: Result<Vec<SerialPortInfo>> = Err(Error::new(
+        ErrorKind::Unknown,
+        "available_ports() not implemented for platform",
+    ));
+
+    ports.map(|mut ports| {
+        ports.sort();
+        PortEnumeration { ports, warnings }
+    })
+}
+
+/// The serial ports found on the system, see [`available_ports_with_warnings`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortEnumeration {
+    /// The serial ports, as returned by [`available_ports`]
+    pub ports: Vec<SerialPortInfo>,
+    /// Problems with individual devices encountered while looking up the ports
+    pub warnings: Vec<EnumerationWarning>,
+}
+
+/// A problem with an individual device encountered while enumerating serial ports
+///
+/// The device is either reported with incomplete information or, if not even its name could be
+/// determined, left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumerationWarning {
+    /// The device the warning is about, e.g. its device file, its sysfs directory or its device
+    /// instance ID on Windows
+    pub device: String,
+    /// A description of the problem
+    pub description: String,
+}
+
+impl EnumerationWarning {
+    pub(crate) fn new<D: Into<String>, T: Into<String>>(device: D, description: T) -> Self {
+        EnumerationWarning {
+            device: device.into(),
+            description: description.into(),
+        }
+    }
+}
+
+impl fmt::Display for EnumerationWarning {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}: {}", self.device, self.description)
+    }
+}
+
+/// Returns the serial ports on the system matching `filter`
+///
+/// This is a shorthand for filtering the result of [`available_ports`] with
+/// [`PortFilter::matches`].
+///
+/// ```no_run
+/// use serialport::PortFilter;
+///
+/// let filter = PortFilter::new().vid(0x0403).manufacturer("FTDI");
+/// for port in serialport::available_ports_filtered(&filter).expect("Failed to list ports") {
+///     println!("{}", port.port_name);
+/// }
+/// ```
+pub fn available_ports_filtered(filter: &PortFilter) -> Result<Vec<SerialPortInfo>> {
+    let mut ports = available_ports()?;
+    ports.retain(|port| filter.matches(port));
+    Ok(ports)
+}
+
+/// Returns the serial port of the USB device with the given serial number
+///
+/// The comparison takes care of the differences in how platforms report serial numbers. See
+/// [`PortFilter::serial_number`] for details.
+///
+/// ## Errors
+///
+/// * `NoDevice` if there is no port with the given serial number.
+/// * `InvalidInput` if multiple ports have the given serial number, for example because the
+///   device provides multiple serial interfaces. The error lists the candidates.
+/// * Any error from enumerating the available ports.
+pub fn find_port_by_serial_number(serial_number: &str) -> Result<SerialPortInfo> {
+    let ports = available_ports_filtered(&PortFilter::new().serial_number(serial_number))?;
+    filter::single_port(ports, &format!("serial number {}", serial_number))
+}
+
+/// Opens the serial port of the USB device with the given vendor and product ID
+///
+/// The port is opened with the given baud rate and the defaults from [`new`]. This is a
+/// shorthand for looking up the port with [`available_ports_filtered`] and opening it.
+///
+/// ```no_run
+/// let port = serialport::open_usb(0x0403, 0x6001, 115_200).expect("Failed to open port");
+/// # let _ = port;
+/// ```
+///
+/// ## Errors
+///
+/// * `NoDevice` if there is no port with the given IDs.
+/// * `InvalidInput` if multiple ports have the given IDs, for example because multiple such
+///   devices are connected. The error lists the candidates.
+/// * Any error from enumerating the available ports or opening the port.
+pub fn open_usb(vid: u16, pid: u16, baud_rate: u32) -> Result<Box<dyn SerialPort>> {
+    let ports = available_ports_filtered(&PortFilter::new().vid(vid).pid(pid))?;
+    let port = filter::single_port(ports, &format!("USB ID {:04x}:{:04x}", vid, pid))?;
+    new(port.port_name, baud_rate).open()
 }
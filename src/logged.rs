@@ -0,0 +1,417 @@
+//! Logging the traffic of serial ports
+//!
+//! A [`LoggedPort`] wraps another port and logs the data going through it as hex dumps along
+//! with all control operations through the [`log`] facade. This module is only available with
+//! the `log` feature enabled.
+//!
+//! ```no_run
+//! use std::io::Write;
+//!
+//! use log::Level;
+//! use serialport::logged::LoggedPort;
+//!
+//! let port = serialport::new("/dev/ttyUSB0", 115_200)
+//!     .open_native()
+//!     .expect("Failed to open port");
+//! let mut port = LoggedPort::new(port).data_level(Level::Debug);
+//! port.write_all(b"AT\r").expect("Failed to write to port");
+//! ```
+
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use log::Level;
+
+use crate::{
+    CancelToken, ClearBuffer, DataBits, Error, FlowControl, LineErrors, Parity, PortStats, Result,
+    SerialPort, StopBits,
+};
+
+/// A port logging its traffic
+///
+/// Data read from and written to the port gets logged at the [data
+/// level](LoggedPort::data_level), which defaults to `Trace`. Changing settings and modem control
+/// lines as well as reading the modem control lines gets logged at the [control
+/// level](LoggedPort::control_level), which defaults to `Debug`. Failing operations are logged
+/// along with their error at the same level. Querying settings is not logged.
+///
+/// All messages are logged with the target `serialport::logged` and start with the name of the
+/// wrapped port.
+pub struct LoggedPort<P> {
+    inner: P,
+    name: String,
+    data_level: Level,
+    control_level: Level,
+    redact: bool,
+}
+
+impl<P: SerialPort> LoggedPort<P> {
+    /// Wraps `inner` for logging its traffic
+    pub fn new(inner: P) -> Self {
+        LoggedPort {
+            name: inner.name().unwrap_or_else(|| "unnamed port".to_string()),
+            inner,
+            data_level: Level::Trace,
+            control_level: Level::Debug,
+            redact: false,
+        }
+    }
+
+    /// Sets the level for logging data read from and written to the port
+    #[must_use]
+    pub fn data_level(mut self, level: Level) -> Self {
+        self.data_level = level;
+        self
+    }
+
+    /// Sets the level for logging settings and modem control lines
+    #[must_use]
+    pub fn control_level(mut self, level: Level) -> Self {
+        self.control_level = level;
+        self
+    }
+
+    /// Sets whether to log only the amount of data instead of the data itself
+    ///
+    /// This keeps sensitive data, like credentials sent to a device, out of the logs.
+    #[must_use]
+    pub fn redact(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+
+    /// Returns a reference to the wrapped port
+    pub fn get_ref(&self) -> &P {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped port
+    ///
+    /// Operations on the returned reference are not logged.
+    pub fn get_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped port
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+
+    fn log_data(&self, operation: &str, result: &io::Result<usize>, data: &[u8]) {
+        match result {
+            Ok(n) => log::log!(
+                self.data_level,
+                "{}: {} {} bytes{}",
+                self.name,
+                operation,
+                n,
+                HexDump {
+                    data: &data[..*n],
+                    redact: self.redact,
+                }
+            ),
+            Err(e) => log::log!(
+                self.data_level,
+                "{}: {} failed: {}",
+                self.name,
+                operation,
+                e
+            ),
+        }
+    }
+
+    fn log_control(&self, operation: fmt::Arguments, result: &Result<()>) {
+        match result {
+            Ok(()) => log::log!(self.control_level, "{}: {}", self.name, operation),
+            Err(e) => log::log!(
+                self.control_level,
+                "{}: {} failed: {}",
+                self.name,
+                operation,
+                e
+            ),
+        }
+    }
+
+    fn log_input(&self, line: &str, result: &Result<bool>) {
+        match result {
+            Ok(level) => log::log!(
+                self.control_level,
+                "{}: read {}: {}",
+                self.name,
+                line,
+                level
+            ),
+            Err(e) => log::log!(
+                self.control_level,
+                "{}: read {} failed: {}",
+                self.name,
+                line,
+                e
+            ),
+        }
+    }
+}
+
+/// Formats data as hex dump with 16 bytes per line, preceded by a line break
+struct HexDump<'a> {
+    data: &'a [u8],
+    redact: bool,
+}
+
+impl fmt::Display for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.redact {
+            return f.write_str(" (redacted)");
+        }
+        for (i, line) in self.data.chunks(16).enumerate() {
+            write!(f, "\n{:04x}:", i * 16)?;
+            for byte in line {
+                write!(f, " {:02x}", byte)?;
+            }
+            write!(f, "{:width$}  ", "", width = (16 - line.len()) * 3)?;
+            for &byte in line {
+                let c = if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                };
+                write!(f, "{}", c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<P: fmt::Debug> fmt::Debug for LoggedPort<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoggedPort")
+            .field("inner", &self.inner)
+            .field("data_level", &self.data_level)
+            .field("control_level", &self.control_level)
+            .field("redact", &self.redact)
+            .finish()
+    }
+}
+
+impl<P: SerialPort> io::Read for LoggedPort<P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.inner.read(buf);
+        self.log_data("read", &result, buf);
+        result
+    }
+}
+
+impl<P: SerialPort> io::Write for LoggedPort<P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.inner.write(buf);
+        self.log_data("wrote", &result, buf);
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.inner.flush();
+        if let Err(e) = &result {
+            log::log!(self.data_level, "{}: flush failed: {}", self.name, e);
+        }
+        result
+    }
+}
+
+impl<P: SerialPort> SerialPort for LoggedPort<P> {
+    fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        self.inner.baud_rate()
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        self.inner.data_bits()
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        self.inner.flow_control()
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        self.inner.parity()
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        self.inner.stop_bits()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.inner.timeout()
+    }
+
+    fn inter_byte_timeout(&self) -> Result<Option<Duration>> {
+        self.inner.inter_byte_timeout()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        let result = self.inner.set_baud_rate(baud_rate);
+        self.log_control(format_args!("set baud rate to {}", baud_rate), &result);
+        result
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        let result = self.inner.set_data_bits(data_bits);
+        self.log_control(format_args!("set data bits to {}", data_bits), &result);
+        result
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        let result = self.inner.set_flow_control(flow_control);
+        self.log_control(
+            format_args!("set flow control to {}", flow_control),
+            &result,
+        );
+        result
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        let result = self.inner.set_parity(parity);
+        self.log_control(format_args!("set parity to {}", parity), &result);
+        result
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        let result = self.inner.set_stop_bits(stop_bits);
+        self.log_control(format_args!("set stop bits to {}", stop_bits), &result);
+        result
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        let result = self.inner.set_timeout(timeout);
+        self.log_control(format_args!("set timeout to {:?}", timeout), &result);
+        result
+    }
+
+    fn set_inter_byte_timeout(&mut self, inter_byte_timeout: Option<Duration>) -> Result<()> {
+        let result = self.inner.set_inter_byte_timeout(inter_byte_timeout);
+        self.log_control(
+            format_args!("set inter-byte timeout to {:?}", inter_byte_timeout),
+            &result,
+        );
+        result
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
+        let result = self.inner.write_request_to_send(level);
+        self.log_control(format_args!("set RTS to {}", level), &result);
+        result
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
+        let result = self.inner.write_data_terminal_ready(level);
+        self.log_control(format_args!("set DTR to {}", level), &result);
+        result
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        let result = self.inner.read_clear_to_send();
+        self.log_input("CTS", &result);
+        result
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        let result = self.inner.read_data_set_ready();
+        self.log_input("DSR", &result);
+        result
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        let result = self.inner.read_ring_indicator();
+        self.log_input("RI", &result);
+        result
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        let result = self.inner.read_carrier_detect();
+        self.log_input("CD", &result);
+        result
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        self.inner.bytes_to_read()
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        self.inner.bytes_to_write()
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        let result = self.inner.clear(buffer_to_clear);
+        self.log_control(format_args!("clear {:?}", buffer_to_clear), &result);
+        result
+    }
+
+    fn line_errors(&self) -> Result<LineErrors> {
+        self.inner.line_errors()
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        self.inner.stats()
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        self.inner.is_connected()
+    }
+
+    fn cancel_token(&self) -> Result<CancelToken> {
+        self.inner.cancel_token()
+    }
+
+    fn read_cancellable(&mut self, buf: &mut [u8], cancel: &CancelToken) -> io::Result<usize> {
+        let result = self.inner.read_cancellable(buf, cancel);
+        self.log_data("read", &result, buf);
+        result
+    }
+
+    fn write_cancellable(&mut self, buf: &[u8], cancel: &CancelToken) -> io::Result<usize> {
+        let result = self.inner.write_cancellable(buf, cancel);
+        self.log_data("wrote", &result, buf);
+        result
+    }
+
+    fn flush_cancellable(&mut self, cancel: &CancelToken) -> io::Result<()> {
+        let result = self.inner.flush_cancellable(cancel);
+        if let Err(e) = &result {
+            log::log!(self.data_level, "{}: flush failed: {}", self.name, e);
+        }
+        result
+    }
+
+    fn wait_readable(&self, timeout: Duration) -> Result<()> {
+        self.inner.wait_readable(timeout)
+    }
+
+    fn wait_writable(&self, timeout: Duration) -> Result<()> {
+        self.inner.wait_writable(timeout)
+    }
+
+    /// Clones the wrapped port, without logging the clone's traffic
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        self.inner.try_clone()
+    }
+
+    fn set_break(&self) -> Result<()> {
+        let result = self.inner.set_break();
+        self.log_control(format_args!("set break"), &result);
+        result
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        let result = self.inner.clear_break();
+        self.log_control(format_args!("clear break"), &result);
+        result
+    }
+}
@@ -0,0 +1,522 @@
+//! A serial port wrapper keeping track of whether the device is still connected.
+
+use std::cell::Cell;
+use std::io;
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+use crate::{
+    CancelToken, ClearBuffer, DataBits, Error, ErrorKind, FlowControl, LineErrors, Parity,
+    PortFilter, PortStats, Result, SerialPort, SerialPortBuilder, SerialPortType, StopBits,
+    UsbPortInfo,
+};
+
+/// The state of a [`ManagedPort`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PortState {
+    /// The port has been configured but is not open.
+    Configured,
+    /// The port is open and usable.
+    Open,
+    /// The device has been disconnected while the port was open.
+    Disconnected,
+}
+
+/// Notification about a [`ManagedPort`] which has been reopened under a different name
+///
+/// See [`ManagedPort::subscribe_renames`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortRenamed {
+    /// The name of the port before reopening it
+    pub old_name: String,
+    /// The name of the port after reopening it
+    pub new_name: String,
+}
+
+/// A serial port which fails fast once its device has been disconnected
+///
+/// The port keeps track of its [`PortState`]. As soon as an operation fails in a way indicating
+/// that the device is gone, the port is considered [`Disconnected`](PortState::Disconnected).
+/// From then on, all operations fail right away with `NoDevice` instead of waiting for the full
+/// timeout, until the port gets reopened with [`recover`](ManagedPort::recover).
+///
+/// Settings changed through the [`SerialPort`] methods are remembered and applied again when
+/// reopening the port.
+///
+/// For USB devices, the port also remembers the identity of the device it has been opened for.
+/// If the device shows up under a different name after being plugged in again, e.g.
+/// `/dev/ttyUSB1` instead of `/dev/ttyUSB0`, [`recover`](ManagedPort::recover) follows it and
+/// notifies the [subscribers](ManagedPort::subscribe_renames) of the new name.
+///
+/// Clones made with [`try_clone`](SerialPort::try_clone) are managed ports as well. They share
+/// the name and the rename subscribers with the port they have been cloned from, so following a
+/// device with any of them renames all of them, and the subscribers get notified once. Each clone
+/// still has to be [recovered](ManagedPort::recover) on its own, which reopens it under the new
+/// name.
+///
+/// Like any [`SerialPort`], a `ManagedPort` can be moved to another thread. For sharing it
+/// between threads, wrap it in a [`SharedPort`](crate::SharedPort).
+///
+/// ```no_run
+/// use std::io::Write;
+/// use serialport::{ManagedPort, PortState};
+///
+/// let mut port = ManagedPort::open(serialport::new("/dev/ttyUSB0", 115_200))
+///     .expect("Failed to open port");
+///
+/// if port.write_all(b"ping").is_err() && port.state() == PortState::Disconnected {
+///     port.recover().expect("Failed to reopen port");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ManagedPort {
+    builder: SerialPortBuilder,
+    port: Option<Box<dyn SerialPort>>,
+    state: Cell<PortState>,
+    identity: Option<UsbPortInfo>,
+    /// The name of the port, shared with its clones
+    path: Arc<Mutex<String>>,
+    rename_subscribers: Arc<Mutex<Vec<mpsc::Sender<PortRenamed>>>>,
+}
+
+impl ManagedPort {
+    /// Creates a port with the given settings without opening it
+    pub fn new(builder: SerialPortBuilder) -> Self {
+        ManagedPort {
+            path: Arc::new(Mutex::new(builder.path.clone())),
+            builder,
+            port: None,
+            state: Cell::new(PortState::Configured),
+            identity: None,
+            rename_subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Opens a port with the given settings
+    pub fn open(builder: SerialPortBuilder) -> Result<Self> {
+        let mut port = Self::new(builder);
+        port.recover()?;
+        Ok(port)
+    }
+
+    /// Returns the current state of the port
+    pub fn state(&self) -> PortState {
+        self.state.get()
+    }
+
+    /// Returns the settings used for opening the port
+    ///
+    /// The path is the one the port has last been opened with, which lags behind the
+    /// [name](SerialPort::name) if a clone has followed the device to a new name since.
+    pub fn builder(&self) -> &SerialPortBuilder {
+        &self.builder
+    }
+
+    /// Returns the USB device the port has been opened for
+    ///
+    /// This is `None` for ports of other types and before the port has been opened.
+    pub fn identity(&self) -> Option<&UsbPortInfo> {
+        self.identity.as_ref()
+    }
+
+    /// Returns a receiver getting notified whenever the port or one of its clones is reopened
+    /// under a different name
+    pub fn subscribe_renames(&mut self) -> mpsc::Receiver<PortRenamed> {
+        let (sender, receiver) = mpsc::channel();
+        self.rename_subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+        receiver
+    }
+
+    /// Opens the port again with the current settings
+    ///
+    /// Any previously opened instance gets closed first. This also opens a port which has just
+    /// been [`Configured`](PortState::Configured).
+    ///
+    /// If the port has been opened for a USB device before and the device is no longer found
+    /// under the port's name, the device is looked up by its vendor ID, product ID and serial
+    /// number. The port gets reopened under the new name if exactly one such device is present.
+    pub fn recover(&mut self) -> Result<()> {
+        self.close();
+        // A clone may have followed the device to a new name in the meantime.
+        self.builder.path = self.path.lock().unwrap_or_else(|e| e.into_inner()).clone();
+
+        let identity = self.identity.take();
+        let current = usb_identity(&self.builder.path);
+        match (identity, current) {
+            (Some(identity), Some(ref current)) if is_same_device(&identity, current) => {
+                self.identity = Some(identity);
+            }
+            (Some(identity), _) => {
+                let result = self.rebind(&identity);
+                self.identity = Some(identity);
+                result?;
+            }
+            (None, current) => self.identity = current,
+        }
+
+        self.port = Some(self.builder.clone().open()?);
+        self.state.set(PortState::Open);
+        Ok(())
+    }
+
+    /// Points the port to the device with the given identity
+    fn rebind(&mut self, identity: &UsbPortInfo) -> Result<()> {
+        let mut filter = PortFilter::new().vid(identity.vid).pid(identity.pid);
+        if let Some(ref serial_number) = identity.serial_number {
+            filter = filter.serial_number(serial_number.clone());
+        }
+        let port = crate::filter::single_port(
+            crate::available_ports_filtered(&filter)?,
+            &format!("the USB device previously found at {}", self.builder.path),
+        )?;
+
+        if port.port_name != self.builder.path {
+            let renamed = PortRenamed {
+                old_name: std::mem::replace(&mut self.builder.path, port.port_name.clone()),
+                new_name: port.port_name.clone(),
+            };
+            *self.path.lock().unwrap_or_else(|e| e.into_inner()) = port.port_name;
+            self.rename_subscribers
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .retain(|subscriber| subscriber.send(renamed.clone()).is_ok());
+        }
+        Ok(())
+    }
+
+    /// Closes the port
+    pub fn close(&mut self) {
+        self.port = None;
+        self.state.set(PortState::Configured);
+    }
+
+    fn port(&self) -> Result<&dyn SerialPort> {
+        match (self.state.get(), self.port.as_ref()) {
+            (PortState::Open, Some(port)) => Ok(port.as_ref()),
+            (PortState::Disconnected, _) => Err(Error::new(
+                ErrorKind::NoDevice,
+                "device has been disconnected",
+            )),
+            _ => Err(Error::new(ErrorKind::NoDevice, "port is not open")),
+        }
+    }
+
+    fn port_mut(&mut self) -> Result<&mut dyn SerialPort> {
+        self.port()?;
+        match self.port.as_mut() {
+            Some(port) => Ok(port.as_mut()),
+            None => unreachable!("an open port is always present"),
+        }
+    }
+
+    fn track<T>(&self, result: Result<T>) -> Result<T> {
+        if let Err(ref e) = result {
+            if is_disconnect(e.kind) {
+                self.state.set(PortState::Disconnected);
+            }
+        }
+        result
+    }
+
+    fn track_io<T>(&self, result: io::Result<T>) -> io::Result<T> {
+        if let Err(ref e) = result {
+            if is_disconnect(ErrorKind::Io(e.kind())) {
+                self.state.set(PortState::Disconnected);
+            }
+        }
+        result
+    }
+}
+
+/// Returns the USB device information of the port named `path`, if it is a USB port
+fn usb_identity(path: &str) -> Option<UsbPortInfo> {
+    crate::available_ports()
+        .ok()?
+        .into_iter()
+        .find(|port| port.port_name == path)
+        .and_then(|port| match port.port_type {
+            SerialPortType::UsbPort(info) => Some(info),
+            _ => None,
+        })
+}
+
+/// Returns whether both port infos belong to the same USB device
+fn is_same_device(a: &UsbPortInfo, b: &UsbPortInfo) -> bool {
+    a.vid == b.vid && a.pid == b.pid && a.serial_number == b.serial_number
+}
+
+/// Returns whether an error of the given kind indicates that the device is gone
+///
+/// Ports passing errors through `io::Error` without keeping the original error may report
+/// `NoDevice` as `NotFound`.
+fn is_disconnect(kind: ErrorKind) -> bool {
+    matches!(
+        kind,
+        ErrorKind::NoDevice
+            | ErrorKind::BrokenPipe
+            | ErrorKind::Io(io::ErrorKind::NotFound)
+            | ErrorKind::Io(io::ErrorKind::BrokenPipe)
+    )
+}
+
+impl io::Read for ManagedPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.port_mut()?.read(buf);
+        match result {
+            // A blocking read returning no data at all means that the device hung up.
+            Ok(0) if !buf.is_empty() => {
+                self.state.set(PortState::Disconnected);
+                Err(Error::new(ErrorKind::NoDevice, "device has been disconnected").into())
+            }
+            result => self.track_io(result),
+        }
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        let result = self.port_mut()?.read_vectored(bufs);
+        match result {
+            Ok(0) if bufs.iter().any(|buf| !buf.is_empty()) => {
+                self.state.set(PortState::Disconnected);
+                Err(Error::new(ErrorKind::NoDevice, "device has been disconnected").into())
+            }
+            result => self.track_io(result),
+        }
+    }
+}
+
+impl io::Write for ManagedPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.port_mut()?.write(buf);
+        self.track_io(result)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        let result = self.port_mut()?.write_vectored(bufs);
+        self.track_io(result)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.port_mut()?.flush();
+        self.track_io(result)
+    }
+}
+
+impl SerialPort for ManagedPort {
+    fn name(&self) -> Option<String> {
+        Some(self.path.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        self.track(self.port()?.baud_rate())
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        self.track(self.port()?.data_bits())
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        self.track(self.port()?.flow_control())
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        self.track(self.port()?.parity())
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        self.track(self.port()?.stop_bits())
+    }
+
+    fn timeout(&self) -> Duration {
+        self.builder.timeout
+    }
+
+    fn inter_byte_timeout(&self) -> Result<Option<Duration>> {
+        self.track(self.port()?.inter_byte_timeout())
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        let result = self.port_mut()?.set_baud_rate(baud_rate);
+        self.track(result)?;
+        self.builder.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        let result = self.port_mut()?.set_data_bits(data_bits);
+        self.track(result)?;
+        self.builder.data_bits = data_bits;
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        let result = self.port_mut()?.set_flow_control(flow_control);
+        self.track(result)?;
+        self.builder.flow_control = flow_control;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        let result = self.port_mut()?.set_parity(parity);
+        self.track(result)?;
+        self.builder.parity = parity;
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        let result = self.port_mut()?.set_stop_bits(stop_bits);
+        self.track(result)?;
+        self.builder.stop_bits = stop_bits;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        let result = self.port_mut()?.set_timeout(timeout);
+        self.track(result)?;
+        self.builder.timeout = timeout;
+        Ok(())
+    }
+
+    fn set_inter_byte_timeout(&mut self, inter_byte_timeout: Option<Duration>) -> Result<()> {
+        let result = self.port_mut()?.set_inter_byte_timeout(inter_byte_timeout);
+        self.track(result)?;
+        self.builder.inter_byte_timeout = inter_byte_timeout;
+        Ok(())
+    }
+
+    fn apply_settings(&mut self, settings: &SerialPortBuilder) -> Result<()> {
+        let result = self.port_mut()?.apply_settings(settings);
+        self.track(result)?;
+        self.builder = SerialPortBuilder {
+            path: self.builder.path.clone(),
+            dtr_on_open: self.builder.dtr_on_open,
+            ..settings.clone()
+        };
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
+        let result = self.port_mut()?.write_request_to_send(level);
+        self.track(result)
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
+        let result = self.port_mut()?.write_data_terminal_ready(level);
+        self.track(result)
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        let result = self.port_mut()?.read_clear_to_send();
+        self.track(result)
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        let result = self.port_mut()?.read_data_set_ready();
+        self.track(result)
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        let result = self.port_mut()?.read_ring_indicator();
+        self.track(result)
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        let result = self.port_mut()?.read_carrier_detect();
+        self.track(result)
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        self.track(self.port()?.bytes_to_read())
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        self.track(self.port()?.bytes_to_write())
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        self.track(self.port()?.clear(buffer_to_clear))
+    }
+
+    fn line_errors(&self) -> Result<LineErrors> {
+        self.track(self.port()?.line_errors())
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        self.track(self.port()?.take_error())
+    }
+
+    /// Returns the statistics of the current port, which start over when reconnecting
+    fn stats(&self) -> Result<PortStats> {
+        self.track(self.port()?.stats())
+    }
+
+    /// Returns a managed port sharing the name and the rename subscribers with this one
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        let port = self.track(self.port()?.try_clone())?;
+        Ok(Box::new(ManagedPort {
+            builder: self.builder.clone(),
+            port: Some(port),
+            state: Cell::new(PortState::Open),
+            identity: self.identity.clone(),
+            path: self.path.clone(),
+            rename_subscribers: self.rename_subscribers.clone(),
+        }))
+    }
+
+    /// Returns the token of the current port, which doesn't apply to the port opened when
+    /// reconnecting
+    fn cancel_token(&self) -> Result<CancelToken> {
+        self.track(self.port()?.cancel_token())
+    }
+
+    fn read_cancellable(&mut self, buf: &mut [u8], cancel: &CancelToken) -> io::Result<usize> {
+        let result = self.port_mut()?.read_cancellable(buf, cancel);
+        match result {
+            Ok(0) if !buf.is_empty() => {
+                self.state.set(PortState::Disconnected);
+                Err(Error::new(ErrorKind::NoDevice, "device has been disconnected").into())
+            }
+            result => self.track_io(result),
+        }
+    }
+
+    fn write_cancellable(&mut self, buf: &[u8], cancel: &CancelToken) -> io::Result<usize> {
+        let result = self.port_mut()?.write_cancellable(buf, cancel);
+        self.track_io(result)
+    }
+
+    fn flush_cancellable(&mut self, cancel: &CancelToken) -> io::Result<()> {
+        let result = self.port_mut()?.flush_cancellable(cancel);
+        self.track_io(result)
+    }
+
+    fn wait_readable(&self, timeout: Duration) -> Result<()> {
+        self.track(self.port()?.wait_readable(timeout))
+    }
+
+    fn wait_writable(&self, timeout: Duration) -> Result<()> {
+        self.track(self.port()?.wait_writable(timeout))
+    }
+
+    fn set_break(&self) -> Result<()> {
+        self.track(self.port()?.set_break())
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        self.track(self.port()?.clear_break())
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        let connected = match self.port() {
+            Ok(port) => self.track(port.is_connected())?,
+            Err(_) => return Ok(false),
+        };
+        if !connected {
+            self.state.set(PortState::Disconnected);
+        }
+        Ok(connected)
+    }
+}
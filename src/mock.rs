@@ -0,0 +1,861 @@
+//! Serial ports for testing
+//!
+//! A [`MockPort`] implements [`SerialPort`] without any hardware or operating system support, so
+//! code talking to serial devices can be unit tested on any machine, including CI runners and
+//! Windows, where [`TTYPort::pair`](crate::TTYPort) is not available.
+//!
+//! ```
+//! use std::io::{Read, Write};
+//! use serialport::mock::MockPort;
+//! use serialport::SerialPort;
+//!
+//! let (mut app, mut device) = MockPort::pair();
+//!
+//! app.write_all(b"AT\r").unwrap();
+//! let mut request = [0u8; 3];
+//! device.read_exact(&mut request).unwrap();
+//! assert_eq!(&request, b"AT\r");
+//!
+//! // The device's modem control outputs show up at the application's inputs.
+//! device.write_request_to_send(false).unwrap();
+//! assert!(!app.read_clear_to_send().unwrap());
+//! ```
+//!
+//! A [`Script`] plays the role of a device on the other end of a port, replying to requests as
+//! scripted. A [`FaultyPort`] wraps another port and makes selected operations fail, for testing
+//! error handling and reconnection logic.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::{
+    CancelToken, ClearBuffer, DataBits, Error, ErrorKind, FlowControl, LineErrors, Parity,
+    PortStats, Result, SerialPort, SerialPortExt, StopBits,
+};
+
+/// A serial port existing only in memory
+///
+/// A port either stands on its own, created with [`MockPort::new`], or is connected to a second
+/// port, created with [`MockPort::pair`]. Data written to a port gets received by its peer. For a
+/// port on its own, written data can be inspected with [`take_output`](MockPort::take_output)
+/// and data to be received can be provided with [`push_input`](MockPort::push_input).
+///
+/// The modem control lines are wired like with a null modem cable: RTS of one port drives CTS of
+/// the other, DTR drives DSR and CD. The inputs can also be set directly, e.g. for simulating a
+/// ringing modem with [`set_ring_indicator`](MockPort::set_ring_indicator).
+///
+/// Port settings are only recorded and reported back, they have no effect on the data transfer.
+/// Reads block for at most the port's timeout and fail with `TimedOut` if no data arrived. Writes
+/// never block.
+#[derive(Debug)]
+pub struct MockPort {
+    input: Arc<Endpoint>,
+    output: Arc<Endpoint>,
+    port_name: String,
+    baud_rate: u32,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+    timeout: Duration,
+    stats: PortStats,
+}
+
+/// The input buffer and modem control lines of a port, shared with its clones and its peer
+#[derive(Debug)]
+struct Endpoint {
+    state: Mutex<EndpointState>,
+    readable: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct EndpointState {
+    data: VecDeque<u8>,
+    clear_to_send: bool,
+    data_set_ready: bool,
+    ring_indicator: bool,
+    carrier_detect: bool,
+    request_to_send: bool,
+    data_terminal_ready: bool,
+    break_state: bool,
+}
+
+impl Endpoint {
+    fn new() -> Arc<Self> {
+        Arc::new(Endpoint {
+            state: Mutex::new(EndpointState {
+                clear_to_send: true,
+                data_set_ready: true,
+                carrier_detect: true,
+                request_to_send: true,
+                data_terminal_ready: true,
+                ..EndpointState::default()
+            }),
+            readable: Condvar::new(),
+        })
+    }
+
+    fn state(&self) -> MutexGuard<'_, EndpointState> {
+        // The state stays consistent even if a panic occurred while holding the lock.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&self, data: &[u8]) {
+        self.state().data.extend(data);
+        self.readable.notify_all();
+    }
+}
+
+impl MockPort {
+    /// Creates a port on its own, named `mock`
+    ///
+    /// The modem control inputs report the device as present and ready.
+    pub fn new() -> Self {
+        Self::with_endpoints("mock", Endpoint::new(), Endpoint::new())
+    }
+
+    /// Creates two ports connected to each other, named `mock0` and `mock1`
+    pub fn pair() -> (Self, Self) {
+        let first = Endpoint::new();
+        let second = Endpoint::new();
+        (
+            Self::with_endpoints("mock0", first.clone(), second.clone()),
+            Self::with_endpoints("mock1", second, first),
+        )
+    }
+
+    fn with_endpoints(name: &str, input: Arc<Endpoint>, output: Arc<Endpoint>) -> Self {
+        MockPort {
+            input,
+            output,
+            port_name: name.to_string(),
+            baud_rate: 9600,
+            data_bits: DataBits::Eight,
+            flow_control: FlowControl::None,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            timeout: Duration::from_millis(0),
+            stats: PortStats::default(),
+        }
+    }
+
+    /// Appends `data` to the input buffer as if it had been received on the line
+    pub fn push_input(&self, data: &[u8]) {
+        self.input.push(data);
+    }
+
+    /// Removes and returns the data written to the port which has not been read by its peer yet
+    pub fn take_output(&self) -> Vec<u8> {
+        self.output.state().data.drain(..).collect()
+    }
+
+    /// Returns the level of the RTS output as set with
+    /// [`write_request_to_send`](SerialPort::write_request_to_send)
+    pub fn request_to_send(&self) -> bool {
+        self.input.state().request_to_send
+    }
+
+    /// Returns the level of the DTR output as set with
+    /// [`write_data_terminal_ready`](SerialPort::write_data_terminal_ready)
+    pub fn data_terminal_ready(&self) -> bool {
+        self.input.state().data_terminal_ready
+    }
+
+    /// Returns whether a break condition is being transmitted
+    pub fn break_state(&self) -> bool {
+        self.input.state().break_state
+    }
+
+    /// Sets the level of the CTS input
+    pub fn set_clear_to_send(&self, level: bool) {
+        self.input.state().clear_to_send = level;
+    }
+
+    /// Sets the level of the DSR input
+    pub fn set_data_set_ready(&self, level: bool) {
+        self.input.state().data_set_ready = level;
+    }
+
+    /// Sets the level of the RI input
+    pub fn set_ring_indicator(&self, level: bool) {
+        self.input.state().ring_indicator = level;
+    }
+
+    /// Sets the level of the CD input
+    pub fn set_carrier_detect(&self, level: bool) {
+        self.input.state().carrier_detect = level;
+    }
+
+    /// Attempts to clone the `MockPort`
+    ///
+    /// Both instances share the buffers and modem control lines, but each of them keeps its own
+    /// settings.
+    pub fn try_clone_native(&self) -> Result<MockPort> {
+        Ok(MockPort {
+            input: self.input.clone(),
+            output: self.output.clone(),
+            port_name: self.port_name.clone(),
+            stats: PortStats::default(),
+            ..*self
+        })
+    }
+
+    fn read_input(&self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let state = self.input.state();
+        let (mut state, _) = self
+            .input
+            .readable
+            .wait_timeout_while(state, self.timeout, |state| state.data.is_empty())
+            .unwrap_or_else(|e| e.into_inner());
+        if state.data.is_empty() {
+            return Err(Error::new(ErrorKind::Timeout, "Operation timed out").into());
+        }
+
+        let n = buf.len().min(state.data.len());
+        for (dst, src) in buf.iter_mut().zip(state.data.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+}
+
+impl Default for MockPort {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl io::Read for MockPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.read_input(buf);
+        self.stats.count_read(&result);
+        result
+    }
+}
+
+impl io::Write for MockPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.push(buf);
+        self.stats.count_write(&Ok(buf.len()));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for MockPort {
+    fn name(&self) -> Option<String> {
+        Some(self.port_name.clone())
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        Ok(self.data_bits)
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        Ok(self.flow_control)
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        Ok(self.parity)
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        Ok(self.stop_bits)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        self.data_bits = data_bits;
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        self.flow_control = flow_control;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        self.parity = parity;
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        self.stop_bits = stop_bits;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
+        self.input.state().request_to_send = level;
+        self.output.state().clear_to_send = level;
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
+        self.input.state().data_terminal_ready = level;
+        let mut peer = self.output.state();
+        peer.data_set_ready = level;
+        peer.carrier_detect = level;
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        Ok(self.input.state().clear_to_send)
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        Ok(self.input.state().data_set_ready)
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        Ok(self.input.state().ring_indicator)
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        Ok(self.input.state().carrier_detect)
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        Ok(self.input.state().data.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        // Written data is handed over to the peer right away.
+        Ok(0)
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        Ok(None)
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        Ok(self.stats)
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        if let ClearBuffer::Input | ClearBuffer::All = buffer_to_clear {
+            self.input.state().data.clear();
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.try_clone_native()?))
+    }
+
+    fn set_break(&self) -> Result<()> {
+        self.input.state().break_state = true;
+        Ok(())
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        self.input.state().break_state = false;
+        Ok(())
+    }
+}
+
+/// The default time a [`Script`] waits for expected data
+pub const DEFAULT_EXPECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The behavior of a simulated device, as a sequence of steps
+///
+/// A script expects requests and sends replies in the order the steps were added. It is run on
+/// the device's end of a port, usually the second port of a [`MockPort::pair`], while the code
+/// under test talks to the first one.
+///
+/// ```
+/// use std::io::{Read, Write};
+/// use std::time::Duration;
+/// use serialport::mock::{MockPort, Script};
+/// use serialport::SerialPort;
+///
+/// let (mut app, device) = MockPort::pair();
+/// let device = Script::new()
+///     .expect(b"AT\r")
+///     .delay(Duration::from_millis(20))
+///     .send(b"OK\r\n")
+///     .spawn(device);
+///
+/// app.set_timeout(Duration::from_secs(1)).unwrap();
+/// app.write_all(b"AT\r").unwrap();
+/// let mut response = [0u8; 4];
+/// app.read_exact(&mut response).unwrap();
+/// assert_eq!(&response, b"OK\r\n");
+///
+/// // Fails if the device did not receive what it expected.
+/// device.join().unwrap().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Script {
+    steps: Vec<Step>,
+    timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Expect(Vec<u8>),
+    Send(Vec<u8>),
+    Delay(Duration),
+}
+
+impl Script {
+    /// Creates an empty script waiting for expected data for at most the
+    /// [`DEFAULT_EXPECT_TIMEOUT`]
+    pub fn new() -> Self {
+        Script {
+            steps: Vec::new(),
+            timeout: DEFAULT_EXPECT_TIMEOUT,
+        }
+    }
+
+    /// Waits for receiving exactly `data`
+    #[must_use]
+    pub fn expect(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.steps.push(Step::Expect(data.as_ref().to_vec()));
+        self
+    }
+
+    /// Sends `data`
+    #[must_use]
+    pub fn send(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.steps.push(Step::Send(data.as_ref().to_vec()));
+        self
+    }
+
+    /// Pauses for `duration`
+    #[must_use]
+    pub fn delay(mut self, duration: Duration) -> Self {
+        self.steps.push(Step::Delay(duration));
+        self
+    }
+
+    /// Sets the time to wait for the data of each [`expect`](Script::expect) step
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs the script on `port`, blocking until all steps are done
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(InvalidData)` if the received data differs from the expected one.
+    /// * `Timeout` if the expected data did not arrive in time.
+    /// * Any error from reading or writing `port`.
+    pub fn run<P: SerialPort + ?Sized>(&self, port: &mut P) -> Result<()> {
+        for step in &self.steps {
+            match step {
+                Step::Expect(expected) => {
+                    let mut received = vec![0u8; expected.len()];
+                    port.read_exact_timeout(&mut received, self.timeout)?;
+                    if received != *expected {
+                        return Err(Error::new(
+                            ErrorKind::Io(io::ErrorKind::InvalidData),
+                            format!("expected {:02x?}, received {:02x?}", expected, received),
+                        ));
+                    }
+                }
+                Step::Send(data) => io::Write::write_all(port, data)?,
+                Step::Delay(duration) => thread::sleep(*duration),
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the script on `port` in a background thread
+    ///
+    /// The thread's result tells whether the script completed, see [`run`](Script::run).
+    pub fn spawn<P: SerialPort + 'static>(self, mut port: P) -> JoinHandle<Result<()>> {
+        thread::spawn(move || self.run(&mut port))
+    }
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A fault to inject into an operation of a [`FaultyPort`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Transfers at most the given number of bytes, like a read or write returning early
+    Partial(usize),
+    /// Fails the operation with `TimedOut`
+    TimedOut,
+    /// Fails the operation with `Interrupted`
+    Interrupted,
+    /// Fails the operation and all following ones with `NoDevice`, like an unplugged device
+    Disconnect,
+}
+
+/// A port failing at scripted points
+///
+/// The reads and writes are counted separately, starting at zero. A fault injected for a read or
+/// write index takes effect when the operation with that index is performed. Operations without
+/// an injected fault are passed on to the wrapped port.
+///
+/// ```
+/// use std::io::{ErrorKind, Read};
+/// use serialport::mock::{Fault, FaultyPort, MockPort};
+///
+/// let port = MockPort::new();
+/// port.push_input(b"hello");
+///
+/// let mut port = FaultyPort::new(port);
+/// port.inject_read(0, Fault::Partial(2));
+/// port.inject_read(1, Fault::TimedOut);
+///
+/// let mut buf = [0u8; 5];
+/// assert_eq!(port.read(&mut buf).unwrap(), 2);
+/// assert_eq!(port.read(&mut buf).unwrap_err().kind(), ErrorKind::TimedOut);
+/// assert_eq!(port.read(&mut buf).unwrap(), 3);
+/// ```
+#[derive(Debug)]
+pub struct FaultyPort<P> {
+    inner: P,
+    read_faults: BTreeMap<usize, Fault>,
+    write_faults: BTreeMap<usize, Fault>,
+    reads: usize,
+    writes: usize,
+    disconnected: bool,
+}
+
+impl<P: SerialPort> FaultyPort<P> {
+    /// Wraps `inner` without any faults injected yet
+    pub fn new(inner: P) -> Self {
+        FaultyPort {
+            inner,
+            read_faults: BTreeMap::new(),
+            write_faults: BTreeMap::new(),
+            reads: 0,
+            writes: 0,
+            disconnected: false,
+        }
+    }
+
+    /// Injects `fault` into the read with the given index
+    pub fn inject_read(&mut self, index: usize, fault: Fault) {
+        self.read_faults.insert(index, fault);
+    }
+
+    /// Injects `fault` into the write with the given index
+    pub fn inject_write(&mut self, index: usize, fault: Fault) {
+        self.write_faults.insert(index, fault);
+    }
+
+    /// Makes this and all following operations fail with `NoDevice`
+    pub fn disconnect(&mut self) {
+        self.disconnected = true;
+    }
+
+    /// Returns the number of reads performed so far
+    pub fn reads(&self) -> usize {
+        self.reads
+    }
+
+    /// Returns the number of writes performed so far
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+
+    /// Returns a reference to the wrapped port
+    pub fn get_ref(&self) -> &P {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped port
+    pub fn get_mut(&mut self) -> &mut P {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped port
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.disconnected {
+            Err(Error::new(
+                ErrorKind::NoDevice,
+                "Device has been disconnected",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Counts a read of `len` bytes and returns the number of bytes to pass on
+    fn next_read(&mut self, len: usize) -> io::Result<usize> {
+        self.check()?;
+        let fault = self.read_faults.remove(&self.reads);
+        self.reads += 1;
+        self.apply(fault, len)
+    }
+
+    /// Counts a write of `len` bytes and returns the number of bytes to pass on
+    fn next_write(&mut self, len: usize) -> io::Result<usize> {
+        self.check()?;
+        let fault = self.write_faults.remove(&self.writes);
+        self.writes += 1;
+        self.apply(fault, len)
+    }
+
+    /// Applies `fault` to an operation on `len` bytes and returns the number of bytes to pass on
+    fn apply(&mut self, fault: Option<Fault>, len: usize) -> io::Result<usize> {
+        match fault {
+            None => Ok(len),
+            Some(Fault::Partial(n)) => Ok(len.min(n)),
+            Some(Fault::TimedOut) => {
+                Err(Error::new(ErrorKind::Timeout, "Operation timed out").into())
+            }
+            Some(Fault::Interrupted) => Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "Operation interrupted",
+            )),
+            Some(Fault::Disconnect) => {
+                self.disconnected = true;
+                self.check()?;
+                Ok(len)
+            }
+        }
+    }
+}
+
+impl<P: SerialPort> io::Read for FaultyPort<P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.next_read(buf.len())?;
+        self.inner.read(&mut buf[..len])
+    }
+}
+
+impl<P: SerialPort> io::Write for FaultyPort<P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.next_write(buf.len())?;
+        self.inner.write(&buf[..len])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.check()?;
+        self.inner.flush()
+    }
+}
+
+impl<P: SerialPort> SerialPort for FaultyPort<P> {
+    fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        self.check()?;
+        self.inner.baud_rate()
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        self.check()?;
+        self.inner.data_bits()
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        self.check()?;
+        self.inner.flow_control()
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        self.check()?;
+        self.inner.parity()
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        self.check()?;
+        self.inner.stop_bits()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.inner.timeout()
+    }
+
+    fn inter_byte_timeout(&self) -> Result<Option<Duration>> {
+        self.check()?;
+        self.inner.inter_byte_timeout()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        self.check()?;
+        self.inner.set_baud_rate(baud_rate)
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        self.check()?;
+        self.inner.set_data_bits(data_bits)
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        self.check()?;
+        self.inner.set_flow_control(flow_control)
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        self.check()?;
+        self.inner.set_parity(parity)
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        self.check()?;
+        self.inner.set_stop_bits(stop_bits)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.check()?;
+        self.inner.set_timeout(timeout)
+    }
+
+    fn set_inter_byte_timeout(&mut self, inter_byte_timeout: Option<Duration>) -> Result<()> {
+        self.check()?;
+        self.inner.set_inter_byte_timeout(inter_byte_timeout)
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
+        self.check()?;
+        self.inner.write_request_to_send(level)
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
+        self.check()?;
+        self.inner.write_data_terminal_ready(level)
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        self.check()?;
+        self.inner.read_clear_to_send()
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        self.check()?;
+        self.inner.read_data_set_ready()
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        self.check()?;
+        self.inner.read_ring_indicator()
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        self.check()?;
+        self.inner.read_carrier_detect()
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        self.check()?;
+        self.inner.bytes_to_read()
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        self.check()?;
+        self.inner.bytes_to_write()
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        self.check()?;
+        self.inner.clear(buffer_to_clear)
+    }
+
+    fn line_errors(&self) -> Result<LineErrors> {
+        self.check()?;
+        self.inner.line_errors()
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        self.check()?;
+        self.inner.take_error()
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        self.check()?;
+        self.inner.stats()
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        if self.disconnected {
+            return Ok(false);
+        }
+        self.inner.is_connected()
+    }
+
+    fn cancel_token(&self) -> Result<CancelToken> {
+        self.check()?;
+        self.inner.cancel_token()
+    }
+
+    fn read_cancellable(&mut self, buf: &mut [u8], cancel: &CancelToken) -> io::Result<usize> {
+        let len = self.next_read(buf.len())?;
+        self.inner.read_cancellable(&mut buf[..len], cancel)
+    }
+
+    fn write_cancellable(&mut self, buf: &[u8], cancel: &CancelToken) -> io::Result<usize> {
+        let len = self.next_write(buf.len())?;
+        self.inner.write_cancellable(&buf[..len], cancel)
+    }
+
+    fn flush_cancellable(&mut self, cancel: &CancelToken) -> io::Result<()> {
+        self.check()?;
+        self.inner.flush_cancellable(cancel)
+    }
+
+    fn wait_readable(&self, timeout: Duration) -> Result<()> {
+        self.check()?;
+        self.inner.wait_readable(timeout)
+    }
+
+    fn wait_writable(&self, timeout: Duration) -> Result<()> {
+        self.check()?;
+        self.inner.wait_writable(timeout)
+    }
+
+    /// Clones the wrapped port, without any faults
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        self.check()?;
+        self.inner.try_clone()
+    }
+
+    fn set_break(&self) -> Result<()> {
+        self.check()?;
+        self.inner.set_break()
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        self.check()?;
+        self.inner.clear_break()
+    }
+}
@@ -0,0 +1,181 @@
+//! Observing the activity of all ports.
+
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Receives notifications about the activity of native ports
+///
+/// Observers are registered globally with [`add_observer`] and get notified about all
+/// `TTYPort`s and `COMPort`s, including the ones opened through
+/// [`SerialPortBuilder::open`](crate::SerialPortBuilder::open). This allows metrics
+/// systems and test harnesses to observe ports without wrapping them at every call site.
+///
+/// Notifications are delivered synchronously on the thread performing the operation, so
+/// observers should return quickly. All methods do nothing by default.
+///
+/// `name` is the name of the port, if it has one.
+pub trait PortObserver: Send + Sync {
+    /// Called after a port has been opened or cloned
+    fn on_open(&self, name: Option<&str>) {
+        let _ = name;
+    }
+
+    /// Called when a port which has been reported to [`on_open`](PortObserver::on_open) gets
+    /// closed
+    fn on_close(&self, name: Option<&str>) {
+        let _ = name;
+    }
+
+    /// Called after data has been read from a port
+    fn on_read(&self, name: Option<&str>, data: &[u8]) {
+        let _ = (name, data);
+    }
+
+    /// Called after data has been written to a port
+    fn on_write(&self, name: Option<&str>, data: &[u8]) {
+        let _ = (name, data);
+    }
+}
+
+/// Identifies an observer registered with [`add_observer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(u64);
+
+#[derive(Default)]
+struct Registry {
+    observers: RwLock<Vec<(ObserverId, Arc<dyn PortObserver>)>>,
+    next_id: AtomicU64,
+}
+
+/// The number of registered observers, for skipping notifications without taking a lock
+static OBSERVER_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn registry() -> &'static Registry {
+    // TODO: Switch to a `static` `RwLock` when bumping our MSRV to 1.63 or later.
+    static REGISTRY: AtomicPtr<Registry> = AtomicPtr::new(ptr::null_mut());
+
+    let mut registry = REGISTRY.load(Ordering::Acquire);
+    if registry.is_null() {
+        let new = Box::into_raw(Box::default());
+        registry = match REGISTRY.compare_exchange(
+            ptr::null_mut(),
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => new,
+            Err(existing) => {
+                // Another thread has been faster.
+                drop(unsafe { Box::from_raw(new) });
+                existing
+            }
+        };
+    }
+    // The registry is never freed once it has been published.
+    unsafe { &*registry }
+}
+
+/// Registers `observer` for receiving notifications about the activity of all native ports
+///
+/// Returns the ID for unregistering the observer with [`remove_observer`].
+///
+/// ```
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use std::sync::Arc;
+///
+/// use serialport::PortObserver;
+///
+/// #[derive(Default)]
+/// struct BytesRead(AtomicU64);
+///
+/// impl PortObserver for BytesRead {
+///     fn on_read(&self, _name: Option<&str>, data: &[u8]) {
+///         self.0.fetch_add(data.len() as u64, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let bytes_read = Arc::new(BytesRead::default());
+/// let id = serialport::add_observer(bytes_read.clone());
+/// // ... open and use ports anywhere in the application
+/// serialport::remove_observer(id);
+/// ```
+pub fn add_observer(observer: Arc<dyn PortObserver>) -> ObserverId {
+    let registry = registry();
+    let id = ObserverId(registry.next_id.fetch_add(1, Ordering::Relaxed));
+    let mut observers = registry
+        .observers
+        .write()
+        .unwrap_or_else(|e| e.into_inner());
+    observers.push((id, observer));
+    OBSERVER_COUNT.store(observers.len(), Ordering::Release);
+    id
+}
+
+/// Unregisters the observer with the given ID
+///
+/// Returns whether the observer was registered.
+pub fn remove_observer(id: ObserverId) -> bool {
+    let mut observers = registry()
+        .observers
+        .write()
+        .unwrap_or_else(|e| e.into_inner());
+    let count = observers.len();
+    observers.retain(|(observer_id, _)| *observer_id != id);
+    OBSERVER_COUNT.store(observers.len(), Ordering::Release);
+    observers.len() != count
+}
+
+/// Calls `notify` for all registered observers
+///
+/// The observers are called without holding the lock, so they may register or unregister
+/// observers themselves.
+pub(crate) fn notify(notify: impl Fn(&dyn PortObserver)) {
+    if OBSERVER_COUNT.load(Ordering::Acquire) == 0 {
+        return;
+    }
+    let observers: Vec<Arc<dyn PortObserver>> = registry()
+        .observers
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(_, observer)| observer.clone())
+        .collect();
+    for observer in observers {
+        notify(&*observer);
+    }
+}
+
+/// Reports the data of a successful read or write to `notify`
+pub(crate) fn notify_io(
+    result: &std::io::Result<usize>,
+    data: &[u8],
+    notify: impl Fn(&dyn PortObserver, &[u8]),
+) {
+    if let Ok(n) = result {
+        if *n > 0 {
+            self::notify(|observer| notify(observer, &data[..*n]));
+        }
+    }
+}
+
+/// Reports the data of a successful vectored read or write to `notify`
+///
+/// The data is only collected from `bufs` if there are observers.
+#[cfg(unix)]
+pub(crate) fn notify_io_vectored<B: std::ops::Deref<Target = [u8]>>(
+    result: &std::io::Result<usize>,
+    bufs: &[B],
+    notify: impl Fn(&dyn PortObserver, &[u8]),
+) {
+    if let Ok(n) = result {
+        if *n > 0 && OBSERVER_COUNT.load(Ordering::Acquire) > 0 {
+            let data: Vec<u8> = bufs
+                .iter()
+                .flat_map(|buf| buf.iter().copied())
+                .take(*n)
+                .collect();
+            self::notify(|observer| notify(observer, &data));
+        }
+    }
+}
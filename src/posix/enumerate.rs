@@ -10,6 +10,7 @@ cfg_if! {
     if #[cfg(any(target_os = "ios", target_os = "macos"))] {
         use core_foundation::base::CFType;
         use core_foundation::base::TCFType;
+        use core_foundation::data::CFData;
         use core_foundation::dictionary::CFDictionary;
         use core_foundation::dictionary::CFMutableDictionary;
         use core_foundation::number::CFNumber;
@@ -27,24 +28,45 @@ cfg_if! {
 }
 
 #[cfg(any(
-    target_os = "freebsd",
+    target_os = "android",
     target_os = "ios",
     target_os = "linux",
     target_os = "macos"
 ))]
+use crate::BluetoothPortInfo;
+#[cfg(any(
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "ios",
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
 use crate::SerialPortType;
-#[cfg(any(target_os = "ios", target_os = "linux", target_os = "macos"))]
-use crate::UsbPortInfo;
 #[cfg(any(
     target_os = "android",
+    target_os = "freebsd",
+    target_os = "ios",
+    target_os = "linux",
+    target_os = "macos"
+))]
+use crate::UsbClass;
+#[cfg(any(
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "ios",
+    target_os = "linux",
+    target_os = "macos"
+))]
+use crate::UsbPortInfo;
+use crate::{EnumerationWarning, Result, SerialPortInfo};
+#[cfg(any(
     target_os = "ios",
     all(target_os = "linux", not(target_env = "musl"), feature = "libudev"),
     target_os = "macos",
-    target_os = "netbsd",
-    target_os = "openbsd",
 ))]
 use crate::{Error, ErrorKind};
-use crate::{Result, SerialPortInfo};
 
 /// Retrieves the udev property value named by `key`. If the value exists, then it will be
 /// converted to a String, otherwise None will be returned.
@@ -109,6 +131,29 @@ fn udev_restore_spaces(source: String) -> String {
     source.replace('_', " ")
 }
 
+/// The directory where udev creates symlinks named after the serial ports' USB identities
+#[cfg(any(target_os = "android", target_os = "linux"))]
+const SERIAL_BY_ID: &str = "/dev/serial/by-id";
+
+/// The directory where udev creates symlinks named after the serial ports' physical paths
+#[cfg(any(target_os = "android", target_os = "linux"))]
+const SERIAL_BY_PATH: &str = "/dev/serial/by-path";
+
+/// Looks up a symlink in `dir` which points to the device file `port`. If there are several of
+/// them, the first one in lexicographical order is returned.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+fn find_alias(dir: &str, port: impl AsRef<std::path::Path>) -> Option<String> {
+    let device = port.as_ref().canonicalize().ok()?;
+    std::path::Path::new(dir)
+        .read_dir()
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|link| link.canonicalize().map_or(false, |target| target == device))
+        .map(|link| link.to_string_lossy().into_owned())
+        .min()
+}
+
 #[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))]
 fn port_type(d: &libudev::Device) -> Result<SerialPortType> {
     match d.property_value("ID_BUS").and_then(OsStr::to_str) {
@@ -123,15 +168,19 @@ fn port_type(d: &libudev::Device) -> Result<SerialPortType> {
             let product =
                 udev_property_encoded_or_replaced_as_string(d, "ID_MODEL_ENC", "ID_MODEL")
                     .or_else(|| udev_property_as_string(d, "ID_MODEL_FROM_DATABASE"));
+            let (device_class, interface_class) = udev_usb_classes(d);
             Ok(SerialPortType::UsbPort(UsbPortInfo {
                 vid: udev_hex_property_as_int(d, "ID_VENDOR_ID", &u16::from_str_radix)?,
                 pid: udev_hex_property_as_int(d, "ID_MODEL_ID", &u16::from_str_radix)?,
                 serial_number,
                 manufacturer,
                 product,
-                #[cfg(feature = "usbportinfo-interface")]
                 interface: udev_hex_property_as_int(d, "ID_USB_INTERFACE_NUM", &u8::from_str_radix)
                     .ok(),
+                location: udev_usb_location(d),
+                bcd_device: udev_hex_property_as_int(d, "ID_REVISION", &u16::from_str_radix).ok(),
+                device_class,
+                interface_class,
             }))
         }
         Some("pci") => {
@@ -154,19 +203,28 @@ fn port_type(d: &libudev::Device) -> Result<SerialPortType> {
                     "ID_USB_MODEL_ENC",
                     "ID_USB_MODEL",
                 );
+                let (device_class, interface_class) = udev_usb_classes(d);
                 Ok(SerialPortType::UsbPort(UsbPortInfo {
                     vid: udev_hex_property_as_int(d, "ID_USB_VENDOR_ID", &u16::from_str_radix)?,
                     pid: udev_hex_property_as_int(d, "ID_USB_MODEL_ID", &u16::from_str_radix)?,
                     serial_number: udev_property_as_string(d, "ID_USB_SERIAL_SHORT"),
                     manufacturer,
                     product,
-                    #[cfg(feature = "usbportinfo-interface")]
                     interface: udev_hex_property_as_int(
                         d,
                         "ID_USB_INTERFACE_NUM",
                         &u8::from_str_radix,
                     )
                     .ok(),
+                    location: udev_usb_location(d),
+                    bcd_device: udev_hex_property_as_int(
+                        d,
+                        "ID_USB_REVISION",
+                        &u16::from_str_radix,
+                    )
+                    .ok(),
+                    device_class,
+                    interface_class,
                 }))
             } else {
                 Ok(SerialPortType::PciPort)
@@ -177,6 +235,10 @@ fn port_type(d: &libudev::Device) -> Result<SerialPortType> {
             .as_deref()
             .and_then(parse_modalias)
             .map_or(Ok(SerialPortType::Unknown), |port_info| {
+                let port_info = UsbPortInfo {
+                    location: udev_usb_location(d),
+                    ..port_info
+                };
                 Ok(SerialPortType::UsbPort(port_info))
             }),
         _ => Ok(SerialPortType::Unknown),
@@ -210,6 +272,44 @@ fn find_usb_interface_from_parents(parent: Option<libudev::Device>) -> Option<li
     Some(p)
 }
 
+/// Returns the device of the hardware providing the port, given the parent of the tty device.
+/// Since Linux 6.5, UART ports are represented by a controller and a port device on the
+/// `serial-base` bus in between, which get skipped.
+#[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))]
+fn udev_uart_device(parent: libudev::Device) -> Option<libudev::Device> {
+    if parent.subsystem() == Some(OsStr::new("serial-base")) {
+        parent.parent()?.parent()
+    } else {
+        Some(parent)
+    }
+}
+
+/// Returns the bus and port path (e.g. `1-1.4.2`) of the USB device `d` belongs to. This is the
+/// name of the device in sysfs.
+#[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))]
+fn udev_usb_location(d: &libudev::Device) -> Option<String> {
+    let mut p = d.parent()?;
+
+    // limit the query depth
+    for _ in 0..4 {
+        if p.devtype().and_then(OsStr::to_str) == Some("usb_device") {
+            return p.sysname()?.to_str().map(str::to_owned);
+        }
+        p = p.parent()?;
+    }
+
+    None
+}
+
+/// Returns the class codes of the USB device and interface `d` belongs to from the interface's
+/// modalias.
+#[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))]
+fn udev_usb_classes(d: &libudev::Device) -> (Option<UsbClass>, Option<UsbClass>) {
+    find_usb_interface_from_parents(d.parent())
+        .and_then(get_modalias_from_device)
+        .map_or((None, None), |moda| parse_modalias_classes(&moda))
+}
+
 #[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))]
 fn get_modalias_from_device(d: libudev::Device) -> Option<String> {
     Some(
@@ -253,7 +353,6 @@ fn parse_modalias(moda: &str) -> Option<UsbPortInfo> {
         manufacturer: None,
         product: None,
         // Only attempt to find the interface if the feature is enabled.
-        #[cfg(feature = "usbportinfo-interface")]
         interface: mod_tail.get(pid_start + 4..).and_then(|mod_tail| {
             mod_tail.find("in").and_then(|i_start| {
                 mod_tail
@@ -261,9 +360,45 @@ fn parse_modalias(moda: &str) -> Option<UsbPortInfo> {
                     .and_then(|interface| u8::from_str_radix(interface, 16).ok())
             })
         }),
+        location: None,
+        bcd_device: mod_tail
+            .get(pid_start + 5..)
+            .and_then(|mod_tail| mod_tail.strip_prefix('d'))
+            .and_then(|bcd_device| bcd_device.get(..4))
+            .and_then(|bcd_device| u16::from_str_radix(bcd_device, 16).ok()),
+        device_class: parse_modalias_classes(moda).0,
+        interface_class: parse_modalias_classes(moda).1,
     })
 }
 
+/// Extracts the device and interface class codes from a modalias like the one described above
+#[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))]
+fn parse_modalias_classes(moda: &str) -> (Option<UsbClass>, Option<UsbClass>) {
+    // The fields follow each other in a fixed order, so search from the end of the previous one,
+    // starting after the vendor and product IDs.
+    let mut tail = moda
+        .find("usb:v")
+        .and_then(|start| moda.get(start + 14..))
+        .unwrap_or_default();
+    let mut next = |marker: &str| {
+        let start = tail.find(marker)? + marker.len();
+        let value = tail.get(start..start + 2)?;
+        tail = tail.get(start + 2..)?;
+        u8::from_str_radix(value, 16).ok()
+    };
+    let mut next_class = |class, subclass, protocol| {
+        Some(UsbClass {
+            class: next(class)?,
+            subclass: next(subclass)?,
+            protocol: next(protocol)?,
+        })
+    };
+
+    let device_class = next_class("dc", "dsc", "dp");
+    let interface_class = next_class("ic", "isc", "ip");
+    (device_class, interface_class)
+}
+
 #[cfg(any(target_os = "ios", target_os = "macos"))]
 fn get_parent_device_by_type(
     device: io_object_t,
@@ -344,10 +479,22 @@ fn get_string_property(device_type: io_registry_entry_t, property: &str) -> Resu
         .ok_or(Error::new(ErrorKind::Unknown, "Failed to get string value"))
 }
 
+/// Returns the class codes of a USB device or interface, given by `kind`, from its IOKit properties
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+fn get_usb_class(device: io_registry_entry_t, kind: &str) -> Option<UsbClass> {
+    let property = |name| get_int_property(device, &format!("b{}{}", kind, name)).ok();
+    Some(UsbClass {
+        class: property("Class")? as u8,
+        subclass: property("SubClass")? as u8,
+        protocol: property("Protocol")? as u8,
+    })
+}
+
 #[cfg(any(target_os = "ios", target_os = "macos"))]
 /// Determine the serial port type based on the service object (like that returned by
-/// `IOIteratorNext`). Specific properties are extracted for USB devices.
-fn port_type(service: io_object_t) -> SerialPortType {
+/// `IOIteratorNext`). Specific properties are extracted for USB devices. Without `details`, only
+/// the vendor and product ID get looked up.
+fn port_type(service: io_object_t, details: bool) -> SerialPortType {
     let bluetooth_device_class_name = b"IOBluetoothSerialClient\0".as_ptr() as *const c_char;
     let usb_device_class_name = b"IOUSBHostInterface\0".as_ptr() as *const c_char;
     let legacy_usb_device_class_name = kIOUSBDeviceClassName;
@@ -355,9 +502,14 @@ fn port_type(service: io_object_t) -> SerialPortType {
     let maybe_usb_device = get_parent_device_by_type(service, usb_device_class_name)
         .or_else(|| get_parent_device_by_type(service, legacy_usb_device_class_name));
     if let Some(usb_device) = maybe_usb_device {
+        let vid = get_int_property(usb_device, "idVendor").unwrap_or_default() as u16;
+        let pid = get_int_property(usb_device, "idProduct").unwrap_or_default() as u16;
+        if !details {
+            return SerialPortType::UsbPort(usb_port_info_from_ids(vid, pid));
+        }
         SerialPortType::UsbPort(UsbPortInfo {
-            vid: get_int_property(usb_device, "idVendor").unwrap_or_default() as u16,
-            pid: get_int_property(usb_device, "idProduct").unwrap_or_default() as u16,
+            vid,
+            pid,
             serial_number: get_string_property(usb_device, "USB Serial Number").ok(),
             manufacturer: get_string_property(usb_device, "USB Vendor Name").ok(),
             product: get_string_property(usb_device, "USB Product Name").ok(),
@@ -367,10 +519,22 @@ fn port_type(service: io_object_t) -> SerialPortType {
             //
             // https://developer.apple.com/documentation/bundleresources/entitlements/com_apple_developer_driverkit_transport_usb
             // https://developer.apple.com/library/archive/documentation/DeviceDrivers/Conceptual/USBBook/USBOverview/USBOverview.html#//apple_ref/doc/uid/TP40002644-BBCEACAJ
-            #[cfg(feature = "usbportinfo-interface")]
             interface: get_int_property(usb_device, "bInterfaceNumber")
                 .map(|x| x as u8)
                 .ok(),
+            location: get_int_property(usb_device, "locationID")
+                .map(|x| format!("{:#010x}", x))
+                .ok(),
+            bcd_device: get_int_property(usb_device, "bcdDevice")
+                .map(|x| x as u16)
+                .ok(),
+            // With the legacy USB stack, the device's properties are already at hand. Otherwise,
+            // they are found at the interface's parent.
+            device_class: get_usb_class(usb_device, "Device").or_else(|| {
+                get_parent_device_by_type(service, b"IOUSBHostDevice\0".as_ptr() as *const c_char)
+                    .and_then(|device| get_usb_class(device, "Device"))
+            }),
+            interface_class: get_usb_class(usb_device, "Interface"),
         })
     } else if get_parent_device_by_type(service, bluetooth_device_class_name).is_some() {
         SerialPortType::BluetoothPort
@@ -379,11 +543,446 @@ fn port_type(service: io_object_t) -> SerialPortType {
     }
 }
 
+/// Builds the information about a USB device of which only the vendor and product ID are known
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+fn usb_port_info_from_ids(vid: u16, pid: u16) -> UsbPortInfo {
+    UsbPortInfo {
+        vid,
+        pid,
+        serial_number: None,
+        manufacturer: None,
+        product: None,
+        interface: None,
+        location: None,
+        bcd_device: None,
+        device_class: None,
+        interface_class: None,
+    }
+}
+
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+/// Returns a specific property of the given device as raw bytes.
+fn get_data_property(device_type: io_registry_entry_t, property: &str) -> Result<Vec<u8>> {
+    let cf_property = CFString::new(property);
+
+    let cf_type_ref = unsafe {
+        IORegistryEntryCreateCFProperty(
+            device_type,
+            cf_property.as_concrete_TypeRef(),
+            kCFAllocatorDefault,
+            0,
+        )
+    };
+    if cf_type_ref.is_null() {
+        return Err(Error::new(ErrorKind::Unknown, "Failed to get property"));
+    }
+
+    let cf_type = unsafe { CFType::wrap_under_create_rule(cf_type_ref) };
+    cf_type
+        .downcast::<CFData>()
+        .map(|data| data.bytes().to_vec())
+        .ok_or(Error::new(ErrorKind::Unknown, "Failed to get data value"))
+}
+
+/// Determines the remote device of a Bluetooth serial port from the properties of the
+/// `IOBluetoothSerialClient` it belongs to. Returns `None` for any other port.
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+fn bluetooth_port_info(service: io_object_t) -> Option<BluetoothPortInfo> {
+    let client = get_parent_device_by_type(
+        service,
+        b"IOBluetoothSerialClient\0".as_ptr() as *const c_char,
+    )?;
+    // The address is reported as six bytes. Unbound ports report an all zero address.
+    let address = get_data_property(client, "BTAddress")
+        .ok()
+        .filter(|address| address.len() == 6 && address.iter().any(|&b| b != 0))
+        .map(|address| {
+            let octets: Vec<_> = address.iter().map(|b| format!("{:02X}", b)).collect();
+            octets.join(":")
+        });
+    Some(BluetoothPortInfo {
+        address,
+        name: get_string_property(client, "BTName").ok(),
+    })
+}
+
+/// Splits the `key=value` pairs FreeBSD reports through the `%pnpinfo` and `%location` sysctls
+/// of a device. Values may be quoted.
+#[cfg(any(
+    target_os = "freebsd",
+    all(
+        test,
+        target_os = "linux",
+        not(target_env = "musl"),
+        feature = "libudev"
+    )
+))]
+fn parse_sysctl_fields(s: &str) -> Vec<(&str, &str)> {
+    let mut fields = Vec::new();
+    let mut rest = s.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = &rest[..eq];
+        let value = &rest[eq + 1..];
+        let (value, remainder) = if let Some(quoted) = value.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            }
+        } else {
+            match value.find(' ') {
+                Some(end) => (&value[..end], &value[end..]),
+                None => (value, ""),
+            }
+        };
+        fields.push((key, value));
+        rest = remainder.trim_start();
+    }
+    fields
+}
+
+/// Builds the USB port information from the sysctls FreeBSD provides for the device behind a
+/// ucom(4) port, e.g.
+///
+/// ```text
+/// dev.uftdi.0.%pnpinfo: vendor=0x0403 product=0x6001 devclass=0x00 devsubclass=0x00 devproto=0x00 sernum="A5002kMh" release=0x0600 mode=host intclass=0xff intsubclass=0xff intprotocol=0xff
+/// dev.uftdi.0.%location: bus=0 hubaddr=1 port=2 devaddr=3 interface=0 ugen=ugen0.3
+/// dev.uftdi.0.%desc: FTDI FT232R USB UART, class 0/0, rev 2.00/6.00, addr 3
+/// ```
+///
+/// The description names the manufacturer and the product without telling them apart, so it is
+/// reported as the product as a whole.
+#[cfg(any(
+    target_os = "freebsd",
+    all(
+        test,
+        target_os = "linux",
+        not(target_env = "musl"),
+        feature = "libudev"
+    )
+))]
+fn parse_ucom_sysctls(
+    pnpinfo: &str,
+    location: Option<&str>,
+    desc: Option<&str>,
+) -> Option<UsbPortInfo> {
+    let pnpinfo = parse_sysctl_fields(pnpinfo);
+    let field = |name: &str| {
+        pnpinfo
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+    };
+    let hex_u16 = |name: &str| {
+        field(name).and_then(|value| u16::from_str_radix(value.trim_start_matches("0x"), 16).ok())
+    };
+    let hex_u8 = |name: &str| {
+        field(name).and_then(|value| u8::from_str_radix(value.trim_start_matches("0x"), 16).ok())
+    };
+    let class = |class: &str, subclass: &str, protocol: &str| {
+        Some(UsbClass {
+            class: hex_u8(class)?,
+            subclass: hex_u8(subclass)?,
+            protocol: hex_u8(protocol)?,
+        })
+    };
+
+    Some(UsbPortInfo {
+        vid: hex_u16("vendor")?,
+        pid: hex_u16("product")?,
+        serial_number: field("sernum")
+            .filter(|serial| !serial.is_empty())
+            .map(String::from),
+        manufacturer: None,
+        product: desc
+            .and_then(|desc| desc.split(", class ").next())
+            .filter(|product| !product.is_empty())
+            .map(String::from),
+        interface: location.and_then(|location| {
+            parse_sysctl_fields(location)
+                .into_iter()
+                .find(|(key, _)| *key == "interface")
+                .and_then(|(_, value)| value.parse().ok())
+        }),
+        location: None,
+        bcd_device: hex_u16("release"),
+        device_class: class("devclass", "devsubclass", "devproto"),
+        interface_class: class("intclass", "intsubclass", "intprotocol"),
+    })
+}
+
+cfg_if! {
+    if #[cfg(any(target_os = "android", target_os = "linux"))] {
+        use std::fs;
+        use std::io;
+        use std::path::{Path, PathBuf};
+
+        /// Reads the trimmed contents of a sysfs attribute file, which is `None` if the attribute
+        /// doesn't exist
+        fn read_attribute(dir: &Path, file: &str) -> io::Result<Option<String>> {
+            match fs::read_to_string(dir.join(file)) {
+                Ok(s) => Ok(Some(s.trim().to_owned())),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e),
+            }
+        }
+
+        fn read_file_to_u8(dir: &Path, file: &str) -> Option<u8> {
+            u8::from_str_radix(&read_attribute(dir, file).ok()??, 16).ok()
+        }
+
+        /// Returns the sysfs directory of the hardware providing the port, given the port's
+        /// `device` directory. Since Linux 6.5, UART ports are represented by a controller and a
+        /// port device on the `serial-base` bus in between, which get skipped.
+        fn read_hardware_path(path: &Path) -> Option<PathBuf> {
+            let path = path.canonicalize().ok()?;
+            let subsystem = path.join("subsystem").canonicalize().ok()?;
+            if subsystem.file_name()? == "serial-base" {
+                Some(path.parent()?.parent()?.to_path_buf())
+            } else {
+                Some(path)
+            }
+        }
+
+        fn read_driver(hardware_path: &Path) -> Option<String> {
+            let driver = hardware_path.join("driver").canonicalize().ok()?;
+            Some(driver.file_name()?.to_string_lossy().into_owned())
+        }
+
+        /// Determines the port type of a serial device which isn't backed by a hardware device,
+        /// given its `/sys/class/tty` directory. These are Bluetooth serial ports bound with
+        /// rfcomm(1) and the serial functions of a USB gadget, e.g. `g_serial` or the `acm` and
+        /// `gser` functions configured through configfs. Returns `None` for any other device.
+        fn read_virtual_port_type(tty_path: &Path) -> Option<SerialPortType> {
+            let name = tty_path.file_name()?.to_str()?;
+            if name.starts_with("rfcomm") {
+                Some(SerialPortType::BluetoothPort)
+            } else if name.starts_with("ttyGS") {
+                Some(SerialPortType::Unknown)
+            } else {
+                None
+            }
+        }
+
+        /// Determines the remote device of a Bluetooth serial port bound with rfcomm(1), given its
+        /// `/sys/class/tty` directory. Returns `None` for any other device.
+        fn read_bluetooth_port_info(tty_path: &Path) -> Option<BluetoothPortInfo> {
+            if !tty_path.file_name()?.to_str()?.starts_with("rfcomm") {
+                return None;
+            }
+            // Unbound ports report an all zero address.
+            let address = read_attribute(tty_path, "address")
+                .ok()
+                .flatten()
+                .map(|address| address.to_uppercase())
+                .filter(|address| address != "00:00:00:00:00:00");
+            Some(BluetoothPortInfo {
+                address,
+                name: None,
+            })
+        }
+
+        fn read_port_type(
+            path: &Path,
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Option<SerialPortType> {
+            let subsystem = path.join("subsystem").canonicalize().ok()?;
+            let subsystem = subsystem.file_name()?.to_string_lossy();
+
+            match subsystem.as_ref() {
+                // Broadcom SoC UARTs (of Raspberry Pi devices).
+                "amba" => Some(SerialPortType::Unknown),
+                "pci" => Some(SerialPortType::PciPort),
+                "platform" => Some(SerialPortType::Unknown),
+                "pnp" => Some(SerialPortType::Unknown),
+                "usb" => usb_port_type(path, warnings),
+                "usb-serial" => usb_port_type(path.parent()?, warnings),
+                _ => None,
+            }
+        }
+
+        fn usb_port_type(
+            interface_path: &Path,
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Option<SerialPortType> {
+            // Report a USB device whose IDs can't be read without its details instead of leaving
+            // it out.
+            match read_usb_port_info(interface_path, warnings) {
+                Ok(info) => info.map(SerialPortType::UsbPort),
+                Err(e) => {
+                    warnings.push(EnumerationWarning::new(
+                        interface_path.to_string_lossy(),
+                        format!("Failed to read the USB device IDs: {}", e),
+                    ));
+                    Some(SerialPortType::Unknown)
+                }
+            }
+        }
+
+        /// Reads the information about the USB device providing the interface at
+        /// `interface_path`, which is `None` if it isn't a USB device
+        ///
+        /// Failing to read the IDs of the device is an error. Failures to read other attributes
+        /// are recorded in `warnings` and leave the attribute out.
+        fn read_usb_port_info(
+            interface_path: &Path,
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> io::Result<Option<UsbPortInfo>> {
+            let device_path = match interface_path.parent() {
+                Some(path) => path,
+                None => return Ok(None),
+            };
+
+            let id = |file: &str| -> io::Result<Option<u16>> {
+                Ok(read_attribute(device_path, file)?
+                    .and_then(|id| u16::from_str_radix(&id, 16).ok()))
+            };
+            let (vid, pid) = match (id("idVendor")?, id("idProduct")?) {
+                (Some(vid), Some(pid)) => (vid, pid),
+                _ => return Ok(None),
+            };
+            let mut attribute = |file: &str| {
+                read_attribute(device_path, file).unwrap_or_else(|e| {
+                    let path = device_path.join(file);
+                    warnings.push(EnumerationWarning::new(path.to_string_lossy(), e.to_string()));
+                    None
+                })
+            };
+            let interface = read_file_to_u8(interface_path, "bInterfaceNumber");
+            let serial_number = attribute("serial");
+            let product = attribute("product");
+            let manufacturer = attribute("manufacturer");
+            // The USB device's directory is named after its bus and port path.
+            let location = device_path.file_name().map(|name| name.to_string_lossy().into_owned());
+            let bcd_device =
+                attribute("bcdDevice").and_then(|bcd| u16::from_str_radix(&bcd, 16).ok());
+            let device_class = read_usb_class(device_path, "bDevice");
+            let interface_class = read_usb_class(interface_path, "bInterface");
+
+            Ok(Some(UsbPortInfo {
+                vid,
+                pid,
+                serial_number,
+                manufacturer,
+                product,
+                interface,
+                location,
+                bcd_device,
+                device_class,
+                interface_class,
+            }))
+        }
+
+        fn read_usb_class(dir: &Path, prefix: &str) -> Option<UsbClass> {
+            Some(UsbClass {
+                class: read_file_to_u8(dir, &format!("{}Class", prefix))?,
+                subclass: read_file_to_u8(dir, &format!("{}SubClass", prefix))?,
+                protocol: read_file_to_u8(dir, &format!("{}Protocol", prefix))?,
+            })
+        }
+
+        /// Scans `/sys/class/tty` for serial devices.
+        fn available_ports_sysfs(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
+            let mut vec = Vec::new();
+            let sys_path = Path::new("/sys/class/tty/");
+            let dev_path = Path::new("/dev");
+            for path in sys_path.read_dir()? {
+                let raw_path = match path {
+                    Ok(path) => path.path(),
+                    Err(e) => {
+                        let device = sys_path.to_string_lossy();
+                        warnings.push(EnumerationWarning::new(device, e.to_string()));
+                        continue;
+                    }
+                };
+                let mut path = raw_path.clone();
+
+                // Some serial devices are virtual and come without a `device` directory.
+                let virtual_port_type = read_virtual_port_type(&raw_path);
+
+                path.push("device");
+                if !path.is_dir() && virtual_port_type.is_none() {
+                    continue;
+                }
+
+                // Determine port type and proceed, if it's a known.
+                //
+                // TODO: Switch to a likely more readable let-else statement when our MSRV supports
+                // it.
+                let hardware_path = read_hardware_path(&path);
+                let port_type = virtual_port_type
+                    .or_else(|| {
+                        hardware_path
+                            .as_deref()
+                            .and_then(|path| read_port_type(path, warnings))
+                    });
+                let port_type = if let Some(port_type) = port_type {
+                    port_type
+                } else {
+                    continue;
+                };
+
+                // Generate the device file path `/dev/DEVICE` from the TTY class path
+                // `/sys/class/tty/DEVICE` and emit a serial device if this path exists. There are
+                // no further checks (yet) due to `Path::is_file` reports only regular files.
+                //
+                // See https://github.com/serialport/serialport-rs/issues/66 for details.
+                if let Some(file_name) = raw_path.file_name() {
+                    let device_file = dev_path.join(file_name);
+                    if !device_file.exists() {
+                        continue;
+                    }
+
+                    // The 8250 driver registers a fixed number of legacy ports, regardless of
+                    // whether the hardware is present. Only report the ones which can be opened.
+                    let driver = hardware_path.as_deref().and_then(read_driver);
+                    if driver.as_deref() == Some("serial8250")
+                        && crate::new(device_file.to_string_lossy(), 9600).open().is_err()
+                    {
+                        continue;
+                    }
+
+                    vec.push(SerialPortInfo {
+                        port_name: device_file.to_string_lossy().to_string(),
+                        port_type,
+                        friendly_name: None,
+                        description: None,
+                        instance_id: None,
+                        counterpart: None,
+                        by_id: find_alias(SERIAL_BY_ID, &device_file),
+                        by_path: find_alias(SERIAL_BY_PATH, &device_file),
+                        bluetooth: read_bluetooth_port_info(&raw_path),
+                    });
+                }
+            }
+            Ok(vec)
+        }
+    }
+}
+
 cfg_if! {
     if #[cfg(any(target_os = "ios", target_os = "macos"))] {
         /// Scans the system for serial ports and returns a list of them.
         /// The `SerialPortInfo` struct contains the name of the port which can be used for opening it.
-        pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+        pub fn available_ports(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
+            available_ports_iokit(warnings, true)
+        }
+
+        /// Scans the system for serial ports without looking up the string properties of USB
+        /// devices, which takes a while for every device.
+        pub fn available_ports_lossy(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
+            available_ports_iokit(warnings, false)
+        }
+
+        fn available_ports_iokit(
+            warnings: &mut Vec<EnumerationWarning>,
+            details: bool,
+        ) -> Result<Vec<SerialPortInfo>> {
             use mach2::kern_return::KERN_SUCCESS;
             use mach2::port::{mach_port_t, MACH_PORT_NULL};
 
@@ -467,29 +1066,50 @@ cfg_if! {
                         let props = props.assume_init();
                         let props: CFDictionary<CFString, *const c_void> = CFDictionary::wrap_under_create_rule(props);
 
-                        for key in ["IOCalloutDevice", "IODialinDevice"].iter() {
-                            let cf_key = CFString::new(key);
-
-                            if let Some(cf_ref) = props.find(cf_key) {
-                                let cf_type = CFType::wrap_under_get_rule(*cf_ref);
-                                match cf_type
-                                     .downcast::<CFString>()
-                                     .map(|s| s.to_string())
-                                {
-                                    Some(path) => {
-                                        vec.push(SerialPortInfo {
-                                            port_name: path,
-                                            port_type: port_type(modem_service),
-                                        });
-                                    }
-                                    None => return Err(Error::new(ErrorKind::Unknown, format!("Failed to get string value for {}", key))),
-                                }
-                            } else {
-                                return Err(Error::new(ErrorKind::Unknown, format!("Key {} missing in dict", key)));
+                        let path = |key: &str| {
+                            props
+                                .find(CFString::new(key))
+                                .and_then(|cf_ref| {
+                                    CFType::wrap_under_get_rule(*cf_ref).downcast::<CFString>()
+                                })
+                                .map(|s| s.to_string())
+                        };
+                        let paths = match (path("IOCalloutDevice"), path("IODialinDevice")) {
+                            (Some(callout), Some(dialin)) => [callout, dialin],
+                            (callout, dialin) => {
+                                let device = callout
+                                    .or(dialin)
+                                    .unwrap_or_else(|| "IOSerialBSDClient".to_string());
+                                warnings.push(EnumerationWarning::new(
+                                    device,
+                                    "Failed to get the device nodes of the port",
+                                ));
+                                continue;
                             }
+                        };
+
+                        // Both device nodes belong to the same port, so each one names the other
+                        // as its counterpart.
+                        let port_type = port_type(modem_service, details);
+                        let bluetooth = bluetooth_port_info(modem_service);
+                        for (path, _counterpart) in [(&paths[0], &paths[1]), (&paths[1], &paths[0])].iter() {
+                            vec.push(SerialPortInfo {
+                                port_name: path.to_string(),
+                                port_type: port_type.clone(),
+                                friendly_name: None,
+                                description: None,
+                                instance_id: None,
+                                counterpart: Some(_counterpart.to_string()),
+                                by_id: None,
+                                by_path: None,
+                                bluetooth: bluetooth.clone(),
+                            });
                         }
                     } else {
-                        return Err(Error::new(ErrorKind::Unknown, format!("ERROR: {}", result)));
+                        warnings.push(EnumerationWarning::new(
+                            "IOSerialBSDClient",
+                            format!("Failed to get the properties of the port: {}", result),
+                        ));
                     }
                 }
             }
@@ -499,30 +1119,71 @@ cfg_if! {
         /// Scans the system for serial ports and returns a list of them.
         /// The `SerialPortInfo` struct contains the name of the port
         /// which can be used for opening it.
-        pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+        ///
+        /// The ports are looked up with udev. If udev is not available, e.g. within a container,
+        /// `/sys/class/tty` is scanned instead.
+        pub fn available_ports(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
+            available_ports_udev(warnings).or_else(|_| {
+                warnings.clear();
+                available_ports_sysfs(warnings)
+            })
+        }
+
+        fn available_ports_udev(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
             let mut vec = Vec::new();
-            if let Ok(context) = libudev::Context::new() {
-                let mut enumerator = libudev::Enumerator::new(&context)?;
-                enumerator.match_subsystem("tty")?;
-                let devices = enumerator.scan_devices()?;
-                for d in devices {
-                    if let Some(p) = d.parent() {
-                        if let Some(devnode) = d.devnode() {
-                            if let Some(path) = devnode.to_str() {
-                                if let Some(driver) = p.driver() {
-                                    if driver == "serial8250" && crate::new(path, 9600).open().is_err() {
-                                        continue;
-                                    }
-                                }
-                                // Stop bubbling up port_type errors here so problematic ports are just
-                                // skipped instead of causing no ports to be returned.
-                                if let Ok(pt) = port_type(&d) {
-                                    vec.push(SerialPortInfo {
-                                        port_name: String::from(path),
-                                        port_type: pt,
-                                    });
+            let context = libudev::Context::new()?;
+            let mut enumerator = libudev::Enumerator::new(&context)?;
+            enumerator.match_subsystem("tty")?;
+            let devices = enumerator.scan_devices()?;
+            for d in devices {
+                // Some serial devices are virtual and come without a parent.
+                if let Some(pt) = d.syspath().and_then(read_virtual_port_type) {
+                    if let Some(path) = d.devnode().and_then(|devnode| devnode.to_str()) {
+                        vec.push(SerialPortInfo {
+                            port_name: String::from(path),
+                            port_type: pt,
+                            friendly_name: None,
+                            description: None,
+                            instance_id: None,
+                            counterpart: None,
+                            by_id: None,
+                            by_path: None,
+                            bluetooth: d.syspath().and_then(read_bluetooth_port_info),
+                        });
+                    }
+                    continue;
+                }
+
+                if let Some(p) = d.parent() {
+                    if let Some(devnode) = d.devnode() {
+                        if let Some(path) = devnode.to_str() {
+                            if let Some(driver) = udev_uart_device(p).as_ref().and_then(libudev::Device::driver) {
+                                if driver == "serial8250" && crate::new(path, 9600).open().is_err() {
+                                    continue;
                                 }
                             }
+                            // Stop bubbling up port_type errors here so problematic ports are
+                            // reported without their details instead of causing no ports to be
+                            // returned.
+                            let pt = port_type(&d).unwrap_or_else(|e| {
+                                warnings.push(EnumerationWarning::new(path, e.to_string()));
+                                SerialPortType::Unknown
+                            });
+                            vec.push(SerialPortInfo {
+                                port_name: String::from(path),
+                                port_type: pt,
+                                friendly_name: None,
+                                description: None,
+                                instance_id: None,
+                                counterpart: None,
+                                by_id: find_alias(SERIAL_BY_ID, path),
+                                by_path: find_alias(SERIAL_BY_PATH, path),
+                                bluetooth: None,
+                            });
                         }
                     }
                 }
@@ -530,142 +1191,338 @@ cfg_if! {
             Ok(vec)
         }
     } else if #[cfg(target_os = "linux")] {
-        use std::fs::File;
-        use std::io::Read;
-        use std::path::Path;
-
-        fn read_file_to_trimmed_string(dir: &Path, file: &str) -> Option<String> {
-            let path = dir.join(file);
-            let mut s = String::new();
-            File::open(path).ok()?.read_to_string(&mut s).ok()?;
-            Some(s.trim().to_owned())
-        }
-
-        fn read_file_to_u16(dir: &Path, file: &str) -> Option<u16> {
-            u16::from_str_radix(&read_file_to_trimmed_string(dir, file)?, 16).ok()
+        /// Scans `/sys/class/tty` for serial devices (on Linux systems without libudev).
+        pub fn available_ports(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
+            available_ports_sysfs(warnings)
         }
+    } else if #[cfg(target_os = "android")] {
+        /// The prefixes of the serial devices reported when `/sys/class/tty` can't be read
+        const DEV_PREFIXES: &[&str] = &["ttyACM", "ttyGS", "ttyS", "ttyUSB"];
 
-        #[cfg(feature = "usbportinfo-interface")]
-        fn read_file_to_u8(dir: &Path, file: &str) -> Option<u8> {
-            u8::from_str_radix(&read_file_to_trimmed_string(dir, file)?, 16).ok()
+        /// Scans the system for serial ports and returns a list of them.
+        /// The `SerialPortInfo` struct contains the name of the port
+        /// which can be used for opening it.
+        ///
+        /// Like on Linux without udev, `/sys/class/tty` is scanned. As apps are usually not
+        /// permitted to read it, the `/dev/ttyACM*`, `/dev/ttyGS*`, `/dev/ttyS*` and
+        /// `/dev/ttyUSB*` devices are reported otherwise. Information about USB devices is provided where their sysfs
+        /// entries can be read.
+        pub fn available_ports(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
+            match available_ports_sysfs(warnings) {
+                Ok(ports) if !ports.is_empty() => Ok(ports),
+                _ => {
+                    warnings.clear();
+                    available_ports_dev(warnings)
+                }
+            }
         }
 
-        fn read_port_type(path: &Path) -> Option<SerialPortType> {
-            let path = path
-                .canonicalize()
-                .ok()?;
-            let subsystem = path.join("subsystem").canonicalize().ok()?;
-            let subsystem = subsystem.file_name()?.to_string_lossy();
+        fn available_ports_dev(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
+            let mut vec = Vec::new();
+            for path in Path::new("/dev").read_dir()? {
+                let path = match path {
+                    Ok(path) => path,
+                    Err(e) => {
+                        warnings.push(EnumerationWarning::new("/dev", e.to_string()));
+                        continue;
+                    }
+                };
+                let filename = path.file_name();
+                let filename_string = filename.to_string_lossy();
+                let is_port = DEV_PREFIXES.iter().any(|prefix| {
+                    filename_string
+                        .strip_prefix(prefix)
+                        .map_or(false, |unit| unit.starts_with(|c: char| c.is_ascii_digit()))
+                });
+                if !is_port {
+                    continue;
+                }
 
-            match subsystem.as_ref() {
-                // Broadcom SoC UARTs (of Raspberry Pi devices).
-                "amba" => Some(SerialPortType::Unknown),
-                "pci" => Some(SerialPortType::PciPort),
-                "pnp" => Some(SerialPortType::Unknown),
-                "usb" => usb_port_type(&path),
-                "usb-serial" => usb_port_type(path.parent()?),
-                _ => None,
+                let device_file = path.path();
+                vec.push(SerialPortInfo {
+                    port_name: device_file.to_string_lossy().to_string(),
+                    port_type: read_dev_port_type(&filename_string, warnings),
+                    friendly_name: None,
+                    description: None,
+                    instance_id: None,
+                    counterpart: None,
+                    by_id: find_alias(SERIAL_BY_ID, &device_file),
+                    by_path: find_alias(SERIAL_BY_PATH, &device_file),
+                    bluetooth: None,
+                });
             }
+            Ok(vec)
         }
 
-        fn usb_port_type(interface_path: &Path) -> Option<SerialPortType> {
-            let info = read_usb_port_info(interface_path)?;
-            Some(SerialPortType::UsbPort(info))
+        /// Determines the port type of a device from its entry in `/sys/class/tty` or, for USB
+        /// serial converters, `/sys/bus/usb-serial/devices`. Either of them may be inaccessible.
+        fn read_dev_port_type(
+            name: &str,
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> SerialPortType {
+            read_hardware_path(&Path::new("/sys/class/tty").join(name).join("device"))
+                .and_then(|path| read_port_type(&path, warnings))
+                .or_else(|| {
+                    let path = Path::new("/sys/bus/usb-serial/devices").join(name);
+                    let path = path.canonicalize().ok()?;
+                    usb_port_type(path.parent()?, warnings)
+                })
+                .unwrap_or(SerialPortType::Unknown)
         }
+    } else if #[cfg(target_os = "freebsd")] {
+        use std::collections::HashMap;
+        use std::ffi::CString;
+        use std::mem;
+        use std::path::Path;
+        use std::ptr;
 
-        fn read_usb_port_info(interface_path: &Path) -> Option<UsbPortInfo> {
-            let device_path = interface_path.parent()?;
+        use nix::libc;
 
-            let vid = read_file_to_u16(&device_path, "idVendor")?;
-            let pid = read_file_to_u16(&device_path, "idProduct")?;
-            #[cfg(feature = "usbportinfo-interface")]
-            let interface = read_file_to_u8(&interface_path, &"bInterfaceNumber");
-            let serial_number = read_file_to_trimmed_string(&device_path, &"serial");
-            let product = read_file_to_trimmed_string(&device_path, &"product");
-            let manufacturer = read_file_to_trimmed_string(&device_path, &"manufacturer");
+        /// The leading components of the OIDs of the `sysctl.name` and `sysctl.next` queries,
+        /// which return the name and the OID of the leaf after the OID appended to them.
+        const SYSCTL_NAME: [libc::c_int; 2] = [0, 1];
+        const SYSCTL_NEXT: [libc::c_int; 2] = [0, 2];
 
-            Some(UsbPortInfo {
-                vid,
-                pid,
-                serial_number,
-                manufacturer,
-                product,
-                #[cfg(feature = "usbportinfo-interface")]
-                interface,
-            })
+        /// Looks up the OID of a sysctl like `dev.uftdi.0.%pnpinfo`.
+        fn sysctl_oid(name: &str) -> Option<Vec<libc::c_int>> {
+            let name = CString::new(name).ok()?;
+            let mut oid = vec![0; libc::CTL_MAXNAME as usize];
+            let mut len = oid.len();
+            let result =
+                unsafe { libc::sysctlnametomib(name.as_ptr(), oid.as_mut_ptr(), &mut len) };
+            if result != 0 {
+                return None;
+            }
+            oid.truncate(len);
+            Some(oid)
         }
 
-        /// Scans `/sys/class/tty` for serial devices (on Linux systems without libudev).
-        pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
-            let mut vec = Vec::new();
-            let sys_path = Path::new("/sys/class/tty/");
-            let dev_path = Path::new("/dev");
-            for path in sys_path.read_dir().expect("/sys/class/tty/ doesn't exist on this system") {
-                let raw_path = path?.path().clone();
-                let mut path = raw_path.clone();
+        /// Reads a string valued sysctl by its OID, which includes the queries of the
+        /// `sysctl` node.
+        fn sysctl_oid_string(oid: &[libc::c_int]) -> Option<String> {
+            let mut len: libc::size_t = 0;
+            let result = unsafe {
+                libc::sysctl(
+                    oid.as_ptr(),
+                    oid.len() as libc::c_uint,
+                    ptr::null_mut(),
+                    &mut len,
+                    ptr::null(),
+                    0,
+                )
+            };
+            if result != 0 {
+                return None;
+            }
 
-                path.push("device");
-                if !path.is_dir() {
-                    continue;
-                }
+            let mut buf = vec![0u8; len];
+            let result = unsafe {
+                libc::sysctl(
+                    oid.as_ptr(),
+                    oid.len() as libc::c_uint,
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    &mut len,
+                    ptr::null(),
+                    0,
+                )
+            };
+            if result != 0 {
+                return None;
+            }
+            buf.truncate(len);
+            while buf.last() == Some(&0) {
+                buf.pop();
+            }
+            String::from_utf8(buf).ok()
+        }
 
-                // Determine port type and proceed, if it's a known.
-                //
-                // TODO: Switch to a likely more readable let-else statement when our MSRV supports
-                // it.
-                let port_type = read_port_type(&path);
-                let port_type = if let Some(port_type) = port_type {
-                    port_type
-                } else {
-                    continue;
-                };
+        /// Reads a string valued sysctl like `dev.uftdi.0.%pnpinfo`.
+        fn sysctl_string(name: &str) -> Option<String> {
+            sysctl_oid_string(&sysctl_oid(name)?)
+        }
 
-                // Generate the device file path `/dev/DEVICE` from the TTY class path
-                // `/sys/class/tty/DEVICE` and emit a serial device if this path exists. There are
-                // no further checks (yet) due to `Path::is_file` reports only regular files.
-                //
-                // See https://github.com/serialport/serialport-rs/issues/66 for details.
-                if let Some(file_name) = raw_path.file_name() {
-                    let device_file = dev_path.join(file_name);
-                    if !device_file.exists() {
-                        continue;
-                    }
+        /// Lists the names of all leaves below a sysctl node like `dev`, walking the tree with
+        /// `sysctl.next`.
+        fn sysctl_leaves(node: &str) -> Vec<String> {
+            let mut leaves = Vec::new();
+            let prefix = match sysctl_oid(node) {
+                Some(prefix) => prefix,
+                None => return leaves,
+            };
 
-                    vec.push(SerialPortInfo {
-                        port_name: device_file.to_string_lossy().to_string(),
-                        port_type,
-                    });
+            let mut oid = prefix.clone();
+            loop {
+                let query = [&SYSCTL_NEXT[..], &oid].concat();
+                let mut next = [0 as libc::c_int; libc::CTL_MAXNAME as usize];
+                let mut len = mem::size_of_val(&next);
+                let result = unsafe {
+                    libc::sysctl(
+                        query.as_ptr(),
+                        query.len() as libc::c_uint,
+                        next.as_mut_ptr() as *mut libc::c_void,
+                        &mut len,
+                        ptr::null(),
+                        0,
+                    )
+                };
+                // The walk ends with `ENOENT` after the last leaf of the whole tree.
+                if result != 0 {
+                    break;
+                }
+                oid = next[..len / mem::size_of::<libc::c_int>()].to_vec();
+                if !oid.starts_with(&prefix) {
+                    break;
+                }
+                if let Some(name) = sysctl_oid_string(&[&SYSCTL_NAME[..], &oid].concat()) {
+                    leaves.push(name);
                 }
             }
-            Ok(vec)
+            leaves
+        }
+
+        /// Maps the tty names of the ucom(4) devices present, like `U0`, to the sysctl nodes of
+        /// their drivers, like `dev.uftdi.0`.
+        ///
+        /// ucom(4) adds a `ttyname` leaf to the node of every device it serves, whichever driver
+        /// the device is attached to.
+        fn ucom_nodes() -> HashMap<String, String> {
+            sysctl_leaves("dev")
+                .into_iter()
+                .filter_map(|leaf| {
+                    let node = leaf.strip_suffix(".ttyname")?;
+                    Some((sysctl_string(&leaf)?, node.to_string()))
+                })
+                .collect()
+        }
+
+        /// Determines the port type of a `/dev/cuaU*` device, named like `cuaU0` or `cuaU0.1` for
+        /// devices with multiple ports.
+        fn ucom_port_type(nodes: &HashMap<String, String>, filename: &str) -> SerialPortType {
+            let ttyname = filename.trim_start_matches("cua");
+            let ttyname = ttyname.split('.').next().unwrap_or(ttyname);
+            nodes
+                .get(ttyname)
+                .and_then(|node| {
+                    let pnpinfo = sysctl_string(&format!("{}.%pnpinfo", node))?;
+                    let location = sysctl_string(&format!("{}.%location", node));
+                    let desc = sysctl_string(&format!("{}.%desc", node));
+                    parse_ucom_sysctls(&pnpinfo, location.as_deref(), desc.as_deref())
+                })
+                .map_or(SerialPortType::Unknown, SerialPortType::UsbPort)
         }
-    } else if #[cfg(target_os = "freebsd")] {
-        use std::path::Path;
 
         /// Scans the system for serial ports and returns a list of them.
         /// The `SerialPortInfo` struct contains the name of the port
         /// which can be used for opening it.
-        pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+        ///
+        /// USB devices are identified through the sysctl nodes of their ucom(4) drivers.
+        pub fn available_ports(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
             let mut vec = Vec::new();
+            let nodes = ucom_nodes();
             let dev_path = Path::new("/dev/");
             for path in dev_path.read_dir()? {
-                let path = path?;
+                let path = match path {
+                    Ok(path) => path,
+                    Err(e) => {
+                        let device = dev_path.to_string_lossy();
+                        warnings.push(EnumerationWarning::new(device, e.to_string()));
+                        continue;
+                    }
+                };
                 let filename = path.file_name();
                 let filename_string = filename.to_string_lossy();
                 if filename_string.starts_with("cuaU") || filename_string.starts_with("cuau") || filename_string.starts_with("cuad") {
                     if !filename_string.ends_with(".init") && !filename_string.ends_with(".lock") {
                         vec.push(SerialPortInfo {
                             port_name: path.path().to_string_lossy().to_string(),
-                            port_type: SerialPortType::Unknown,
+                            port_type: if filename_string.starts_with("cuaU") {
+                                ucom_port_type(&nodes, &filename_string)
+                            } else {
+                                SerialPortType::Unknown
+                            },
+                            friendly_name: None,
+                            description: None,
+                            instance_id: None,
+                            counterpart: None,
+                            by_id: None,
+                            by_path: None,
+                            bluetooth: None,
                         });
                     }
                 }
             }
             Ok(vec)
         }
+    } else if #[cfg(any(target_os = "netbsd", target_os = "openbsd"))] {
+        use std::path::Path;
+
+        /// The prefix of the callout devices of com(4) and ucom(4) ports, e.g. `cua00` and
+        /// `cuaU0` on OpenBSD
+        #[cfg(target_os = "openbsd")]
+        const CALLOUT_PREFIX: &str = "cua";
+
+        /// The prefix of the callout devices of com(4) and ucom(4) ports, e.g. `dty00` and
+        /// `dtyU0` on NetBSD
+        #[cfg(target_os = "netbsd")]
+        const CALLOUT_PREFIX: &str = "dty";
+
+        /// Scans the system for serial ports and returns a list of them.
+        /// The `SerialPortInfo` struct contains the name of the port
+        /// which can be used for opening it.
+        ///
+        /// Only the callout devices are reported, as opening the corresponding dialin devices
+        /// (`/dev/tty*`) blocks until the carrier detect line gets asserted.
+        pub fn available_ports(
+            warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
+            let mut vec = Vec::new();
+            let dev_path = Path::new("/dev/");
+            for path in dev_path.read_dir()? {
+                let path = match path {
+                    Ok(path) => path,
+                    Err(e) => {
+                        let device = dev_path.to_string_lossy();
+                        warnings.push(EnumerationWarning::new(device, e.to_string()));
+                        continue;
+                    }
+                };
+                let filename = path.file_name();
+                let filename_string = filename.to_string_lossy();
+                // Port numbers follow the prefix right away for com(4) ports, while ucom(4) ports
+                // are marked with a `U` in between.
+                let is_port = filename_string
+                    .strip_prefix(CALLOUT_PREFIX)
+                    .and_then(|unit| unit.strip_prefix('U').or(Some(unit)))
+                    .map_or(false, |unit| unit.starts_with(|c: char| c.is_ascii_digit()));
+                if is_port {
+                    vec.push(SerialPortInfo {
+                        port_name: path.path().to_string_lossy().to_string(),
+                        port_type: SerialPortType::Unknown,
+                        friendly_name: None,
+                        description: None,
+                        instance_id: None,
+                        counterpart: None,
+                        by_id: None,
+                        by_path: None,
+                        bluetooth: None,
+                    });
+                }
+            }
+            Ok(vec)
+        }
     } else {
         /// Enumerating serial ports on this platform is not supported
-        pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+        pub fn available_ports(
+            _warnings: &mut Vec<EnumerationWarning>,
+        ) -> Result<Vec<SerialPortInfo>> {
             Err(Error::new(
                 ErrorKind::Unknown,
                 "Not implemented for this OS",
@@ -674,6 +1531,15 @@ cfg_if! {
     }
 }
 
+/// Scans the system for serial ports. Looking up the ports along with their information is
+/// cheap on platforms other than macOS, so this is the same as [`available_ports`].
+#[cfg(not(any(target_os = "ios", target_os = "macos")))]
+pub fn available_ports_lossy(
+    warnings: &mut Vec<EnumerationWarning>,
+) -> Result<Vec<SerialPortInfo>> {
+    available_ports(warnings)
+}
+
 #[cfg(all(
     test,
     target_os = "linux",
@@ -691,6 +1557,117 @@ mod tests {
         true
     }
 
+    #[test]
+    fn find_alias_resolves_symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("serialport-alias-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let links = dir.join("by-id");
+        std::fs::create_dir_all(&links).unwrap();
+        let device = dir.join("ttyUSB0");
+        let other = dir.join("ttyUSB1");
+        std::fs::write(&device, b"").unwrap();
+        std::fs::write(&other, b"").unwrap();
+        symlink(
+            "../ttyUSB0",
+            links.join("usb-FTDI_FT232R_USB_UART_A5002kMh-if00-port0"),
+        )
+        .unwrap();
+
+        let alias = find_alias(links.to_str().unwrap(), &device);
+        let missing = find_alias(links.to_str().unwrap(), &other);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            alias,
+            Some(
+                links
+                    .join("usb-FTDI_FT232R_USB_UART_A5002kMh-if00-port0")
+                    .to_string_lossy()
+                    .into_owned()
+            )
+        );
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn read_usb_port_info_warns_about_unreadable_attributes() {
+        let dir = std::env::temp_dir().join(format!("serialport-sysfs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let interface = dir.join("1-1:1.0");
+        std::fs::create_dir_all(&interface).unwrap();
+        std::fs::write(dir.join("idVendor"), b"0403\n").unwrap();
+        std::fs::write(dir.join("idProduct"), b"6001\n").unwrap();
+        std::fs::write(dir.join("product"), b"FT232R USB UART\n").unwrap();
+        // Reading a directory fails like an attribute which can't be read.
+        std::fs::create_dir(dir.join("manufacturer")).unwrap();
+
+        let mut warnings = Vec::new();
+        let info = read_usb_port_info(&interface, &mut warnings);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let info = info.unwrap().expect("USB device not recognized");
+        assert_eq!((info.vid, info.pid), (0x0403, 0x6001));
+        assert_eq!(info.product.as_deref(), Some("FT232R USB UART"));
+        assert_eq!(info.manufacturer, None);
+        assert_eq!(info.serial_number, None);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].device.ends_with("manufacturer"));
+    }
+
+    #[test]
+    fn parse_ucom_sysctls_ftdi() {
+        const PNPINFO: &str = "vendor=0x0403 product=0x6001 devclass=0x00 devsubclass=0x00 \
+            devproto=0x00 sernum=\"A5002kMh\" release=0x0600 mode=host intclass=0xff \
+            intsubclass=0xff intprotocol=0xff";
+        const LOCATION: &str = "bus=0 hubaddr=1 port=2 devaddr=3 interface=0 ugen=ugen0.3";
+        const DESC: &str = "FTDI FT232R USB UART, class 0/0, rev 2.00/6.00, addr 3";
+
+        let info = parse_ucom_sysctls(PNPINFO, Some(LOCATION), Some(DESC)).expect("parse failed");
+
+        assert_eq!(info.vid, 0x0403);
+        assert_eq!(info.pid, 0x6001);
+        assert_eq!(info.serial_number.as_deref(), Some("A5002kMh"));
+        assert_eq!(info.product.as_deref(), Some("FTDI FT232R USB UART"));
+        assert_eq!(info.interface, Some(0));
+        assert_eq!(info.bcd_device, Some(0x0600));
+        assert_eq!(
+            info.interface_class,
+            Some(UsbClass {
+                class: 0xff,
+                subclass: 0xff,
+                protocol: 0xff
+            })
+        );
+    }
+
+    #[test]
+    fn parse_ucom_sysctls_without_serial_number() {
+        const PNPINFO: &str = "vendor=0x1a86 product=0x7523 devclass=0xff devsubclass=0x00 \
+            devproto=0x00 sernum=\"\" release=0x0264 mode=host intclass=0xff intsubclass=0x01 \
+            intprotocol=0x02";
+
+        let info = parse_ucom_sysctls(PNPINFO, None, None).expect("parse failed");
+
+        assert_eq!(info.vid, 0x1a86);
+        assert_eq!(info.pid, 0x7523);
+        assert!(info.serial_number.is_none());
+        assert!(info.product.is_none());
+        assert!(parse_ucom_sysctls("mode=host", None, None).is_none());
+
+        // Out of range class codes are rejected instead of truncated.
+        {
+            let info = parse_ucom_sysctls(
+                "vendor=0x1a86 product=0x7523 devclass=0x1ff devsubclass=0x00 devproto=0x00",
+                None,
+                None,
+            )
+            .expect("parse failed");
+            assert!(info.device_class.is_none());
+        }
+    }
+
     #[test]
     fn parse_modalias_canonical() {
         const MODALIAS: &str = "usb:v303Ap1001d0101dcEFdsc02dp01ic02isc02ip00in0C";
@@ -700,8 +1677,32 @@ mod tests {
         assert_eq!(port_info.vid, 0x303A, "vendor parse invalid");
         assert_eq!(port_info.pid, 0x1001, "product parse invalid");
 
-        #[cfg(feature = "usbportinfo-interface")]
         assert_eq!(port_info.interface, Some(0x0C), "interface parse invalid");
+
+        assert_eq!(
+            port_info.bcd_device,
+            Some(0x0101),
+            "bcdDevice parse invalid"
+        );
+
+        assert_eq!(
+            port_info.device_class,
+            Some(UsbClass {
+                class: 0xEF,
+                subclass: 0x02,
+                protocol: 0x01
+            }),
+            "device class parse invalid"
+        );
+        assert_eq!(
+            port_info.interface_class,
+            Some(UsbClass {
+                class: 0x02,
+                subclass: 0x02,
+                protocol: 0x00
+            }),
+            "interface class parse invalid"
+        );
     }
 
     #[test]
@@ -716,14 +1717,14 @@ mod tests {
         let info = parse_modalias("usb:vdcdcpabcd").unwrap();
         assert_eq!(info.vid, 0xdcdc);
         assert_eq!(info.pid, 0xabcd);
-        #[cfg(feature = "usbportinfo-interface")]
         assert!(info.interface.is_none());
+        assert!(info.bcd_device.is_none());
+        assert!(info.device_class.is_none() && info.interface_class.is_none());
 
         // Vendor and product ID plus an interface number.
         let info = parse_modalias("usb:v1234p5678indc").unwrap();
         assert_eq!(info.vid, 0x1234);
         assert_eq!(info.pid, 0x5678);
-        #[cfg(feature = "usbportinfo-interface")]
         assert_eq!(info.interface, Some(0xdc));
     }
 }
@@ -21,7 +21,7 @@ impl From<nix::Error> for Error {
         use nix::errno::Errno as E;
         use ErrorKind as K;
         let kind = match e {
-            E::ETIMEDOUT => K::Io(IO::TimedOut),
+            E::ETIMEDOUT => K::Timeout,
             E::ECONNABORTED => K::Io(IO::ConnectionAborted),
             E::ECONNRESET => K::Io(IO::ConnectionReset),
             E::ECONNREFUSED => K::Io(IO::ConnectionRefused),
@@ -30,10 +30,23 @@ impl From<nix::Error> for Error {
             E::EADDRNOTAVAIL => K::Io(IO::AddrNotAvailable),
             E::EAGAIN => K::Io(IO::WouldBlock),
             E::EINTR => K::Io(IO::Interrupted),
-            E::EACCES => K::Io(IO::PermissionDenied),
+            E::EACCES | E::EPERM => K::PermissionDenied,
+            E::EBUSY => K::Busy,
+            E::EPIPE => K::BrokenPipe,
             E::ENOENT => K::Io(IO::NotFound),
+            E::ENOTTY | E::EOPNOTSUPP | E::ENOSYS => K::Unsupported,
+            // Terminals fail with EIO once they have been hung up.
+            E::EIO | E::ENXIO | E::ENODEV => K::NoDevice,
             _ => K::Unknown,
         };
         Error::new(kind, e.desc())
     }
 }
+
+/// Returns whether opening a device failed because it is busy or not accessible yet
+///
+/// Device nodes may show up before udev has adjusted their permissions, so missing permissions
+/// count as well.
+pub(crate) fn is_busy(error: &Error) -> bool {
+    matches!(error.kind(), ErrorKind::Busy | ErrorKind::PermissionDenied)
+}
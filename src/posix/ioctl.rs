@@ -12,6 +12,8 @@ mod raw {
 
     ioctl_none_bad!(tiocexcl, libc::TIOCEXCL);
     ioctl_none_bad!(tiocnxcl, libc::TIOCNXCL);
+    #[cfg(target_os = "linux")]
+    ioctl_read_bad!(tiocgexcl, libc::TIOCGEXCL, libc::c_int);
     ioctl_read_bad!(tiocmget, libc::TIOCMGET, libc::c_int);
     ioctl_none_bad!(tiocsbrk, libc::TIOCSBRK);
     ioctl_none_bad!(tioccbrk, libc::TIOCCBRK);
@@ -33,6 +35,9 @@ mod raw {
     #[cfg(any(target_os = "android", target_os = "linux"))]
     ioctl_read_bad!(tiocoutq, libc::TIOCOUTQ, libc::c_int);
 
+    #[cfg(target_os = "linux")]
+    ioctl_read_bad!(tiocgicount, libc::TIOCGICOUNT, super::SerialIcounter);
+
     // See: /usr/include/sys/ttycom.h
     #[cfg(any(
         target_os = "dragonfly",
@@ -44,6 +49,15 @@ mod raw {
     ))]
     ioctl_read!(tiocoutq, b't', 115, libc::c_int);
 
+    // See: /usr/include/sys/ttycom.h
+    ioctl_read!(
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        tiocptygname,
+        b't',
+        83,
+        [libc::c_char; 128]
+    );
+
     ioctl_write_ptr_bad!(tiocmbic, libc::TIOCMBIC, libc::c_int);
     ioctl_write_ptr_bad!(tiocmbis, libc::TIOCMBIS, libc::c_int);
     ioctl_read!(
@@ -116,6 +130,14 @@ pub fn tiocnxcl(fd: RawFd) -> Result<()> {
         .map_err(|e| e.into())
 }
 
+#[cfg(target_os = "linux")]
+pub fn tiocgexcl(fd: RawFd) -> Result<bool> {
+    let mut exclusive: libc::c_int = 0;
+    unsafe { raw::tiocgexcl(fd, &mut exclusive) }
+        .map(|_| exclusive != 0)
+        .map_err(|e| e.into())
+}
+
 pub fn tiocmget(fd: RawFd) -> Result<SerialLines> {
     let mut status: libc::c_int = 0;
     unsafe { raw::tiocmget(fd, &mut status) }
@@ -142,6 +164,34 @@ pub fn fionread(fd: RawFd) -> Result<u32> {
         .map_err(|e| e.into())
 }
 
+/// The counters reported by `TIOCGICOUNT`, see `struct serial_icounter_struct` in
+/// `linux/serial.h`
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Default)]
+pub struct SerialIcounter {
+    pub cts: libc::c_int,
+    pub dsr: libc::c_int,
+    pub rng: libc::c_int,
+    pub dcd: libc::c_int,
+    pub rx: libc::c_int,
+    pub tx: libc::c_int,
+    pub frame: libc::c_int,
+    pub overrun: libc::c_int,
+    pub parity: libc::c_int,
+    pub brk: libc::c_int,
+    pub buf_overrun: libc::c_int,
+    pub reserved: [libc::c_int; 9],
+}
+
+#[cfg(target_os = "linux")]
+pub fn tiocgicount(fd: RawFd) -> Result<SerialIcounter> {
+    let mut counters = SerialIcounter::default();
+    unsafe { raw::tiocgicount(fd, &mut counters) }
+        .map(|_| counters)
+        .map_err(|e| e.into())
+}
+
 pub fn tiocoutq(fd: RawFd) -> Result<u32> {
     let mut retval: libc::c_int = 0;
     unsafe { raw::tiocoutq(fd, &mut retval) }
@@ -149,6 +199,15 @@ pub fn tiocoutq(fd: RawFd) -> Result<u32> {
         .map_err(|e| e.into())
 }
 
+/// Returns the path of the slave device belonging to the pseudo terminal master `fd`
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+pub fn tiocptygname(fd: RawFd) -> Result<String> {
+    let mut name = [0 as libc::c_char; 128];
+    unsafe { raw::tiocptygname(fd, &mut name) }?;
+    let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) };
+    Ok(name.to_string_lossy().into_owned())
+}
+
 pub fn tiocmbic(fd: RawFd, status: SerialLines) -> Result<()> {
     let bits = status.bits() as libc::c_int;
     unsafe { raw::tiocmbic(fd, &bits) }
@@ -0,0 +1,224 @@
+//! UUCP style lock files
+//!
+//! Legacy software like minicom or pppd marks devices in use by creating a lock file named
+//! `LCK..<device>` in a shared lock directory. The file contains the PID of the owning process
+//! as ten characters wide ASCII number followed by a newline. Lock files of processes which are
+//! no longer running are stale and get removed.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use nix::errno::Errno;
+use nix::sys::signal;
+use nix::unistd::{self, Pid};
+
+use crate::{Error, ErrorKind, Result};
+
+/// The directory for lock files
+#[cfg(any(target_os = "android", target_os = "linux"))]
+const LOCK_DIR: &str = "/var/lock";
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+const LOCK_DIR: &str = "/var/spool/lock";
+
+/// A lock file held by this process, which gets removed when dropped
+#[derive(Debug)]
+pub(crate) struct LockFile {
+    path: PathBuf,
+}
+
+impl LockFile {
+    /// Locks the device at `device_path`
+    ///
+    /// ## Errors
+    ///
+    /// * `Busy` if another running process holds the lock.
+    /// * `PermissionDenied` if the lock directory is not writable.
+    /// * `Io` for any other error while accessing the lock file.
+    pub(crate) fn acquire(device_path: &str) -> Result<LockFile> {
+        LockFile::acquire_in(Path::new(LOCK_DIR), device_path)
+    }
+
+    fn acquire_in(dir: &Path, device_path: &str) -> Result<LockFile> {
+        // Symlinks like the ones in `/dev/serial/by-id` have to lock the device they point to.
+        let device = fs::canonicalize(device_path).unwrap_or_else(|_| device_path.into());
+        let name = match device.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "device path has no file name to lock",
+                ))
+            }
+        };
+        let path = dir.join(format!("LCK..{}", name));
+
+        // Retry once after removing a stale lock file.
+        for _ in 0..2 {
+            match create(dir, &path) {
+                Ok(()) => return Ok(LockFile { path }),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+            match read_pid(&path) {
+                Ok(Some(pid)) if is_running(pid) => {
+                    return Err(Error::new(
+                        ErrorKind::Busy,
+                        format!("device is locked by process {}", pid),
+                    ))
+                }
+                // Lock files get created complete, so one without a PID belongs to software
+                // using another format and is left alone.
+                Ok(None) => {
+                    return Err(Error::new(
+                        ErrorKind::Busy,
+                        format!("device is locked by {}", path.display()),
+                    ))
+                }
+                Ok(Some(_)) => match fs::remove_file(&path) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                },
+                // The owner removed the lock file in the meantime.
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(Error::new(
+            ErrorKind::Busy,
+            "device got locked by another process",
+        ))
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        // Don't remove a lock file which has been taken over by another process.
+        if read_pid(&self.path).ok().flatten() == Some(unistd::getpid().as_raw()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Creates the lock file at `path` in `dir` with the PID of this process, failing if it already
+/// exists
+///
+/// The PID gets written to a temporary file first, which is then linked to `path`. This way,
+/// other processes never see a lock file without a PID and mistake it for a stale one.
+fn create(dir: &Path, path: &Path) -> io::Result<()> {
+    static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let temp = dir.join(format!(
+        "LTMP.{}.{}",
+        unistd::getpid(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o644)
+        .open(&temp)?;
+    let result = writeln!(file, "{:>10}", unistd::getpid().as_raw())
+        .and_then(|_| fs::hard_link(&temp, path));
+    let _ = fs::remove_file(&temp);
+    result
+}
+
+/// Returns the PID stored in the lock file at `path`, or `None` if the file does not contain one
+///
+/// Besides the ASCII format, this accepts the PID stored as a binary 32 bit integer, as done by
+/// some older software.
+fn read_pid(path: &Path) -> io::Result<Option<i32>> {
+    let content = fs::read(path)?;
+    let ascii = std::str::from_utf8(&content)
+        .ok()
+        .and_then(|content| content.trim().parse().ok());
+    Ok(match (ascii, <[u8; 4]>::try_from(content.as_slice())) {
+        (Some(pid), _) => Some(pid),
+        (None, Ok(binary)) => Some(i32::from_ne_bytes(binary)),
+        (None, Err(_)) => None,
+    })
+}
+
+/// Returns whether the process with the given PID is running
+fn is_running(pid: i32) -> bool {
+    if pid <= 0 {
+        return false;
+    }
+    // Processes of other users can't be signalled but still exist.
+    matches!(
+        signal::kill(Pid::from_raw(pid), None),
+        Ok(()) | Err(Errno::EPERM)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("serialport-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn lock_file_is_created_and_removed() {
+        let dir = lock_dir("lock");
+        let path = dir.join("LCK..ttyTEST0");
+
+        let lock = LockFile::acquire_in(&dir, "/dev/ttyTEST0").unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, format!("{:>10}\n", std::process::id()));
+
+        let error = LockFile::acquire_in(&dir, "/dev/ttyTEST0").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Busy);
+
+        drop(lock);
+        assert!(!path.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stale_lock_file_is_replaced() {
+        let dir = lock_dir("stale");
+        let path = dir.join("LCK..ttyTEST1");
+        // PIDs are limited to 2^22 on Linux and lower elsewhere.
+        fs::write(&path, format!("{:>10}\n", i32::MAX)).unwrap();
+
+        let lock = LockFile::acquire_in(&dir, "/dev/ttyTEST1").unwrap();
+        assert_eq!(read_pid(&path).unwrap(), Some(unistd::getpid().as_raw()));
+
+        drop(lock);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn foreign_lock_file_is_kept() {
+        let dir = lock_dir("foreign");
+        let path = dir.join("LCK..ttyTEST2");
+        fs::write(&path, "locked by someone\n").unwrap();
+
+        let error = LockFile::acquire_in(&dir, "/dev/ttyTEST2").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Busy);
+        assert!(path.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn binary_pid_is_read() {
+        let dir = lock_dir("binary");
+        let path = dir.join("LCK..ttyTEST3");
+        fs::write(&path, unistd::getpid().as_raw().to_ne_bytes()).unwrap();
+
+        let error = LockFile::acquire_in(&dir, "/dev/ttyTEST3").unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Busy);
+        assert_eq!(read_pid(&path).unwrap(), Some(unistd::getpid().as_raw()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,9 +1,16 @@
 pub use self::enumerate::*;
+pub use self::pipe::*;
 pub use self::tty::*;
 
+pub(crate) use self::error::is_busy;
+pub(crate) use self::pipe::is_pipe_path;
+pub(crate) use self::poll::{ready_now, select_readable};
+
 mod enumerate;
 mod error;
 mod ioctl;
+mod lock;
+mod pipe;
 mod poll;
 mod termios;
 mod tty;
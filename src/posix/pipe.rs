@@ -0,0 +1,305 @@
+use std::io::{self, Read};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::posix::ioctl;
+use crate::{
+    ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity, PortStats, Result, SerialPort,
+    SerialPortBuilder, StopBits,
+};
+
+/// Returns whether `path` refers to a socket to be opened with [`PipeSerialPort`]
+pub(crate) fn is_pipe_path(path: &str) -> bool {
+    std::fs::metadata(path).map_or(false, |metadata| metadata.file_type().is_socket())
+}
+
+/// The device side of an emulated serial port
+///
+/// The server listens on a Unix domain socket. Applications connect to it with
+/// [`PipeSerialPort`], which behaves like a regular serial port. As
+/// [`SerialPortBuilder::open`] recognizes sockets, applications opening a port by path can be
+/// pointed to the socket without changes. Test code accepts these connections and plays the
+/// role of the device by reading and writing the returned streams.
+///
+/// The socket file gets removed when the server is dropped.
+///
+/// ```no_run
+/// use std::io::{Read, Write};
+/// use serialport::PipeSerialServer;
+///
+/// let server = PipeSerialServer::bind("/tmp/fake-device").expect("Failed to bind");
+/// let mut port = serialport::new("/tmp/fake-device", 9600)
+///     .open()
+///     .expect("Failed to connect");
+/// let mut device = server.accept().expect("Failed to accept");
+///
+/// port.write_all(b"AT\r").unwrap();
+/// let mut request = [0u8; 3];
+/// device.read_exact(&mut request).unwrap();
+/// device.write_all(b"OK\r\n").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PipeSerialServer {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl PipeSerialServer {
+    /// Starts listening for connections at `path`
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(AddrInUse)` if `path` already exists.
+    /// * `Io` for any other error while creating the socket.
+    pub fn bind(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&path)?;
+        Ok(PipeSerialServer { listener, path })
+    }
+
+    /// Returns the path to connect to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Waits for a [`PipeSerialPort`] to connect and returns the device's end of the connection
+    pub fn accept(&self) -> Result<UnixStream> {
+        let (stream, _) = self.listener.accept()?;
+        Ok(stream)
+    }
+}
+
+impl Drop for PipeSerialServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// The application side of an emulated serial port
+///
+/// See [`PipeSerialServer`] for providing the device side. Port settings are only recorded and
+/// reported back, they have no effect on the data transfer. The modem control lines report
+/// the device as present and ready.
+#[derive(Debug)]
+pub struct PipeSerialPort {
+    stream: UnixStream,
+    port_name: String,
+    baud_rate: u32,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+    timeout: Duration,
+    stats: PortStats,
+}
+
+impl PipeSerialPort {
+    /// Connects to the [`PipeSerialServer`] listening at the builder's path
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if no server is listening at the path.
+    /// * `Io` for any other error while connecting.
+    pub fn open(builder: &SerialPortBuilder) -> Result<Self> {
+        let stream = UnixStream::connect(&builder.path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => {
+                Error::new(ErrorKind::NoDevice, e.to_string())
+            }
+            _ => Error::from(e),
+        })?;
+
+        Ok(PipeSerialPort {
+            stream,
+            port_name: builder.path.clone(),
+            baud_rate: builder.baud_rate,
+            data_bits: builder.data_bits,
+            flow_control: builder.flow_control,
+            parity: builder.parity,
+            stop_bits: builder.stop_bits,
+            timeout: builder.timeout,
+            stats: PortStats::default(),
+        })
+    }
+
+    /// Attempts to clone the `PipeSerialPort`
+    ///
+    /// Both instances share the connection, but each of them keeps its own settings.
+    pub fn try_clone_native(&self) -> Result<PipeSerialPort> {
+        Ok(PipeSerialPort {
+            stream: self.stream.try_clone()?,
+            port_name: self.port_name.clone(),
+            stats: PortStats::default(),
+            ..*self
+        })
+    }
+}
+
+impl AsRawFd for PipeSerialPort {
+    fn as_raw_fd(&self) -> RawFd {
+        self.stream.as_raw_fd()
+    }
+}
+
+impl io::Read for PipeSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = super::poll::wait_read_fd(self.stream.as_raw_fd(), self.timeout, &[], None)
+            .and_then(|_| match self.stream.read(buf) {
+                // The device closed its end, like an unplugged device.
+                Ok(0) if !buf.is_empty() => Err(super::poll::disconnected()),
+                result => result,
+            });
+        self.stats.count_read(&result);
+        result
+    }
+}
+
+impl io::Write for PipeSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = super::poll::wait_write_fd(self.stream.as_raw_fd(), self.timeout, &[], None)
+            .and_then(|_| self.stream.write(buf));
+        self.stats.count_write(&result);
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl SerialPort for PipeSerialPort {
+    fn name(&self) -> Option<String> {
+        Some(self.port_name.clone())
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        Ok(self.data_bits)
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        Ok(self.flow_control)
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        Ok(self.parity)
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        Ok(self.stop_bits)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        self.data_bits = data_bits;
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        self.flow_control = flow_control;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        self.parity = parity;
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        self.stop_bits = stop_bits;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        ioctl::fionread(self.stream.as_raw_fd())
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        // Written data is handed over to the server right away.
+        Ok(0)
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        Ok(self.stats)
+    }
+
+    fn wait_readable(&self, timeout: Duration) -> Result<()> {
+        super::poll::wait_read_fd(self.stream.as_raw_fd(), timeout, &[], None).map_err(Error::from)
+    }
+
+    fn wait_writable(&self, timeout: Duration) -> Result<()> {
+        super::poll::wait_write_fd(self.stream.as_raw_fd(), timeout, &[], None).map_err(Error::from)
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        Ok(!super::poll::is_hung_up(self.stream.as_raw_fd())?)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        if let ClearBuffer::Input | ClearBuffer::All = buffer_to_clear {
+            let mut buf = [0u8; 256];
+            let mut pending = self.bytes_to_read()? as usize;
+            while pending > 0 {
+                let n = (&self.stream).read(&mut buf[..pending.min(256)])?;
+                if n == 0 {
+                    break;
+                }
+                pending -= n;
+            }
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.try_clone_native()?))
+    }
+
+    fn set_break(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        Ok(())
+    }
+}
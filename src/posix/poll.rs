@@ -7,43 +7,59 @@ use std::time::Duration;
 
 use nix::libc::c_int;
 use nix::poll::{PollFd, PollFlags};
-#[cfg(target_os = "linux")]
 use nix::sys::signal::SigSet;
 #[cfg(any(target_os = "linux", test))]
 use nix::sys::time::TimeSpec;
 
-pub fn wait_read_fd(fd: RawFd, timeout: Duration) -> io::Result<()> {
-    wait_fd(fd, PollFlags::POLLIN, timeout)
-}
+use crate::cancel;
+use crate::{CancelToken, Error, ErrorKind};
 
-pub fn wait_write_fd(fd: RawFd, timeout: Duration) -> io::Result<()> {
-    wait_fd(fd, PollFlags::POLLOUT, timeout)
+pub fn wait_read_fd(
+    fd: RawFd,
+    timeout: Duration,
+    cancel: &[Option<&CancelToken>],
+    sigmask: Option<&SigSet>,
+) -> io::Result<()> {
+    wait_fd(fd, PollFlags::POLLIN, timeout, cancel, sigmask)
 }
 
-fn wait_fd(fd: RawFd, events: PollFlags, timeout: Duration) -> io::Result<()> {
-    use nix::errno::Errno::{EIO, EPIPE};
+pub fn wait_write_fd(
+    fd: RawFd,
+    timeout: Duration,
+    cancel: &[Option<&CancelToken>],
+    sigmask: Option<&SigSet>,
+) -> io::Result<()> {
+    wait_fd(fd, PollFlags::POLLOUT, timeout, cancel, sigmask)
+}
 
-    let mut fd = PollFd::new(fd, events);
+/// Waits for `events` on `fd`, returning early with a `Cancelled` error if any token in `cancel`
+/// gets cancelled
+///
+/// On Linux, `sigmask` replaces the signal mask of the thread while waiting.
+fn wait_fd(
+    fd: RawFd,
+    events: PollFlags,
+    timeout: Duration,
+    cancel: &[Option<&CancelToken>],
+    sigmask: Option<&SigSet>,
+) -> io::Result<()> {
+    use nix::errno::Errno::EIO;
 
-    let wait = match poll_clamped(&mut fd, timeout) {
-        Ok(r) => r,
-        Err(e) => return Err(io::Error::from(crate::Error::from(e))),
-    };
+    let mut fds = [PollFd::new(fd, events); MAX_POLL_FDS];
+    let wait = poll_cancellable(&mut fds, timeout, cancel, sigmask)?;
     // All errors generated by poll or ppoll are already caught by the nix wrapper around libc, so
     // here we only need to check if there's at least 1 event
-    if wait != 1 {
-        return Err(io::Error::new(
-            io::ErrorKind::TimedOut,
-            "Operation timed out",
-        ));
+    if wait == 0 {
+        return Err(Error::new(ErrorKind::Timeout, "Operation timed out").into());
     }
 
-    // Check the result of ppoll() by looking at the revents field
-    match fd.revents() {
-        Some(e) if e == events => return Ok(()),
-        // If there was a hangout or invalid request
-        Some(e) if e.contains(PollFlags::POLLHUP) || e.contains(PollFlags::POLLNVAL) => {
-            return Err(io::Error::new(io::ErrorKind::BrokenPipe, EPIPE.desc()));
+    // Check the result of ppoll() by looking at the revents field. Data still pending after a
+    // hangup gets read before reporting the hangup.
+    match fds[0].revents() {
+        Some(e) if e.contains(events) => return Ok(()),
+        // If there was a hangup, an error condition or an invalid request, the device is gone.
+        Some(e) if e.intersects(PollFlags::POLLHUP | PollFlags::POLLERR | PollFlags::POLLNVAL) => {
+            return Err(disconnected());
         }
         Some(_) | None => (),
     }
@@ -51,12 +67,114 @@ fn wait_fd(fd: RawFd, events: PollFlags, timeout: Duration) -> io::Result<()> {
     Err(io::Error::new(io::ErrorKind::Other, EIO.desc()))
 }
 
+/// Waits up to `timeout` for any token in `cancel` to get cancelled, failing with a `Cancelled`
+/// error if one does
+pub fn wait_cancelled(
+    cancel: &[Option<&CancelToken>],
+    timeout: Duration,
+    sigmask: Option<&SigSet>,
+) -> io::Result<()> {
+    // Negative descriptors are ignored by poll().
+    let mut fds = [PollFd::new(-1, PollFlags::empty()); MAX_POLL_FDS];
+    poll_cancellable(&mut fds, timeout, cancel, sigmask).map(drop)
+}
+
+/// Waits up to `timeout` for any of `fds` to become readable and returns the indices of the ready
+/// ones
+///
+/// Descriptors reporting a hangup or an error condition count as ready, so reading them reports
+/// the error.
+pub fn select_readable(fds: &[RawFd], timeout: Duration) -> io::Result<Vec<usize>> {
+    let mut poll_fds: Vec<PollFd> = fds
+        .iter()
+        .map(|&fd| PollFd::new(fd, PollFlags::POLLIN))
+        .collect();
+    let wait =
+        poll_clamped(&mut poll_fds, timeout, None).map_err(|e| io::Error::from(Error::from(e)))?;
+    if wait == 0 {
+        return Err(Error::new(ErrorKind::Timeout, "Operation timed out").into());
+    }
+
+    Ok(poll_fds
+        .iter()
+        .enumerate()
+        .filter(|(_, fd)| fd.revents().map_or(false, |e| !e.is_empty()))
+        .map(|(index, _)| index)
+        .collect())
+}
+
+/// Returns whether `fd` is readable and writable right now, checking only the requested
+/// directions
+///
+/// A hangup or an error condition counts as ready, so the following read or write reports it.
+pub fn ready_now(fd: RawFd, readable: bool, writable: bool) -> io::Result<(bool, bool)> {
+    let mut events = PollFlags::empty();
+    events.set(PollFlags::POLLIN, readable);
+    events.set(PollFlags::POLLOUT, writable);
+    let mut poll_fd = PollFd::new(fd, events);
+    nix::poll::poll(slice::from_mut(&mut poll_fd), 0)
+        .map_err(|e| io::Error::from(Error::from(e)))?;
+
+    let revents = poll_fd.revents().unwrap_or_else(PollFlags::empty);
+    let failed = revents.intersects(PollFlags::POLLHUP | PollFlags::POLLERR | PollFlags::POLLNVAL);
+    Ok((
+        readable && (failed || revents.contains(PollFlags::POLLIN)),
+        writable && (failed || revents.contains(PollFlags::POLLOUT)),
+    ))
+}
+
+/// The number of descriptors polled at most: the device and up to two cancel tokens
+const MAX_POLL_FDS: usize = 3;
+
+/// Polls the first entry of `fds` together with the self-pipes of the tokens in `cancel`, which
+/// become readable when cancelling
+fn poll_cancellable(
+    fds: &mut [PollFd; MAX_POLL_FDS],
+    timeout: Duration,
+    cancel: &[Option<&CancelToken>],
+    sigmask: Option<&SigSet>,
+) -> io::Result<c_int> {
+    cancel::check_all(cancel)?;
+
+    let mut count = 1;
+    for token in cancel.iter().flatten().take(MAX_POLL_FDS - 1) {
+        fds[count] = PollFd::new(token.fd(), PollFlags::POLLIN);
+        count += 1;
+    }
+    let wait = poll_clamped(&mut fds[..count], timeout, sigmask)
+        .map_err(|e| io::Error::from(Error::from(e)))?;
+
+    cancel::check_all(cancel)?;
+    Ok(wait)
+}
+
+/// Returns whether `fd` reports a hangup or an error condition without waiting
+pub fn is_hung_up(fd: RawFd) -> io::Result<bool> {
+    let mut fd = PollFd::new(fd, PollFlags::empty());
+    nix::poll::poll(slice::from_mut(&mut fd), 0).map_err(|e| io::Error::from(Error::from(e)))?;
+    let hangup = PollFlags::POLLHUP | PollFlags::POLLERR | PollFlags::POLLNVAL;
+    Ok(fd.revents().map_or(false, |e| e.intersects(hangup)))
+}
+
+/// The error reported for I/O on a device which has been disconnected
+pub fn disconnected() -> io::Error {
+    Error::new(ErrorKind::NoDevice, "device has been disconnected").into()
+}
+
 /// Poll with a duration clamped to the maximum value representable by the `TimeSpec` used by
 /// `ppoll`.
+///
+/// The signal mask of the thread gets replaced by `sigmask` atomically for the duration of the
+/// call. Without one, no signals are blocked while waiting.
 #[cfg(target_os = "linux")]
-fn poll_clamped(fd: &mut PollFd, timeout: Duration) -> nix::Result<c_int> {
+fn poll_clamped(
+    fds: &mut [PollFd],
+    timeout: Duration,
+    sigmask: Option<&SigSet>,
+) -> nix::Result<c_int> {
     let spec = clamped_time_spec(timeout);
-    nix::poll::ppoll(slice::from_mut(fd), Some(spec), Some(SigSet::empty()))
+    let sigmask = sigmask.copied().unwrap_or_else(SigSet::empty);
+    nix::poll::ppoll(fds, Some(spec), Some(sigmask))
 }
 
 #[cfg(any(target_os = "linux", test))]
@@ -83,11 +201,17 @@ fn clamped_time_spec(duration: Duration) -> TimeSpec {
 }
 
 // Poll with a duration clamped to the maximum millisecond value representable by the `c_int` used
-// by `poll`.
+// by `poll`. Signal masks are only supported with `ppoll` on Linux, so `sigmask` is always `None`
+// here.
 #[cfg(not(target_os = "linux"))]
-fn poll_clamped(fd: &mut PollFd, timeout: Duration) -> nix::Result<c_int> {
+fn poll_clamped(
+    fds: &mut [PollFd],
+    timeout: Duration,
+    sigmask: Option<&SigSet>,
+) -> nix::Result<c_int> {
+    debug_assert!(sigmask.is_none());
     let millis = clamped_millis_c_int(timeout);
-    nix::poll::poll(slice::from_mut(fd), millis)
+    nix::poll::poll(fds, millis)
 }
 
 #[cfg(any(not(target_os = "linux"), test))]
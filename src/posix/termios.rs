@@ -5,6 +5,7 @@ use crate::{DataBits, FlowControl, Parity, Result, StopBits};
 use nix::libc;
 
 use std::os::unix::prelude::*;
+use std::time::Duration;
 
 cfg_if! {
     if #[cfg(any(
@@ -202,6 +203,43 @@ pub(crate) fn set_stop_bits(termios: &mut Termios, stop_bits: StopBits) {
     };
 }
 
+/// Returns whether the settings made by the setters above have been applied as `requested`,
+/// comparing them with the `actual` settings read back from the port.
+///
+/// The baud rate isn't compared, as drivers may round it to the closest rate they support.
+pub(crate) fn settings_applied(requested: &Termios, actual: &Termios) -> bool {
+    let cflags = libc::CSIZE | libc::CSTOPB | libc::PARENB | libc::PARODD | libc::CRTSCTS;
+    let iflags = libc::INPCK | libc::IGNPAR | libc::IXON | libc::IXOFF;
+    requested.c_cflag & cflags == actual.c_cflag & cflags
+        && requested.c_iflag & iflags == actual.c_iflag & iflags
+        && requested.c_cc[libc::VMIN] == actual.c_cc[libc::VMIN]
+        && requested.c_cc[libc::VTIME] == actual.c_cc[libc::VTIME]
+}
+
+/// Configures `VMIN` and `VTIME` for ending a read after the line went idle for the given
+/// duration. `VTIME` has a resolution of 100 ms and the duration gets rounded up accordingly.
+pub(crate) fn set_inter_byte_timeout(termios: &mut Termios, inter_byte_timeout: Option<Duration>) {
+    match inter_byte_timeout {
+        Some(duration) => {
+            let deciseconds = (duration.as_millis() + 99) / 100;
+            termios.c_cc[libc::VMIN] = u8::MAX as libc::cc_t;
+            termios.c_cc[libc::VTIME] = deciseconds.clamp(1, u8::MAX as u128) as libc::cc_t;
+        }
+        None => {
+            termios.c_cc[libc::VMIN] = 1;
+            termios.c_cc[libc::VTIME] = 0;
+        }
+    }
+}
+
+/// Returns the inter-byte timeout configured through `VTIME`.
+pub(crate) fn inter_byte_timeout(termios: &Termios) -> Option<Duration> {
+    match termios.c_cc[libc::VTIME] {
+        0 => None,
+        deciseconds => Some(Duration::from_millis(deciseconds as u64 * 100)),
+    }
+}
+
 #[cfg(any(
     target_os = "android",
     all(
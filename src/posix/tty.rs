@@ -1,17 +1,29 @@
+use std::io::{IoSlice, IoSliceMut};
 use std::mem::MaybeUninit;
 use std::os::unix::prelude::*;
 use std::path::Path;
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use std::{io, mem};
+use std::{fmt, io, mem, slice};
 
-use nix::fcntl::{fcntl, OFlag};
+use nix::fcntl::{fcntl, FdFlag, OFlag};
+use nix::sys::signal::SigSet;
+#[cfg(target_os = "linux")]
+use nix::sys::signal::Signal;
+use nix::sys::uio;
 use nix::{libc, unistd};
 
+use crate::cancel;
 use crate::posix::ioctl::{self, SerialLines};
+use crate::posix::lock::LockFile;
 use crate::posix::termios;
 use crate::{
-    ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity, Result, SerialPort,
-    SerialPortBuilder, StopBits,
+    AccessMode, CancelToken, ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity,
+    PortStats, ReadHalf, Result, SerialPort, SerialPortBuilder, SerialPortExt, ShareMode, StopBits,
+    Strictness, WriteHalf,
 };
 
 /// Convenience method for removing exclusive access from
@@ -61,10 +73,36 @@ fn close(fd: RawFd) {
 pub struct TTYPort {
     fd: RawFd,
     timeout: Duration,
-    exclusive: bool,
+    /// Whether `TIOCEXCL` is set, shared with the clones of the port as it applies to the device
+    exclusive: Arc<AtomicBool>,
     port_name: Option<String>,
+    strictness: Strictness,
+    /// Whether the port is in non-blocking mode, shared with the clones of the port as they share
+    /// the `O_NONBLOCK` flag
+    nonblocking: Arc<AtomicBool>,
+    /// The I/O statistics, shared by all users of a reference to the port
+    stats: Mutex<PortStats>,
+    /// Whether the port has been reported to the observers
+    observed: bool,
+    /// The UUCP lock file for the device, shared with the clones of the port
+    lock: Option<Arc<LockFile>>,
+    /// The settings to restore when the port and all its clones are closed
+    saved_settings: Option<Arc<SavedSettings>>,
+    /// The token for cancelling blocking operations, created on first use and shared with the
+    /// clones of the port
+    cancel: Arc<Mutex<Option<CancelToken>>>,
+    /// Whether reads, writes and waits interrupted by signals get retried
+    retry_interrupted: bool,
+    /// The signal mask of the thread while waiting for the device
+    #[cfg(target_os = "linux")]
+    wait_signal_mask: Option<SignalMask>,
+    /// The line error counters at the time of the last call to `take_error()`
+    #[cfg(target_os = "linux")]
+    reported_errors: Mutex<crate::LineErrors>,
+    /// The baud rate set with `IOSSIOSPEED`, shared with the clones of the port as it applies to
+    /// the device
     #[cfg(any(target_os = "ios", target_os = "macos"))]
-    baud_rate: u32,
+    baud_rate: Arc<AtomicU32>,
 }
 
 /// Specifies the duration of a transmission break
@@ -76,6 +114,71 @@ pub enum BreakDuration {
     Arbitrary(std::num::NonZeroI32),
 }
 
+/// A set of signals blocked while a port waits for its device
+///
+/// Signals are given by their numbers, e.g. `libc::SIGUSR1`. See
+/// [`TTYPort::set_wait_signal_mask`] for how the mask gets applied.
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignalMask(SigSet);
+
+#[cfg(target_os = "linux")]
+impl SignalMask {
+    /// Creates a mask without any signals
+    pub fn empty() -> SignalMask {
+        SignalMask(SigSet::empty())
+    }
+
+    /// Creates a mask with all signals
+    pub fn all() -> SignalMask {
+        SignalMask(SigSet::all())
+    }
+
+    /// Returns the signal mask of the calling thread
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` for any error while querying the mask.
+    pub fn current() -> Result<SignalMask> {
+        Ok(SignalMask(SigSet::thread_get_mask()?))
+    }
+
+    /// Adds `signal` to the mask
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `signal` is not a valid signal number.
+    pub fn add(&mut self, signal: libc::c_int) -> Result<()> {
+        self.0.add(to_signal(signal)?);
+        Ok(())
+    }
+
+    /// Removes `signal` from the mask
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `signal` is not a valid signal number.
+    pub fn remove(&mut self, signal: libc::c_int) -> Result<()> {
+        self.0.remove(to_signal(signal)?);
+        Ok(())
+    }
+
+    /// Returns whether `signal` is in the mask
+    pub fn contains(&self, signal: libc::c_int) -> bool {
+        to_signal(signal).map_or(false, |signal| self.0.contains(signal))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn to_signal(signal: libc::c_int) -> Result<Signal> {
+    Signal::try_from(signal).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid signal number {}", signal),
+        )
+    })
+}
+
 /// Wrapper for RawFd to assure that it's properly closed,
 /// even if the enclosing function exits early.
 ///
@@ -102,7 +205,8 @@ impl TTYPort {
     /// `path` should be the path to a TTY device, e.g., `/dev/ttyS0`.
     ///
     /// Ports are opened in exclusive mode by default. If this is undesirable
-    /// behavior, use `TTYPort::set_exclusive(false)`.
+    /// behavior, use `SerialPortBuilder::share_mode(ShareMode::Shared)` or
+    /// `TTYPort::set_exclusive(false)`.
     ///
     /// If the port settings differ from the default settings, characters received
     /// before the new settings become active may be garbled. To remove those
@@ -110,95 +214,120 @@ impl TTYPort {
     ///
     /// ## Errors
     ///
-    /// * `NoDevice` if the device could not be opened. This could indicate that
-    ///    the device is already in use.
+    /// * `NoDevice` if the device could not be opened.
+    /// * `Busy` if the device is already in use.
+    /// * `PermissionDenied` if access to the device is not permitted.
     /// * `InvalidInput` if `path` is not a valid device name.
     /// * `Io` for any other error while opening or initializing the device.
     pub fn open(builder: &SerialPortBuilder) -> Result<TTYPort> {
+        TTYPort::open_device(builder).map_err(|e| e.with_context("opening", Some(&builder.path)))
+    }
+
+    fn open_device(builder: &SerialPortBuilder) -> Result<TTYPort> {
         use nix::fcntl::FcntlArg::F_SETFL;
-        use nix::libc::{cfmakeraw, tcgetattr, tcsetattr};
+
+        // Take the lock before touching the device, as other software respecting it does.
+        let lock = if builder.uucp_lock {
+            Some(Arc::new(LockFile::acquire(&builder.path)?))
+        } else {
+            None
+        };
 
         let path = Path::new(&builder.path);
+        let access_mode = match builder.access_mode {
+            AccessMode::ReadWrite => OFlag::O_RDWR,
+            AccessMode::ReadOnly => OFlag::O_RDONLY,
+            AccessMode::WriteOnly => OFlag::O_WRONLY,
+        };
+        let mut flags = access_mode | OFlag::O_NOCTTY | OFlag::O_NONBLOCK;
+        flags.set(OFlag::O_CLOEXEC, !builder.inheritable);
         let fd = OwnedFd(nix::fcntl::open(
             path,
-            OFlag::O_RDWR | OFlag::O_NOCTTY | OFlag::O_NONBLOCK | OFlag::O_CLOEXEC,
+            flags,
             nix::sys::stat::Mode::empty(),
         )?);
 
-        // Try to claim exclusive access to the port. This is performed even
-        // if the port will later be set as non-exclusive, in order to respect
-        // other applications that may have an exclusive port lock.
-        ioctl::tiocexcl(fd.0)?;
-
-        let mut termios = MaybeUninit::uninit();
-        nix::errno::Errno::result(unsafe { tcgetattr(fd.0, termios.as_mut_ptr()) })?;
-        let mut termios = unsafe { termios.assume_init() };
+        // clear O_NONBLOCK flag
+        fcntl(fd.0, F_SETFL(nix::fcntl::OFlag::empty()))?;
 
-        // setup TTY for binary serial port access
-        // Enable reading from the port and ignore all modem control lines
-        termios.c_cflag |= libc::CREAD | libc::CLOCAL;
-        // Enable raw mode which disables any implicit processing of the input or output data streams
-        // This also sets no timeout period and a read will block until at least one character is
-        // available.
-        unsafe { cfmakeraw(&mut termios) };
-
-        // write settings to TTY
-        unsafe { tcsetattr(fd.0, libc::TCSANOW, &termios) };
-
-        // Read back settings from port and confirm they were applied correctly
-        let mut actual_termios = MaybeUninit::uninit();
-        unsafe { tcgetattr(fd.0, actual_termios.as_mut_ptr()) };
-        let actual_termios = unsafe { actual_termios.assume_init() };
-
-        if actual_termios.c_iflag != termios.c_iflag
-            || actual_termios.c_oflag != termios.c_oflag
-            || actual_termios.c_lflag != termios.c_lflag
-            || actual_termios.c_cflag != termios.c_cflag
-        {
-            return Err(Error::new(
-                ErrorKind::Unknown,
-                "Settings did not apply correctly",
-            ));
+        let saved_settings = match SavedSettings::snapshot(fd.0, builder) {
+            Ok(saved_settings) => saved_settings,
+            Err(_) if builder.skip_tty_checks => None,
+            Err(e) => return Err(e),
         };
 
-        #[cfg(any(target_os = "ios", target_os = "macos"))]
-        if builder.baud_rate > 0 {
-            unsafe { libc::tcflush(fd.0, libc::TCIOFLUSH) };
-        }
-
-        // clear O_NONBLOCK flag
-        fcntl(fd.0, F_SETFL(nix::fcntl::OFlag::empty()))?;
+        // Devices which are not terminals may still be usable for plain reads and writes.
+        let exclusive = match init_tty(fd.0, builder) {
+            Ok(()) => builder.share_mode == ShareMode::Exclusive,
+            Err(_) if builder.skip_tty_checks => false,
+            Err(e) => return Err(e),
+        };
 
-        // Configure the low-level port settings
-        let mut termios = termios::get_termios(fd.0)?;
-        termios::set_parity(&mut termios, builder.parity);
-        termios::set_flow_control(&mut termios, builder.flow_control);
-        termios::set_data_bits(&mut termios, builder.data_bits);
-        termios::set_stop_bits(&mut termios, builder.stop_bits);
-        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-        termios::set_baud_rate(&mut termios, builder.baud_rate)?;
-        #[cfg(any(target_os = "ios", target_os = "macos"))]
-        termios::set_termios(fd.0, &termios, builder.baud_rate)?;
-        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-        termios::set_termios(fd.0, &termios)?;
+        // Start reporting line errors from the time of opening.
+        #[cfg(target_os = "linux")]
+        let reported_errors = line_error_baseline(fd.0);
 
         // Return the final port object
         let mut port = TTYPort {
             fd: fd.into_raw(),
             timeout: builder.timeout,
-            exclusive: true,
+            exclusive: Arc::new(AtomicBool::new(exclusive)),
             port_name: Some(builder.path.clone()),
+            strictness: builder.strictness,
+            nonblocking: Arc::default(),
+            stats: Mutex::default(),
+            observed: false,
+            lock,
+            saved_settings,
+            cancel: Arc::default(),
+            retry_interrupted: builder.retry_interrupted,
+            #[cfg(target_os = "linux")]
+            wait_signal_mask: None,
+            #[cfg(target_os = "linux")]
+            reported_errors,
             #[cfg(any(target_os = "ios", target_os = "macos"))]
-            baud_rate: builder.baud_rate,
+            baud_rate: Arc::new(AtomicU32::new(builder.baud_rate)),
         };
 
         // Ignore setting DTR for pseudo terminals (indicated by baud_rate == 0).
         if builder.baud_rate > 0 {
             if let Some(dtr) = builder.dtr_on_open {
-                port.write_data_terminal_ready(dtr)?;
+                let result = port.write_data_terminal_ready(dtr);
+                if !builder.skip_tty_checks {
+                    result?;
+                }
             }
         }
 
+        Ok(port.observed())
+    }
+
+    /// Opens a TTY device as a serial port once it detects a carrier.
+    ///
+    /// The device is opened like with [`TTYPort::open`]. Afterwards, the modem control lines
+    /// are no longer ignored (`CLOCAL` is cleared) and this waits up to `timeout` for the carrier
+    /// detect (DCD) line to become asserted. Once the carrier gets lost, reads fail with
+    /// `NoDevice`.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(NotConnected)` if no carrier was detected within `timeout`. The device gets closed
+    ///   in this case.
+    /// * Any error from [`TTYPort::open`] or from reading the carrier detect line.
+    pub fn open_wait_for_carrier(
+        builder: &SerialPortBuilder,
+        timeout: Duration,
+    ) -> Result<TTYPort> {
+        let mut port = TTYPort::open(builder)?;
+
+        let mut termios = termios::get_termios(port.fd)?;
+        termios.c_cflag &= !libc::CLOCAL;
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        termios::set_termios(port.fd, &termios, port.baud_rate.load(Ordering::Relaxed))?;
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        termios::set_termios(port.fd, &termios)?;
+
+        port.wait_for_carrier(timeout)?;
         Ok(port)
     }
 
@@ -207,7 +336,7 @@ impl TTYPort {
     /// If a port is exclusive, then trying to open the same device path again
     /// will fail.
     pub fn exclusive(&self) -> bool {
-        self.exclusive
+        self.exclusive.load(Ordering::Relaxed)
     }
 
     /// Sets the exclusivity of the port
@@ -228,20 +357,385 @@ impl TTYPort {
         };
 
         setting_result?;
-        self.exclusive = exclusive;
+        self.exclusive.store(exclusive, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns how operations which are not supported by the port are handled
+    pub fn strictness(&self) -> Strictness {
+        self.strictness
+    }
+
+    /// Sets how operations which are not supported by the port are handled
+    ///
+    /// Pseudo terminals for example don't support the modem control lines.
+    pub fn set_strictness(&mut self, strictness: Strictness) {
+        self.strictness = strictness;
+    }
+
+    /// Returns whether reads, writes and waits interrupted by signals get retried
+    pub fn retry_interrupted(&self) -> bool {
+        self.retry_interrupted
+    }
+
+    /// Sets whether reads, writes and waits interrupted by signals get retried
+    ///
+    /// See [`SerialPortBuilder::retry_interrupted`] for details.
+    pub fn set_retry_interrupted(&mut self, retry_interrupted: bool) {
+        self.retry_interrupted = retry_interrupted;
+    }
+
+    /// Returns the signal mask applied while waiting for the device, if one has been set
+    #[cfg(target_os = "linux")]
+    pub fn wait_signal_mask(&self) -> Option<SignalMask> {
+        self.wait_signal_mask
+    }
+
+    /// Sets the signal mask applied while waiting for the device
+    ///
+    /// Reads, writes and flushes wait for the device with `ppoll()`, which replaces the signal
+    /// mask of the thread with `mask` for the duration of the wait and restores it atomically
+    /// afterwards. This allows keeping a signal blocked while checking for it and only
+    /// unblocking it while waiting, without the race of a signal arriving in between and only
+    /// getting noticed after the timeout. Signals delivered while waiting interrupt it. Disable
+    /// [`retry_interrupted`](TTYPort::set_retry_interrupted) for having reads report them as
+    /// `Io(Interrupted)`.
+    ///
+    /// Without a mask, which is the default, no signals are blocked while waiting. The mask is
+    /// copied to clones of the port but not shared with them.
+    ///
+    /// ```no_run
+    /// use nix::libc;
+    /// use serialport::{SignalMask, TTYPort};
+    ///
+    /// let mut port = TTYPort::open(&serialport::new("/dev/ttyUSB0", 9600)).unwrap();
+    /// // Keep `SIGINT` blocked except for while waiting for data.
+    /// let mut mask = SignalMask::current().unwrap();
+    /// mask.remove(libc::SIGINT).unwrap();
+    /// port.set_wait_signal_mask(Some(mask));
+    /// ```
+    #[cfg(target_os = "linux")]
+    pub fn set_wait_signal_mask(&mut self, mask: Option<SignalMask>) {
+        self.wait_signal_mask = mask;
+    }
+
+    /// Returns the mask for `ppoll()` while waiting for the device
+    fn sigmask(&self) -> Option<&SigSet> {
+        #[cfg(target_os = "linux")]
+        return self.wait_signal_mask.as_ref().map(|mask| &mask.0);
+        #[cfg(not(target_os = "linux"))]
+        return None;
+    }
+
+    /// Returns whether the port is in non-blocking mode
+    ///
+    /// In non-blocking mode, reads and writes don't wait for the timeout but fail with
+    /// `WouldBlock` right away if no data can be transferred.
+    pub fn nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+
+    /// Sets the non-blocking mode of the port
+    ///
+    /// This sets or clears the `O_NONBLOCK` flag of the file descriptor, which is shared by all
+    /// duplicates of it, e.g. the ones created by [`try_clone_native`](TTYPort::try_clone_native).
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` for any error while changing the file status flags.
+    pub fn set_nonblocking(&mut self, nonblocking: bool) -> Result<()> {
+        self.apply_nonblocking(nonblocking)
+    }
+
+    /// Sets the non-blocking mode through a shared reference, e.g. for a `Registration`
+    pub(crate) fn apply_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        let mut flags = OFlag::from_bits_truncate(fcntl(self.fd, nix::fcntl::F_GETFL)?);
+        flags.set(OFlag::O_NONBLOCK, nonblocking);
+        fcntl(self.fd, nix::fcntl::F_SETFL(flags))?;
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns whether child processes inherit the port
+    ///
+    /// This is the case if the `FD_CLOEXEC` flag of the file descriptor is cleared.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` for any error while querying the file descriptor flags.
+    pub fn inheritable(&self) -> Result<bool> {
+        let flags = FdFlag::from_bits_truncate(fcntl(self.fd, nix::fcntl::F_GETFD)?);
+        Ok(!flags.contains(FdFlag::FD_CLOEXEC))
+    }
+
+    /// Sets whether child processes inherit the port
+    ///
+    /// This clears or sets the `FD_CLOEXEC` flag of the file descriptor. Without it, the port
+    /// stays open in child processes after they execute another program, so it can be handed
+    /// over to them. The flag only applies to this file descriptor and not to duplicates of it,
+    /// e.g. the ones created by [`try_clone_native`](TTYPort::try_clone_native).
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` for any error while changing the file descriptor flags.
+    pub fn set_inheritable(&self, inheritable: bool) -> Result<()> {
+        let mut flags = FdFlag::from_bits_truncate(fcntl(self.fd, nix::fcntl::F_GETFD)?);
+        flags.set(FdFlag::FD_CLOEXEC, !inheritable);
+        fcntl(self.fd, nix::fcntl::F_SETFD(flags))?;
+        Ok(())
+    }
+
+    /// Creates a `TTYPort` from a file descriptor opened by other means
+    ///
+    /// Unlike [`from_raw_fd`](FromRawFd::from_raw_fd), this checks that `fd` refers to a
+    /// terminal and fails instead of falling back to defaults for settings it can't query. With
+    /// the `io-safety` feature, `TTYPort` also implements `TryFrom<OwnedFd>` doing the same
+    /// without `unsafe`. The current `O_NONBLOCK` state of the file descriptor is kept and reflected by
+    /// [`nonblocking`](TTYPort::nonblocking). All other settings of the terminal are left as they
+    /// are.
+    ///
+    /// On Linux, [`exclusive`](TTYPort::exclusive) reflects whether the terminal is in exclusive
+    /// mode. Other platforms can't query this and report the port as not exclusive.
+    ///
+    /// ## Safety
+    ///
+    /// On success, the port takes ownership of `fd` and closes it when dropped. `fd` must not be
+    /// closed or used as a port elsewhere. On failure, the caller keeps the ownership of `fd`.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `fd` does not refer to a terminal.
+    /// * `Io` for any other error while querying the file descriptor.
+    pub unsafe fn try_from_raw_fd(fd: RawFd) -> Result<TTYPort> {
+        if !nix::unistd::isatty(fd)? {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "file descriptor does not refer to a terminal",
+            ));
+        }
+
+        Ok(TTYPort {
+            fd,
+            timeout: Duration::from_millis(100),
+            exclusive: Arc::new(AtomicBool::new(is_exclusive(fd)?)),
+            port_name: None,
+            strictness: Strictness::Strict,
+            nonblocking: Arc::new(AtomicBool::new(is_nonblocking(fd)?)),
+            stats: Mutex::default(),
+            observed: false,
+            lock: None,
+            saved_settings: None,
+            cancel: Arc::default(),
+            retry_interrupted: true,
+            #[cfg(target_os = "linux")]
+            wait_signal_mask: None,
+            #[cfg(target_os = "linux")]
+            reported_errors: line_error_baseline(fd),
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            baud_rate: Arc::new(AtomicU32::new(get_termios_speed(fd)?)),
+        }
+        .observed())
+    }
+
+    /// Creates a `TTYPort` from a file descriptor opened by other means and applies the settings
+    /// of `builder`
+    ///
+    /// This is meant for daemons receiving an already opened port, e.g. through systemd socket
+    /// activation or from their parent process. Like [`try_from_raw_fd`](TTYPort::try_from_raw_fd),
+    /// this checks that `fd` refers to a terminal and keeps its `O_NONBLOCK` state. The terminal
+    /// gets configured like by [`TTYPort::open`]. The path of `builder` is only used as the name
+    /// of the port and may be empty. Its access mode and UUCP lock setting are ignored, as they
+    /// apply to opening the device. With the `io-safety` feature,
+    /// [`from_fd_with_settings`](TTYPort::from_fd_with_settings) does the same without `unsafe`.
+    ///
+    /// ## Safety
+    ///
+    /// On success, the port takes ownership of `fd` and closes it when dropped. `fd` must not be
+    /// closed or used as a port elsewhere. On failure, the caller keeps the ownership of `fd` and
+    /// the terminal gets its previous settings back.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `fd` does not refer to a terminal or the settings are not supported.
+    /// * `Io` for any other error while configuring the terminal.
+    pub unsafe fn from_raw_fd_with_settings(
+        fd: RawFd,
+        builder: &SerialPortBuilder,
+    ) -> Result<TTYPort> {
+        let port_name = (!builder.path.is_empty()).then(|| builder.path.as_str());
+        TTYPort::adopt_fd(fd, builder).map_err(|e| e.with_context("configuring", port_name))
+    }
+
+    /// Creates a `TTYPort` from an owned file descriptor and applies the settings of `builder`
+    ///
+    /// See [`from_raw_fd_with_settings`](TTYPort::from_raw_fd_with_settings) for details. The file
+    /// descriptor gets closed on failure.
+    #[cfg(feature = "io-safety")]
+    #[allow(clippy::incompatible_msrv)]
+    pub fn from_fd_with_settings(
+        fd: std::os::unix::io::OwnedFd,
+        builder: &SerialPortBuilder,
+    ) -> Result<TTYPort> {
+        let port = unsafe { TTYPort::from_raw_fd_with_settings(fd.as_raw_fd(), builder)? };
+        // The port owns the file descriptor now.
+        let _ = fd.into_raw_fd();
+        Ok(port)
+    }
+
+    fn adopt_fd(fd: RawFd, builder: &SerialPortBuilder) -> Result<TTYPort> {
+        if !nix::unistd::isatty(fd)? {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "file descriptor does not refer to a terminal",
+            ));
+        }
+        // Configuring the terminal switches it to raw mode and claims exclusive access, so keep
+        // what to restore when handing `fd` back to the caller on failure. Only Linux tells
+        // whether the terminal has been in exclusive mode before.
+        let original = termios::get_termios(fd)?;
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        let original_baud_rate = get_termios_speed(fd)?;
+        let was_exclusive = is_exclusive(fd)?;
+        let restore = scopeguard::guard((), |()| {
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            let _ = termios::set_termios(fd, &original, original_baud_rate);
+            #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+            let _ = termios::set_termios(fd, &original);
+            let _ = if was_exclusive {
+                ioctl::tiocexcl(fd)
+            } else {
+                ioctl::tiocnxcl(fd)
+            };
+        });
+
+        let saved_settings = SavedSettings::snapshot(fd, builder)?;
+        init_tty(fd, builder)?;
+
+        let mut port = TTYPort {
+            fd,
+            timeout: builder.timeout,
+            exclusive: Arc::new(AtomicBool::new(builder.share_mode == ShareMode::Exclusive)),
+            port_name: (!builder.path.is_empty()).then(|| builder.path.clone()),
+            strictness: builder.strictness,
+            nonblocking: Arc::new(AtomicBool::new(is_nonblocking(fd)?)),
+            stats: Mutex::default(),
+            observed: false,
+            lock: None,
+            saved_settings,
+            cancel: Arc::default(),
+            retry_interrupted: builder.retry_interrupted,
+            #[cfg(target_os = "linux")]
+            wait_signal_mask: None,
+            #[cfg(target_os = "linux")]
+            reported_errors: line_error_baseline(fd),
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            baud_rate: Arc::new(AtomicU32::new(builder.baud_rate)),
+        };
+
+        // Ignore setting DTR for pseudo terminals (indicated by baud_rate == 0).
+        if builder.baud_rate > 0 {
+            if let Some(dtr) = builder.dtr_on_open {
+                if let Err(e) = port.write_data_terminal_ready(dtr) {
+                    // Hand the file descriptor back to the caller instead of closing it.
+                    let _ = port.into_raw_fd();
+                    return Err(e);
+                }
+            }
+        }
+
+        scopeguard::ScopeGuard::into_inner(restore);
+        Ok(port.observed())
+    }
+
+    /// Returns a function adding `operation` and the name of the port to errors
+    fn context<'a>(&'a self, operation: &'a str) -> impl FnOnce(Error) -> Error + 'a {
+        move |error| error.with_context(operation, self.port_name.as_deref())
+    }
+
+    /// Adds `operation` and the name of the port to I/O errors
+    fn io_context(&self, error: io::Error, operation: &str) -> io::Error {
+        Error::io_with_context(error, operation, self.port_name.as_deref())
+    }
+
+    /// Applies a single change to the settings of the port
+    fn change_termios(&self, change: impl FnOnce(&mut termios::Termios)) -> Result<()> {
+        let mut termios = termios::get_termios(self.fd)?;
+        change(&mut termios);
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        return termios::set_termios(self.fd, &termios, self.baud_rate.load(Ordering::Relaxed));
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        return termios::set_termios(self.fd, &termios);
+    }
+
+    /// Applies all `settings` at once, leaving the port unchanged if this fails
+    fn apply_termios(&mut self, settings: &SerialPortBuilder) -> Result<()> {
+        let original = termios::get_termios(self.fd)?;
+        let mut termios = original;
+        termios::set_parity(&mut termios, settings.parity);
+        termios::set_flow_control(&mut termios, settings.flow_control);
+        termios::set_data_bits(&mut termios, settings.data_bits);
+        termios::set_stop_bits(&mut termios, settings.stop_bits);
+        termios::set_inter_byte_timeout(&mut termios, settings.inter_byte_timeout);
+
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        termios::set_baud_rate(&mut termios, settings.baud_rate)
+            .map_err(crate::settings::unchanged)?;
+
+        // All settings are applied with a single call, which succeeds as soon as any of them has
+        // been applied. Read them back to tell, and restore the original settings unless all of
+        // them have been applied.
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        let result = termios::set_termios(self.fd, &termios, settings.baud_rate);
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        let result = termios::set_termios(self.fd, &termios);
+        let result = result.and_then(|()| {
+            let actual = termios::get_termios(self.fd)?;
+            let mut requested = termios;
+            // Pseudo terminals keep their hardware settings, which doesn't make them unusable.
+            if is_pseudo_terminal(self.fd) {
+                requested.c_cflag = actual.c_cflag;
+            }
+            if termios::settings_applied(&requested, &actual) {
+                Ok(())
+            } else {
+                Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "the port doesn't support the requested settings",
+                ))
+            }
+        });
+        if let Err(error) = result {
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            let restored =
+                termios::set_termios(self.fd, &original, self.baud_rate.load(Ordering::Relaxed));
+            #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+            let restored = termios::set_termios(self.fd, &original);
+            return Err(match restored {
+                Ok(()) => crate::settings::unchanged(error),
+                Err(_) => crate::settings::rollback_failed(error),
+            });
+        }
+
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        {
+            self.baud_rate.store(settings.baud_rate, Ordering::Relaxed);
+        }
+        self.timeout = settings.timeout;
         Ok(())
     }
 
     fn set_pin(&mut self, pin: ioctl::SerialLines, level: bool) -> Result<()> {
-        if level {
+        let result = if level {
             ioctl::tiocmbis(self.fd, pin)
         } else {
             ioctl::tiocmbic(self.fd, pin)
-        }
+        };
+        self.strictness.or_default(result, ())
     }
 
-    fn read_pin(&mut self, pin: ioctl::SerialLines) -> Result<bool> {
-        ioctl::tiocmget(self.fd).map(|pins| pins.contains(pin))
+    fn read_pin(&mut self, pin: ioctl::SerialLines, default: bool) -> Result<bool> {
+        let result = ioctl::tiocmget(self.fd).map(|pins| pins.contains(pin));
+        self.strictness.or_default(result, default)
     }
 
     /// Create a pair of pseudo serial terminals
@@ -249,6 +743,14 @@ impl TTYPort {
     /// ## Returns
     /// Two connected `TTYPort` objects: `(master, slave)`
     ///
+    /// The slave's name is the path of its device, which can be handed to other processes for
+    /// opening the port. The master has no name, as there is no path for opening it again.
+    ///
+    /// Both ends come up in raw mode with a timeout of 100 ms. Use
+    /// [`pair_with_settings`](TTYPort::pair_with_settings) for choosing other settings.
+    ///
+    /// This function is safe to call from multiple threads at once.
+    ///
     /// ## Errors
     /// Attempting any IO or parameter settings on the slave tty after the master
     /// tty is closed will return errors.
@@ -267,8 +769,8 @@ impl TTYPort {
     /// # let _ = &mut slave;
     /// ```
     pub fn pair() -> Result<(Self, Self)> {
-        // Open the next free pty.
-        let next_pty_fd = nix::pty::posix_openpt(nix::fcntl::OFlag::O_RDWR)?;
+        // Open the next free pty. It must not become the controlling terminal of this process.
+        let next_pty_fd = nix::pty::posix_openpt(OFlag::O_RDWR | OFlag::O_NOCTTY)?;
 
         // Grant access to the associated slave pty
         nix::pty::grantpt(&next_pty_fd)?;
@@ -277,21 +779,7 @@ impl TTYPort {
         nix::pty::unlockpt(&next_pty_fd)?;
 
         // Get the path of the attached slave ptty
-        #[cfg(not(any(
-            target_os = "linux",
-            target_os = "android",
-            target_os = "emscripten",
-            target_os = "fuchsia"
-        )))]
-        let ptty_name = unsafe { nix::pty::ptsname(&next_pty_fd)? };
-
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "android",
-            target_os = "emscripten",
-            target_os = "fuchsia"
-        ))]
-        let ptty_name = nix::pty::ptsname_r(&next_pty_fd)?;
+        let ptty_name = ptsname(&next_pty_fd)?;
 
         // Open the slave port
         #[cfg(any(target_os = "ios", target_os = "macos"))]
@@ -321,10 +809,22 @@ impl TTYPort {
         let slave_tty = TTYPort {
             fd,
             timeout: Duration::from_millis(100),
-            exclusive: true,
+            exclusive: Arc::new(AtomicBool::new(true)),
             port_name: Some(ptty_name),
+            strictness: Strictness::Strict,
+            nonblocking: Arc::default(),
+            stats: Mutex::default(),
+            observed: false,
+            lock: None,
+            saved_settings: None,
+            cancel: Arc::default(),
+            retry_interrupted: true,
+            #[cfg(target_os = "linux")]
+            wait_signal_mask: None,
+            #[cfg(target_os = "linux")]
+            reported_errors: Mutex::default(),
             #[cfg(any(target_os = "ios", target_os = "macos"))]
-            baud_rate,
+            baud_rate: Arc::new(AtomicU32::new(baud_rate)),
         };
 
         // Manually construct the master port here because the
@@ -333,13 +833,70 @@ impl TTYPort {
         let master_tty = TTYPort {
             fd: next_pty_fd.into_raw_fd(),
             timeout: Duration::from_millis(100),
-            exclusive: true,
+            exclusive: Arc::new(AtomicBool::new(true)),
+            // The master is only reachable through the multiplexer, e.g. `/dev/ptmx`, and opening
+            // that creates a new pair.
             port_name: None,
+            strictness: Strictness::Strict,
+            nonblocking: Arc::default(),
+            stats: Mutex::default(),
+            observed: false,
+            lock: None,
+            saved_settings: None,
+            cancel: Arc::default(),
+            retry_interrupted: true,
+            #[cfg(target_os = "linux")]
+            wait_signal_mask: None,
+            #[cfg(target_os = "linux")]
+            reported_errors: Mutex::default(),
             #[cfg(any(target_os = "ios", target_os = "macos"))]
-            baud_rate,
+            baud_rate: Arc::new(AtomicU32::new(baud_rate)),
         };
 
-        Ok((master_tty, slave_tty))
+        Ok((master_tty.observed(), slave_tty.observed()))
+    }
+
+    /// Create a pair of pseudo serial terminals configured by `builder`
+    ///
+    /// This works like [`pair`](TTYPort::pair), but the line settings, inter-byte timeout and
+    /// strictness of `builder` are applied to the slave and its timeout and retry setting to both
+    /// ends. The master
+    /// shares the line settings of the slave. The path and the DTR setting of `builder` are
+    /// ignored.
+    ///
+    /// Both ends are in blocking mode. Use [`set_nonblocking`](TTYPort::set_nonblocking) for
+    /// switching either of them to non-blocking mode.
+    ///
+    /// ## Errors
+    ///
+    /// * Any error from [`pair`](TTYPort::pair).
+    /// * Any error from applying the settings to the slave.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use serialport::{Parity, TTYPort};
+    ///
+    /// let builder = serialport::new("", 115_200)
+    ///     .parity(Parity::Even)
+    ///     .timeout(Duration::from_millis(10));
+    /// let (master, slave) = TTYPort::pair_with_settings(&builder).unwrap();
+    /// # let _ = (master, slave);
+    /// ```
+    pub fn pair_with_settings(builder: &SerialPortBuilder) -> Result<(Self, Self)> {
+        let (mut master, mut slave) = TTYPort::pair()?;
+        slave.apply_settings(builder)?;
+        slave.strictness = builder.strictness;
+        master.strictness = builder.strictness;
+        master.timeout = builder.timeout;
+        slave.retry_interrupted = builder.retry_interrupted;
+        master.retry_interrupted = builder.retry_interrupted;
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        {
+            master.baud_rate.store(builder.baud_rate, Ordering::Relaxed);
+        }
+        Ok((master, slave))
     }
 
     /// Sends 0-valued bits over the port for a set duration
@@ -351,14 +908,23 @@ impl TTYPort {
         .map_err(|e| e.into())
     }
 
+    /// Splits the port into owned halves for reading and writing
+    ///
+    /// The halves share the port, so one thread can block on reading while another one writes
+    /// without cloning it. Both halves use the settings and timeout the port had when splitting
+    /// it.
+    pub fn split(self) -> (ReadHalf<TTYPort>, WriteHalf<TTYPort>) {
+        crate::split::split(self)
+    }
+
     /// Attempts to clone the `SerialPort`. This allow you to write and read simultaneously from the
     /// same serial connection. Please note that if you want a real asynchronous serial port you
     /// should look at [mio-serial](https://crates.io/crates/mio-serial) or
     /// [tokio-serial](https://crates.io/crates/tokio-serial).
     ///
-    /// Also, you must be very careful when changing the settings of a cloned `SerialPort` : since
-    /// the settings are cached on a per object basis, trying to modify them from two different
-    /// objects can cause some nasty behavior.
+    /// The clone shares the non-blocking mode, the exclusivity and the baud rate set on macOS with
+    /// the original port, as they apply to the device. The timeout and the strictness are kept
+    /// separately for every clone.
     ///
     /// This is the same as `SerialPort::try_clone()` but returns the concrete type instead.
     ///
@@ -369,18 +935,246 @@ impl TTYPort {
         let fd_cloned: i32 = fcntl(self.fd, nix::fcntl::F_DUPFD_CLOEXEC(self.fd))?;
         Ok(TTYPort {
             fd: fd_cloned,
-            exclusive: self.exclusive,
+            exclusive: self.exclusive.clone(),
             port_name: self.port_name.clone(),
+            strictness: self.strictness,
+            // The duplicate shares the file status flags with the original.
+            nonblocking: self.nonblocking.clone(),
+            stats: Mutex::default(),
+            observed: false,
+            lock: self.lock.clone(),
+            saved_settings: self.saved_settings.clone(),
+            cancel: self.cancel.clone(),
+            retry_interrupted: self.retry_interrupted,
+            #[cfg(target_os = "linux")]
+            wait_signal_mask: self.wait_signal_mask,
+            #[cfg(target_os = "linux")]
+            reported_errors: Mutex::new(
+                *self
+                    .reported_errors
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner()),
+            ),
             timeout: self.timeout,
             #[cfg(any(target_os = "ios", target_os = "macos"))]
-            baud_rate: self.baud_rate,
+            baud_rate: self.baud_rate.clone(),
+        }
+        .observed())
+    }
+
+    /// Reports the port to the observers and enables reporting its closing
+    fn observed(mut self) -> Self {
+        self.observed = true;
+        crate::observer::notify(|observer| observer.on_open(self.port_name.as_deref()));
+        self
+    }
+
+    /// Returns the token for cancelling blocking operations, if one has been handed out
+    fn active_cancel_token(&self) -> Option<CancelToken> {
+        self.cancel
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Reads from the port with `read`, which transfers data into buffers of `len` bytes in
+    /// total, waiting for data as needed
+    fn read_fd(
+        &self,
+        len: usize,
+        cancel: Option<&CancelToken>,
+        mut read: impl FnMut() -> nix::Result<usize>,
+    ) -> io::Result<usize> {
+        let port_cancel = self.active_cancel_token();
+        let cancel = [port_cancel.as_ref(), cancel];
+        cancel::check_all(&cancel)?;
+        self.retrying(self.timeout, |timeout| {
+            // Blocking reads may block past the timeout, so wait for data first. In non-blocking
+            // mode, read right away and report `WouldBlock` without data.
+            if !self.nonblocking() {
+                super::poll::wait_read_fd(self.fd, timeout, &cancel, self.sigmask())?;
+            }
+
+            match read() {
+                // Terminals report end of file once they have been hung up, e.g. because the
+                // device got unplugged.
+                Ok(0) if len > 0 => Err(super::poll::disconnected()),
+                result => result.map_err(|e| io::Error::from(Error::from(e))),
+            }
+        })
+    }
+
+    /// Writes to the port with `write`, waiting for buffer space as needed
+    fn write_fd(
+        &self,
+        cancel: Option<&CancelToken>,
+        mut write: impl FnMut() -> nix::Result<usize>,
+    ) -> io::Result<usize> {
+        let port_cancel = self.active_cancel_token();
+        let cancel = [port_cancel.as_ref(), cancel];
+        cancel::check_all(&cancel)?;
+        self.retrying(self.timeout, |timeout| {
+            if !self.nonblocking() {
+                super::poll::wait_write_fd(self.fd, timeout, &cancel, self.sigmask())?;
+            }
+
+            write().map_err(|e| io::Error::from(Error::from(e)))
         })
     }
+
+    /// Runs `operation` with the part of `timeout` which is left, repeating it while it gets
+    /// interrupted by signals if enabled for the port
+    fn retrying<T>(
+        &self,
+        timeout: Duration,
+        mut operation: impl FnMut(Duration) -> io::Result<T>,
+    ) -> io::Result<T> {
+        // Timeouts too long for representing their end wait forever anyway.
+        let deadline = Instant::now().checked_add(timeout);
+        let mut remaining = timeout;
+        loop {
+            match operation(remaining) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted && self.retry_interrupted => {
+                    if let Some(deadline) = deadline {
+                        remaining = deadline.saturating_duration_since(Instant::now());
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn flush_fd(&self, cancel: Option<&CancelToken>) -> io::Result<()> {
+        let port_cancel = self.active_cancel_token();
+        let cancel = [port_cancel.as_ref(), cancel];
+        cancel::check_all(&cancel)?;
+        if cancel.iter().any(Option::is_some) {
+            // `tcdrain()` can't be interrupted, so wait for the output buffer to drain while
+            // watching the tokens first.
+            while ioctl::tiocoutq(self.fd).map_or(false, |pending| pending > 0) {
+                super::poll::wait_cancelled(&cancel, DRAIN_POLL_INTERVAL, self.sigmask())?;
+            }
+        }
+
+        let timeout = Instant::now() + self.timeout;
+        loop {
+            return match nix::sys::termios::tcdrain(self.fd) {
+                Ok(_) => Ok(()),
+                Err(nix::errno::Errno::EINTR) => {
+                    // Retry flushing. But only up to the ports timeout for not retrying
+                    // indefinitely in case that it gets interrupted again.
+                    if Instant::now() < timeout {
+                        continue;
+                    } else {
+                        Err(
+                            Error::new(ErrorKind::Timeout, "timeout for retrying flush reached")
+                                .into(),
+                        )
+                    }
+                }
+                Err(_) => Err(io::Error::new(io::ErrorKind::Other, "flush failed")),
+            };
+        }
+    }
+
+    /// Reads from the port, keeping the statistics and notifying the observers
+    fn read_with(&self, buf: &mut [u8], cancel: Option<&CancelToken>) -> io::Result<usize> {
+        let result = self
+            .read_fd(buf.len(), cancel, || nix::unistd::read(self.fd, buf))
+            .map_err(|e| self.io_context(e, "reading"));
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .count_read(&result);
+        crate::observer::notify_io(&result, buf, |observer, data| {
+            observer.on_read(self.port_name.as_deref(), data)
+        });
+        result
+    }
+
+    /// Writes to the port, keeping the statistics and notifying the observers
+    fn write_with(&self, buf: &[u8], cancel: Option<&CancelToken>) -> io::Result<usize> {
+        let result = self
+            .write_fd(cancel, || nix::unistd::write(self.fd, buf))
+            .map_err(|e| self.io_context(e, "writing"));
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .count_write(&result);
+        crate::observer::notify_io(&result, buf, |observer, data| {
+            observer.on_write(self.port_name.as_deref(), data)
+        });
+        result
+    }
+
+    /// Reads into memory which isn't initialized yet, keeping the statistics and notifying the
+    /// observers
+    fn read_uninit_with(&self, buf: &mut [MaybeUninit<u8>]) -> io::Result<usize> {
+        let result = self
+            .read_fd(buf.len(), None, || {
+                let len = unsafe { libc::read(self.fd, buf.as_mut_ptr().cast(), buf.len()) };
+                nix::errno::Errno::result(len).map(|len| len as usize)
+            })
+            .map_err(|e| self.io_context(e, "reading"));
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .count_read(&result);
+        // `read()` has initialized the bytes it reported.
+        let data = result.as_ref().map_or(&[][..], |&len| unsafe {
+            slice::from_raw_parts(buf.as_ptr().cast(), len)
+        });
+        crate::observer::notify_io(&result, data, |observer, data| {
+            observer.on_read(self.port_name.as_deref(), data)
+        });
+        result
+    }
+
+    /// Reads into several buffers with a single `readv()`, keeping the statistics and notifying
+    /// the observers
+    fn read_vectored_with(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        let result = self
+            .read_fd(len, None, || uio::readv(self.fd, bufs))
+            .map_err(|e| self.io_context(e, "reading"));
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .count_read(&result);
+        crate::observer::notify_io_vectored(&result, bufs, |observer, data| {
+            observer.on_read(self.port_name.as_deref(), data)
+        });
+        result
+    }
+
+    /// Writes several buffers with a single `writev()`, keeping the statistics and notifying the
+    /// observers
+    fn write_vectored_with(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let result = self
+            .write_fd(None, || uio::writev(self.fd, bufs))
+            .map_err(|e| self.io_context(e, "writing"));
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .count_write(&result);
+        crate::observer::notify_io_vectored(&result, bufs, |observer, data| {
+            observer.on_write(self.port_name.as_deref(), data)
+        });
+        result
+    }
 }
 
+/// The interval for checking whether the output buffer has been drained while flushing with a
+/// cancel token
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 impl Drop for TTYPort {
     fn drop(&mut self) {
+        // The saved settings get restored once the last clone has dropped them.
         close(self.fd);
+        if self.observed {
+            crate::observer::notify(|observer| observer.on_close(self.port_name.as_deref()));
+        }
     }
 }
 
@@ -390,8 +1184,49 @@ impl AsRawFd for TTYPort {
     }
 }
 
+#[cfg(feature = "io-safety")]
+#[allow(clippy::incompatible_msrv)]
+impl std::os::unix::io::AsFd for TTYPort {
+    fn as_fd(&self) -> std::os::unix::io::BorrowedFd<'_> {
+        // The descriptor stays open for the lifetime of the port.
+        unsafe { std::os::unix::io::BorrowedFd::borrow_raw(self.fd) }
+    }
+}
+
+/// Creates a `TTYPort` from an owned file descriptor, checking that it refers to a terminal
+///
+/// See [`TTYPort::try_from_raw_fd`] for details. The file descriptor gets closed on failure.
+#[cfg(feature = "io-safety")]
+#[allow(clippy::incompatible_msrv)]
+impl TryFrom<std::os::unix::io::OwnedFd> for TTYPort {
+    type Error = Error;
+
+    fn try_from(fd: std::os::unix::io::OwnedFd) -> Result<Self> {
+        let port = unsafe { TTYPort::try_from_raw_fd(fd.as_raw_fd())? };
+        // The port owns the file descriptor now.
+        let _ = fd.into_raw_fd();
+        Ok(port)
+    }
+}
+
+#[cfg(feature = "io-safety")]
+#[allow(clippy::incompatible_msrv)]
+impl From<TTYPort> for std::os::unix::io::OwnedFd {
+    fn from(port: TTYPort) -> Self {
+        unsafe { std::os::unix::io::OwnedFd::from_raw_fd(port.into_raw_fd()) }
+    }
+}
+
 impl IntoRawFd for TTYPort {
-    fn into_raw_fd(self) -> RawFd {
+    fn into_raw_fd(mut self) -> RawFd {
+        // The caller takes over the device with its current settings, unless clones are still
+        // using it.
+        if let Some(saved_settings) = self.saved_settings.take() {
+            if let Ok(saved_settings) = Arc::try_unwrap(saved_settings) {
+                saved_settings.discard();
+            }
+        }
+
         // Pull just the file descriptor out. We also prevent the destructor
         // from being run by calling `mem::forget`. If we didn't do this, the
         // port would be closed, which would make `into_raw_fd` unusable.
@@ -401,15 +1236,246 @@ impl IntoRawFd for TTYPort {
     }
 }
 
+/// Returns the path of the slave device belonging to the pseudo terminal master `fd`
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "emscripten",
+    target_os = "fuchsia"
+))]
+fn ptsname(fd: &nix::pty::PtyMaster) -> Result<String> {
+    Ok(nix::pty::ptsname_r(fd)?)
+}
+
+/// Returns the path of the slave device belonging to the pseudo terminal master `fd`
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+fn ptsname(fd: &nix::pty::PtyMaster) -> Result<String> {
+    ioctl::tiocptygname(fd.as_raw_fd())
+}
+
+/// Returns the path of the slave device belonging to the pseudo terminal master `fd`
+///
+/// `ptsname` returns its result in a static buffer, so concurrent calls get serialized. This
+/// does not protect against calls from outside of this library.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "emscripten",
+    target_os = "fuchsia",
+    target_os = "ios",
+    target_os = "macos"
+)))]
+fn ptsname(fd: &nix::pty::PtyMaster) -> Result<String> {
+    static PTSNAME_LOCK: AtomicBool = AtomicBool::new(false);
+
+    while PTSNAME_LOCK
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        std::thread::yield_now();
+    }
+    let name = unsafe { nix::pty::ptsname(fd) };
+    PTSNAME_LOCK.store(false, Ordering::Release);
+
+    Ok(name?)
+}
+
+/// The settings of a terminal before opening it as a port, which get restored when dropped
+///
+/// The settings are shared by all clones of a port. They keep their own duplicate of the file
+/// descriptor, so they get restored after the last clone has been closed.
+struct SavedSettings {
+    fd: RawFd,
+    termios: termios::Termios,
+    #[cfg(any(target_os = "ios", target_os = "macos"))]
+    baud_rate: u32,
+}
+
+impl SavedSettings {
+    /// Reads the current settings of `fd` if `builder` asks for restoring them
+    fn snapshot(fd: RawFd, builder: &SerialPortBuilder) -> Result<Option<Arc<SavedSettings>>> {
+        if !builder.restore_settings {
+            return Ok(None);
+        }
+        let termios = termios::get_termios(fd)?;
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        let baud_rate = get_termios_speed(fd)?;
+        Ok(Some(Arc::new(SavedSettings {
+            fd: fcntl(fd, nix::fcntl::F_DUPFD_CLOEXEC(0))?,
+            termios,
+            #[cfg(any(target_os = "ios", target_os = "macos"))]
+            baud_rate,
+        })))
+    }
+
+    /// Closes the duplicate file descriptor without restoring the settings
+    fn discard(self) {
+        let saved_settings = mem::ManuallyDrop::new(self);
+        let _ = unistd::close(saved_settings.fd);
+    }
+}
+
+impl Drop for SavedSettings {
+    fn drop(&mut self) {
+        // Errors are ignored, as the port is being closed anyway.
+        #[cfg(any(target_os = "ios", target_os = "macos"))]
+        let _ = termios::set_termios(self.fd, &self.termios, self.baud_rate);
+        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+        let _ = termios::set_termios(self.fd, &self.termios);
+        let _ = unistd::close(self.fd);
+    }
+}
+
+impl fmt::Debug for SavedSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SavedSettings").finish_non_exhaustive()
+    }
+}
+
 /// Get the baud speed for a port from its file descriptor
 #[cfg(any(target_os = "ios", target_os = "macos"))]
-fn get_termios_speed(fd: RawFd) -> u32 {
+fn get_termios_speed(fd: RawFd) -> Result<u32> {
     let mut termios = MaybeUninit::uninit();
     let res = unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) };
-    nix::errno::Errno::result(res).expect("Failed to get termios data");
+    nix::errno::Errno::result(res)?;
     let termios = unsafe { termios.assume_init() };
-    assert_eq!(termios.c_ospeed, termios.c_ispeed);
-    termios.c_ospeed as u32
+    if termios.c_ospeed != termios.c_ispeed {
+        return Err(split_baud_rate());
+    }
+    Ok(termios.c_ospeed as u32)
+}
+
+/// Returns whether the `O_NONBLOCK` flag is set for `fd`
+fn is_nonblocking(fd: RawFd) -> Result<bool> {
+    let flags = OFlag::from_bits_truncate(fcntl(fd, nix::fcntl::F_GETFL)?);
+    Ok(flags.contains(OFlag::O_NONBLOCK))
+}
+
+/// Returns whether `fd` is in exclusive mode without changing it
+///
+/// Only Linux allows querying this, so the port is considered not exclusive elsewhere.
+fn is_exclusive(fd: RawFd) -> Result<bool> {
+    #[cfg(target_os = "linux")]
+    return ioctl::tiocgexcl(fd);
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = fd;
+        Ok(false)
+    }
+}
+
+/// Returns whether `fd` is the slave of a pseudo terminal, whose hardware settings can't be
+/// changed
+///
+/// Only Linux ignores changes to them, so this is only detected there.
+fn is_pseudo_terminal(fd: RawFd) -> bool {
+    /// The device numbers of the UNIX 98 pseudo terminal slaves, see devices.txt in the kernel
+    #[cfg(target_os = "linux")]
+    const PTY_SLAVE_MAJORS: std::ops::RangeInclusive<u64> = 136..=143;
+
+    #[cfg(target_os = "linux")]
+    return nix::sys::stat::fstat(fd)
+        .map(|stat| PTY_SLAVE_MAJORS.contains(&nix::sys::stat::major(stat.st_rdev)))
+        .unwrap_or(false);
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = fd;
+        false
+    }
+}
+
+/// The error for a port with different input and output baud rates, which can't be represented
+fn split_baud_rate() -> Error {
+    Error::new(ErrorKind::Unknown, "input and output baud rates differ")
+}
+
+/// Claims exclusive access to the terminal `fd` if requested, switches it to raw mode and
+/// applies the settings of `builder`
+fn init_tty(fd: RawFd, builder: &SerialPortBuilder) -> Result<()> {
+    use nix::libc::{cfmakeraw, tcgetattr, tcsetattr};
+
+    // Try to claim exclusive access to the port. This is performed even
+    // if the port will later be set as non-exclusive, in order to respect
+    // other applications that may have an exclusive port lock.
+    ioctl::tiocexcl(fd)?;
+    if builder.share_mode != ShareMode::Exclusive {
+        ioctl::tiocnxcl(fd)?;
+    }
+
+    let mut termios = MaybeUninit::uninit();
+    nix::errno::Errno::result(unsafe { tcgetattr(fd, termios.as_mut_ptr()) })?;
+    let mut termios = unsafe { termios.assume_init() };
+
+    // setup TTY for binary serial port access
+    // Enable reading from the port and ignore all modem control lines
+    termios.c_cflag |= libc::CREAD | libc::CLOCAL;
+    // Enable raw mode which disables any implicit processing of the input or output data streams
+    // This also sets no timeout period and a read will block until at least one character is
+    // available.
+    unsafe { cfmakeraw(&mut termios) };
+
+    // write settings to TTY
+    unsafe { tcsetattr(fd, libc::TCSANOW, &termios) };
+
+    // Read back settings from port and confirm they were applied correctly
+    let mut actual_termios = MaybeUninit::uninit();
+    unsafe { tcgetattr(fd, actual_termios.as_mut_ptr()) };
+    let actual_termios = unsafe { actual_termios.assume_init() };
+
+    if actual_termios.c_iflag != termios.c_iflag
+        || actual_termios.c_oflag != termios.c_oflag
+        || actual_termios.c_lflag != termios.c_lflag
+        || actual_termios.c_cflag != termios.c_cflag
+    {
+        return Err(Error::new(
+            ErrorKind::Unknown,
+            "Settings did not apply correctly",
+        ));
+    };
+
+    #[cfg(any(target_os = "ios", target_os = "macos"))]
+    if builder.baud_rate > 0 {
+        unsafe { libc::tcflush(fd, libc::TCIOFLUSH) };
+    }
+
+    // Configure the low-level port settings
+    let mut termios = termios::get_termios(fd)?;
+    termios::set_parity(&mut termios, builder.parity);
+    termios::set_flow_control(&mut termios, builder.flow_control);
+    termios::set_data_bits(&mut termios, builder.data_bits);
+    termios::set_stop_bits(&mut termios, builder.stop_bits);
+    termios::set_inter_byte_timeout(&mut termios, builder.inter_byte_timeout);
+    #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+    termios::set_baud_rate(&mut termios, builder.baud_rate)?;
+    #[cfg(any(target_os = "ios", target_os = "macos"))]
+    termios::set_termios(fd, &termios, builder.baud_rate)?;
+    #[cfg(not(any(target_os = "ios", target_os = "macos")))]
+    termios::set_termios(fd, &termios)?;
+
+    Ok(())
+}
+
+/// Returns the line error counters of the serial driver for `fd`
+#[cfg(target_os = "linux")]
+fn line_error_counters(fd: RawFd) -> Result<crate::LineErrors> {
+    let counters = ioctl::tiocgicount(fd)?;
+    Ok(crate::LineErrors {
+        frame: counters.frame as u32,
+        overrun: counters.overrun as u32,
+        parity: counters.parity as u32,
+        breaks: counters.brk as u32,
+        buffer_overrun: counters.buf_overrun as u32,
+    })
+}
+
+/// Returns the current line error counters of `fd` as the baseline for `take_error()`
+///
+/// Devices not counting line errors start from zero.
+#[cfg(target_os = "linux")]
+fn line_error_baseline(fd: RawFd) -> Mutex<crate::LineErrors> {
+    Mutex::new(line_error_counters(fd).unwrap_or_default())
 }
 
 impl FromRawFd for TTYPort {
@@ -417,58 +1483,84 @@ impl FromRawFd for TTYPort {
         TTYPort {
             fd,
             timeout: Duration::from_millis(100),
-            exclusive: ioctl::tiocexcl(fd).is_ok(),
+            exclusive: Arc::new(AtomicBool::new(ioctl::tiocexcl(fd).is_ok())),
             // It is not trivial to get the file path corresponding to a file descriptor.
             // We'll punt on it and set it to `None` here.
             port_name: None,
+            strictness: Strictness::Strict,
+            nonblocking: Arc::new(AtomicBool::new(is_nonblocking(fd).unwrap_or(false))),
+            stats: Mutex::default(),
+            observed: false,
+            lock: None,
+            saved_settings: None,
+            cancel: Arc::default(),
+            retry_interrupted: true,
+            #[cfg(target_os = "linux")]
+            wait_signal_mask: None,
+            #[cfg(target_os = "linux")]
+            reported_errors: line_error_baseline(fd),
             // It's not guaranteed that the baud rate in the `termios` struct is correct, as
             // setting an arbitrary baud rate via the `iossiospeed` ioctl overrides that value,
-            // but extract that value anyways as a best-guess of the actual baud rate.
+            // but extract that value anyways as a best-guess of the actual baud rate. Zero
+            // indicates that not even a guess is possible.
             #[cfg(any(target_os = "ios", target_os = "macos"))]
-            baud_rate: get_termios_speed(fd),
+            baud_rate: Arc::new(AtomicU32::new(get_termios_speed(fd).unwrap_or(0))),
         }
+        .observed()
     }
 }
 
 impl io::Read for TTYPort {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if let Err(e) = super::poll::wait_read_fd(self.fd, self.timeout) {
-            return Err(io::Error::from(Error::from(e)));
-        }
+        (&*self).read(buf)
+    }
 
-        nix::unistd::read(self.fd, buf).map_err(|e| io::Error::from(Error::from(e)))
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        (&*self).read_vectored(bufs)
+    }
+}
+
+/// Reads through a shared reference, so one thread can read while another one writes
+///
+/// Like with `TcpStream`, this allows using a port shared with an `Arc` without cloning its file
+/// descriptor.
+impl io::Read for &TTYPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(buf, None)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.read_vectored_with(bufs)
     }
 }
 
 impl io::Write for TTYPort {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        if let Err(e) = super::poll::wait_write_fd(self.fd, self.timeout) {
-            return Err(io::Error::from(Error::from(e)));
-        }
+        (&*self).write(buf)
+    }
 
-        nix::unistd::write(self.fd, buf).map_err(|e| io::Error::from(Error::from(e)))
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        (&*self).write_vectored(bufs)
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        let timeout = Instant::now() + self.timeout;
-        loop {
-            return match nix::sys::termios::tcdrain(self.fd) {
-                Ok(_) => Ok(()),
-                Err(nix::errno::Errno::EINTR) => {
-                    // Retry flushing. But only up to the ports timeout for not retrying
-                    // indefinitely in case that it gets interrupted again.
-                    if Instant::now() < timeout {
-                        continue;
-                    } else {
-                        Err(io::Error::new(
-                            io::ErrorKind::TimedOut,
-                            "timeout for retrying flush reached",
-                        ))
-                    }
-                }
-                Err(_) => Err(io::Error::new(io::ErrorKind::Other, "flush failed")),
-            };
-        }
+        (&*self).flush()
+    }
+}
+
+/// Writes through a shared reference, so one thread can write while another one reads
+impl io::Write for &TTYPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(buf, None)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.write_vectored_with(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_fd(None)
+            .map_err(|e| self.io_context(e, "flushing"))
     }
 }
 
@@ -495,7 +1587,9 @@ impl SerialPort for TTYPort {
     fn baud_rate(&self) -> Result<u32> {
         let termios2 = ioctl::tcgets2(self.fd)?;
 
-        assert!(termios2.c_ospeed == termios2.c_ispeed);
+        if termios2.c_ospeed != termios2.c_ispeed {
+            return Err(split_baud_rate());
+        }
 
         Ok(termios2.c_ospeed)
     }
@@ -516,7 +1610,9 @@ impl SerialPort for TTYPort {
         let ospeed = unsafe { libc::cfgetospeed(&termios) };
         let ispeed = unsafe { libc::cfgetispeed(&termios) };
 
-        assert!(ospeed == ispeed);
+        if ospeed != ispeed {
+            return Err(split_baud_rate());
+        }
 
         Ok(ospeed as u32)
     }
@@ -527,7 +1623,7 @@ impl SerialPort for TTYPort {
     /// desired baud rate.
     #[cfg(any(target_os = "ios", target_os = "macos"))]
     fn baud_rate(&self) -> Result<u32> {
-        Ok(self.baud_rate)
+        Ok(self.baud_rate.load(Ordering::Relaxed))
     }
 
     /// Returns the port's baud rate
@@ -556,7 +1652,9 @@ impl SerialPort for TTYPort {
         let ospeed = unsafe { libc::cfgetospeed(&termios) };
         let ispeed = unsafe { libc::cfgetispeed(&termios) };
 
-        assert!(ospeed == ispeed);
+        if ospeed != ispeed {
+            return Err(split_baud_rate());
+        }
 
         let res: u32 = match ospeed {
             B50 => 50,
@@ -589,7 +1687,12 @@ impl SerialPort for TTYPort {
             B3000000 => 3_000_000,
             B3500000 => 3_500_000,
             B4000000 => 4_000_000,
-            _ => unreachable!(),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::Unknown,
+                    "Non-standard baud rate encountered",
+                ))
+            }
         };
 
         Ok(res)
@@ -646,6 +1749,11 @@ impl SerialPort for TTYPort {
         self.timeout
     }
 
+    fn inter_byte_timeout(&self) -> Result<Option<Duration>> {
+        let termios = termios::get_termios(self.fd)?;
+        Ok(termios::inter_byte_timeout(&termios))
+    }
+
     #[cfg(any(
         target_os = "android",
         target_os = "dragonfly",
@@ -655,53 +1763,41 @@ impl SerialPort for TTYPort {
         target_os = "linux"
     ))]
     fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
-        let mut termios = termios::get_termios(self.fd)?;
-        termios::set_baud_rate(&mut termios, baud_rate)?;
-        termios::set_termios(self.fd, &termios)
+        termios::get_termios(self.fd)
+            .and_then(|mut termios| {
+                termios::set_baud_rate(&mut termios, baud_rate)?;
+                termios::set_termios(self.fd, &termios)
+            })
+            .map_err(self.context("setting baud rate"))
     }
 
     // Mac OS needs special logic for setting arbitrary baud rates.
     #[cfg(any(target_os = "ios", target_os = "macos"))]
     fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
-        ioctl::iossiospeed(self.fd, &(baud_rate as libc::speed_t))?;
-        self.baud_rate = baud_rate;
+        ioctl::iossiospeed(self.fd, &(baud_rate as libc::speed_t))
+            .map_err(self.context("setting baud rate"))?;
+        self.baud_rate.store(baud_rate, Ordering::Relaxed);
         Ok(())
     }
 
     fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
-        let mut termios = termios::get_termios(self.fd)?;
-        termios::set_flow_control(&mut termios, flow_control);
-        #[cfg(any(target_os = "ios", target_os = "macos"))]
-        return termios::set_termios(self.fd, &termios, self.baud_rate);
-        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-        return termios::set_termios(self.fd, &termios);
+        self.change_termios(|termios| termios::set_flow_control(termios, flow_control))
+            .map_err(self.context("setting flow control"))
     }
 
     fn set_parity(&mut self, parity: Parity) -> Result<()> {
-        let mut termios = termios::get_termios(self.fd)?;
-        termios::set_parity(&mut termios, parity);
-        #[cfg(any(target_os = "ios", target_os = "macos"))]
-        return termios::set_termios(self.fd, &termios, self.baud_rate);
-        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-        return termios::set_termios(self.fd, &termios);
+        self.change_termios(|termios| termios::set_parity(termios, parity))
+            .map_err(self.context("setting parity"))
     }
 
     fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
-        let mut termios = termios::get_termios(self.fd)?;
-        termios::set_data_bits(&mut termios, data_bits);
-        #[cfg(any(target_os = "ios", target_os = "macos"))]
-        return termios::set_termios(self.fd, &termios, self.baud_rate);
-        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-        return termios::set_termios(self.fd, &termios);
+        self.change_termios(|termios| termios::set_data_bits(termios, data_bits))
+            .map_err(self.context("setting data bits"))
     }
 
     fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
-        let mut termios = termios::get_termios(self.fd)?;
-        termios::set_stop_bits(&mut termios, stop_bits);
-        #[cfg(any(target_os = "ios", target_os = "macos"))]
-        return termios::set_termios(self.fd, &termios, self.baud_rate);
-        #[cfg(not(any(target_os = "ios", target_os = "macos")))]
-        return termios::set_termios(self.fd, &termios);
+        self.change_termios(|termios| termios::set_stop_bits(termios, stop_bits))
+            .map_err(self.context("setting stop bits"))
     }
 
     fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
@@ -709,6 +1805,16 @@ impl SerialPort for TTYPort {
         Ok(())
     }
 
+    fn set_inter_byte_timeout(&mut self, inter_byte_timeout: Option<Duration>) -> Result<()> {
+        self.change_termios(|termios| termios::set_inter_byte_timeout(termios, inter_byte_timeout))
+            .map_err(self.context("setting inter-byte timeout"))
+    }
+
+    fn apply_settings(&mut self, settings: &SerialPortBuilder) -> Result<()> {
+        self.apply_termios(settings)
+            .map_err(self.context("applying settings"))
+    }
+
     fn write_request_to_send(&mut self, level: bool) -> Result<()> {
         self.set_pin(SerialLines::REQUEST_TO_SEND, level)
     }
@@ -718,19 +1824,19 @@ impl SerialPort for TTYPort {
     }
 
     fn read_clear_to_send(&mut self) -> Result<bool> {
-        self.read_pin(SerialLines::CLEAR_TO_SEND)
+        self.read_pin(SerialLines::CLEAR_TO_SEND, true)
     }
 
     fn read_data_set_ready(&mut self) -> Result<bool> {
-        self.read_pin(SerialLines::DATA_SET_READY)
+        self.read_pin(SerialLines::DATA_SET_READY, true)
     }
 
     fn read_ring_indicator(&mut self) -> Result<bool> {
-        self.read_pin(SerialLines::RING)
+        self.read_pin(SerialLines::RING, false)
     }
 
     fn read_carrier_detect(&mut self) -> Result<bool> {
-        self.read_pin(SerialLines::DATA_CARRIER_DETECT)
+        self.read_pin(SerialLines::DATA_CARRIER_DETECT, true)
     }
 
     fn bytes_to_read(&self) -> Result<u32> {
@@ -738,7 +1844,7 @@ impl SerialPort for TTYPort {
     }
 
     fn bytes_to_write(&self) -> Result<u32> {
-        ioctl::tiocoutq(self.fd)
+        self.strictness.or_default(ioctl::tiocoutq(self.fd), 0)
     }
 
     fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
@@ -755,6 +1861,78 @@ impl SerialPort for TTYPort {
             .map_err(|e| e.into())
     }
 
+    fn stats(&self) -> Result<PortStats> {
+        Ok(*self.stats.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        Ok(!super::poll::is_hung_up(self.fd)?)
+    }
+
+    fn cancel_token(&self) -> Result<CancelToken> {
+        let mut cancel = self.cancel.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(token) = &*cancel {
+            return Ok(token.clone());
+        }
+        let token = CancelToken::new()?;
+        *cancel = Some(token.clone());
+        Ok(token)
+    }
+
+    fn wait_readable(&self, timeout: Duration) -> Result<()> {
+        let cancel = self.active_cancel_token();
+        self.retrying(timeout, |timeout| {
+            super::poll::wait_read_fd(self.fd, timeout, &[cancel.as_ref()], self.sigmask())
+        })
+        .map_err(Error::from)
+        .map_err(self.context("waiting for data"))
+    }
+
+    fn wait_writable(&self, timeout: Duration) -> Result<()> {
+        let cancel = self.active_cancel_token();
+        self.retrying(timeout, |timeout| {
+            super::poll::wait_write_fd(self.fd, timeout, &[cancel.as_ref()], self.sigmask())
+        })
+        .map_err(Error::from)
+        .map_err(self.context("waiting for buffer space"))
+    }
+
+    fn read_cancellable(&mut self, buf: &mut [u8], cancel: &CancelToken) -> io::Result<usize> {
+        self.read_with(buf, Some(cancel))
+    }
+
+    fn read_uninit<'a>(&mut self, buf: &'a mut [MaybeUninit<u8>]) -> io::Result<&'a mut [u8]> {
+        let len = self.read_uninit_with(buf)?;
+        // `read()` has initialized the bytes it reported.
+        Ok(unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), len) })
+    }
+
+    fn write_cancellable(&mut self, buf: &[u8], cancel: &CancelToken) -> io::Result<usize> {
+        self.write_with(buf, Some(cancel))
+    }
+
+    fn flush_cancellable(&mut self, cancel: &CancelToken) -> io::Result<()> {
+        self.flush_fd(Some(cancel))
+            .map_err(|e| self.io_context(e, "flushing"))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn line_errors(&self) -> Result<crate::LineErrors> {
+        line_error_counters(self.fd)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn take_error(&self) -> Result<Option<Error>> {
+        let counters = line_error_counters(self.fd)?;
+        let mut reported = self
+            .reported_errors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let detected = counters.since(&reported);
+        *reported = counters;
+        Ok(detected.to_error())
+    }
+
     fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
         match self.try_clone_native() {
             Ok(p) => Ok(Box::new(p)),
@@ -763,11 +1941,11 @@ impl SerialPort for TTYPort {
     }
 
     fn set_break(&self) -> Result<()> {
-        ioctl::tiocsbrk(self.fd)
+        self.strictness.or_default(ioctl::tiocsbrk(self.fd), ())
     }
 
     fn clear_break(&self) -> Result<()> {
-        ioctl::tioccbrk(self.fd)
+        self.strictness.or_default(ioctl::tioccbrk(self.fd), ())
     }
 }
 
@@ -776,7 +1954,6 @@ fn test_ttyport_into_raw_fd() {
     // `master` must be used here as Dropping it causes slave to be deleted by the OS.
     // TODO: Convert this to a statement-level attribute once
     //       https://github.com/rust-lang/rust/issues/15701 is on stable.
-    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
     #![allow(unused_variables)]
     let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
 
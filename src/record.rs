@@ -0,0 +1,936 @@
+//! Recording the traffic of serial ports
+//!
+//! A [`RecordingPort`] wraps another port and records the data going through it along with
+//! changes to the modem control lines and settings. By default, the recording is a text file with
+//! one [`Record`] per line, so it can be attached to bug reports and inspected with any text
+//! editor. Recordings in [`Format::Pcapng`] can be analyzed with Wireshark instead.
+//!
+//! ```no_run
+//! use serialport::record::RecordingPort;
+//!
+//! let port = serialport::new("/dev/ttyUSB0", 115_200)
+//!     .open_native()
+//!     .expect("Failed to open port");
+//! let mut port = RecordingPort::create(port, "session.txt").expect("Failed to create recording");
+//! // ... use `port` like any other port
+//! port.finish().expect("Failed to write recording");
+//! ```
+//!
+//! Recordings can be read back with [`read_records`] and replayed with a [`ReplayPort`].
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    CancelToken, ClearBuffer, DataBits, Error, ErrorKind, FlowControl, LineErrors, Parity,
+    PortStats, Result, SerialPort, StopBits,
+};
+
+/// The first line of every recording
+const HEADER: &str = "# serialport recording";
+
+/// Something that happened on a port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Data has been read from the port
+    Read(Vec<u8>),
+    /// Data has been written to the port
+    Write(Vec<u8>),
+    /// The RTS output has been set to the given level
+    RequestToSend(bool),
+    /// The DTR output has been set to the given level
+    DataTerminalReady(bool),
+    /// The CTS input has been read with the given level
+    ClearToSend(bool),
+    /// The DSR input has been read with the given level
+    DataSetReady(bool),
+    /// The RI input has been read with the given level
+    RingIndicator(bool),
+    /// The CD input has been read with the given level
+    CarrierDetect(bool),
+    /// A break condition has been started (`true`) or stopped (`false`)
+    Break(bool),
+    /// The baud rate has been changed
+    BaudRate(u32),
+    /// The number of data bits has been changed
+    DataBits(DataBits),
+    /// The flow control mode has been changed
+    FlowControl(FlowControl),
+    /// The parity-checking mode has been changed
+    Parity(Parity),
+    /// The number of stop bits has been changed
+    StopBits(StopBits),
+}
+
+/// An [`Event`] along with the time it happened at
+///
+/// The text representation used in recordings consists of the timestamp in microseconds, the
+/// name of the event and its value, e.g. `1500 write 41540d` for writing `AT\r` 1.5 ms after the
+/// recording started. Data is given in hexadecimal, levels as `0` or `1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The time since the recording started
+    pub timestamp: Duration,
+    /// What happened
+    pub event: Event,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Read(data) => write!(f, "read {}", Hex(data)),
+            Event::Write(data) => write!(f, "write {}", Hex(data)),
+            Event::RequestToSend(level) => write!(f, "rts {}", u8::from(*level)),
+            Event::DataTerminalReady(level) => write!(f, "dtr {}", u8::from(*level)),
+            Event::ClearToSend(level) => write!(f, "cts {}", u8::from(*level)),
+            Event::DataSetReady(level) => write!(f, "dsr {}", u8::from(*level)),
+            Event::RingIndicator(level) => write!(f, "ri {}", u8::from(*level)),
+            Event::CarrierDetect(level) => write!(f, "cd {}", u8::from(*level)),
+            Event::Break(level) => write!(f, "break {}", u8::from(*level)),
+            Event::BaudRate(baud_rate) => write!(f, "baud-rate {}", baud_rate),
+            Event::DataBits(data_bits) => write!(f, "data-bits {}", u8::from(*data_bits)),
+            Event::FlowControl(flow_control) => write!(f, "flow-control {}", flow_control),
+            Event::Parity(parity) => write!(f, "parity {}", parity),
+            Event::StopBits(stop_bits) => write!(f, "stop-bits {}", u8::from(*stop_bits)),
+        }
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.timestamp.as_micros(), self.event)
+    }
+}
+
+impl FromStr for Record {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::new(ErrorKind::InvalidInput, format!("invalid record: {}", s));
+
+        let mut fields = s.split_whitespace();
+        let timestamp = fields
+            .next()
+            .and_then(|micros| micros.parse().ok())
+            .map(Duration::from_micros)
+            .ok_or_else(invalid)?;
+        let name = fields.next().ok_or_else(invalid)?;
+        let value = fields.next().unwrap_or("");
+        if fields.next().is_some() {
+            return Err(invalid());
+        }
+
+        let level = || match value {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            _ => Err(invalid()),
+        };
+        let event = match name {
+            "read" => Event::Read(parse_hex(value).ok_or_else(invalid)?),
+            "write" => Event::Write(parse_hex(value).ok_or_else(invalid)?),
+            "rts" => Event::RequestToSend(level()?),
+            "dtr" => Event::DataTerminalReady(level()?),
+            "cts" => Event::ClearToSend(level()?),
+            "dsr" => Event::DataSetReady(level()?),
+            "ri" => Event::RingIndicator(level()?),
+            "cd" => Event::CarrierDetect(level()?),
+            "break" => Event::Break(level()?),
+            "baud-rate" => Event::BaudRate(value.parse().map_err(|_| invalid())?),
+            "data-bits" => Event::DataBits(
+                value
+                    .parse::<u8>()
+                    .ok()
+                    .and_then(|bits| DataBits::try_from(bits).ok())
+                    .ok_or_else(invalid)?,
+            ),
+            "flow-control" => Event::FlowControl(value.parse().map_err(|_| invalid())?),
+            "parity" => Event::Parity(match value {
+                "None" => Parity::None,
+                "Odd" => Parity::Odd,
+                "Even" => Parity::Even,
+                _ => return Err(invalid()),
+            }),
+            "stop-bits" => Event::StopBits(
+                value
+                    .parse::<u8>()
+                    .ok()
+                    .and_then(|bits| StopBits::try_from(bits).ok())
+                    .ok_or_else(invalid)?,
+            ),
+            _ => return Err(invalid()),
+        };
+
+        Ok(Record { timestamp, event })
+    }
+}
+
+/// Formats data as lowercase hexadecimal digits without separators
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Reads the records of a recording
+///
+/// Empty lines and lines starting with `#` are skipped.
+///
+/// ## Errors
+///
+/// * `InvalidInput` if a line is not a valid [`Record`].
+/// * `Io` for any error while reading.
+pub fn read_records<R: io::Read>(reader: R) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        records.push(line.parse()?);
+    }
+    Ok(records)
+}
+
+/// The file format of a recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// One [`Record`] per line in its text representation, readable with [`read_records`]
+    Text,
+    /// [pcapng](https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-02.html) as used by
+    /// Wireshark
+    ///
+    /// Every event becomes a packet on a single interface with the link type `USER0` (147). Data
+    /// read from the port is marked as inbound and data written to it as outbound. Other events
+    /// become empty packets with the event's text representation as comment, marked as inbound
+    /// for levels read from the modem control inputs and as outbound otherwise.
+    Pcapng,
+}
+
+/// The pcapng link type for user-defined protocols, `LINKTYPE_USER0`
+const PCAPNG_LINK_TYPE: u16 = 147;
+
+/// Builds a pcapng block from its type and body, which must be padded to 32 bits
+fn pcapng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+    let len = (body.len() + 12) as u32;
+    let mut block = Vec::with_capacity(len as usize);
+    block.extend_from_slice(&block_type.to_le_bytes());
+    block.extend_from_slice(&len.to_le_bytes());
+    block.extend_from_slice(body);
+    block.extend_from_slice(&len.to_le_bytes());
+    block
+}
+
+/// Appends a pcapng option padded to 32 bits
+fn pcapng_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend_from_slice(&code.to_le_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    pcapng_padded(body, value);
+}
+
+fn pcapng_padded(body: &mut Vec<u8>, data: &[u8]) {
+    body.extend_from_slice(data);
+    body.resize(body.len() + (4 - data.len() % 4) % 4, 0);
+}
+
+/// Builds the section header and interface description blocks starting a pcapng file
+fn pcapng_header(name: Option<&str>) -> Vec<u8> {
+    let mut section = Vec::new();
+    section.extend_from_slice(&0x1A2B_3C4Du32.to_le_bytes());
+    section.extend_from_slice(&1u16.to_le_bytes());
+    section.extend_from_slice(&0u16.to_le_bytes());
+    // The length of the section is not known upfront.
+    section.extend_from_slice(&(-1i64).to_le_bytes());
+
+    let mut interface = Vec::new();
+    interface.extend_from_slice(&PCAPNG_LINK_TYPE.to_le_bytes());
+    interface.extend_from_slice(&0u16.to_le_bytes());
+    interface.extend_from_slice(&0u32.to_le_bytes());
+    if let Some(name) = name {
+        // if_name
+        pcapng_option(&mut interface, 2, name.as_bytes());
+    }
+    // if_tsresol: microseconds
+    pcapng_option(&mut interface, 9, &[6]);
+    pcapng_option(&mut interface, 0, &[]);
+
+    let mut header = pcapng_block(0x0A0D_0D0A, &section);
+    header.extend(pcapng_block(1, &interface));
+    header
+}
+
+/// Builds an enhanced packet block for `event` happening at `timestamp` since the Unix epoch
+fn pcapng_packet(timestamp: Duration, event: &Event) -> Vec<u8> {
+    const INBOUND: u32 = 0b01;
+    const OUTBOUND: u32 = 0b10;
+
+    let (data, flags, comment) = match event {
+        Event::Read(data) => (&data[..], INBOUND, None),
+        Event::Write(data) => (&data[..], OUTBOUND, None),
+        Event::ClearToSend(_)
+        | Event::DataSetReady(_)
+        | Event::RingIndicator(_)
+        | Event::CarrierDetect(_) => (&[][..], INBOUND, Some(event.to_string())),
+        _ => (&[][..], OUTBOUND, Some(event.to_string())),
+    };
+
+    let micros = timestamp.as_micros() as u64;
+    let mut body = Vec::new();
+    body.extend_from_slice(&0u32.to_le_bytes());
+    body.extend_from_slice(&((micros >> 32) as u32).to_le_bytes());
+    body.extend_from_slice(&(micros as u32).to_le_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    pcapng_padded(&mut body, data);
+    if let Some(comment) = comment {
+        // opt_comment
+        pcapng_option(&mut body, 1, comment.as_bytes());
+    }
+    // epb_flags
+    pcapng_option(&mut body, 2, &flags.to_le_bytes());
+    pcapng_option(&mut body, 0, &[]);
+    pcapng_block(6, &body)
+}
+
+/// A port recording its traffic
+///
+/// Reads, writes, changes to the modem control outputs and settings as well as the levels read
+/// from the modem control inputs are recorded. Failing operations are not recorded.
+///
+/// The recording is written as [`Format::Text`] unless another format is chosen with
+/// [`with_format`](RecordingPort::with_format) or
+/// [`create_with_format`](RecordingPort::create_with_format).
+///
+/// Failures to write the recording don't disturb the communication. Recording stops at the first
+/// failure, which gets reported by [`finish`](RecordingPort::finish).
+pub struct RecordingPort<P> {
+    inner: P,
+    recorder: Mutex<Recorder>,
+}
+
+struct Recorder {
+    sink: Box<dyn Write + Send>,
+    format: Format,
+    start: Instant,
+    /// The time since the Unix epoch at `start`
+    epoch: Duration,
+    error: Option<io::Error>,
+}
+
+impl<P: SerialPort> RecordingPort<P> {
+    /// Wraps `inner` and records its traffic to `sink`
+    pub fn new<W: Write + Send + 'static>(inner: P, sink: W) -> Self {
+        Self::with_format(inner, sink, Format::Text)
+    }
+
+    /// Wraps `inner` and records its traffic to `sink` in the given format
+    pub fn with_format<W: Write + Send + 'static>(inner: P, sink: W, format: Format) -> Self {
+        let mut sink: Box<dyn Write + Send> = Box::new(sink);
+        let name = inner.name();
+        let error = match (format, name) {
+            (Format::Text, Some(name)) => writeln!(sink, "{} of {}", HEADER, name),
+            (Format::Text, None) => writeln!(sink, "{}", HEADER),
+            (Format::Pcapng, name) => sink.write_all(&pcapng_header(name.as_deref())),
+        }
+        .err();
+
+        RecordingPort {
+            inner,
+            recorder: Mutex::new(Recorder {
+                sink,
+                format,
+                start: Instant::now(),
+                epoch: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default(),
+                error,
+            }),
+        }
+    }
+
+    /// Wraps `inner` and records its traffic to a new file at `path`
+    ///
+    /// An existing file at `path` gets overwritten.
+    pub fn create(inner: P, path: impl AsRef<Path>) -> Result<Self> {
+        Self::create_with_format(inner, path, Format::Text)
+    }
+
+    /// Wraps `inner` and records its traffic to a new file at `path` in the given format
+    ///
+    /// An existing file at `path` gets overwritten.
+    pub fn create_with_format(inner: P, path: impl AsRef<Path>, format: Format) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self::with_format(inner, BufWriter::new(file), format))
+    }
+
+    /// Returns a reference to the wrapped port
+    pub fn get_ref(&self) -> &P {
+        &self.inner
+    }
+
+    /// Completes the recording and returns the wrapped port
+    ///
+    /// ## Errors
+    ///
+    /// Returns the first error which occurred while writing the recording.
+    pub fn finish(self) -> Result<P> {
+        let mut recorder = self
+            .recorder
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner());
+        if recorder.error.is_none() {
+            recorder.error = recorder.sink.flush().err();
+        }
+        match recorder.error {
+            Some(e) => Err(e.into()),
+            None => Ok(self.inner),
+        }
+    }
+
+    fn recorder(&self) -> MutexGuard<'_, Recorder> {
+        self.recorder.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn record(&self, event: Event) {
+        let mut recorder = self.recorder();
+        if recorder.error.is_some() {
+            return;
+        }
+        let timestamp = recorder.start.elapsed();
+        let result = match recorder.format {
+            Format::Text => writeln!(recorder.sink, "{}", Record { timestamp, event }),
+            Format::Pcapng => {
+                let packet = pcapng_packet(recorder.epoch + timestamp, &event);
+                recorder.sink.write_all(&packet)
+            }
+        };
+        recorder.error = result.err();
+    }
+
+    /// Records `event` if `result` indicates success
+    fn record_ok<T>(&self, result: Result<T>, event: impl FnOnce(&T) -> Event) -> Result<T> {
+        if let Ok(value) = &result {
+            self.record(event(value));
+        }
+        result
+    }
+}
+
+impl<P: fmt::Debug> fmt::Debug for RecordingPort<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordingPort")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<P: SerialPort> io::Read for RecordingPort<P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.record(Event::Read(buf[..n].to_vec()));
+        }
+        Ok(n)
+    }
+}
+
+impl<P: SerialPort> io::Write for RecordingPort<P> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if n > 0 {
+            self.record(Event::Write(buf[..n].to_vec()));
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<P: SerialPort> SerialPort for RecordingPort<P> {
+    fn name(&self) -> Option<String> {
+        self.inner.name()
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        self.inner.baud_rate()
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        self.inner.data_bits()
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        self.inner.flow_control()
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        self.inner.parity()
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        self.inner.stop_bits()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.inner.timeout()
+    }
+
+    fn inter_byte_timeout(&self) -> Result<Option<Duration>> {
+        self.inner.inter_byte_timeout()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        let result = self.inner.set_baud_rate(baud_rate);
+        self.record_ok(result, |_| Event::BaudRate(baud_rate))
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        let result = self.inner.set_data_bits(data_bits);
+        self.record_ok(result, |_| Event::DataBits(data_bits))
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        let result = self.inner.set_flow_control(flow_control);
+        self.record_ok(result, |_| Event::FlowControl(flow_control))
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        let result = self.inner.set_parity(parity);
+        self.record_ok(result, |_| Event::Parity(parity))
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        let result = self.inner.set_stop_bits(stop_bits);
+        self.record_ok(result, |_| Event::StopBits(stop_bits))
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.inner.set_timeout(timeout)
+    }
+
+    fn set_inter_byte_timeout(&mut self, inter_byte_timeout: Option<Duration>) -> Result<()> {
+        self.inner.set_inter_byte_timeout(inter_byte_timeout)
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
+        let result = self.inner.write_request_to_send(level);
+        self.record_ok(result, |_| Event::RequestToSend(level))
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
+        let result = self.inner.write_data_terminal_ready(level);
+        self.record_ok(result, |_| Event::DataTerminalReady(level))
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        let result = self.inner.read_clear_to_send();
+        self.record_ok(result, |level| Event::ClearToSend(*level))
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        let result = self.inner.read_data_set_ready();
+        self.record_ok(result, |level| Event::DataSetReady(*level))
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        let result = self.inner.read_ring_indicator();
+        self.record_ok(result, |level| Event::RingIndicator(*level))
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        let result = self.inner.read_carrier_detect();
+        self.record_ok(result, |level| Event::CarrierDetect(*level))
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        self.inner.bytes_to_read()
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        self.inner.bytes_to_write()
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        self.inner.clear(buffer_to_clear)
+    }
+
+    fn line_errors(&self) -> Result<LineErrors> {
+        self.inner.line_errors()
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        self.inner.take_error()
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        self.inner.stats()
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        self.inner.is_connected()
+    }
+
+    fn cancel_token(&self) -> Result<CancelToken> {
+        self.inner.cancel_token()
+    }
+
+    fn read_cancellable(&mut self, buf: &mut [u8], cancel: &CancelToken) -> io::Result<usize> {
+        let n = self.inner.read_cancellable(buf, cancel)?;
+        if n > 0 {
+            self.record(Event::Read(buf[..n].to_vec()));
+        }
+        Ok(n)
+    }
+
+    fn write_cancellable(&mut self, buf: &[u8], cancel: &CancelToken) -> io::Result<usize> {
+        let n = self.inner.write_cancellable(buf, cancel)?;
+        if n > 0 {
+            self.record(Event::Write(buf[..n].to_vec()));
+        }
+        Ok(n)
+    }
+
+    fn flush_cancellable(&mut self, cancel: &CancelToken) -> io::Result<()> {
+        self.inner.flush_cancellable(cancel)
+    }
+
+    fn wait_readable(&self, timeout: Duration) -> Result<()> {
+        self.inner.wait_readable(timeout)
+    }
+
+    fn wait_writable(&self, timeout: Duration) -> Result<()> {
+        self.inner.wait_writable(timeout)
+    }
+
+    /// Clones the wrapped port, without recording the clone's traffic
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        self.inner.try_clone()
+    }
+
+    fn set_break(&self) -> Result<()> {
+        let result = self.inner.set_break();
+        self.record_ok(result, |_| Event::Break(true))
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        let result = self.inner.clear_break();
+        self.record_ok(result, |_| Event::Break(false))
+    }
+}
+
+/// A port replaying a recording
+///
+/// The port acts like the device from the recording: the data read during the recording becomes
+/// available for reading and the modem control inputs change their levels at the same time
+/// relative to the creation of the port as they did relative to the start of the recording.
+/// Everything the application did during the recording, like writing data or changing settings,
+/// is ignored. Data written to the port gets discarded.
+///
+/// After the end of the recording the port behaves like a silent device and reads time out.
+///
+/// ```no_run
+/// use std::io::Read;
+///
+/// use serialport::record::ReplayPort;
+///
+/// let mut port = ReplayPort::open("session.txt").expect("Failed to open recording");
+/// let mut response = [0u8; 4];
+/// port.read_exact(&mut response).expect("Failed to read from replay");
+/// ```
+#[derive(Debug)]
+pub struct ReplayPort {
+    replay: Arc<Mutex<Replay>>,
+    port_name: String,
+    baud_rate: u32,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+    timeout: Duration,
+}
+
+/// The progress of a replay, shared with the clones of a port
+#[derive(Debug)]
+struct Replay {
+    records: Vec<Record>,
+    position: usize,
+    start: Instant,
+    data: VecDeque<u8>,
+    clear_to_send: bool,
+    data_set_ready: bool,
+    ring_indicator: bool,
+    carrier_detect: bool,
+}
+
+impl Replay {
+    /// Applies all records which are due by now
+    fn advance(&mut self) {
+        let elapsed = self.start.elapsed();
+        while let Some(record) = self.records.get(self.position) {
+            if record.timestamp > elapsed {
+                break;
+            }
+            match &record.event {
+                Event::Read(data) => self.data.extend(data),
+                Event::ClearToSend(level) => self.clear_to_send = *level,
+                Event::DataSetReady(level) => self.data_set_ready = *level,
+                Event::RingIndicator(level) => self.ring_indicator = *level,
+                Event::CarrierDetect(level) => self.carrier_detect = *level,
+                _ => {}
+            }
+            self.position += 1;
+        }
+    }
+
+    /// Returns the time until the next data becomes available, if there is any more data
+    fn next_data(&self) -> Option<Duration> {
+        self.records[self.position..]
+            .iter()
+            .find(|record| matches!(&record.event, Event::Read(data) if !data.is_empty()))
+            .map(|record| record.timestamp.saturating_sub(self.start.elapsed()))
+    }
+}
+
+impl ReplayPort {
+    /// Creates a port replaying `records`
+    ///
+    /// The records are expected to be ordered by their timestamps. The replay starts right away.
+    pub fn new(records: Vec<Record>) -> Self {
+        Self::with_name("replay", records)
+    }
+
+    /// Creates a port replaying the recording at `path`
+    ///
+    /// The name of the port is `path`.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if the file is not a valid recording.
+    /// * `Io` for any error while reading the file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let records = read_records(File::open(path)?)?;
+        Ok(Self::with_name(&path.to_string_lossy(), records))
+    }
+
+    fn with_name(name: &str, records: Vec<Record>) -> Self {
+        ReplayPort {
+            replay: Arc::new(Mutex::new(Replay {
+                records,
+                position: 0,
+                start: Instant::now(),
+                data: VecDeque::new(),
+                clear_to_send: false,
+                data_set_ready: false,
+                ring_indicator: false,
+                carrier_detect: false,
+            })),
+            port_name: name.to_string(),
+            baud_rate: 9600,
+            data_bits: DataBits::Eight,
+            flow_control: FlowControl::None,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            timeout: Duration::from_millis(0),
+        }
+    }
+
+    /// Returns whether all records have been replayed and all data has been read
+    pub fn is_finished(&self) -> bool {
+        let mut replay = self.replay();
+        replay.advance();
+        replay.position == replay.records.len() && replay.data.is_empty()
+    }
+
+    /// Attempts to clone the port, returning a `ReplayPort`
+    ///
+    /// The clone shares the progress of the replay with this port.
+    pub fn try_clone_native(&self) -> Result<ReplayPort> {
+        Ok(ReplayPort {
+            replay: self.replay.clone(),
+            port_name: self.port_name.clone(),
+            ..*self
+        })
+    }
+
+    fn replay(&self) -> MutexGuard<'_, Replay> {
+        let mut replay = self.replay.lock().unwrap_or_else(|e| e.into_inner());
+        replay.advance();
+        replay
+    }
+}
+
+impl io::Read for ReplayPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let wait = {
+                let mut replay = self.replay();
+                if !replay.data.is_empty() {
+                    let n = buf.len().min(replay.data.len());
+                    for (dst, src) in buf.iter_mut().zip(replay.data.drain(..n)) {
+                        *dst = src;
+                    }
+                    return Ok(n);
+                }
+                replay.next_data()
+            };
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match wait {
+                Some(wait) if wait <= remaining => thread::sleep(wait),
+                _ => {
+                    thread::sleep(remaining);
+                    return Err(Error::new(ErrorKind::Timeout, "Operation timed out").into());
+                }
+            }
+        }
+    }
+}
+
+impl io::Write for ReplayPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for ReplayPort {
+    fn name(&self) -> Option<String> {
+        Some(self.port_name.clone())
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        Ok(self.data_bits)
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        Ok(self.flow_control)
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        Ok(self.parity)
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        Ok(self.stop_bits)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        self.data_bits = data_bits;
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        self.flow_control = flow_control;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        self.parity = parity;
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        self.stop_bits = stop_bits;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        Ok(self.replay().clear_to_send)
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        Ok(self.replay().data_set_ready)
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        Ok(self.replay().ring_indicator)
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        Ok(self.replay().carrier_detect)
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        Ok(self.replay().data.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        // Written data is discarded right away.
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        if let ClearBuffer::Input | ClearBuffer::All = buffer_to_clear {
+            self.replay().data.clear();
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.try_clone_native()?))
+    }
+
+    fn set_break(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+//! Integrating ports into custom event loops.
+
+use std::ops::BitOr;
+
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
+#[cfg(windows)]
+use std::os::windows::io::{AsRawHandle, RawHandle};
+
+use crate::Result;
+#[cfg(unix)]
+use crate::TTYPort;
+#[cfg(windows)]
+use crate::{windows::Waiter, COMPort, SerialPort};
+
+/// The directions of I/O an event loop waits for, or which are ready
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Interest {
+    readable: bool,
+    writable: bool,
+}
+
+impl Interest {
+    /// Neither reading nor writing
+    pub const NONE: Interest = Interest {
+        readable: false,
+        writable: false,
+    };
+
+    /// Reading, i.e. received data
+    pub const READABLE: Interest = Interest {
+        readable: true,
+        writable: false,
+    };
+
+    /// Writing, i.e. space in the output buffer
+    pub const WRITABLE: Interest = Interest {
+        readable: false,
+        writable: true,
+    };
+
+    /// Returns whether reading is included
+    pub fn is_readable(self) -> bool {
+        self.readable
+    }
+
+    /// Returns whether writing is included
+    pub fn is_writable(self) -> bool {
+        self.writable
+    }
+
+    /// Returns whether neither reading nor writing is included
+    pub fn is_empty(self) -> bool {
+        !self.readable && !self.writable
+    }
+}
+
+impl BitOr for Interest {
+    type Output = Interest;
+
+    fn bitor(self, other: Interest) -> Interest {
+        Interest {
+            readable: self.readable || other.readable,
+            writable: self.writable || other.writable,
+        }
+    }
+}
+
+/// A port prepared for waiting on it in a custom event loop
+///
+/// The registration hands out the object to wait for: the file descriptor of the port on POSIX
+/// and an event on Windows. The usual cycle is:
+///
+/// 1. [`arm`](Registration::arm) the registration with the directions to wait for,
+/// 2. wait for the object with the event loop,
+/// 3. [`clear`](Registration::clear) the registration, which returns the ready directions,
+/// 4. read or write through [`port`](Registration::port) until the port would block.
+///
+/// On POSIX, the port is switched to non-blocking mode while registered, so reads and writes
+/// fail with `WouldBlock` instead of waiting. Arming only records the interest there, as the
+/// descriptor can be polled directly. On Windows, arming starts waiting for received data in
+/// the background. The event stays signaled until clearing the registration. Writing never
+/// waits for buffer space there, so arming for writing signals the event right away.
+///
+/// ```no_run
+/// use std::io::Read;
+/// use serialport::{Interest, Registration};
+///
+/// let port = serialport::new("/dev/ttyUSB0", 9600).open_native().expect("Failed to open");
+/// let mut registration = Registration::new(&port).expect("Failed to register");
+///
+/// registration.arm(Interest::READABLE).expect("Failed to arm");
+/// // ... wait for `registration.raw_fd()` or `registration.raw_event()` ...
+/// if registration.clear().expect("Failed to clear").is_readable() {
+///     let mut buf = [0u8; 64];
+///     let _ = registration.port().read(&mut buf);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Registration<'a> {
+    #[cfg(unix)]
+    port: &'a TTYPort,
+    /// The mode to restore when dropping the registration
+    #[cfg(unix)]
+    was_nonblocking: bool,
+    #[cfg(windows)]
+    port: &'a COMPort,
+    /// The background wait for received data, boxed as the system writes to it while pending
+    #[cfg(windows)]
+    waiter: Box<Waiter>,
+    interest: Interest,
+}
+
+impl<'a> Registration<'a> {
+    /// Registers `port` and switches it to non-blocking mode until the registration is dropped
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the mode of the port could not be changed.
+    #[cfg(unix)]
+    pub fn new(port: &'a TTYPort) -> Result<Registration<'a>> {
+        let was_nonblocking = port.nonblocking();
+        port.apply_nonblocking(true)?;
+        Ok(Registration {
+            port,
+            was_nonblocking,
+            interest: Interest::NONE,
+        })
+    }
+
+    /// Registers `port`
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the event could not be created.
+    #[cfg(windows)]
+    pub fn new(port: &'a COMPort) -> Result<Registration<'a>> {
+        Ok(Registration {
+            port,
+            waiter: Box::new(Waiter::new(port.as_raw_handle() as _)?),
+            interest: Interest::NONE,
+        })
+    }
+
+    /// Returns the registered port, for reading and writing through the shared reference
+    #[cfg(unix)]
+    pub fn port(&self) -> &'a TTYPort {
+        self.port
+    }
+
+    /// Returns the registered port, for reading and writing through the shared reference
+    #[cfg(windows)]
+    pub fn port(&self) -> &'a COMPort {
+        self.port
+    }
+
+    /// Returns the file descriptor to poll for the armed interest
+    #[cfg(unix)]
+    pub fn raw_fd(&self) -> RawFd {
+        self.port.as_raw_fd()
+    }
+
+    /// Returns the event which gets signaled when the armed interest is ready
+    ///
+    /// The event is owned by the registration and must not be closed.
+    #[cfg(windows)]
+    pub fn raw_event(&self) -> RawHandle {
+        self.waiter.event() as RawHandle
+    }
+
+    /// Returns the armed interest, which is empty after clearing
+    pub fn interest(&self) -> Interest {
+        self.interest
+    }
+
+    /// Arms the registration for waiting for `interest`, replacing any armed interest
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if waiting for received data could not be started on
+    /// Windows.
+    pub fn arm(&mut self, interest: Interest) -> Result<()> {
+        self.clear()?;
+        #[cfg(windows)]
+        {
+            let received = interest.is_readable() && self.waiter.start()?;
+            if received || interest.is_writable() {
+                self.waiter.signal();
+            }
+        }
+        self.interest = interest;
+        Ok(())
+    }
+
+    /// Disarms the registration and returns the part of the armed interest which is ready
+    ///
+    /// A disconnected device counts as ready, so the following read or write reports the error.
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the state of the port could not be queried.
+    pub fn clear(&mut self) -> Result<Interest> {
+        let interest = std::mem::replace(&mut self.interest, Interest::NONE);
+
+        #[cfg(unix)]
+        let (readable, writable) = crate::posix::ready_now(
+            self.port.as_raw_fd(),
+            interest.is_readable(),
+            interest.is_writable(),
+        )?;
+
+        #[cfg(windows)]
+        let (readable, writable) = {
+            let received = self.waiter.finish();
+            self.waiter.reset();
+            let readable = interest.is_readable() && (received || self.port.bytes_to_read()? > 0);
+            (readable, interest.is_writable())
+        };
+
+        Ok(Interest { readable, writable })
+    }
+}
+
+impl<'a> Drop for Registration<'a> {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if !self.was_nonblocking {
+            let _ = self.port.apply_nonblocking(false);
+        }
+    }
+}
@@ -0,0 +1,570 @@
+//! A client for serial ports shared over the network with RFC 2217.
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{
+    ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity, PortStats, Result, SerialPort,
+    SerialPortBuilder, StopBits,
+};
+
+// Telnet commands
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+// Telnet options
+const BINARY: u8 = 0;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const COM_PORT_OPTION: u8 = 44;
+
+// COM port option commands sent by the client. The server responds with the command plus 100.
+const SET_BAUDRATE: u8 = 1;
+const SET_DATASIZE: u8 = 2;
+const SET_PARITY: u8 = 3;
+const SET_STOPSIZE: u8 = 4;
+const SET_CONTROL: u8 = 5;
+const SET_MODEMSTATE_MASK: u8 = 11;
+const PURGE_DATA: u8 = 12;
+const SERVER_OFFSET: u8 = 100;
+const NOTIFY_MODEMSTATE: u8 = 7 + SERVER_OFFSET;
+
+// Values for SET_CONTROL
+const CONTROL_NO_FLOW_CONTROL: u8 = 1;
+const CONTROL_XON_XOFF: u8 = 2;
+const CONTROL_HARDWARE: u8 = 3;
+const CONTROL_BREAK_ON: u8 = 5;
+const CONTROL_BREAK_OFF: u8 = 6;
+const CONTROL_DTR_ON: u8 = 8;
+const CONTROL_DTR_OFF: u8 = 9;
+const CONTROL_RTS_ON: u8 = 11;
+const CONTROL_RTS_OFF: u8 = 12;
+
+// Bits of the modem state
+const MODEMSTATE_CTS: u8 = 0x10;
+const MODEMSTATE_DSR: u8 = 0x20;
+const MODEMSTATE_RI: u8 = 0x40;
+const MODEMSTATE_CD: u8 = 0x80;
+
+/// The minimum time to wait for the server to accept the COM port option
+const MIN_NEGOTIATION_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A serial port on a terminal server, accessed over TCP with the Telnet COM port control
+/// option from [RFC 2217](https://www.rfc-editor.org/rfc/rfc2217)
+///
+/// This allows talking to serial ports shared by servers like ser2net or by network terminal
+/// servers like any local port. Settings and the modem control outputs are forwarded to the
+/// server, the modem control inputs report the state last notified by the server.
+///
+/// ```no_run
+/// use std::io::Write;
+/// use serialport::Rfc2217Port;
+///
+/// let mut port = Rfc2217Port::open(&serialport::new("terminal-server:2217", 115_200))
+///     .expect("Failed to connect");
+/// port.write_all(b"AT\r").expect("Failed to write to port");
+/// ```
+#[derive(Debug)]
+pub struct Rfc2217Port {
+    stream: TcpStream,
+    port_name: String,
+    baud_rate: u32,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+    timeout: Duration,
+    stats: PortStats,
+    /// Data received from the server, without Telnet commands
+    data: Mutex<VecDeque<u8>>,
+    telnet: TelnetState,
+    com_port_option: Option<bool>,
+    modem_state: u8,
+}
+
+/// The state of parsing the Telnet commands received from the server
+#[derive(Debug)]
+enum TelnetState {
+    Data,
+    Command,
+    Negotiation(u8),
+    Subnegotiation(Vec<u8>),
+    SubnegotiationCommand(Vec<u8>),
+}
+
+impl Rfc2217Port {
+    /// Connects to the server at the builder's path and configures the port
+    ///
+    /// The path is the address of the server, e.g. `terminal-server:2217`. The connection and the
+    /// negotiation of the COM port option may take up to the builder's timeout, but at least one
+    /// second.
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if the server refuses the connection or does not support RFC 2217.
+    /// * `Io` for any other error while connecting.
+    pub fn open(builder: &SerialPortBuilder) -> Result<Self> {
+        let timeout = builder.timeout.max(MIN_NEGOTIATION_TIMEOUT);
+        let stream = connect(&builder.path, timeout)?;
+        stream.set_nodelay(true)?;
+
+        let mut port = Rfc2217Port {
+            stream,
+            port_name: builder.path.clone(),
+            baud_rate: builder.baud_rate,
+            data_bits: builder.data_bits,
+            flow_control: builder.flow_control,
+            parity: builder.parity,
+            stop_bits: builder.stop_bits,
+            timeout: builder.timeout,
+            stats: PortStats::default(),
+            data: Mutex::new(VecDeque::new()),
+            telnet: TelnetState::Data,
+            com_port_option: None,
+            modem_state: 0,
+        };
+
+        port.send(&[
+            IAC,
+            WILL,
+            COM_PORT_OPTION,
+            IAC,
+            WILL,
+            BINARY,
+            IAC,
+            DO,
+            BINARY,
+            IAC,
+            DO,
+            SUPPRESS_GO_AHEAD,
+        ])?;
+        let deadline = Instant::now() + timeout;
+        while port.com_port_option.is_none() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) || !port.receive(Some(remaining))? {
+                break;
+            }
+        }
+        if port.com_port_option != Some(true) {
+            return Err(Error::new(
+                ErrorKind::NoDevice,
+                format!("{} does not support RFC 2217", builder.path),
+            ));
+        }
+
+        port.com_port_command(SET_MODEMSTATE_MASK, &[0xff])?;
+        port.set_baud_rate(builder.baud_rate)?;
+        port.set_data_bits(builder.data_bits)?;
+        port.set_parity(builder.parity)?;
+        port.set_stop_bits(builder.stop_bits)?;
+        port.set_flow_control(builder.flow_control)?;
+        if let Some(dtr) = builder.dtr_on_open {
+            port.write_data_terminal_ready(dtr)?;
+        }
+
+        Ok(port)
+    }
+
+    fn send(&self, data: &[u8]) -> io::Result<()> {
+        (&self.stream).write_all(data)
+    }
+
+    fn com_port_command(&self, command: u8, value: &[u8]) -> Result<()> {
+        let mut message = vec![IAC, SB, COM_PORT_OPTION, command];
+        escape(value, &mut message);
+        message.extend_from_slice(&[IAC, SE]);
+        Ok(self.send(&message)?)
+    }
+
+    /// Receives data from the server, waiting up to `timeout` or not at all for `None`
+    ///
+    /// Returns whether any data has been received.
+    fn receive(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
+        let mut buf = [0u8; 1024];
+        let result = match timeout {
+            Some(timeout) => {
+                self.stream.set_read_timeout(Some(timeout))?;
+                self.stream.read(&mut buf)
+            }
+            None => {
+                self.stream.set_nonblocking(true)?;
+                let result = self.stream.read(&mut buf);
+                self.stream.set_nonblocking(false)?;
+                result
+            }
+        };
+
+        match result {
+            Ok(0) => Err(Error::new(ErrorKind::NoDevice, "connection closed by the server").into()),
+            Ok(n) => {
+                for &byte in &buf[..n] {
+                    self.parse(byte)?;
+                }
+                Ok(true)
+            }
+            Err(e)
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut =>
+            {
+                Ok(false)
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Processes all data which has already been received from the server
+    fn receive_pending(&mut self) -> Result<()> {
+        while self.receive(None)? {}
+        Ok(())
+    }
+
+    fn parse(&mut self, byte: u8) -> io::Result<()> {
+        self.telnet = match std::mem::replace(&mut self.telnet, TelnetState::Data) {
+            TelnetState::Data if byte == IAC => TelnetState::Command,
+            TelnetState::Data => {
+                self.data().push_back(byte);
+                TelnetState::Data
+            }
+            TelnetState::Command => match byte {
+                IAC => {
+                    self.data().push_back(IAC);
+                    TelnetState::Data
+                }
+                WILL | WONT | DO | DONT => TelnetState::Negotiation(byte),
+                SB => TelnetState::Subnegotiation(Vec::new()),
+                // Other commands have no meaning for a serial port.
+                _ => TelnetState::Data,
+            },
+            TelnetState::Negotiation(command) => {
+                self.negotiate(command, byte)?;
+                TelnetState::Data
+            }
+            TelnetState::Subnegotiation(sb) if byte == IAC => {
+                TelnetState::SubnegotiationCommand(sb)
+            }
+            TelnetState::Subnegotiation(mut sb) => {
+                sb.push(byte);
+                TelnetState::Subnegotiation(sb)
+            }
+            TelnetState::SubnegotiationCommand(mut sb) => match byte {
+                SE => {
+                    self.subnegotiation(&sb);
+                    TelnetState::Data
+                }
+                _ => {
+                    sb.push(byte);
+                    TelnetState::Subnegotiation(sb)
+                }
+            },
+        };
+        Ok(())
+    }
+
+    /// Responds to the server's request to enable or disable an option
+    fn negotiate(&mut self, command: u8, option: u8) -> io::Result<()> {
+        match (command, option) {
+            (DO, COM_PORT_OPTION) => self.com_port_option = Some(true),
+            (DONT, COM_PORT_OPTION) => self.com_port_option = Some(false),
+            // These options have been requested by the client already.
+            (DO, BINARY) | (WILL, BINARY) | (WILL, SUPPRESS_GO_AHEAD) => {}
+            (DO, _) => self.send(&[IAC, WONT, option])?,
+            (WILL, _) => self.send(&[IAC, DONT, option])?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Handles the server's notifications and responses to settings
+    fn subnegotiation(&mut self, sb: &[u8]) {
+        let (command, value) = match sb {
+            [COM_PORT_OPTION, command, value @ ..] => (*command, value),
+            _ => return,
+        };
+        match (command.wrapping_sub(SERVER_OFFSET), value) {
+            (SET_BAUDRATE, &[a, b, c, d]) => {
+                let baud_rate = u32::from_be_bytes([a, b, c, d]);
+                if baud_rate != 0 {
+                    self.baud_rate = baud_rate;
+                }
+            }
+            (SET_DATASIZE, &[data_bits]) => {
+                if let Ok(data_bits) = DataBits::try_from(data_bits) {
+                    self.data_bits = data_bits;
+                }
+            }
+            (SET_PARITY, &[parity]) => match parity {
+                1 => self.parity = Parity::None,
+                2 => self.parity = Parity::Odd,
+                3 => self.parity = Parity::Even,
+                _ => {}
+            },
+            (SET_STOPSIZE, &[stop_bits]) => match stop_bits {
+                1 => self.stop_bits = StopBits::One,
+                2 => self.stop_bits = StopBits::Two,
+                _ => {}
+            },
+            _ if command == NOTIFY_MODEMSTATE => {
+                if let [modem_state] = value {
+                    self.modem_state = *modem_state;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn data(&self) -> MutexGuard<'_, VecDeque<u8>> {
+        self.data.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn read_data(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        self.receive_pending()?;
+        let deadline = Instant::now() + self.timeout;
+        while self.data().is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                return Err(Error::new(ErrorKind::Timeout, "Operation timed out").into());
+            }
+            self.receive(Some(remaining))?;
+        }
+
+        let mut data = self.data();
+        let n = buf.len().min(data.len());
+        for (dst, src) in buf.iter_mut().zip(data.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+
+    fn read_modem_state(&mut self, mask: u8) -> Result<bool> {
+        self.receive_pending()?;
+        Ok(self.modem_state & mask != 0)
+    }
+}
+
+/// Connects to `address`, trying all addresses it resolves to
+fn connect(address: &str, timeout: Duration) -> Result<TcpStream> {
+    let mut last_error = Error::new(
+        ErrorKind::NoDevice,
+        format!("{} does not resolve to any address", address),
+    );
+    for addr in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                last_error = Error::new(ErrorKind::NoDevice, e.to_string());
+            }
+            Err(e) => last_error = e.into(),
+        }
+    }
+    Err(last_error)
+}
+
+/// Appends `data` to `buf`, escaping the Telnet command byte
+fn escape(data: &[u8], buf: &mut Vec<u8>) {
+    for &byte in data {
+        buf.push(byte);
+        if byte == IAC {
+            buf.push(IAC);
+        }
+    }
+}
+
+impl io::Read for Rfc2217Port {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.read_data(buf);
+        self.stats.count_read(&result);
+        result
+    }
+}
+
+impl io::Write for Rfc2217Port {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut escaped = Vec::with_capacity(buf.len());
+        escape(buf, &mut escaped);
+        if self.timeout > Duration::from_secs(0) {
+            self.stream.set_write_timeout(Some(self.timeout))?;
+        }
+        let result = self.send(&escaped).map(|_| buf.len());
+        self.stats.count_write(&result);
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl SerialPort for Rfc2217Port {
+    fn name(&self) -> Option<String> {
+        Some(self.port_name.clone())
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        Ok(self.data_bits)
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        Ok(self.flow_control)
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        Ok(self.parity)
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        Ok(self.stop_bits)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        if baud_rate == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "baud rate must not be zero",
+            ));
+        }
+        self.com_port_command(SET_BAUDRATE, &baud_rate.to_be_bytes())?;
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        self.com_port_command(SET_DATASIZE, &[u8::from(data_bits)])?;
+        self.data_bits = data_bits;
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        let value = match flow_control {
+            FlowControl::None => CONTROL_NO_FLOW_CONTROL,
+            FlowControl::Software => CONTROL_XON_XOFF,
+            FlowControl::Hardware => CONTROL_HARDWARE,
+        };
+        self.com_port_command(SET_CONTROL, &[value])?;
+        self.flow_control = flow_control;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        let value = match parity {
+            Parity::None => 1,
+            Parity::Odd => 2,
+            Parity::Even => 3,
+        };
+        self.com_port_command(SET_PARITY, &[value])?;
+        self.parity = parity;
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        let value = match stop_bits {
+            StopBits::One => 1,
+            StopBits::Two => 2,
+        };
+        self.com_port_command(SET_STOPSIZE, &[value])?;
+        self.stop_bits = stop_bits;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
+        let value = if level {
+            CONTROL_RTS_ON
+        } else {
+            CONTROL_RTS_OFF
+        };
+        self.com_port_command(SET_CONTROL, &[value])
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
+        let value = if level {
+            CONTROL_DTR_ON
+        } else {
+            CONTROL_DTR_OFF
+        };
+        self.com_port_command(SET_CONTROL, &[value])
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        self.read_modem_state(MODEMSTATE_CTS)
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        self.read_modem_state(MODEMSTATE_DSR)
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        self.read_modem_state(MODEMSTATE_RI)
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        self.read_modem_state(MODEMSTATE_CD)
+    }
+
+    /// Returns the number of bytes received from the server so far
+    ///
+    /// Data still buffered by the server or in transit is not included.
+    fn bytes_to_read(&self) -> Result<u32> {
+        Ok(self.data().len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        // Written data is handed over to the network stack right away.
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        let value = match buffer_to_clear {
+            ClearBuffer::Input => 1,
+            ClearBuffer::Output => 2,
+            ClearBuffer::All => 3,
+        };
+        self.com_port_command(PURGE_DATA, &[value])?;
+        if let ClearBuffer::Input | ClearBuffer::All = buffer_to_clear {
+            self.data().clear();
+        }
+        Ok(())
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        Ok(self.stats)
+    }
+
+    /// Cloning is not supported as the connection's Telnet state can't be shared.
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "cloning not supported by RFC 2217 ports",
+        ))
+    }
+
+    fn set_break(&self) -> Result<()> {
+        self.com_port_command(SET_CONTROL, &[CONTROL_BREAK_ON])
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        self.com_port_command(SET_CONTROL, &[CONTROL_BREAK_OFF])
+    }
+}
@@ -0,0 +1,168 @@
+//! Waiting for data on several ports at once.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::os::unix::io::{AsRawFd, RawFd};
+#[cfg(windows)]
+use std::os::windows::io::AsRawHandle;
+
+use crate::{Error, ErrorKind, Result};
+
+/// Waits for data on multiple ports from a single thread
+///
+/// Ports get added with [`add`](Selector::add), which returns a key identifying them.
+/// [`select`](Selector::select) then blocks until at least one of the ports has data to read
+/// and returns the keys of all ready ports. This allows gateways serving many devices to get by
+/// without a thread per port.
+///
+/// The selector borrows the ports, which can still be read through shared references, e.g.
+/// with `(&port).read(..)`.
+///
+/// ```no_run
+/// use std::io::Read;
+/// use std::time::Duration;
+/// use serialport::Selector;
+///
+/// let ports = ["/dev/ttyUSB0", "/dev/ttyUSB1"]
+///     .iter()
+///     .map(|path| serialport::new(*path, 9600).open_native())
+///     .collect::<serialport::Result<Vec<_>>>()
+///     .expect("Failed to open ports");
+///
+/// let mut selector = Selector::new();
+/// let keys = ports
+///     .iter()
+///     .map(|port| selector.add(port))
+///     .collect::<serialport::Result<Vec<_>>>()
+///     .expect("Failed to add ports");
+///
+/// for key in selector.select(Duration::from_secs(1)).expect("No data received") {
+///     let index = keys.iter().position(|&k| k == key).unwrap();
+///     let mut buf = [0u8; 64];
+///     let len = (&ports[index]).read(&mut buf).expect("Failed to read");
+///     println!("{}: {:?}", index, &buf[..len]);
+/// }
+/// ```
+///
+/// On Windows, at most 64 ports can be waited for. Reading from a port while a selector waits
+/// for it on another thread is not supported there.
+pub struct Selector<'a> {
+    keys: Vec<usize>,
+    #[cfg(unix)]
+    fds: Vec<RawFd>,
+    #[cfg(windows)]
+    waiters: Vec<crate::windows::Waiter>,
+    next_key: usize,
+    ports: PhantomData<&'a ()>,
+}
+
+impl<'a> Selector<'a> {
+    /// Creates a selector without any ports
+    pub fn new() -> Self {
+        Selector {
+            keys: Vec::new(),
+            #[cfg(unix)]
+            fds: Vec::new(),
+            #[cfg(windows)]
+            waiters: Vec::new(),
+            next_key: 0,
+            ports: PhantomData,
+        }
+    }
+
+    /// Adds a port and returns the key identifying it in the results of `select()`
+    ///
+    /// This works with any pollable descriptor, like the ones of `TTYPort` and `PipeSerialPort`.
+    ///
+    /// ## Errors
+    ///
+    /// This never fails on POSIX. Windows creates an event for each port, which may fail.
+    #[cfg(unix)]
+    pub fn add(&mut self, port: &'a impl AsRawFd) -> Result<usize> {
+        self.fds.push(port.as_raw_fd());
+        Ok(self.push_key())
+    }
+
+    /// Adds a port and returns the key identifying it in the results of `select()`
+    ///
+    /// ## Errors
+    ///
+    /// This function returns an error if the resources for waiting could not be created.
+    #[cfg(windows)]
+    pub fn add(&mut self, port: &'a impl AsRawHandle) -> Result<usize> {
+        let waiter = crate::windows::Waiter::new(port.as_raw_handle() as _)?;
+        self.waiters.push(waiter);
+        Ok(self.push_key())
+    }
+
+    fn push_key(&mut self) -> usize {
+        let key = self.next_key;
+        self.next_key += 1;
+        self.keys.push(key);
+        key
+    }
+
+    /// Removes the port identified by `key`, returning whether it was added before
+    pub fn remove(&mut self, key: usize) -> bool {
+        let index = match self.keys.iter().position(|&k| k == key) {
+            Some(index) => index,
+            None => return false,
+        };
+        self.keys.remove(index);
+        #[cfg(unix)]
+        self.fds.remove(index);
+        #[cfg(windows)]
+        self.waiters.remove(index);
+        true
+    }
+
+    /// Returns the number of ports
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns whether no ports have been added
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Waits up to `timeout` for data on any of the ports and returns the keys of all ports
+    /// with data to read
+    ///
+    /// Ports which have been disconnected count as ready, so reading them reports the error.
+    ///
+    /// ## Errors
+    ///
+    /// * `Timeout` if no port received data within `timeout`.
+    /// * `InvalidInput` if there are no ports to wait for, or too many of them on Windows.
+    /// * `Io` for any other error while waiting.
+    pub fn select(&mut self, timeout: Duration) -> Result<Vec<usize>> {
+        if self.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "no ports to wait for"));
+        }
+
+        #[cfg(unix)]
+        let ready = crate::posix::select_readable(&self.fds, timeout)?;
+        #[cfg(windows)]
+        let ready = crate::windows::select_readable(&mut self.waiters, timeout)?;
+
+        Ok(ready.into_iter().map(|index| self.keys[index]).collect())
+    }
+}
+
+impl<'a> Default for Selector<'a> {
+    fn default() -> Self {
+        Selector::new()
+    }
+}
+
+impl<'a> fmt::Debug for Selector<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Selector")
+            .field("keys", &self.keys)
+            .finish()
+    }
+}
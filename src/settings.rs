@@ -0,0 +1,253 @@
+//! Reading and applying multiple port settings at once.
+
+use std::time::Duration;
+
+use crate::{
+    DataBits, Error, FlowControl, Parity, Result, SerialPort, SerialPortBuilder, StopBits,
+};
+
+/// A snapshot of the settings of an open port
+///
+/// The snapshot covers the same settings as [`SerialPort::apply_settings`]. It is obtained with
+/// [`SerialPort::try_settings`] or [`SerialPort::settings_lossy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortSettings {
+    /// The baud rate in symbols-per-second
+    pub baud_rate: u32,
+    /// Number of bits used to represent a character sent on the line
+    pub data_bits: DataBits,
+    /// The type of signalling to use for controlling data transfer
+    pub flow_control: FlowControl,
+    /// The type of parity to use for error checking
+    pub parity: Parity,
+    /// Number of bits to use to signal the end of a character
+    pub stop_bits: StopBits,
+    /// Amount of time to wait to receive data before timing out
+    pub timeout: Duration,
+    /// Maximum time to wait for the next byte once data has started arriving
+    pub inter_byte_timeout: Option<Duration>,
+}
+
+/// A setting which can be read from an open port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Setting {
+    /// The baud rate
+    BaudRate,
+    /// The number of data bits
+    DataBits,
+    /// The flow control mode
+    FlowControl,
+    /// The parity-checking mode
+    Parity,
+    /// The number of stop bits
+    StopBits,
+    /// The inter-byte timeout
+    InterByteTimeout,
+}
+
+/// A snapshot of the settings of an open port, including the ones which could not be read
+///
+/// Returned by [`SerialPort::settings_lossy`]. Settings which could not be read are listed in
+/// `unknown` and hold the default value of [`SerialPortBuilder`] in `settings`, with a baud rate
+/// of zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LossySettings {
+    /// The settings read from the port, with defaults for the unknown ones
+    pub settings: PortSettings,
+    /// The settings which could not be read from the port
+    pub unknown: Vec<Setting>,
+}
+
+impl PortSettings {
+    pub(crate) fn from_port<P: SerialPort + ?Sized>(port: &P) -> Result<Self> {
+        Ok(PortSettings {
+            baud_rate: port.baud_rate()?,
+            data_bits: port.data_bits()?,
+            flow_control: port.flow_control()?,
+            parity: port.parity()?,
+            stop_bits: port.stop_bits()?,
+            timeout: port.timeout(),
+            inter_byte_timeout: port.inter_byte_timeout()?,
+        })
+    }
+
+    pub(crate) fn from_port_lossy<P: SerialPort + ?Sized>(port: &P) -> LossySettings {
+        let mut unknown = Vec::new();
+        let u = &mut unknown;
+
+        let settings = PortSettings {
+            baud_rate: or_unknown(port.baud_rate(), Setting::BaudRate, 0, u),
+            data_bits: or_unknown(port.data_bits(), Setting::DataBits, DataBits::Eight, u),
+            flow_control: or_unknown(
+                port.flow_control(),
+                Setting::FlowControl,
+                FlowControl::None,
+                u,
+            ),
+            parity: or_unknown(port.parity(), Setting::Parity, Parity::None, u),
+            stop_bits: or_unknown(port.stop_bits(), Setting::StopBits, StopBits::One, u),
+            timeout: port.timeout(),
+            inter_byte_timeout: or_unknown(
+                port.inter_byte_timeout(),
+                Setting::InterByteTimeout,
+                None,
+                u,
+            ),
+        };
+
+        LossySettings { settings, unknown }
+    }
+
+    fn from_builder(builder: &SerialPortBuilder) -> Self {
+        PortSettings {
+            baud_rate: builder.baud_rate,
+            data_bits: builder.data_bits,
+            flow_control: builder.flow_control,
+            parity: builder.parity,
+            stop_bits: builder.stop_bits,
+            timeout: builder.timeout,
+            inter_byte_timeout: builder.inter_byte_timeout,
+        }
+    }
+
+    /// Returns a builder for `path` with these settings and the defaults for all others
+    pub(crate) fn to_builder(self, path: String) -> SerialPortBuilder {
+        let mut builder = crate::new(path, self.baud_rate)
+            .data_bits(self.data_bits)
+            .flow_control(self.flow_control)
+            .parity(self.parity)
+            .stop_bits(self.stop_bits)
+            .timeout(self.timeout);
+        builder.inter_byte_timeout = self.inter_byte_timeout;
+        builder
+    }
+
+    /// Returns the changes turning `self` into `other` along with the changes undoing them
+    fn changes_to(&self, other: &PortSettings) -> Vec<(Change, Change)> {
+        let mut changes = Vec::new();
+        if self.baud_rate != other.baud_rate {
+            changes.push((
+                Change::BaudRate(other.baud_rate),
+                Change::BaudRate(self.baud_rate),
+            ));
+        }
+        if self.data_bits != other.data_bits {
+            changes.push((
+                Change::DataBits(other.data_bits),
+                Change::DataBits(self.data_bits),
+            ));
+        }
+        if self.flow_control != other.flow_control {
+            changes.push((
+                Change::FlowControl(other.flow_control),
+                Change::FlowControl(self.flow_control),
+            ));
+        }
+        if self.parity != other.parity {
+            changes.push((Change::Parity(other.parity), Change::Parity(self.parity)));
+        }
+        if self.stop_bits != other.stop_bits {
+            changes.push((
+                Change::StopBits(other.stop_bits),
+                Change::StopBits(self.stop_bits),
+            ));
+        }
+        if self.timeout != other.timeout {
+            changes.push((
+                Change::Timeout(other.timeout),
+                Change::Timeout(self.timeout),
+            ));
+        }
+        if self.inter_byte_timeout != other.inter_byte_timeout {
+            changes.push((
+                Change::InterByteTimeout(other.inter_byte_timeout),
+                Change::InterByteTimeout(self.inter_byte_timeout),
+            ));
+        }
+        changes
+    }
+}
+
+/// Returns the value from `result` or records `setting` as unknown and returns `default`
+fn or_unknown<T>(result: Result<T>, setting: Setting, default: T, unknown: &mut Vec<Setting>) -> T {
+    result.unwrap_or_else(|_| {
+        unknown.push(setting);
+        default
+    })
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Change {
+    BaudRate(u32),
+    DataBits(DataBits),
+    FlowControl(FlowControl),
+    Parity(Parity),
+    StopBits(StopBits),
+    Timeout(Duration),
+    InterByteTimeout(Option<Duration>),
+}
+
+impl Change {
+    fn apply<P: SerialPort + ?Sized>(self, port: &mut P) -> Result<()> {
+        match self {
+            Change::BaudRate(baud_rate) => port.set_baud_rate(baud_rate),
+            Change::DataBits(data_bits) => port.set_data_bits(data_bits),
+            Change::FlowControl(flow_control) => port.set_flow_control(flow_control),
+            Change::Parity(parity) => port.set_parity(parity),
+            Change::StopBits(stop_bits) => port.set_stop_bits(stop_bits),
+            Change::Timeout(timeout) => port.set_timeout(timeout),
+            Change::InterByteTimeout(timeout) => port.set_inter_byte_timeout(timeout),
+        }
+    }
+}
+
+/// Applies `builder`'s settings one by one through the port's setters and undoes the ones already
+/// applied if a later one fails
+///
+/// This is the fallback for ports which can't apply all settings in a single step.
+pub(crate) fn apply_with_rollback<P: SerialPort + ?Sized>(
+    port: &mut P,
+    builder: &SerialPortBuilder,
+) -> Result<()> {
+    let current = PortSettings::from_port(port)?;
+    let changes = current.changes_to(&PortSettings::from_builder(builder));
+
+    for (i, (change, _)) in changes.iter().enumerate() {
+        if let Err(error) = change.apply(port) {
+            // The failed change is undone as well as it might have been applied partially.
+            let rollback = changes[..=i]
+                .iter()
+                .rev()
+                .map(|(_, undo)| undo.apply(port))
+                .fold(Ok(()), Result::and);
+            return Err(match rollback {
+                Ok(()) => unchanged(error),
+                Err(_) => rollback_failed(error),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Annotates an error from applying settings after the previous settings have been restored
+pub(crate) fn unchanged(error: Error) -> Error {
+    Error::new(
+        error.kind,
+        format!("{}; port settings were left unchanged", error.description),
+    )
+}
+
+/// Annotates an error from applying settings after restoring the previous settings failed too
+pub(crate) fn rollback_failed(error: Error) -> Error {
+    Error::new(
+        error.kind,
+        format!(
+            "{}; restoring the previous port settings failed, the port may be partially configured",
+            error.description
+        ),
+    )
+}
@@ -0,0 +1,112 @@
+//! A serial port shared between threads with support for request/response transactions.
+
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{ClearBuffer, Error, ErrorKind, Result, SerialPort};
+
+/// A serial port which can be shared between multiple owners and threads
+///
+/// Cloning a `SharedPort` hands out another reference to the same underlying port. Access to the
+/// port is serialized, so a [`transact`](SharedPort::transact) call from one owner never
+/// interleaves with I/O from another owner.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use serialport::SharedPort;
+///
+/// let port = serialport::new("/dev/ttyUSB0", 115_200).open().expect("Failed to open port");
+/// let shared = SharedPort::new(port);
+///
+/// let response = shared
+///     .transact(b"AT\r", |data| data.ends_with(b"OK\r\n"), Duration::from_secs(1))
+///     .expect("Transaction failed");
+/// # let _ = response;
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedPort {
+    port: Arc<Mutex<Box<dyn SerialPort>>>,
+}
+
+impl SharedPort {
+    /// Wraps the given port for shared access
+    pub fn new(port: Box<dyn SerialPort>) -> Self {
+        SharedPort {
+            port: Arc::new(Mutex::new(port)),
+        }
+    }
+
+    /// Acquires exclusive access to the underlying port
+    ///
+    /// The port stays locked for all other owners until the returned guard is dropped.
+    pub fn lock(&self) -> MutexGuard<'_, Box<dyn SerialPort>> {
+        // A panic while holding the lock does not leave the port in an inconsistent state from
+        // our point of view, so just carry on.
+        self.port.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Writes `request` and collects the response until `matcher` accepts it
+    ///
+    /// Any data pending in the input buffer is discarded before sending the request, so that
+    /// stale data does not get mistaken for the response. After sending, received data is
+    /// accumulated and passed to `matcher` after every read. The accumulated data is returned as
+    /// soon as `matcher` returns `true`.
+    ///
+    /// The port is locked for the whole transaction, so other owners can't interleave their I/O
+    /// with it. The port's timeout is restored when the transaction completes.
+    ///
+    /// ## Errors
+    ///
+    /// * `Timeout` if no complete response was received within `timeout`.
+    /// * Any error from configuring, writing to or reading from the port.
+    pub fn transact<F>(&self, request: &[u8], mut matcher: F, timeout: Duration) -> Result<Vec<u8>>
+    where
+        F: FnMut(&[u8]) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+
+        let port = self.lock();
+        let original_timeout = port.timeout();
+        let mut port = scopeguard::guard(port, |mut port| {
+            let _ = port.set_timeout(original_timeout);
+        });
+
+        port.clear(ClearBuffer::Input)?;
+        port.set_timeout(timeout)?;
+        port.write_all(request)?;
+        port.flush()?;
+
+        let mut response = Vec::new();
+        let mut buffer = [0u8; 256];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::ZERO {
+                return Err(Error::new(
+                    ErrorKind::Timeout,
+                    "no complete response received before timeout",
+                ));
+            }
+            port.set_timeout(remaining)?;
+
+            match port.read(&mut buffer) {
+                Ok(n) => {
+                    response.extend_from_slice(&buffer[..n]);
+                    if matcher(&response) {
+                        return Ok(response);
+                    }
+                }
+                Err(ref e)
+                    if e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl From<Box<dyn SerialPort>> for SharedPort {
+    fn from(port: Box<dyn SerialPort>) -> Self {
+        SharedPort::new(port)
+    }
+}
@@ -0,0 +1,137 @@
+//! Splitting a serial port into halves for reading and writing from different threads.
+
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{ClearBuffer, Error, ErrorKind, Result, SerialPort};
+
+/// The reading half of a port, created by `split()` on a `TTYPort` or `COMPort`
+///
+/// The half only offers reading and querying the input buffer. Settings of the port can't be
+/// changed while it is split, so both halves always agree on them. Use
+/// [`reunite`](ReadHalf::reunite) to get back the whole port.
+#[derive(Debug)]
+pub struct ReadHalf<P> {
+    port: Arc<P>,
+}
+
+/// The writing half of a port, created by `split()` on a `TTYPort` or `COMPort`
+///
+/// The half only offers writing and querying the output buffer. Settings of the port can't be
+/// changed while it is split, so both halves always agree on them.
+#[derive(Debug)]
+pub struct WriteHalf<P> {
+    port: Arc<P>,
+}
+
+/// Splits `port` into halves sharing it
+pub(crate) fn split<P>(port: P) -> (ReadHalf<P>, WriteHalf<P>) {
+    let port = Arc::new(port);
+    (ReadHalf { port: port.clone() }, WriteHalf { port })
+}
+
+impl<P: SerialPort> ReadHalf<P> {
+    /// Returns the name of the port
+    pub fn name(&self) -> Option<String> {
+        self.port.name()
+    }
+
+    /// Returns the timeout for reading
+    pub fn timeout(&self) -> Duration {
+        self.port.timeout()
+    }
+
+    /// Gets the number of bytes available to be read from the input buffer
+    ///
+    /// ## Errors
+    ///
+    /// Any error from querying the port.
+    pub fn bytes_to_read(&self) -> Result<u32> {
+        self.port.bytes_to_read()
+    }
+
+    /// Discards all bytes in the input buffer
+    ///
+    /// ## Errors
+    ///
+    /// Any error from clearing the buffer of the port.
+    pub fn clear(&self) -> Result<()> {
+        self.port.clear(ClearBuffer::Input)
+    }
+}
+
+impl<P> ReadHalf<P> {
+    /// Joins the halves back into the whole port
+    ///
+    /// ## Errors
+    ///
+    /// `InvalidInput` if the halves were not split from the same port. Both halves get dropped
+    /// in this case.
+    pub fn reunite(self, writer: WriteHalf<P>) -> Result<P> {
+        if !Arc::ptr_eq(&self.port, &writer.port) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "halves were split from different ports",
+            ));
+        }
+        drop(writer);
+        // The halves hold the only references to the port.
+        Ok(
+            Arc::try_unwrap(self.port)
+                .unwrap_or_else(|_| unreachable!("port shared beyond halves")),
+        )
+    }
+}
+
+impl<P> io::Read for ReadHalf<P>
+where
+    for<'a> &'a P: io::Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self.port).read(buf)
+    }
+}
+
+impl<P: SerialPort> WriteHalf<P> {
+    /// Returns the name of the port
+    pub fn name(&self) -> Option<String> {
+        self.port.name()
+    }
+
+    /// Returns the timeout for writing
+    pub fn timeout(&self) -> Duration {
+        self.port.timeout()
+    }
+
+    /// Gets the number of bytes written to the output buffer but not yet transmitted
+    ///
+    /// ## Errors
+    ///
+    /// Any error from querying the port.
+    pub fn bytes_to_write(&self) -> Result<u32> {
+        self.port.bytes_to_write()
+    }
+
+    /// Discards all bytes in the output buffer
+    ///
+    /// ## Errors
+    ///
+    /// Any error from clearing the buffer of the port.
+    pub fn clear(&self) -> Result<()> {
+        self.port.clear(ClearBuffer::Output)
+    }
+}
+
+impl<P> io::Write for WriteHalf<P>
+where
+    for<'a> &'a P: io::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self.port).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self.port).flush()
+    }
+}
@@ -0,0 +1,47 @@
+//! Counting the I/O operations of a port.
+
+use std::io;
+
+/// Statistics about the I/O operations on a port
+///
+/// Obtained with [`SerialPort::stats`](crate::SerialPort::stats). The counters start when the
+/// port gets opened and are kept separately for every handle, so a port and its clones from
+/// [`try_clone`](crate::SerialPort::try_clone) count their own operations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortStats {
+    /// The number of bytes read from the port
+    pub bytes_read: u64,
+    /// The number of bytes written to the port
+    pub bytes_written: u64,
+    /// The number of calls to read from the port, including failed ones
+    pub reads: u64,
+    /// The number of calls to write to the port, including failed ones
+    pub writes: u64,
+    /// The number of reads and writes which timed out
+    pub timeouts: u64,
+    /// The number of reads and writes which failed for any other reason than a timeout
+    pub errors: u64,
+}
+
+impl PortStats {
+    /// Counts a read with the given result
+    pub(crate) fn count_read(&mut self, result: &io::Result<usize>) {
+        self.reads += 1;
+        self.count(result, |stats, n| stats.bytes_read += n);
+    }
+
+    /// Counts a write with the given result
+    pub(crate) fn count_write(&mut self, result: &io::Result<usize>) {
+        self.writes += 1;
+        self.count(result, |stats, n| stats.bytes_written += n);
+    }
+
+    fn count(&mut self, result: &io::Result<usize>, count_bytes: impl FnOnce(&mut Self, u64)) {
+        match result {
+            Ok(n) => count_bytes(self, *n as u64),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => self.timeouts += 1,
+            Err(_) => self.errors += 1,
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Collecting diagnostic information for bug reports.
+
+#[cfg(any(target_os = "linux", windows))]
+use crate::{Error, ErrorKind};
+use crate::{LossySettings, SerialPort, SerialPortInfo};
+
+/// Diagnostic information about the system's serial ports, for attaching to bug reports
+///
+/// Obtained with [`support_bundle`]. With the `serde` feature enabled, the bundle can be
+/// serialized into a single file with any serde data format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SupportBundle {
+    /// The version of this library
+    pub crate_version: String,
+    /// The platform the application is running on
+    pub platform: PlatformInfo,
+    /// The serial ports found on the system along with their drivers
+    pub ports: Vec<PortReport>,
+    /// The error from enumerating the serial ports, if any
+    pub enumeration_error: Option<String>,
+    /// The state of the ports handed to [`support_bundle`]
+    pub open_ports: Vec<OpenPortReport>,
+}
+
+/// Information about the platform the application is running on
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlatformInfo {
+    /// The operating system this library was built for, e.g. `linux`
+    pub os: String,
+    /// The CPU architecture this library was built for, e.g. `x86_64`
+    pub arch: String,
+    /// The release of the running kernel, e.g. `6.1.0-18-amd64`
+    ///
+    /// This is only available on POSIX systems.
+    pub os_release: Option<String>,
+}
+
+/// A serial port found on the system
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortReport {
+    /// The port as reported by [`available_ports`](crate::available_ports)
+    pub info: SerialPortInfo,
+    /// The name of the kernel driver serving the port, e.g. `ftdi_sio`
+    ///
+    /// This is only available on Linux.
+    pub driver: Option<String>,
+    /// The version of the kernel module providing the driver
+    ///
+    /// This is only available on Linux and only for modules declaring a version.
+    pub driver_version: Option<String>,
+}
+
+/// The state of an open port
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpenPortReport {
+    /// The name of the port, if it has one
+    pub name: Option<String>,
+    /// The current settings of the port
+    pub settings: LossySettings,
+    /// The line errors detected on the port so far, if the port supports counting them
+    pub line_errors: Option<LineErrors>,
+}
+
+/// Counters for errors detected on the line by the serial driver
+///
+/// Obtained with [`SerialPort::line_errors`]. The counters start when the driver gets loaded and
+/// keep counting across opening and closing the port.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LineErrors {
+    /// Characters received with a framing error, e.g. because of a baud rate mismatch
+    pub frame: u32,
+    /// Characters lost because the hardware receive buffer overflowed
+    pub overrun: u32,
+    /// Characters received with a parity error
+    pub parity: u32,
+    /// Break conditions received
+    pub breaks: u32,
+    /// Characters lost because the driver's receive buffer overflowed
+    pub buffer_overrun: u32,
+}
+
+impl LineErrors {
+    /// Returns the errors counted since the counters were at `earlier`
+    #[cfg(target_os = "linux")]
+    pub(crate) fn since(&self, earlier: &LineErrors) -> LineErrors {
+        LineErrors {
+            frame: self.frame.wrapping_sub(earlier.frame),
+            overrun: self.overrun.wrapping_sub(earlier.overrun),
+            parity: self.parity.wrapping_sub(earlier.parity),
+            breaks: self.breaks.wrapping_sub(earlier.breaks),
+            buffer_overrun: self.buffer_overrun.wrapping_sub(earlier.buffer_overrun),
+        }
+    }
+
+    /// Returns an error describing the conditions with a non-zero count, if there are any
+    #[cfg(any(target_os = "linux", windows))]
+    pub(crate) fn to_error(self) -> Option<Error> {
+        let conditions: Vec<_> = [
+            (self.frame, "framing error"),
+            (self.overrun, "overrun"),
+            (self.parity, "parity error"),
+            (self.breaks, "break condition"),
+            (self.buffer_overrun, "receive buffer overrun"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count != 0)
+        .map(|(_, condition)| *condition)
+        .collect();
+        if conditions.is_empty() {
+            return None;
+        }
+        Some(Error::new(
+            ErrorKind::Io(std::io::ErrorKind::InvalidData),
+            format!("line errors detected: {}", conditions.join(", ")),
+        ))
+    }
+}
+
+/// Gathers diagnostic information about the system's serial ports and the given open ports
+///
+/// The bundle contains the platform, the enumerated ports with their drivers, and the current
+/// settings and line error counters of `ports`. It is meant to be attached to bug reports as a
+/// single artifact. Gathering the information never fails; whatever could not be determined is
+/// left out.
+///
+/// ```no_run
+/// let port = serialport::new("/dev/ttyUSB0", 115_200).open().expect("Failed to open port");
+/// let bundle = serialport::support_bundle(&[&*port]);
+/// println!("{:#?}", bundle);
+/// ```
+pub fn support_bundle(ports: &[&dyn SerialPort]) -> SupportBundle {
+    let (ports_found, enumeration_error) = match crate::available_ports() {
+        Ok(ports) => (ports, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+
+    SupportBundle {
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        platform: PlatformInfo {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            os_release: os_release(),
+        },
+        ports: ports_found
+            .into_iter()
+            .map(|info| {
+                let (driver, driver_version) = driver(&info.port_name);
+                PortReport {
+                    info,
+                    driver,
+                    driver_version,
+                }
+            })
+            .collect(),
+        enumeration_error,
+        open_ports: ports
+            .iter()
+            .map(|port| OpenPortReport {
+                name: port.name(),
+                settings: port.settings_lossy(),
+                line_errors: port.line_errors().ok(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(unix)]
+fn os_release() -> Option<String> {
+    use nix::libc;
+
+    let mut uts = std::mem::MaybeUninit::<libc::utsname>::uninit();
+    if unsafe { libc::uname(uts.as_mut_ptr()) } != 0 {
+        return None;
+    }
+    let uts = unsafe { uts.assume_init() };
+    let release = unsafe { std::ffi::CStr::from_ptr(uts.release.as_ptr()) };
+    Some(release.to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+fn os_release() -> Option<String> {
+    None
+}
+
+/// Looks up the driver of a port and the version of the kernel module providing it in sysfs.
+#[cfg(target_os = "linux")]
+fn driver(port_name: &str) -> (Option<String>, Option<String>) {
+    use std::path::Path;
+
+    let file_name = |path: &Path| {
+        path.canonicalize()
+            .ok()?
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    };
+
+    let device = match Path::new(port_name).file_name() {
+        Some(name) => Path::new("/sys/class/tty").join(name).join("device"),
+        None => return (None, None),
+    };
+    let driver = file_name(&device.join("driver"));
+    let version = file_name(&device.join("driver/module"))
+        .and_then(|module| std::fs::read_to_string(format!("/sys/module/{}/version", module)).ok())
+        .map(|version| version.trim().to_string());
+    (driver, version)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn driver(_port_name: &str) -> (Option<String>, Option<String>) {
+    (None, None)
+}
@@ -0,0 +1,800 @@
+//! Notifications about serial ports appearing and disappearing
+//!
+//! A [`PortWatcher`] delivers a [`PortEvent`] whenever a serial port shows up in or vanishes from
+//! the list returned by [`available_ports`](crate::available_ports). This saves applications from
+//! polling and diffing the port list themselves.
+//!
+//! ```no_run
+//! use serialport::watcher::{PortEvent, PortWatcher};
+//!
+//! let watcher = PortWatcher::new().expect("Failed to start watching ports");
+//! while let Some(event) = watcher.recv() {
+//!     match event {
+//!         PortEvent::Added(info) => println!("{} added", info.port_name),
+//!         PortEvent::Removed(info) => println!("{} removed", info.port_name),
+//!     }
+//! }
+//! ```
+//!
+//! Applications which can't use a background thread can compare [`PortSnapshot`]s taken between
+//! their polls instead:
+//!
+//! ```no_run
+//! use serialport::watcher::PortSnapshot;
+//!
+//! let mut snapshot = PortSnapshot::take().expect("Failed to list ports");
+//! // ... later, e.g. on every timer tick of a GUI
+//! let changes = snapshot.refresh().expect("Failed to list ports");
+//! for info in &changes.added {
+//!     println!("{} added", info.port_name);
+//! }
+//! ```
+//!
+//! A [`RemovalNotifier`] tells about the removal of the device backing an already open port, so
+//! applications learn about it right away instead of from an I/O error later on. It gets woken up
+//! the same way as a [`PortWatcher`].
+//!
+//! ## Platform notes
+//!
+//! The port list gets re-enumerated whenever the system notifies about a device change, so changes
+//! are reported right away:
+//!
+//! * On Linux with the `libudev` feature enabled, udev events for the `tty` subsystem are used.
+//! * On Windows, device interface notifications are received by a message-only window.
+//! * On macOS, IOKit notifications about serial services being matched and terminated are used.
+//!
+//! The port list is only re-enumerated on these notifications, as enumerating may briefly open
+//! ports for probing them. The polling interval then merely bounds how long the background thread
+//! keeps running after the watcher has been dropped. On all other platforms, and when the
+//! notifications can't be set up, for example because udev is not available in a container, the
+//! port list is re-enumerated at the polling interval instead. Changes are reported with a delay
+//! of up to the polling interval then.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::{Error, ErrorKind, Result, SerialPort, SerialPortInfo};
+
+/// The default interval for re-enumerating the available ports where the system doesn't notify
+/// about device changes
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A change to the set of available serial ports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortEvent {
+    /// A port became available.
+    Added(SerialPortInfo),
+    /// A port is no longer available.
+    Removed(SerialPortInfo),
+}
+
+/// Watches for serial ports being added to or removed from the system
+///
+/// Ports which are already present when the watcher is created are not reported. A port whose
+/// information changes under the same name, for example because a different USB device got
+/// plugged in, is reported as removed and added again.
+///
+/// Events are collected by a background thread which exits once the watcher has been dropped.
+#[derive(Debug)]
+pub struct PortWatcher {
+    events: Receiver<PortEvent>,
+    stop: Arc<AtomicBool>,
+}
+
+impl PortWatcher {
+    /// Starts watching with the [`DEFAULT_POLL_INTERVAL`]
+    pub fn new() -> Result<Self> {
+        Self::with_poll_interval(DEFAULT_POLL_INTERVAL)
+    }
+
+    /// Starts watching and re-enumerates the available ports every `interval` where the system
+    /// doesn't notify about device changes
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the initial enumeration of the available ports fails or if the
+    /// background thread could not be started.
+    pub fn with_poll_interval(interval: Duration) -> Result<Self> {
+        let known = crate::available_ports()?;
+        let (sender, events) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_stop = stop.clone();
+        thread::Builder::new()
+            .name("serialport-watcher".into())
+            .spawn(move || watch(known, interval, &sender, &thread_stop))?;
+
+        Ok(PortWatcher { events, stop })
+    }
+
+    /// Blocks until the next event is available
+    ///
+    /// Returns `None` if the background thread has stopped.
+    pub fn recv(&self) -> Option<PortEvent> {
+        self.events.recv().ok()
+    }
+
+    /// Waits for the next event for at most `timeout`
+    ///
+    /// Returns `None` if no event occurred within `timeout` or if the background thread has
+    /// stopped.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<PortEvent> {
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => Some(event),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+
+    /// Returns the next event if one is available without blocking
+    pub fn try_recv(&self) -> Option<PortEvent> {
+        self.events.try_recv().ok()
+    }
+}
+
+impl Drop for PortWatcher {
+    fn drop(&mut self) {
+        // The background thread notices this at its next wakeup at the latest. There is no need
+        // to wait for it here.
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Notifies about the removal of the device backing an open port
+///
+/// The device is considered removed as soon as the port's name no longer shows up in the list
+/// returned by [`available_ports`](crate::available_ports). Therefore the port has to be opened
+/// by the name listed there, and not for example by a symbolic link pointing to the device.
+///
+/// Like a [`PortWatcher`], the notifier checks for the removal whenever the system notifies about
+/// a device change and falls back to polling where these notifications are not available. See the
+/// [platform notes](self#platform-notes) for details.
+///
+/// ```no_run
+/// use serialport::watcher::RemovalNotifier;
+///
+/// let port = serialport::new("/dev/ttyUSB0", 115_200).open().expect("Failed to open port");
+/// let removal = RemovalNotifier::new(&*port).expect("Failed to watch port");
+///
+/// removal.wait();
+/// println!("Device has been unplugged");
+/// ```
+#[derive(Debug)]
+pub struct RemovalNotifier {
+    state: Arc<RemovalState>,
+}
+
+#[derive(Debug, Default)]
+struct RemovalState {
+    removed: Mutex<bool>,
+    condvar: Condvar,
+    stop: AtomicBool,
+}
+
+impl RemovalState {
+    fn removed(&self) -> MutexGuard<'_, bool> {
+        self.removed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl RemovalNotifier {
+    /// Starts watching for the removal of `port` with the [`DEFAULT_POLL_INTERVAL`]
+    pub fn new<P: SerialPort + ?Sized>(port: &P) -> Result<Self> {
+        Self::with_poll_interval(port, DEFAULT_POLL_INTERVAL)
+    }
+
+    /// Starts watching for the removal of `port` and checks for it every `interval` where the
+    /// system doesn't notify about device changes
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if the port has no name or if it is not among the available ports.
+    /// * Any error from enumerating the available ports or starting the background thread.
+    pub fn with_poll_interval<P: SerialPort + ?Sized>(
+        port: &P,
+        interval: Duration,
+    ) -> Result<Self> {
+        let port_name = port
+            .name()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "port has no name to watch for"))?;
+        if !crate::available_ports()?
+            .iter()
+            .any(|info| info.port_name == port_name)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} is not among the available ports", port_name),
+            ));
+        }
+
+        let state = Arc::new(RemovalState::default());
+        let thread_state = state.clone();
+        thread::Builder::new()
+            .name("serialport-removal".into())
+            .spawn(move || watch_removal(&port_name, interval, &thread_state))?;
+
+        Ok(RemovalNotifier { state })
+    }
+
+    /// Blocks until the device has been removed
+    pub fn wait(&self) {
+        let mut removed = self.state.removed();
+        while !*removed {
+            removed = self
+                .state
+                .condvar
+                .wait(removed)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Waits for at most `timeout` for the device to be removed
+    ///
+    /// Returns whether the device has been removed.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let removed = self.state.removed();
+        let (removed, _) = self
+            .state
+            .condvar
+            .wait_timeout_while(removed, timeout, |removed| !*removed)
+            .unwrap_or_else(|e| e.into_inner());
+        *removed
+    }
+
+    /// Returns whether the device has been removed without blocking
+    pub fn is_removed(&self) -> bool {
+        *self.state.removed()
+    }
+}
+
+impl Drop for RemovalNotifier {
+    fn drop(&mut self) {
+        self.state.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The serial ports available at one point in time
+///
+/// Comparing snapshots taken at different times tells which ports showed up or vanished in
+/// between. Like with the [`PortWatcher`], a port whose information changes under the same name
+/// is reported as removed and added again.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortSnapshot {
+    ports: Vec<SerialPortInfo>,
+}
+
+impl PortSnapshot {
+    /// Takes a snapshot of the ports returned by [`available_ports`](crate::available_ports)
+    pub fn take() -> Result<Self> {
+        crate::available_ports().map(Self::from_ports)
+    }
+
+    /// Creates a snapshot of the given ports
+    pub fn from_ports(mut ports: Vec<SerialPortInfo>) -> Self {
+        ports.sort();
+        PortSnapshot { ports }
+    }
+
+    /// Returns the ports in the snapshot, sorted naturally by their names
+    pub fn ports(&self) -> &[SerialPortInfo] {
+        &self.ports
+    }
+
+    /// Returns the changes turning `old` into `new`
+    pub fn diff(old: &PortSnapshot, new: &PortSnapshot) -> PortChanges {
+        changes(&old.ports, &new.ports)
+    }
+
+    /// Re-enumerates the available ports, replaces the snapshot with the result and returns the
+    /// changes since the snapshot was taken
+    ///
+    /// The snapshot is left unchanged if enumerating the ports fails.
+    pub fn refresh(&mut self) -> Result<PortChanges> {
+        let current = Self::take()?;
+        let changes = Self::diff(self, &current);
+        *self = current;
+        Ok(changes)
+    }
+}
+
+/// The ports which showed up or vanished between two [`PortSnapshot`]s
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortChanges {
+    /// The ports which became available
+    pub added: Vec<SerialPortInfo>,
+    /// The ports which are no longer available
+    pub removed: Vec<SerialPortInfo>,
+}
+
+impl PortChanges {
+    /// Returns whether no ports have been added or removed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Returns the changes as events like the ones delivered by a [`PortWatcher`], removals first
+    pub fn into_events(self) -> Vec<PortEvent> {
+        let removed = self.removed.into_iter().map(PortEvent::Removed);
+        let added = self.added.into_iter().map(PortEvent::Added);
+        removed.chain(added).collect()
+    }
+}
+
+fn watch(
+    mut known: Vec<SerialPortInfo>,
+    interval: Duration,
+    sender: &Sender<PortEvent>,
+    stop: &AtomicBool,
+) {
+    let mut trigger = Trigger::new(interval);
+
+    while !stop.load(Ordering::Relaxed) {
+        if !trigger.wait() || stop.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        // Enumeration may fail transiently while devices are being set up. Just try again with
+        // the next wakeup.
+        let current = match crate::available_ports() {
+            Ok(ports) => ports,
+            Err(_) => continue,
+        };
+
+        for event in diff(&known, &current) {
+            if sender.send(event).is_err() {
+                return;
+            }
+        }
+        known = current;
+    }
+}
+
+fn watch_removal(port_name: &str, interval: Duration, state: &RemovalState) {
+    let mut trigger = Trigger::new(interval);
+
+    while !state.stop.load(Ordering::Relaxed) {
+        if !trigger.wait() {
+            continue;
+        }
+
+        let present = match crate::available_ports() {
+            Ok(ports) => ports.iter().any(|info| info.port_name == port_name),
+            Err(_) => continue,
+        };
+
+        if !present {
+            *state.removed() = true;
+            state.condvar.notify_all();
+            return;
+        }
+    }
+}
+
+/// Returns the events turning `old` into `new`
+fn diff(old: &[SerialPortInfo], new: &[SerialPortInfo]) -> Vec<PortEvent> {
+    changes(old, new).into_events()
+}
+
+/// Returns the changes turning `old` into `new`
+fn changes(old: &[SerialPortInfo], new: &[SerialPortInfo]) -> PortChanges {
+    PortChanges {
+        added: new
+            .iter()
+            .filter(|port| !old.contains(port))
+            .cloned()
+            .collect(),
+        removed: old
+            .iter()
+            .filter(|port| !new.contains(port))
+            .cloned()
+            .collect(),
+    }
+}
+
+#[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))]
+struct Trigger {
+    interval: Duration,
+    socket: Option<libudev::MonitorSocket>,
+}
+
+#[cfg(all(target_os = "linux", not(target_env = "musl"), feature = "libudev"))]
+impl Trigger {
+    fn new(interval: Duration) -> Self {
+        // Fall back to plain polling if udev is not available, e.g. in containers.
+        let socket = libudev::Context::new().ok().and_then(|context| {
+            let mut monitor = libudev::Monitor::new(&context).ok()?;
+            monitor.match_subsystem("tty").ok()?;
+            monitor.listen().ok()
+        });
+
+        Trigger { interval, socket }
+    }
+
+    /// Waits for a udev event or until the polling interval has elapsed
+    ///
+    /// Returns whether the ports have to be enumerated again, which is the case after an event or
+    /// when falling back to polling.
+    fn wait(&mut self) -> bool {
+        use nix::poll::{poll, PollFd, PollFlags};
+        use std::os::unix::io::AsRawFd;
+
+        let socket = match self.socket {
+            Some(ref mut socket) => socket,
+            None => return poll_interval(self.interval),
+        };
+
+        let millis = self.interval.as_millis().min(nix::libc::c_int::MAX as u128);
+        let mut fds = [PollFd::new(socket.as_raw_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, millis as nix::libc::c_int) {
+            Ok(0) => return false,
+            Ok(_) => {}
+            Err(_) => return poll_interval(self.interval),
+        }
+
+        // Only the fact that something changed is of interest. Drain all pending events before
+        // enumerating the ports again.
+        while socket.receive_event().is_some() {}
+        true
+    }
+}
+
+#[cfg(windows)]
+struct Trigger {
+    interval: Duration,
+    window: winapi::shared::windef::HWND,
+    notification: winapi::um::winuser::HDEVNOTIFY,
+}
+
+#[cfg(windows)]
+impl Trigger {
+    fn new(interval: Duration) -> Self {
+        use std::ffi::OsStr;
+        use std::os::windows::ffi::OsStrExt;
+        use std::{mem, ptr};
+
+        use winapi::um::dbt::{DBT_DEVTYP_DEVICEINTERFACE, DEV_BROADCAST_DEVICEINTERFACE_W};
+        use winapi::um::winuser::*;
+
+        let class: Vec<u16> = OsStr::new("STATIC").encode_wide().chain(Some(0)).collect();
+        let mut trigger = Trigger {
+            interval,
+            window: ptr::null_mut(),
+            notification: ptr::null_mut(),
+        };
+
+        // Device notifications are delivered as messages to a window. A message-only window is
+        // sufficient for receiving them. Fall back to plain polling if it can't be set up.
+        unsafe {
+            trigger.window = CreateWindowExW(
+                0,
+                class.as_ptr(),
+                ptr::null(),
+                0,
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+            );
+            if trigger.window.is_null() {
+                return trigger;
+            }
+
+            let mut filter: DEV_BROADCAST_DEVICEINTERFACE_W = mem::zeroed();
+            filter.dbcc_size = mem::size_of::<DEV_BROADCAST_DEVICEINTERFACE_W>() as u32;
+            filter.dbcc_devicetype = DBT_DEVTYP_DEVICEINTERFACE;
+            trigger.notification = RegisterDeviceNotificationW(
+                trigger.window as _,
+                &mut filter as *mut _ as _,
+                DEVICE_NOTIFY_WINDOW_HANDLE | DEVICE_NOTIFY_ALL_INTERFACE_CLASSES,
+            );
+        }
+
+        trigger
+    }
+
+    /// Waits for a device notification or until the polling interval has elapsed
+    ///
+    /// Returns whether the ports have to be enumerated again, which is the case after a
+    /// notification or when falling back to polling.
+    fn wait(&mut self) -> bool {
+        use std::{mem, ptr};
+
+        use winapi::shared::winerror::WAIT_TIMEOUT;
+        use winapi::um::winbase::{INFINITE, WAIT_FAILED};
+        use winapi::um::winuser::*;
+
+        if self.notification.is_null() {
+            return poll_interval(self.interval);
+        }
+
+        let millis = self.interval.as_millis().min(INFINITE as u128 - 1);
+        unsafe {
+            match MsgWaitForMultipleObjects(0, ptr::null(), 0, millis as u32, QS_ALLINPUT) {
+                WAIT_TIMEOUT => return false,
+                WAIT_FAILED => return poll_interval(self.interval),
+                _ => {}
+            }
+
+            // Only the fact that something changed is of interest. The notifications are sent to
+            // the window and get handled while draining all pending messages.
+            let mut msg: MSG = mem::zeroed();
+            while PeekMessageW(&mut msg, ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+                DispatchMessageW(&msg);
+            }
+        }
+        true
+    }
+}
+
+#[cfg(windows)]
+impl Drop for Trigger {
+    fn drop(&mut self) {
+        use winapi::um::winuser::{DestroyWindow, UnregisterDeviceNotification};
+
+        unsafe {
+            if !self.notification.is_null() {
+                UnregisterDeviceNotification(self.notification);
+            }
+            if !self.window.is_null() {
+                DestroyWindow(self.window);
+            }
+        }
+    }
+}
+
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+struct Trigger {
+    interval: Duration,
+    port: io_kit_sys::IONotificationPortRef,
+    iterators: Vec<io_kit_sys::types::io_iterator_t>,
+}
+
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+impl Trigger {
+    fn new(interval: Duration) -> Self {
+        use std::ptr;
+
+        use io_kit_sys::keys::{kIOFirstMatchNotification, kIOTerminatedNotification};
+        use io_kit_sys::serial::keys::kIOSerialBSDServiceValue;
+        use io_kit_sys::*;
+        use mach2::kern_return::KERN_SUCCESS;
+
+        let mut trigger = Trigger {
+            interval,
+            port: unsafe { IONotificationPortCreate(kIOMasterPortDefault) },
+            iterators: Vec::new(),
+        };
+        // Fall back to plain polling if the notifications can't be set up.
+        if trigger.port.is_null() {
+            return trigger;
+        }
+
+        for notification in [kIOFirstMatchNotification, kIOTerminatedNotification] {
+            unsafe {
+                // IOServiceAddMatchingNotification consumes the matching dictionary.
+                let matching = IOServiceMatching(kIOSerialBSDServiceValue);
+                if matching.is_null() {
+                    continue;
+                }
+                let mut iterator = 0;
+                if IOServiceAddMatchingNotification(
+                    trigger.port,
+                    notification as *mut _,
+                    matching as _,
+                    drain_iterator,
+                    ptr::null_mut(),
+                    &mut iterator,
+                ) == KERN_SUCCESS
+                {
+                    // The notification gets armed by consuming the services already matching.
+                    drain_iterator(ptr::null_mut(), iterator);
+                    trigger.iterators.push(iterator);
+                }
+            }
+        }
+
+        trigger
+    }
+
+    /// Waits for an IOKit notification or until the polling interval has elapsed
+    ///
+    /// Returns whether the ports have to be enumerated again, which is the case after a
+    /// notification or when falling back to polling.
+    fn wait(&mut self) -> bool {
+        use std::{mem, ptr};
+
+        use io_kit_sys::{IODispatchCalloutFromMessage, IONotificationPortGetMachPort};
+        use mach2::message::*;
+        use mach2::port::MACH_PORT_NULL;
+
+        /// Large enough for receiving the notification messages from IOKit
+        #[repr(C)]
+        struct Message {
+            header: mach_msg_header_t,
+            body: [u8; 4096],
+        }
+
+        if self.iterators.is_empty() {
+            return poll_interval(self.interval);
+        }
+
+        let port = unsafe { IONotificationPortGetMachPort(self.port) };
+        let mut timeout = self
+            .interval
+            .as_millis()
+            .min(mach_msg_timeout_t::MAX as u128) as mach_msg_timeout_t;
+        let mut notified = false;
+        loop {
+            let mut message: Message = unsafe { mem::zeroed() };
+            let result = unsafe {
+                mach_msg(
+                    &mut message.header,
+                    MACH_RCV_MSG | MACH_RCV_TIMEOUT,
+                    0,
+                    mem::size_of::<Message>() as mach_msg_size_t,
+                    port,
+                    timeout,
+                    MACH_PORT_NULL,
+                )
+            };
+            match result {
+                // Only the fact that something changed is of interest. Dispatching the message
+                // re-arms the notification and all pending messages get drained before
+                // enumerating the ports again.
+                MACH_MSG_SUCCESS => unsafe {
+                    IODispatchCalloutFromMessage(
+                        ptr::null_mut(),
+                        &mut message.header,
+                        self.port as *mut _,
+                    );
+                },
+                MACH_RCV_TIMED_OUT => return notified,
+                _ => return poll_interval(self.interval),
+            }
+            notified = true;
+            timeout = 0;
+        }
+    }
+}
+
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+impl Drop for Trigger {
+    fn drop(&mut self) {
+        use io_kit_sys::{IONotificationPortDestroy, IOObjectRelease};
+
+        unsafe {
+            for &iterator in &self.iterators {
+                IOObjectRelease(iterator);
+            }
+            if !self.port.is_null() {
+                IONotificationPortDestroy(self.port);
+            }
+        }
+    }
+}
+
+/// Releases all services from a notification iterator
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+unsafe extern "C" fn drain_iterator(
+    _refcon: *mut std::os::raw::c_void,
+    iterator: io_kit_sys::types::io_iterator_t,
+) {
+    use io_kit_sys::{IOIteratorNext, IOObjectRelease};
+
+    loop {
+        let service = IOIteratorNext(iterator);
+        if service == 0 {
+            return;
+        }
+        IOObjectRelease(service);
+    }
+}
+
+#[cfg(not(any(
+    all(target_os = "linux", not(target_env = "musl"), feature = "libudev"),
+    windows,
+    target_os = "ios",
+    target_os = "macos"
+)))]
+struct Trigger {
+    interval: Duration,
+}
+
+#[cfg(not(any(
+    all(target_os = "linux", not(target_env = "musl"), feature = "libudev"),
+    windows,
+    target_os = "ios",
+    target_os = "macos"
+)))]
+impl Trigger {
+    fn new(interval: Duration) -> Self {
+        Trigger { interval }
+    }
+
+    /// Waits until the polling interval has elapsed
+    ///
+    /// Returns whether the ports have to be enumerated again, which is always the case.
+    fn wait(&mut self) -> bool {
+        poll_interval(self.interval)
+    }
+}
+
+/// Sleeps for the polling interval when there are no notifications about device changes
+///
+/// Returns `true`, as the ports have to be enumerated again afterwards.
+fn poll_interval(interval: Duration) -> bool {
+    thread::sleep(interval);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SerialPortType;
+
+    fn port(name: &str, port_type: SerialPortType) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: name.to_string(),
+            port_type,
+            friendly_name: None,
+            description: None,
+            instance_id: None,
+            counterpart: None,
+            by_id: None,
+            by_path: None,
+            bluetooth: None,
+        }
+    }
+
+    #[test]
+    fn diff_reports_added_and_removed_ports() {
+        let a = port("/dev/ttyS0", SerialPortType::Unknown);
+        let b = port("/dev/ttyS1", SerialPortType::Unknown);
+        let c = port("/dev/ttyS2", SerialPortType::Unknown);
+
+        let events = diff(&[a.clone(), b.clone()], &[b, c.clone()]);
+        assert_eq!(events, vec![PortEvent::Removed(a), PortEvent::Added(c)]);
+    }
+
+    #[test]
+    fn diff_reports_changed_port_as_replaced() {
+        let old = port("/dev/ttyUSB0", SerialPortType::Unknown);
+        let new = port("/dev/ttyUSB0", SerialPortType::PciPort);
+
+        let events = diff(std::slice::from_ref(&old), std::slice::from_ref(&new));
+        assert_eq!(events, vec![PortEvent::Removed(old), PortEvent::Added(new)]);
+    }
+
+    #[test]
+    fn diff_of_unchanged_ports_is_empty() {
+        let ports = [port("/dev/ttyS0", SerialPortType::Unknown)];
+        assert!(diff(&ports, &ports).is_empty());
+    }
+
+    #[test]
+    fn snapshot_diff_reports_changes() {
+        let a = port("/dev/ttyS0", SerialPortType::Unknown);
+        let b = port("/dev/ttyS1", SerialPortType::Unknown);
+        let c = port("/dev/ttyS2", SerialPortType::Unknown);
+
+        let old = PortSnapshot::from_ports(vec![b.clone(), a.clone()]);
+        let new = PortSnapshot::from_ports(vec![c.clone(), b.clone()]);
+        assert_eq!(old.ports(), [a.clone(), b]);
+
+        let changes = PortSnapshot::diff(&old, &new);
+        assert_eq!(
+            changes,
+            PortChanges {
+                added: vec![c],
+                removed: vec![a],
+            }
+        );
+        assert!(PortSnapshot::diff(&new, &new).is_empty());
+    }
+}
@@ -0,0 +1,714 @@
+//! Serial ports in the browser, using the Web Serial API
+//!
+//! The Web Serial API is asynchronous and the browser's main thread must never block. Opening a
+//! port is therefore done with the `async` constructors of [`WebSerialPort`], and its blocking
+//! `SerialPort` interface works on data buffered in the background: reads return what has been
+//! received so far and writes get queued to the browser.
+//!
+//! The API is still marked unstable in `web-sys`, so building this module requires
+//! `RUSTFLAGS=--cfg=web_sys_unstable_apis`.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use std::{fmt, io};
+
+use js_sys::{Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    FlowControlType, ParityType, ReadableStreamDefaultReader, Serial, SerialOptions,
+    SerialOutputSignals, WritableStreamDefaultWriter,
+};
+
+use crate::{
+    ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity, PortStats, Result, SerialPort,
+    SerialPortBuilder, StopBits,
+};
+
+thread_local! {
+    /// The JavaScript objects of the open ports, by port ID
+    ///
+    /// JavaScript objects can't leave the thread they have been created on, while a
+    /// `SerialPort` has to be `Send`. Ports only keep their ID and look the objects up here.
+    static PORTS: RefCell<HashMap<u32, JsPort>> = RefCell::new(HashMap::new());
+}
+
+static NEXT_PORT_ID: AtomicU32 = AtomicU32::new(0);
+
+/// The JavaScript objects of an open port
+struct JsPort {
+    port: web_sys::SerialPort,
+    reader: ReadableStreamDefaultReader,
+    writer: WritableStreamDefaultWriter,
+}
+
+/// The state shared between the clones of a port and its background read loop
+#[derive(Debug, Default)]
+struct State {
+    /// Data received but not read yet
+    input: VecDeque<u8>,
+    /// The number of bytes handed to the browser but not written yet
+    pending_output: usize,
+    /// The error which ended the read loop or failed a background operation
+    error: Option<Error>,
+    /// The last framing, parity or break condition reported by the browser
+    line_error: Option<Error>,
+    /// Whether the read loop has ended
+    closed: bool,
+    /// The task waiting for data in [`WebSerialPort::readable`]
+    waker: Option<Waker>,
+}
+
+/// The open port, closing it when the last clone is dropped
+#[derive(Debug)]
+struct Shared {
+    id: u32,
+    state: Mutex<State>,
+}
+
+impl Shared {
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Records the failure of a background operation, reported by the next read or write
+    fn fail(&self, error: JsValue) {
+        let mut state = self.state();
+        state.error.get_or_insert_with(|| js_error(error));
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Runs `operation` on the JavaScript objects of the port
+    ///
+    /// This fails if the port is used from another thread than the one it has been opened on.
+    fn with_js<T>(&self, operation: impl FnOnce(&JsPort) -> T) -> Result<T> {
+        PORTS.with(|ports| {
+            ports.borrow().get(&self.id).map(operation).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Unsupported,
+                    "Web Serial ports can only be used on the thread they have been opened on",
+                )
+            })
+        })
+    }
+
+    /// Marks the port as closed after its read loop has ended
+    fn close(&self) {
+        let mut state = self.state();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Replaces the reader of the port after its readable stream has failed, returning whether
+    /// the port provided a new stream
+    fn reopen_reader(&self) -> bool {
+        PORTS.with(|ports| match ports.borrow_mut().get_mut(&self.id) {
+            Some(js) => {
+                let readable = js.port.readable();
+                if readable.is_null() {
+                    return false;
+                }
+                js.reader.release_lock();
+                js.reader = readable.get_reader().unchecked_into();
+                true
+            }
+            None => false,
+        })
+    }
+
+    /// Waits for `promise` in the background, recording its failure
+    fn spawn(self: &Arc<Self>, promise: js_sys::Promise) {
+        let shared = Arc::downgrade(self);
+        spawn_local(async move {
+            if let Err(e) = JsFuture::from(promise).await {
+                if let Some(shared) = shared.upgrade() {
+                    shared.fail(e);
+                }
+            }
+        });
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let js = PORTS.with(|ports| ports.borrow_mut().remove(&self.id));
+        if let Some(js) = js {
+            spawn_local(async move {
+                // The read loop ends with the cancelled read. Both streams have to be unlocked
+                // before the port can be closed.
+                let _ = JsFuture::from(js.reader.cancel()).await;
+                js.reader.release_lock();
+                let _ = JsFuture::from(js.writer.close()).await;
+                js.writer.release_lock();
+                let _ = JsFuture::from(js.port.close()).await;
+            });
+        }
+    }
+}
+
+/// A serial port opened through the browser's Web Serial API
+///
+/// As the browser's main thread must not block, reads return the data received so far, failing
+/// with a `TimedOut` error right away if there is none. Await [`readable`](Self::readable)
+/// before reading to wait for data. Writes queue the data to the browser and complete right
+/// away. [`bytes_to_write`](SerialPort::bytes_to_write) reports the data not written yet.
+///
+/// Web Serial only applies the settings when opening a port. They can't be changed afterwards,
+/// so reopen the port to change them. The modem status lines can only be read asynchronously
+/// with [`signals`](Self::signals).
+///
+/// A port and its clones can only be used on the thread it has been opened on.
+pub struct WebSerialPort {
+    shared: Arc<Shared>,
+    port_name: String,
+    baud_rate: u32,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+    timeout: Duration,
+    stats: PortStats,
+}
+
+impl WebSerialPort {
+    /// Lets the user pick a port in the browser's port chooser and opens it with the settings of
+    /// `builder`
+    ///
+    /// Browsers only show the chooser in response to a user action like a click. The path of
+    /// `builder` is only used as the name of the port.
+    ///
+    /// ## Errors
+    ///
+    /// * `Unsupported` if the browser does not support Web Serial.
+    /// * `NoDevice` if the user did not pick a port.
+    /// * `InvalidInput` if the settings are not supported by Web Serial.
+    /// * `Busy` if the port is already open.
+    pub async fn request(builder: &SerialPortBuilder) -> Result<WebSerialPort> {
+        let options = serial_options(builder)?;
+        let port = JsFuture::from(serial()?.request_port())
+            .await
+            .map_err(js_error)?;
+        WebSerialPort::open_port(port.unchecked_into(), &options, builder).await
+    }
+
+    /// Opens a port the user has granted access to before with the settings of `builder`
+    ///
+    /// The path of `builder` is the index of the port in the list of granted ports, e.g. `"0"`
+    /// for the first one. [`granted_ports`](Self::granted_ports) returns this list.
+    ///
+    /// ## Errors
+    ///
+    /// * `Unsupported` if the browser does not support Web Serial.
+    /// * `NoDevice` if there is no granted port at the index.
+    /// * `InvalidInput` if the path is not an index or the settings are not supported by Web
+    ///   Serial.
+    /// * `Busy` if the port is already open.
+    pub async fn open(builder: &SerialPortBuilder) -> Result<WebSerialPort> {
+        let options = serial_options(builder)?;
+        let index = builder.path.parse::<u32>().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Web Serial ports are opened by their index in the granted ports",
+            )
+        })?;
+
+        let ports = granted(&serial()?).await?;
+        let port = ports.get(index);
+        if port.is_undefined() {
+            return Err(Error::new(ErrorKind::NoDevice, "No such granted port"));
+        }
+        WebSerialPort::open_port(port.unchecked_into(), &options, builder).await
+    }
+
+    /// Returns the USB vendor and product IDs of the ports the user has granted access to
+    ///
+    /// The IDs are `None` for ports not connected through USB, e.g. Bluetooth ports. Pass the
+    /// index of a port as path to [`open`](Self::open) to open it.
+    pub async fn granted_ports() -> Result<Vec<Option<(u16, u16)>>> {
+        let ports = granted(&serial()?).await?;
+        Ok(ports
+            .iter()
+            .map(|port| {
+                let info = port.unchecked_into::<web_sys::SerialPort>().get_info();
+                let id = |key: &str| {
+                    Reflect::get(&info, &JsValue::from_str(key))
+                        .ok()
+                        .and_then(|id| id.as_f64())
+                        .map(|id| id as u16)
+                };
+                id("usbVendorId").zip(id("usbProductId"))
+            })
+            .collect())
+    }
+
+    async fn open_port(
+        port: web_sys::SerialPort,
+        options: &SerialOptions,
+        builder: &SerialPortBuilder,
+    ) -> Result<WebSerialPort> {
+        JsFuture::from(port.open(options)).await.map_err(js_error)?;
+
+        let reader = port.readable().get_reader().unchecked_into();
+        let writer = match port.writable().get_writer() {
+            Ok(writer) => writer,
+            Err(e) => {
+                let _ = JsFuture::from(port.close()).await;
+                return Err(js_error(e));
+            }
+        };
+
+        let id = NEXT_PORT_ID.fetch_add(1, Ordering::Relaxed);
+        let shared = Arc::new(Shared {
+            id,
+            state: Mutex::new(State::default()),
+        });
+        PORTS.with(|ports| {
+            ports.borrow_mut().insert(
+                id,
+                JsPort {
+                    port,
+                    reader,
+                    writer,
+                },
+            )
+        });
+        spawn_local(read_loop(Arc::downgrade(&shared)));
+
+        Ok(WebSerialPort {
+            shared,
+            port_name: builder.path.clone(),
+            baud_rate: builder.baud_rate,
+            data_bits: builder.data_bits,
+            flow_control: builder.flow_control,
+            parity: builder.parity,
+            stop_bits: builder.stop_bits,
+            timeout: builder.timeout,
+            stats: PortStats::default(),
+        })
+    }
+
+    /// Waits until data is available for reading or the port has failed
+    pub fn readable(&self) -> impl Future<Output = ()> + '_ {
+        Readable {
+            shared: &self.shared,
+        }
+    }
+
+    /// Reads the modem status lines
+    ///
+    /// Returns whether CTS, DSR, RI and CD are asserted, in this order.
+    pub async fn signals(&self) -> Result<(bool, bool, bool, bool)> {
+        let signals = self.shared.with_js(|js| js.port.get_signals())?;
+        let signals = JsFuture::from(signals).await.map_err(js_error)?;
+        let signal = |key: &str| {
+            Reflect::get(&signals, &JsValue::from_str(key))
+                .ok()
+                .and_then(|level| level.as_bool())
+                .unwrap_or(false)
+        };
+        Ok((
+            signal("clearToSend"),
+            signal("dataSetReady"),
+            signal("ringIndicator"),
+            signal("dataCarrierDetect"),
+        ))
+    }
+
+    /// Sets the output signals in `signals` in the background
+    fn set_signals(&self, signals: &SerialOutputSignals) -> Result<()> {
+        let promise = self
+            .shared
+            .with_js(|js| js.port.set_signals_with_signals(signals))?;
+        self.shared.spawn(promise.unchecked_into());
+        Ok(())
+    }
+
+    /// Returns the error recorded by a background operation, if any
+    fn check_failed(&self) -> io::Result<()> {
+        match self.shared.state().error.take() {
+            Some(error) => Err(error.into()),
+            None => Ok(()),
+        }
+    }
+
+    fn read_buffered(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.shared.state();
+        if state.input.is_empty() {
+            if let Some(error) = state.error.take() {
+                return Err(error.into());
+            }
+            if state.closed {
+                return Err(Error::new(ErrorKind::NoDevice, "The port has been closed").into());
+            }
+            if !buf.is_empty() {
+                return Err(Error::new(ErrorKind::Timeout, "Operation timed out").into());
+            }
+        }
+
+        let len = buf.len().min(state.input.len());
+        for (dst, src) in buf.iter_mut().zip(state.input.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+
+    fn write_queued(&self, buf: &[u8]) -> io::Result<usize> {
+        self.check_failed()?;
+        let promise = self
+            .shared
+            .with_js(|js| js.writer.write_with_chunk(&Uint8Array::from(buf)))?;
+
+        let len = buf.len();
+        self.shared.state().pending_output += len;
+        let shared = Arc::downgrade(&self.shared);
+        spawn_local(async move {
+            let result = JsFuture::from(promise.unchecked_into::<js_sys::Promise>()).await;
+            if let Some(shared) = shared.upgrade() {
+                shared.state().pending_output -= len;
+                if let Err(e) = result {
+                    shared.fail(e);
+                }
+            }
+        });
+        Ok(len)
+    }
+}
+
+impl fmt::Debug for WebSerialPort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSerialPort")
+            .field("id", &self.shared.id)
+            .field("port_name", &self.port_name)
+            .finish()
+    }
+}
+
+/// The future returned by [`WebSerialPort::readable`]
+struct Readable<'a> {
+    shared: &'a Shared,
+}
+
+impl Future for Readable<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.shared.state();
+        if !state.input.is_empty() || state.error.is_some() || state.closed {
+            return Poll::Ready(());
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Moves the data received by the port into its input buffer until the port is closed or fails
+async fn read_loop(shared: Weak<Shared>) {
+    loop {
+        let read = match shared.upgrade() {
+            Some(shared) => shared.with_js(|js| js.reader.read()),
+            None => return,
+        };
+        let result = match read {
+            Ok(read) => JsFuture::from(read).await,
+            Err(_) => return,
+        };
+        let shared = match shared.upgrade() {
+            Some(shared) => shared,
+            None => return,
+        };
+
+        let chunk = match result {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                // Line errors only end the current stream, the port provides a new one.
+                let error = js_error(e);
+                if error.kind() == ErrorKind::Io(io::ErrorKind::InvalidData)
+                    && shared.reopen_reader()
+                {
+                    shared.state().line_error = Some(error);
+                    continue;
+                }
+                shared.state().error.get_or_insert(error);
+                shared.close();
+                return;
+            }
+        };
+        let done = Reflect::get(&chunk, &JsValue::from_str("done"))
+            .map(|done| done.is_truthy())
+            .unwrap_or(true);
+        if done {
+            shared.close();
+            return;
+        }
+
+        if let Ok(value) = Reflect::get(&chunk, &JsValue::from_str("value")) {
+            let mut state = shared.state();
+            state
+                .input
+                .extend(value.unchecked_into::<Uint8Array>().to_vec());
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Returns the Web Serial API of the window or worker
+fn serial() -> Result<Serial> {
+    let global = js_sys::global();
+    let navigator = Reflect::get(&global, &JsValue::from_str("navigator")).unwrap_or_default();
+    match Reflect::get(&navigator, &JsValue::from_str("serial")) {
+        Ok(serial) if !serial.is_undefined() => Ok(serial.unchecked_into()),
+        _ => Err(Error::new(
+            ErrorKind::Unsupported,
+            "The browser does not support Web Serial",
+        )),
+    }
+}
+
+/// Returns the ports the user has granted access to
+async fn granted(serial: &Serial) -> Result<js_sys::Array> {
+    let ports = JsFuture::from(serial.get_ports()).await.map_err(js_error)?;
+    Ok(ports.unchecked_into())
+}
+
+/// Converts the settings of `builder` into Web Serial options
+fn serial_options(builder: &SerialPortBuilder) -> Result<SerialOptions> {
+    let data_bits = match builder.data_bits {
+        DataBits::Seven | DataBits::Eight => u8::from(builder.data_bits),
+        DataBits::Five | DataBits::Six => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Web Serial only supports 7 and 8 data bits",
+            ))
+        }
+    };
+    let flow_control = match builder.flow_control {
+        FlowControl::None => FlowControlType::None,
+        FlowControl::Hardware => FlowControlType::Hardware,
+        FlowControl::Software => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Web Serial does not support software flow control",
+            ))
+        }
+    };
+    let parity = match builder.parity {
+        Parity::None => ParityType::None,
+        Parity::Odd => ParityType::Odd,
+        Parity::Even => ParityType::Even,
+    };
+
+    let options = SerialOptions::new(builder.baud_rate);
+    options.set_data_bits(data_bits);
+    options.set_flow_control(flow_control);
+    options.set_parity(parity);
+    options.set_stop_bits(u8::from(builder.stop_bits));
+    Ok(options)
+}
+
+/// Converts a rejected promise into an error
+///
+/// Web Serial reports errors as `DOMException`s, whose name tells the cause.
+fn js_error(error: JsValue) -> Error {
+    let property = |key: &str| {
+        Reflect::get(&error, &JsValue::from_str(key))
+            .ok()
+            .and_then(|value| value.as_string())
+    };
+    let kind = match property("name").as_deref() {
+        Some("NotFoundError") => ErrorKind::NoDevice,
+        Some("InvalidStateError") | Some("NetworkError") => ErrorKind::Busy,
+        Some("SecurityError") | Some("NotAllowedError") => {
+            ErrorKind::Io(io::ErrorKind::PermissionDenied)
+        }
+        Some("BreakError") | Some("FramingError") | Some("ParityError") => {
+            ErrorKind::Io(io::ErrorKind::InvalidData)
+        }
+        _ => ErrorKind::Io(io::ErrorKind::Other),
+    };
+    let description = property("message")
+        .or_else(|| error.as_string())
+        .unwrap_or_else(|| "Web Serial operation failed".to_string());
+    Error::new(kind, description)
+}
+
+/// Returns the error for settings which can't be changed on an open port
+fn reopen_required() -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        "Web Serial ports have to be reopened to change their settings",
+    )
+}
+
+/// Returns the error for the modem status lines, which can't be read synchronously
+fn signals_async() -> Error {
+    Error::new(
+        ErrorKind::Unsupported,
+        "Web Serial modem status lines can only be read with WebSerialPort::signals()",
+    )
+}
+
+impl io::Read for WebSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.read_buffered(buf);
+        self.stats.count_read(&result);
+        result
+    }
+}
+
+impl io::Write for WebSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.write_queued(buf);
+        self.stats.count_write(&result);
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // The browser can't be waited for, so this only reports failed writes.
+        self.check_failed()
+    }
+}
+
+impl SerialPort for WebSerialPort {
+    fn name(&self) -> Option<String> {
+        Some(self.port_name.clone())
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        Ok(self.data_bits)
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        Ok(self.flow_control)
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        Ok(self.parity)
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        Ok(self.stop_bits)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> Result<()> {
+        Err(reopen_required())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> Result<()> {
+        Err(reopen_required())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> Result<()> {
+        Err(reopen_required())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> Result<()> {
+        Err(reopen_required())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> Result<()> {
+        Err(reopen_required())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        // Only recorded, as reads never block.
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> Result<()> {
+        let signals = SerialOutputSignals::new();
+        signals.set_request_to_send(level);
+        self.set_signals(&signals)
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> Result<()> {
+        let signals = SerialOutputSignals::new();
+        signals.set_data_terminal_ready(level);
+        self.set_signals(&signals)
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        Err(signals_async())
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        Err(signals_async())
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        Err(signals_async())
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        Err(signals_async())
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        Ok(self.shared.state().input.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        Ok(self.shared.state().pending_output as u32)
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        Ok(self.stats)
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        Ok(self.shared.state().line_error.take())
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        Ok(!self.shared.state().closed)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        // Data handed to the browser can't be taken back.
+        if let ClearBuffer::Input | ClearBuffer::All = buffer_to_clear {
+            self.shared.state().input.clear();
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        Ok(Box::new(WebSerialPort {
+            shared: Arc::clone(&self.shared),
+            port_name: self.port_name.clone(),
+            stats: PortStats::default(),
+            ..*self
+        }))
+    }
+
+    fn set_break(&self) -> Result<()> {
+        let signals = SerialOutputSignals::new();
+        signals.set_break(true);
+        self.set_signals(&signals)
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        let signals = SerialOutputSignals::new();
+        signals.set_break(false);
+        self.set_signals(&signals)
+    }
+}
@@ -1,22 +1,33 @@
-use std::mem::MaybeUninit;
+use std::io::IoSlice;
+use std::mem::{ManuallyDrop, MaybeUninit};
 use std::os::windows::prelude::*;
-use std::time::Duration;
-use std::{io, ptr};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{io, ptr, slice};
 
 use winapi::shared::minwindef::*;
+use winapi::shared::winerror::ERROR_IO_PENDING;
 use winapi::um::commapi::*;
+use winapi::um::errhandlingapi::GetLastError;
 use winapi::um::fileapi::*;
 use winapi::um::handleapi::*;
+use winapi::um::minwinbase::SECURITY_ATTRIBUTES;
 use winapi::um::processthreadsapi::GetCurrentProcess;
+use winapi::um::synchapi::WaitForMultipleObjects;
 use winapi::um::winbase::*;
 use winapi::um::winnt::{
-    DUPLICATE_SAME_ACCESS, FILE_ATTRIBUTE_NORMAL, GENERIC_READ, GENERIC_WRITE, HANDLE, MAXDWORD,
+    DUPLICATE_SAME_ACCESS, FILE_ATTRIBUTE_NORMAL, FILE_SHARE_READ, GENERIC_READ, GENERIC_WRITE,
+    HANDLE, MAXDWORD,
 };
 
+use crate::cancel;
 use crate::windows::dcb;
+use crate::windows::overlapped::{self, Overlapped};
 use crate::{
-    ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity, Result, SerialPort,
-    SerialPortBuilder, StopBits,
+    AccessMode, CancelToken, ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity,
+    PortStats, ReadHalf, Result, SerialPort, SerialPortBuilder, SerialPortExt, ShareMode, StopBits,
+    Strictness, WriteHalf,
 };
 
 /// A serial port implementation for Windows COM ports
@@ -28,12 +39,49 @@ use crate::{
 #[derive(Debug)]
 pub struct COMPort {
     handle: HANDLE,
-    timeout: Duration,
+    /// The timeouts, shared with the clones of the port as they apply to the device
+    timeouts: Arc<Mutex<Timeouts>>,
     port_name: Option<String>,
+    strictness: Strictness,
+    /// The I/O statistics, shared by all users of a reference to the port
+    stats: Mutex<PortStats>,
+    /// Whether the port has been reported to the observers
+    observed: bool,
+    /// Error flags cleared by `ClearCommError()` but not yet reported by `take_error()`
+    pending_errors: AtomicU32,
+    /// The `OVERLAPPED` structures reused by reads and writes, created on first use
+    read_overlapped: Mutex<Option<Overlapped>>,
+    write_overlapped: Mutex<Option<Overlapped>>,
+    /// The `OVERLAPPED` structure reused for waiting for received data
+    wait_overlapped: Mutex<Option<Overlapped>>,
+    /// The token for cancelling blocking operations, created on first use and shared with the
+    /// clones of the port
+    cancel: Arc<Mutex<Option<CancelToken>>>,
+}
+
+// Error flags reported by `ClearCommError()`, missing from winapi
+const CE_RXOVER: DWORD = 0x0001;
+const CE_OVERRUN: DWORD = 0x0002;
+const CE_RXPARITY: DWORD = 0x0004;
+const CE_FRAME: DWORD = 0x0008;
+const CE_BREAK: DWORD = 0x0010;
+
+// Event reported by `WaitCommEvent()` when a character was received, missing from winapi
+pub(crate) const EV_RXCHAR: DWORD = 0x0001;
+
+/// The timeouts set for a port
+#[derive(Debug, Clone, Copy)]
+struct Timeouts {
+    timeout: Duration,
+    inter_byte_timeout: Option<Duration>,
 }
 
 unsafe impl Send for COMPort {}
 
+// All operations on the handle going through shared references are safe to be called from
+// multiple threads and all other state accessed through shared references is synchronized.
+unsafe impl Sync for COMPort {}
+
 impl COMPort {
     /// Opens a COM port as a serial device.
     ///
@@ -45,25 +93,47 @@ impl COMPort {
     ///
     /// ## Errors
     ///
-    /// * `NoDevice` if the device could not be opened. This could indicate that
-    ///    the device is already in use.
+    /// * `NoDevice` if the device could not be opened.
+    /// * `Busy` if the device is already in use. Windows reports this as access being denied, so
+    ///   a missing permission shows up as `Busy` as well.
     /// * `InvalidInput` if `port` is not a valid device name.
     /// * `Io` for any other I/O error while opening or initializing the device.
     pub fn open(builder: &SerialPortBuilder) -> Result<COMPort> {
+        COMPort::open_device(builder).map_err(|e| e.with_context("opening", Some(&builder.path)))
+    }
+
+    fn open_device(builder: &SerialPortBuilder) -> Result<COMPort> {
         let mut name = Vec::<u16>::with_capacity(4 + builder.path.len() + 1);
 
         name.extend(r"\\.\".encode_utf16());
         name.extend(builder.path.encode_utf16());
         name.push(0);
 
+        let access_mode = match builder.access_mode {
+            AccessMode::ReadWrite => GENERIC_READ | GENERIC_WRITE,
+            AccessMode::ReadOnly => GENERIC_READ,
+            AccessMode::WriteOnly => GENERIC_WRITE,
+        };
+        let share_mode = match builder.share_mode {
+            ShareMode::Exclusive => 0,
+            ShareMode::ShareRead => FILE_SHARE_READ,
+            ShareMode::Shared => FILE_SHARE_READ | FILE_SHARE_WRITE,
+        };
+
+        let mut security_attributes = SECURITY_ATTRIBUTES {
+            nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as DWORD,
+            lpSecurityDescriptor: ptr::null_mut(),
+            bInheritHandle: builder.inheritable as BOOL,
+        };
+
         let handle = unsafe {
             CreateFileW(
                 name.as_ptr(),
-                GENERIC_READ | GENERIC_WRITE,
-                0,
-                ptr::null_mut(),
+                access_mode,
+                share_mode,
+                &mut security_attributes,
                 OPEN_EXISTING,
-                FILE_ATTRIBUTE_NORMAL,
+                FILE_ATTRIBUTE_NORMAL | FILE_FLAG_OVERLAPPED,
                 0 as HANDLE,
             )
         };
@@ -75,33 +145,127 @@ impl COMPort {
         // create the COMPort here so the handle is getting closed
         // if one of the calls to `get_dcb()` or `set_dcb()` fails
         let mut com = COMPort::open_from_raw_handle(handle as RawHandle);
+        com.init(builder)?;
+        com.port_name = Some(builder.path.clone());
+        Ok(com.observed())
+    }
+
+    /// Creates a `COMPort` from a handle opened by other means and applies the settings of
+    /// `builder`
+    ///
+    /// This is meant for services receiving an already opened port from their parent process.
+    /// Like [`try_from_raw_handle`](COMPort::try_from_raw_handle), this checks that `handle`
+    /// refers to a communications device. The path of `builder` is only used as the name of the
+    /// port and may be empty. Its access mode and exclusivity are ignored, as they were decided
+    /// when the handle was opened. With the `io-safety` feature,
+    /// [`from_handle_with_settings`](COMPort::from_handle_with_settings) does the same without
+    /// `unsafe`.
+    ///
+    /// ## Safety
+    ///
+    /// On success, the port takes ownership of `handle` and closes it when dropped. `handle` must
+    /// not be closed or used as a port elsewhere. On failure, the caller keeps the ownership of
+    /// `handle`.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `handle` does not refer to a communications device or the settings
+    ///   are not supported.
+    /// * `Io` for any other error while configuring the device.
+    pub unsafe fn from_raw_handle_with_settings(
+        handle: RawHandle,
+        builder: &SerialPortBuilder,
+    ) -> Result<COMPort> {
+        let port_name = (!builder.path.is_empty()).then(|| builder.path.as_str());
+        check_comm_device(handle as HANDLE)
+            .map_err(|e| e.with_context("configuring", port_name))?;
+
+        let mut com = COMPort::open_from_raw_handle(handle);
+        if let Err(e) = com.init(builder) {
+            // Hand the handle back to the caller instead of closing it.
+            let _ = com.into_raw_handle();
+            return Err(e.with_context("configuring", port_name));
+        }
+        com.port_name = port_name.map(String::from);
+        Ok(com.observed())
+    }
 
-        let mut dcb = dcb::get_dcb(handle)?;
+    /// Creates a `COMPort` from an owned handle and applies the settings of `builder`
+    ///
+    /// See [`from_raw_handle_with_settings`](COMPort::from_raw_handle_with_settings) for details.
+    /// The handle gets closed on failure.
+    #[cfg(feature = "io-safety")]
+    #[allow(clippy::incompatible_msrv)]
+    pub fn from_handle_with_settings(
+        handle: std::os::windows::io::OwnedHandle,
+        builder: &SerialPortBuilder,
+    ) -> Result<COMPort> {
+        let com =
+            unsafe { COMPort::from_raw_handle_with_settings(handle.as_raw_handle(), builder)? };
+        // The port owns the handle now.
+        let _ = handle.into_raw_handle();
+        Ok(com)
+    }
+
+    /// Applies the settings and timeouts of `builder` to a freshly opened port
+    fn init(&mut self, builder: &SerialPortBuilder) -> Result<()> {
+        self.strictness = builder.strictness;
+
+        let mut dcb = dcb::get_dcb(self.handle)?;
         dcb::init(&mut dcb);
         dcb::set_baud_rate(&mut dcb, builder.baud_rate);
         dcb::set_data_bits(&mut dcb, builder.data_bits);
         dcb::set_parity(&mut dcb, builder.parity);
         dcb::set_stop_bits(&mut dcb, builder.stop_bits);
         dcb::set_flow_control(&mut dcb, builder.flow_control);
-        dcb::set_dcb(handle, dcb)?;
+        dcb::set_dcb(self.handle, dcb)?;
+
+        // Reads wait for this event instead of polling. Devices which don't support it fall back
+        // to their timeouts.
+        unsafe { SetCommMask(self.handle, EV_RXCHAR) };
 
         if let Some(dtr) = builder.dtr_on_open {
-            com.write_data_terminal_ready(dtr)?;
+            self.write_data_terminal_ready(dtr)?;
         }
 
-        com.set_timeout(builder.timeout)?;
-        com.port_name = Some(builder.path.clone());
+        self.set_comm_timeouts(builder.timeout, builder.inter_byte_timeout)
+    }
+
+    /// Opens a COM port once it detects a carrier
+    ///
+    /// The port is opened like with [`COMPort::open`]. Afterwards, this waits up to `timeout`
+    /// for the carrier detect (DCD) line to become asserted.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io(NotConnected)` if no carrier was detected within `timeout`. The port gets closed
+    ///   in this case.
+    /// * Any error from [`COMPort::open`] or from reading the carrier detect line.
+    pub fn open_wait_for_carrier(
+        builder: &SerialPortBuilder,
+        timeout: Duration,
+    ) -> Result<COMPort> {
+        let mut com = COMPort::open(builder)?;
+        com.wait_for_carrier(timeout)?;
         Ok(com)
     }
 
+    /// Splits the port into owned halves for reading and writing
+    ///
+    /// The halves share the port, so one thread can block on reading while another one writes
+    /// without cloning it. Both halves use the settings and timeout the port had when splitting
+    /// it.
+    pub fn split(self) -> (ReadHalf<COMPort>, WriteHalf<COMPort>) {
+        crate::split::split(self)
+    }
+
     /// Attempts to clone the `SerialPort`. This allow you to write and read simultaneously from the
     /// same serial connection. Please note that if you want a real asynchronous serial port you
     /// should look at [mio-serial](https://crates.io/crates/mio-serial) or
     /// [tokio-serial](https://crates.io/crates/tokio-serial).
     ///
-    /// Also, you must be very careful when changing the settings of a cloned `SerialPort` : since
-    /// the settings are cached on a per object basis, trying to modify them from two different
-    /// objects can cause some nasty behavior.
+    /// The clone shares the timeouts with the original port, as they apply to the device. The
+    /// strictness is kept separately for every clone.
     ///
     /// This is the same as `SerialPort::try_clone()` but returns the concrete type instead.
     ///
@@ -111,42 +275,403 @@ impl COMPort {
     pub fn try_clone_native(&self) -> Result<COMPort> {
         let process_handle: HANDLE = unsafe { GetCurrentProcess() };
         let mut cloned_handle: HANDLE = INVALID_HANDLE_VALUE;
-        unsafe {
+        let result = unsafe {
             DuplicateHandle(
                 process_handle,
                 self.handle,
                 process_handle,
                 &mut cloned_handle,
                 0,
-                TRUE,
+                FALSE,
                 DUPLICATE_SAME_ACCESS,
-            );
-            if cloned_handle != INVALID_HANDLE_VALUE {
-                Ok(COMPort {
-                    handle: cloned_handle,
-                    port_name: self.port_name.clone(),
-                    timeout: self.timeout,
-                })
-            } else {
-                Err(super::error::last_os_error())
-            }
+            )
+        };
+        if result == 0 || cloned_handle == INVALID_HANDLE_VALUE {
+            return Err(super::error::last_os_error());
+        }
+
+        Ok(COMPort {
+            handle: cloned_handle,
+            port_name: self.port_name.clone(),
+            timeouts: self.timeouts.clone(),
+            strictness: self.strictness,
+            stats: Mutex::default(),
+            observed: false,
+            pending_errors: AtomicU32::new(0),
+            read_overlapped: Mutex::default(),
+            write_overlapped: Mutex::default(),
+            wait_overlapped: Mutex::default(),
+            cancel: self.cancel.clone(),
+        }
+        .observed())
+    }
+
+    /// Returns how operations which are not supported by the port are handled
+    pub fn strictness(&self) -> Strictness {
+        self.strictness
+    }
+
+    /// Sets how operations which are not supported by the port are handled
+    pub fn set_strictness(&mut self, strictness: Strictness) {
+        self.strictness = strictness;
+    }
+
+    /// Returns a function adding `operation` and the name of the port to errors
+    fn context<'a>(&'a self, operation: &'a str) -> impl FnOnce(Error) -> Error + 'a {
+        move |error| error.with_context(operation, self.port_name.as_deref())
+    }
+
+    /// Adds `operation` and the name of the port to I/O errors
+    fn io_context(&self, error: io::Error, operation: &str) -> io::Error {
+        Error::io_with_context(error, operation, self.port_name.as_deref())
+    }
+
+    /// Applies a single change to the settings of the port
+    fn change_dcb(&self, change: impl FnOnce(&mut DCB)) -> Result<()> {
+        let mut dcb = dcb::get_dcb(self.handle)?;
+        change(&mut dcb);
+        dcb::set_dcb(self.handle, dcb)
+    }
+
+    /// Applies all `settings` at once, leaving the port unchanged if this fails
+    fn apply_dcb(&mut self, settings: &SerialPortBuilder) -> Result<()> {
+        let original = dcb::get_dcb(self.handle)?;
+        let mut dcb = original;
+        dcb::set_baud_rate(&mut dcb, settings.baud_rate);
+        dcb::set_data_bits(&mut dcb, settings.data_bits);
+        dcb::set_parity(&mut dcb, settings.parity);
+        dcb::set_stop_bits(&mut dcb, settings.stop_bits);
+        dcb::set_flow_control(&mut dcb, settings.flow_control);
+        dcb::set_dcb(self.handle, dcb).map_err(crate::settings::unchanged)?;
+
+        if let Err(error) = self.set_comm_timeouts(settings.timeout, settings.inter_byte_timeout) {
+            return Err(match dcb::set_dcb(self.handle, original) {
+                Ok(()) => crate::settings::unchanged(error),
+                Err(_) => crate::settings::rollback_failed(error),
+            });
         }
+
+        Ok(())
     }
 
     fn escape_comm_function(&mut self, function: DWORD) -> Result<()> {
-        match unsafe { EscapeCommFunction(self.handle, function) } {
+        let result = match unsafe { EscapeCommFunction(self.handle, function) } {
             0 => Err(super::error::last_os_error()),
             _ => Ok(()),
-        }
+        };
+        self.strictness.or_default(result, ())
     }
 
-    fn read_pin(&mut self, pin: DWORD) -> Result<bool> {
+    fn read_pin(&mut self, pin: DWORD, default: bool) -> Result<bool> {
         let mut status: DWORD = 0;
 
-        match unsafe { GetCommModemStatus(self.handle, &mut status) } {
+        let result = match unsafe { GetCommModemStatus(self.handle, &mut status) } {
             0 => Err(super::error::last_os_error()),
             _ => Ok(status & pin != 0),
+        };
+        self.strictness.or_default(result, default)
+    }
+
+    /// Returns the status of the port and keeps the error flags cleared along with it for
+    /// `take_error()`
+    fn clear_comm_error(&self) -> Result<COMSTAT> {
+        let mut errors: DWORD = 0;
+        let mut comstat = MaybeUninit::uninit();
+
+        if unsafe { ClearCommError(self.handle, &mut errors, comstat.as_mut_ptr()) != 0 } {
+            self.pending_errors.fetch_or(errors, Ordering::Relaxed);
+            unsafe { Ok(comstat.assume_init()) }
+        } else {
+            Err(super::error::last_os_error())
+        }
+    }
+
+    /// Waits up to `timeout` for received data, returning whether there is any
+    ///
+    /// Waiting for the `EV_RXCHAR` event wakes up as soon as data arrives instead of polling. If
+    /// the device can't wait for events, this returns `true` right away and the read falls back
+    /// to the timeouts of the device.
+    fn wait_for_rx(&self, timeout: Duration, cancel: &[Option<&CancelToken>]) -> io::Result<bool> {
+        overlapped::with_cached(&self.wait_overlapped, |overlapped| {
+            let mut events: DWORD = 0;
+            let started = unsafe { WaitCommEvent(self.handle, &mut events, overlapped.start()) };
+            if started == 0 && unsafe { GetLastError() } != ERROR_IO_PENDING {
+                return Ok(true);
+            }
+
+            // Data received before starting to wait doesn't trigger the event.
+            let available = self
+                .clear_comm_error()
+                .map_or(true, |status| status.cbInQue > 0);
+            let wait = if available {
+                0
+            } else {
+                Self::timeout_constant(timeout)
+            };
+            let signaled = overlapped.is_complete_within(wait, cancel);
+            if !signaled {
+                // Abort only this wait. Changing the event mask would complete the waits of
+                // everyone else waiting for events of the port as well, e.g. of a `Registration`.
+                overlapped.cancel(self.handle);
+            }
+            // The wait has to be finished before reusing the structure. Errors show up when
+            // reading anyway.
+            let _ = overlapped.wait(self.handle, TRUE, &[]);
+            cancel::check_all(cancel)?;
+            Ok(available || signaled)
+        })
+    }
+
+    /// Waits up to `timeout` for the output buffer of the driver to have space left
+    fn wait_for_tx_space(&self, timeout: Duration) -> Result<()> {
+        let cancel = self.active_cancel_token();
+        let cancel = [cancel.as_ref()];
+        let queue_size = self.tx_queue_size();
+        let deadline = Instant::now() + timeout;
+        loop {
+            let pending = self.clear_comm_error()?.cbOutQue;
+            // Drivers not reporting the size of their buffer accept writes at any time.
+            if queue_size == 0 || pending < queue_size {
+                return Ok(());
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::ZERO {
+                return Err(Error::new(ErrorKind::Timeout, "Operation timed out"));
+            }
+            wait_cancelled(
+                &cancel,
+                Self::timeout_constant(remaining).min(DRAIN_POLL_INTERVAL),
+            )?;
+        }
+    }
+
+    /// Returns the size of the output buffer of the driver, or zero if it is not known
+    fn tx_queue_size(&self) -> DWORD {
+        let mut properties = MaybeUninit::<COMMPROP>::uninit();
+        if unsafe { GetCommProperties(self.handle, properties.as_mut_ptr()) } == 0 {
+            return 0;
+        }
+        unsafe { properties.assume_init() }.dwCurrentTxQueue
+    }
+
+    /// Returns the token for cancelling blocking operations, if one has been handed out
+    fn active_cancel_token(&self) -> Option<CancelToken> {
+        self.cancel
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn read_handle(
+        &self,
+        buf: &mut [MaybeUninit<u8>],
+        cancel: Option<&CancelToken>,
+    ) -> io::Result<usize> {
+        let port_cancel = self.active_cancel_token();
+        let cancel = [port_cancel.as_ref(), cancel];
+        cancel::check_all(&cancel)?;
+        if !buf.is_empty() && !self.wait_for_rx(self.timeouts().timeout, &cancel)? {
+            return Err(Error::new(ErrorKind::Timeout, "Operation timed out").into());
+        }
+
+        let len = overlapped::with_cached(&self.read_overlapped, |overlapped| {
+            let started = unsafe {
+                ReadFile(
+                    self.handle,
+                    buf.as_mut_ptr() as LPVOID,
+                    buf.len() as DWORD,
+                    ptr::null_mut(),
+                    overlapped.start(),
+                )
+            };
+            overlapped.wait(self.handle, started, &cancel)
+        })?;
+
+        match len {
+            0 => Err(Error::new(ErrorKind::Timeout, "Operation timed out").into()),
+            len => Ok(len as usize),
+        }
+    }
+
+    fn write_handle(&self, buf: &[u8], cancel: Option<&CancelToken>) -> io::Result<usize> {
+        let port_cancel = self.active_cancel_token();
+        let cancel = [port_cancel.as_ref(), cancel];
+        cancel::check_all(&cancel)?;
+
+        let len = overlapped::with_cached(&self.write_overlapped, |overlapped| {
+            let started = unsafe {
+                WriteFile(
+                    self.handle,
+                    buf.as_ptr() as LPVOID,
+                    buf.len() as DWORD,
+                    ptr::null_mut(),
+                    overlapped.start(),
+                )
+            };
+            overlapped.wait(self.handle, started, &cancel)
+        })?;
+
+        match len {
+            // Nothing gets written if the write timeout expires before any data got sent.
+            0 if !buf.is_empty() => {
+                Err(Error::new(ErrorKind::Timeout, "Operation timed out").into())
+            }
+            len => Ok(len as usize),
+        }
+    }
+
+    fn flush_handle(&self, cancel: Option<&CancelToken>) -> io::Result<()> {
+        let port_cancel = self.active_cancel_token();
+        let cancel = [port_cancel.as_ref(), cancel];
+        cancel::check_all(&cancel)?;
+        if cancel.iter().any(Option::is_some) {
+            // `FlushFileBuffers()` can't be cancelled, so wait for the output buffer to drain
+            // while watching the tokens first.
+            while self
+                .clear_comm_error()
+                .map_or(false, |status| status.cbOutQue > 0)
+            {
+                wait_cancelled(&cancel, DRAIN_POLL_INTERVAL)?;
+            }
+        }
+
+        match unsafe { FlushFileBuffers(self.handle) } {
+            0 => Err(super::error::last_io_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads from the port, keeping the statistics and notifying the observers
+    fn read_with(&self, buf: &mut [u8], cancel: Option<&CancelToken>) -> io::Result<usize> {
+        // `ReadFile()` only writes initialized bytes.
+        let uninit = unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+        let result = self
+            .read_handle(uninit, cancel)
+            .map_err(|e| self.io_context(e, "reading"));
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .count_read(&result);
+        crate::observer::notify_io(&result, buf, |observer, data| {
+            observer.on_read(self.port_name.as_deref(), data)
+        });
+        result
+    }
+
+    /// Reads into memory which isn't initialized yet, keeping the statistics and notifying the
+    /// observers
+    fn read_uninit_with(&self, buf: &mut [MaybeUninit<u8>]) -> io::Result<usize> {
+        let result = self
+            .read_handle(buf, None)
+            .map_err(|e| self.io_context(e, "reading"));
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .count_read(&result);
+        // `ReadFile()` has initialized the bytes it reported.
+        let data = result.as_ref().map_or(&[][..], |&len| unsafe {
+            slice::from_raw_parts(buf.as_ptr().cast(), len)
+        });
+        crate::observer::notify_io(&result, data, |observer, data| {
+            observer.on_read(self.port_name.as_deref(), data)
+        });
+        result
+    }
+
+    /// Writes to the port, keeping the statistics and notifying the observers
+    fn write_with(&self, buf: &[u8], cancel: Option<&CancelToken>) -> io::Result<usize> {
+        let result = self
+            .write_handle(buf, cancel)
+            .map_err(|e| self.io_context(e, "writing"));
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .count_write(&result);
+        crate::observer::notify_io(&result, buf, |observer, data| {
+            observer.on_write(self.port_name.as_deref(), data)
+        });
+        result
+    }
+
+    /// Writes several buffers with a single `WriteFile()`
+    ///
+    /// `WriteFileGather()` only works with files opened for unbuffered I/O, so more than one
+    /// non-empty buffer gets copied into a single one first. This still saves a system call and
+    /// a wait for each further buffer.
+    fn write_vectored_with(&self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let mut non_empty = bufs.iter().filter(|buf| !buf.is_empty());
+        let first = non_empty.next().map_or(&[][..], |buf| &**buf);
+        if non_empty.next().is_none() {
+            return self.write_with(first, None);
+        }
+
+        let mut data = Vec::with_capacity(bufs.iter().map(|buf| buf.len()).sum());
+        for buf in bufs {
+            data.extend_from_slice(buf);
+        }
+        self.write_with(&data, None)
+    }
+
+    /// Reports the port to the observers and enables reporting its closing
+    fn observed(mut self) -> Self {
+        self.observed = true;
+        crate::observer::notify(|observer| observer.on_open(self.port_name.as_deref()));
+        self
+    }
+
+    /// Returns whether child processes inherit the port
+    ///
+    /// This is the case if the `HANDLE_FLAG_INHERIT` flag of the handle is set.
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` for any error while querying the handle flags.
+    pub fn inheritable(&self) -> Result<bool> {
+        let mut flags: DWORD = 0;
+        if unsafe { GetHandleInformation(self.handle, &mut flags) } == 0 {
+            return Err(super::error::last_os_error());
+        }
+        Ok(flags & HANDLE_FLAG_INHERIT != 0)
+    }
+
+    /// Sets whether child processes inherit the port
+    ///
+    /// This sets or clears the `HANDLE_FLAG_INHERIT` flag of the handle. With it, child
+    /// processes created with handle inheritance enabled get a copy of the handle, so the port can
+    /// be handed over to them. The flag only applies to this handle and not to duplicates of it,
+    /// e.g. the ones created by [`try_clone_native`](COMPort::try_clone_native).
+    ///
+    /// ## Errors
+    ///
+    /// * `Io` for any error while changing the handle flags.
+    pub fn set_inheritable(&self, inheritable: bool) -> Result<()> {
+        let flags = if inheritable { HANDLE_FLAG_INHERIT } else { 0 };
+        if unsafe { SetHandleInformation(self.handle, HANDLE_FLAG_INHERIT, flags) } == 0 {
+            return Err(super::error::last_os_error());
         }
+        Ok(())
+    }
+
+    /// Creates a `COMPort` from a handle opened by other means
+    ///
+    /// Unlike [`from_raw_handle`](FromRawHandle::from_raw_handle), this checks that `handle`
+    /// refers to a communications device. All settings of the device are left as they are. With
+    /// the `io-safety` feature, `COMPort` also implements `TryFrom<OwnedHandle>` doing the same
+    /// without `unsafe`.
+    ///
+    /// ## Safety
+    ///
+    /// On success, the port takes ownership of `handle` and closes it when dropped. `handle` must
+    /// not be closed or used as a port elsewhere. On failure, the caller keeps the ownership of
+    /// `handle`.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `handle` does not refer to a communications device.
+    /// * `Io` for any other error while querying the handle.
+    pub unsafe fn try_from_raw_handle(handle: RawHandle) -> Result<COMPort> {
+        check_comm_device(handle as HANDLE)?;
+        Ok(COMPort::open_from_raw_handle(handle).observed())
     }
 
     fn open_from_raw_handle(handle: RawHandle) -> Self {
@@ -154,12 +679,68 @@ impl COMPort {
         // We'll punt and set it `None` here.
         COMPort {
             handle: handle as HANDLE,
-            timeout: Duration::from_millis(100),
+            timeouts: Arc::new(Mutex::new(Timeouts {
+                timeout: Duration::from_millis(100),
+                inter_byte_timeout: None,
+            })),
             port_name: None,
+            strictness: Strictness::Strict,
+            stats: Mutex::default(),
+            observed: false,
+            pending_errors: AtomicU32::new(0),
+            read_overlapped: Mutex::default(),
+            write_overlapped: Mutex::default(),
+            wait_overlapped: Mutex::default(),
+            cancel: Arc::default(),
+        }
+    }
+
+    fn set_comm_timeouts(
+        &self,
+        timeout: Duration,
+        inter_byte_timeout: Option<Duration>,
+    ) -> Result<()> {
+        let timeout_constant = Self::timeout_constant(timeout);
+
+        // Without an inter-byte timeout, reads return immediately with the data available or wait
+        // up to `timeout` for the first byte. With an inter-byte timeout, reads additionally wait
+        // for more data until the line stayed idle for the given interval.
+        let (read_interval, read_multiplier) = match inter_byte_timeout {
+            // The interval only starts with the first byte and a zero total timeout means no
+            // total timeout at all, so reads would wait for data forever. Return the data
+            // available right away instead, as documented for `MAXDWORD` as the interval alone.
+            Some(_) if timeout_constant == 0 => (MAXDWORD, 0),
+            Some(interval) => (Self::timeout_constant(interval).max(1), 0),
+            None => (MAXDWORD, MAXDWORD),
+        };
+
+        let mut timeouts = COMMTIMEOUTS {
+            ReadIntervalTimeout: read_interval,
+            ReadTotalTimeoutMultiplier: read_multiplier,
+            ReadTotalTimeoutConstant: timeout_constant,
+            WriteTotalTimeoutMultiplier: 0,
+            WriteTotalTimeoutConstant: timeout_constant,
+        };
+
+        // Keep the shared timeouts locked until they match the device again.
+        let mut shared = self.timeouts.lock().unwrap_or_else(|e| e.into_inner());
+        if unsafe { SetCommTimeouts(self.handle, &mut timeouts) } == 0 {
+            return Err(super::error::last_os_error());
         }
+
+        *shared = Timeouts {
+            timeout,
+            inter_byte_timeout,
+        };
+        Ok(())
     }
 
-    fn timeout_constant(duration: Duration) -> DWORD {
+    /// Returns the timeouts currently set for the device
+    fn timeouts(&self) -> Timeouts {
+        *self.timeouts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn timeout_constant(duration: Duration) -> DWORD {
         let milliseconds = duration.as_millis();
         // In the way we are setting up COMMTIMEOUTS, a timeout_constant of MAXDWORD gets rejected.
         // Let's clamp the timeout constant for values of MAXDWORD and above. See remarks at
@@ -172,11 +753,42 @@ impl COMPort {
     }
 }
 
+/// The interval for checking whether the output buffer has been drained while flushing with a
+/// cancel token, in milliseconds
+const DRAIN_POLL_INTERVAL: DWORD = 10;
+
+/// Waits up to `timeout` milliseconds for any token in `cancel` to get cancelled, failing with a
+/// `Cancelled` error if one does
+fn wait_cancelled(cancel: &[Option<&CancelToken>], timeout: DWORD) -> io::Result<()> {
+    let events: Vec<HANDLE> = cancel.iter().flatten().map(|token| token.event()).collect();
+    if !events.is_empty() {
+        unsafe {
+            WaitForMultipleObjects(events.len() as DWORD, events.as_ptr(), FALSE, timeout);
+        }
+    }
+    cancel::check_all(cancel).map_err(io::Error::from)
+}
+
+/// Checks that `handle` refers to a communications device
+fn check_comm_device(handle: HANDLE) -> Result<()> {
+    match dcb::get_dcb(handle) {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::Unsupported => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "handle does not refer to a communications device",
+        )),
+        Err(e) => Err(e),
+    }
+}
+
 impl Drop for COMPort {
     fn drop(&mut self) {
         unsafe {
             CloseHandle(self.handle);
         }
+        if self.observed {
+            crate::observer::notify(|observer| observer.on_close(self.port_name.as_deref()));
+        }
     }
 }
 
@@ -188,69 +800,105 @@ impl AsRawHandle for COMPort {
 
 impl FromRawHandle for COMPort {
     unsafe fn from_raw_handle(handle: RawHandle) -> Self {
-        COMPort::open_from_raw_handle(handle)
+        COMPort::open_from_raw_handle(handle).observed()
     }
 }
 
 impl IntoRawHandle for COMPort {
     fn into_raw_handle(self) -> RawHandle {
-        let Self { handle, .. } = self;
+        // Prevent the destructor from closing the handle, but release everything else the port
+        // holds, like the events of the `OVERLAPPED` structures.
+        let mut port = ManuallyDrop::new(self);
+        unsafe {
+            ptr::drop_in_place(&mut port.timeouts);
+            ptr::drop_in_place(&mut port.port_name);
+            ptr::drop_in_place(&mut port.stats);
+            ptr::drop_in_place(&mut port.read_overlapped);
+            ptr::drop_in_place(&mut port.write_overlapped);
+            ptr::drop_in_place(&mut port.wait_overlapped);
+            ptr::drop_in_place(&mut port.cancel);
+        }
+
+        port.handle as RawHandle
+    }
+}
+
+#[cfg(feature = "io-safety")]
+#[allow(clippy::incompatible_msrv)]
+impl std::os::windows::io::AsHandle for COMPort {
+    fn as_handle(&self) -> std::os::windows::io::BorrowedHandle<'_> {
+        // The handle stays open for the lifetime of the port.
+        unsafe { std::os::windows::io::BorrowedHandle::borrow_raw(self.handle as RawHandle) }
+    }
+}
+
+/// Creates a `COMPort` from an owned handle, checking that it refers to a communications device
+///
+/// See [`COMPort::try_from_raw_handle`] for details. The handle gets closed on failure.
+#[cfg(feature = "io-safety")]
+#[allow(clippy::incompatible_msrv)]
+impl TryFrom<std::os::windows::io::OwnedHandle> for COMPort {
+    type Error = Error;
 
-        handle as RawHandle
+    fn try_from(handle: std::os::windows::io::OwnedHandle) -> Result<Self> {
+        let port = unsafe { COMPort::try_from_raw_handle(handle.as_raw_handle())? };
+        // The port owns the handle now.
+        let _ = handle.into_raw_handle();
+        Ok(port)
+    }
+}
+
+#[cfg(feature = "io-safety")]
+#[allow(clippy::incompatible_msrv)]
+impl From<COMPort> for std::os::windows::io::OwnedHandle {
+    fn from(port: COMPort) -> Self {
+        unsafe { std::os::windows::io::OwnedHandle::from_raw_handle(port.into_raw_handle()) }
     }
 }
 
 impl io::Read for COMPort {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        let mut len: DWORD = 0;
+        (&*self).read(buf)
+    }
+}
 
-        match unsafe {
-            ReadFile(
-                self.handle,
-                buf.as_mut_ptr() as LPVOID,
-                buf.len() as DWORD,
-                &mut len,
-                ptr::null_mut(),
-            )
-        } {
-            0 => Err(io::Error::last_os_error()),
-            _ => {
-                if len != 0 {
-                    Ok(len as usize)
-                } else {
-                    Err(io::Error::new(
-                        io::ErrorKind::TimedOut,
-                        "Operation timed out",
-                    ))
-                }
-            }
-        }
+/// Reads through a shared reference, so one thread can read while another one writes
+///
+/// Like with `TcpStream`, this allows using a port shared with an `Arc` without duplicating its
+/// handle.
+impl io::Read for &COMPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_with(buf, None)
     }
 }
 
 impl io::Write for COMPort {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let mut len: DWORD = 0;
+        (&*self).write(buf)
+    }
 
-        match unsafe {
-            WriteFile(
-                self.handle,
-                buf.as_ptr() as LPVOID,
-                buf.len() as DWORD,
-                &mut len,
-                ptr::null_mut(),
-            )
-        } {
-            0 => Err(io::Error::last_os_error()),
-            _ => Ok(len as usize),
-        }
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        (&*self).write_vectored(bufs)
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        match unsafe { FlushFileBuffers(self.handle) } {
-            0 => Err(io::Error::last_os_error()),
-            _ => Ok(()),
-        }
+        (&*self).flush()
+    }
+}
+
+/// Writes through a shared reference, so one thread can write while another one reads
+impl io::Write for &COMPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_with(buf, None)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.write_vectored_with(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_handle(None)
+            .map_err(|e| self.io_context(e, "flushing"))
     }
 }
 
@@ -260,26 +908,26 @@ impl SerialPort for COMPort {
     }
 
     fn timeout(&self) -> Duration {
-        self.timeout
+        self.timeouts().timeout
     }
 
-    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
-        let timeout_constant = Self::timeout_constant(timeout);
+    fn inter_byte_timeout(&self) -> Result<Option<Duration>> {
+        Ok(self.timeouts().inter_byte_timeout)
+    }
 
-        let mut timeouts = COMMTIMEOUTS {
-            ReadIntervalTimeout: MAXDWORD,
-            ReadTotalTimeoutMultiplier: MAXDWORD,
-            ReadTotalTimeoutConstant: timeout_constant,
-            WriteTotalTimeoutMultiplier: 0,
-            WriteTotalTimeoutConstant: timeout_constant,
-        };
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.set_comm_timeouts(timeout, self.timeouts().inter_byte_timeout)
+            .map_err(self.context("setting timeout"))
+    }
 
-        if unsafe { SetCommTimeouts(self.handle, &mut timeouts) } == 0 {
-            return Err(super::error::last_os_error());
-        }
+    fn set_inter_byte_timeout(&mut self, inter_byte_timeout: Option<Duration>) -> Result<()> {
+        self.set_comm_timeouts(self.timeouts().timeout, inter_byte_timeout)
+            .map_err(self.context("setting inter-byte timeout"))
+    }
 
-        self.timeout = timeout;
-        Ok(())
+    fn apply_settings(&mut self, settings: &SerialPortBuilder) -> Result<()> {
+        self.apply_dcb(settings)
+            .map_err(self.context("applying settings"))
     }
 
     fn write_request_to_send(&mut self, level: bool) -> Result<()> {
@@ -299,19 +947,19 @@ impl SerialPort for COMPort {
     }
 
     fn read_clear_to_send(&mut self) -> Result<bool> {
-        self.read_pin(MS_CTS_ON)
+        self.read_pin(MS_CTS_ON, true)
     }
 
     fn read_data_set_ready(&mut self) -> Result<bool> {
-        self.read_pin(MS_DSR_ON)
+        self.read_pin(MS_DSR_ON, true)
     }
 
     fn read_ring_indicator(&mut self) -> Result<bool> {
-        self.read_pin(MS_RING_ON)
+        self.read_pin(MS_RING_ON, false)
     }
 
     fn read_carrier_detect(&mut self) -> Result<bool> {
-        self.read_pin(MS_RLSD_ON)
+        self.read_pin(MS_RLSD_ON, true)
     }
 
     fn baud_rate(&self) -> Result<u32> {
@@ -370,55 +1018,110 @@ impl SerialPort for COMPort {
     }
 
     fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
-        let mut dcb = dcb::get_dcb(self.handle)?;
-        dcb::set_baud_rate(&mut dcb, baud_rate);
-        dcb::set_dcb(self.handle, dcb)
+        self.change_dcb(|dcb| dcb::set_baud_rate(dcb, baud_rate))
+            .map_err(self.context("setting baud rate"))
     }
 
     fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
-        let mut dcb = dcb::get_dcb(self.handle)?;
-        dcb::set_data_bits(&mut dcb, data_bits);
-        dcb::set_dcb(self.handle, dcb)
+        self.change_dcb(|dcb| dcb::set_data_bits(dcb, data_bits))
+            .map_err(self.context("setting data bits"))
     }
 
     fn set_parity(&mut self, parity: Parity) -> Result<()> {
-        let mut dcb = dcb::get_dcb(self.handle)?;
-        dcb::set_parity(&mut dcb, parity);
-        dcb::set_dcb(self.handle, dcb)
+        self.change_dcb(|dcb| dcb::set_parity(dcb, parity))
+            .map_err(self.context("setting parity"))
     }
 
     fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
-        let mut dcb = dcb::get_dcb(self.handle)?;
-        dcb::set_stop_bits(&mut dcb, stop_bits);
-        dcb::set_dcb(self.handle, dcb)
+        self.change_dcb(|dcb| dcb::set_stop_bits(dcb, stop_bits))
+            .map_err(self.context("setting stop bits"))
     }
 
     fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
-        let mut dcb = dcb::get_dcb(self.handle)?;
-        dcb::set_flow_control(&mut dcb, flow_control);
-        dcb::set_dcb(self.handle, dcb)
+        self.change_dcb(|dcb| dcb::set_flow_control(dcb, flow_control))
+            .map_err(self.context("setting flow control"))
     }
 
     fn bytes_to_read(&self) -> Result<u32> {
-        let mut errors: DWORD = 0;
-        let mut comstat = MaybeUninit::uninit();
+        self.clear_comm_error().map(|comstat| comstat.cbInQue)
+    }
 
-        if unsafe { ClearCommError(self.handle, &mut errors, comstat.as_mut_ptr()) != 0 } {
-            unsafe { Ok(comstat.assume_init().cbInQue) }
-        } else {
-            Err(super::error::last_os_error())
+    fn bytes_to_write(&self) -> Result<u32> {
+        let result = self.clear_comm_error().map(|comstat| comstat.cbOutQue);
+        self.strictness.or_default(result, 0)
+    }
+
+    fn take_error(&self) -> Result<Option<Error>> {
+        self.clear_comm_error()?;
+        let errors = self.pending_errors.swap(0, Ordering::Relaxed);
+        let flag = |mask: DWORD| u32::from(errors & mask != 0);
+        let detected = crate::LineErrors {
+            frame: flag(CE_FRAME),
+            overrun: flag(CE_OVERRUN),
+            parity: flag(CE_RXPARITY),
+            breaks: flag(CE_BREAK),
+            buffer_overrun: flag(CE_RXOVER),
+        };
+        Ok(detected.to_error())
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        Ok(*self.stats.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        let mut status: DWORD = 0;
+        if unsafe { GetCommModemStatus(self.handle, &mut status) } != 0 {
+            return Ok(true);
+        }
+        match super::error::last_os_error() {
+            e if e.kind == ErrorKind::NoDevice => Ok(false),
+            e => Err(e),
         }
     }
 
-    fn bytes_to_write(&self) -> Result<u32> {
-        let mut errors: DWORD = 0;
-        let mut comstat = MaybeUninit::uninit();
+    fn cancel_token(&self) -> Result<CancelToken> {
+        let mut cancel = self.cancel.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(token) = &*cancel {
+            return Ok(token.clone());
+        }
+        let token = CancelToken::new()?;
+        *cancel = Some(token.clone());
+        Ok(token)
+    }
 
-        if unsafe { ClearCommError(self.handle, &mut errors, comstat.as_mut_ptr()) != 0 } {
-            unsafe { Ok(comstat.assume_init().cbOutQue) }
-        } else {
-            Err(super::error::last_os_error())
+    fn wait_readable(&self, timeout: Duration) -> Result<()> {
+        let cancel = self.active_cancel_token();
+        match self.wait_for_rx(timeout, &[cancel.as_ref()]) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(Error::new(ErrorKind::Timeout, "Operation timed out")),
+            Err(e) => Err(Error::from(e)),
         }
+        .map_err(self.context("waiting for data"))
+    }
+
+    fn wait_writable(&self, timeout: Duration) -> Result<()> {
+        self.wait_for_tx_space(timeout)
+            .map_err(self.context("waiting for buffer space"))
+    }
+
+    fn read_cancellable(&mut self, buf: &mut [u8], cancel: &CancelToken) -> io::Result<usize> {
+        self.read_with(buf, Some(cancel))
+    }
+
+    fn read_uninit<'a>(&mut self, buf: &'a mut [MaybeUninit<u8>]) -> io::Result<&'a mut [u8]> {
+        let len = self.read_uninit_with(buf)?;
+        // `ReadFile()` has initialized the bytes it reported.
+        Ok(unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), len) })
+    }
+
+    fn write_cancellable(&mut self, buf: &[u8], cancel: &CancelToken) -> io::Result<usize> {
+        self.write_with(buf, Some(cancel))
+    }
+
+    fn flush_cancellable(&mut self, cancel: &CancelToken) -> io::Result<()> {
+        self.flush_handle(Some(cancel))
+            .map_err(|e| self.io_context(e, "flushing"))
     }
 
     fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
@@ -443,19 +1146,21 @@ impl SerialPort for COMPort {
     }
 
     fn set_break(&self) -> Result<()> {
-        if unsafe { SetCommBreak(self.handle) != 0 } {
+        let result = if unsafe { SetCommBreak(self.handle) != 0 } {
             Ok(())
         } else {
             Err(super::error::last_os_error())
-        }
+        };
+        self.strictness.or_default(result, ())
     }
 
     fn clear_break(&self) -> Result<()> {
-        if unsafe { ClearCommBreak(self.handle) != 0 } {
+        let result = if unsafe { ClearCommBreak(self.handle) != 0 } {
             Ok(())
         } else {
             Err(super::error::last_os_error())
-        }
+        };
+        self.strictness.or_default(result, ())
     }
 }
 
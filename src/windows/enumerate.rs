@@ -1,5 +1,5 @@
 use std::collections::HashSet;
-use std::{mem, ptr};
+use std::{io, mem, ptr};
 
 use winapi::ctypes::c_void;
 use winapi::shared::guiddef::*;
@@ -7,11 +7,15 @@ use winapi::shared::minwindef::*;
 use winapi::shared::winerror::*;
 use winapi::um::cfgmgr32::*;
 use winapi::um::cguid::GUID_NULL;
+use winapi::um::errhandlingapi::GetLastError;
 use winapi::um::setupapi::*;
 use winapi::um::winnt::{KEY_READ, REG_SZ};
 use winapi::um::winreg::*;
 
-use crate::{Error, ErrorKind, Result, SerialPortInfo, SerialPortType, UsbPortInfo};
+use crate::UsbClass;
+use crate::{
+    EnumerationWarning, Error, ErrorKind, Result, SerialPortInfo, SerialPortType, UsbPortInfo,
+};
 
 /// takes normal Rust `str` and outputs a null terminated UTF-16 encoded string
 fn as_utf16(utf8: &str) -> Vec<u16> {
@@ -177,11 +181,53 @@ fn parse_usb_port_info(hardware_id: &str, parent_hardware_id: Option<&str>) -> O
         manufacturer: None,
         product: None,
 
-        #[cfg(feature = "usbportinfo-interface")]
         interface,
+        location: None,
+        bcd_device: None,
+        device_class: None,
+        interface_class: None,
     })
 }
 
+/// Extracts the class codes from a compatible ID like `USB\Class_02&SubClass_02&Prot_01`
+fn parse_class(compatible_id: &str) -> Option<UsbClass> {
+    let field = |marker: &str| {
+        let start = compatible_id.find(marker)? + marker.len();
+        let value = compatible_id.get(start..start + 2)?;
+        u8::from_str_radix(value, 16).ok()
+    };
+
+    Some(UsbClass {
+        class: field("\\Class_")?,
+        subclass: field("&SubClass_")?,
+        protocol: field("&Prot_")?,
+    })
+}
+
+/// Extracts the device release number from a hardware ID like `USB\VID_0403&PID_6001&REV_0600`
+fn parse_revision(hardware_id: &str) -> Option<u16> {
+    let rev_start = hardware_id.find("&REV_")?;
+    let rev = hardware_id.get(rev_start + 5..rev_start + 9)?;
+    u16::from_str_radix(rev, 16).ok()
+}
+
+/// Extracts the address of the remote device from the instance ID of a Bluetooth serial port like
+/// `BTHENUM\{00001101-0000-1000-8000-00805F9B34FB}_LOCALMFG&0002\7&2A8C8C3F&0&001122AABBCC_C00000000`
+///
+/// Ports not bound to a device yet carry an all zero address, which yields `None`.
+fn parse_bluetooth_address(instance_id: &str) -> Option<String> {
+    let last = instance_id.rsplit('&').next()?;
+    let address = last.split('_').next()?;
+    if address.len() != 12
+        || !address.chars().all(|c| c.is_ascii_hexdigit())
+        || address.chars().all(|c| c == '0')
+    {
+        return None;
+    }
+    let octets: Vec<&str> = (0..12).step_by(2).map(|i| &address[i..i + 2]).collect();
+    Some(octets.join(":").to_uppercase())
+}
+
 struct PortDevices {
     /// Handle to a device information set.
     hdi: HDEVINFO,
@@ -298,7 +344,7 @@ impl PortDevice {
         };
         if res == FALSE {
             // Try to retrieve hardware id property.
-            self.property(SPDRP_HARDWAREID)
+            self.property(SPDRP_HARDWAREID).ok().flatten()
         } else {
             let actual_result_len = working_buffer_len.min(desired_result_len as usize);
             Some(from_utf16_lossy_trimmed(&result_buf[..actual_result_len]))
@@ -378,22 +424,218 @@ impl PortDevice {
         from_utf16_lossy_trimmed(port_name)
     }
 
+    /// Retrieves the friendly name of this device's parent, e.g. the name of the remote device of
+    /// a Bluetooth serial port.
+    fn parent_friendly_name(&mut self) -> Option<String> {
+        let mut parent = 0;
+        let res = unsafe { CM_Get_Parent(&mut parent, self.devinfo_data.DevInst, 0) };
+        if res != CR_SUCCESS {
+            return None;
+        }
+
+        let mut value_type = 0;
+        let mut property_buf = [0u16; MAX_PATH];
+        let mut byte_len = 2 * property_buf.len() as ULONG;
+        let res = unsafe {
+            CM_Get_DevNode_Registry_PropertyW(
+                parent,
+                CM_DRP_FRIENDLYNAME,
+                &mut value_type,
+                property_buf.as_mut_ptr() as *mut c_void,
+                &mut byte_len,
+                0,
+            )
+        };
+        if res != CR_SUCCESS || value_type != REG_SZ {
+            return None;
+        }
+
+        Some(from_utf16_lossy_trimmed(&property_buf)).filter(|s| !s.is_empty())
+    }
+
+    // Determines the remote device if this is a Bluetooth serial port. Without `details`, only
+    // the address contained in the instance id gets reported.
+    pub fn bluetooth_port_info(&mut self, details: bool) -> Option<crate::BluetoothPortInfo> {
+        let instance_id = self
+            .instance_id()
+            .filter(|id| id.to_uppercase().starts_with("BTHENUM\\"))?;
+        // Only ports bound to a remote device are children of that device's node. Otherwise,
+        // the parent is the Bluetooth enumerator itself.
+        let name = if details {
+            self.parent_instance_id()
+                .filter(|parent| parent.to_uppercase().starts_with("BTHENUM\\DEV_"))
+                .and_then(|_| self.parent_friendly_name())
+        } else {
+            None
+        };
+        Some(crate::BluetoothPortInfo {
+            address: parse_bluetooth_address(&instance_id),
+            name,
+        })
+    }
+
     // Determines the port_type for this device, and if it's a USB port populate the various fields.
-    pub fn port_type(&mut self) -> SerialPortType {
-        self.instance_id()
-            .map(|s| (s, self.parent_instance_id())) // Get parent instance id if it exists.
-            .and_then(|(d, p)| parse_usb_port_info(&d, p.as_deref()))
-            .map(|mut info: UsbPortInfo| {
-                info.manufacturer = self.property(SPDRP_MFG);
-                info.product = self.property(SPDRP_FRIENDLYNAME);
-                SerialPortType::UsbPort(info)
-            })
-            .unwrap_or(SerialPortType::Unknown)
+    // Without `details`, only the information contained in the instance ids gets reported.
+    // Properties of USB devices which fail to be read are left out and recorded in `warnings`.
+    pub fn port_type(
+        &mut self,
+        details: bool,
+        warnings: &mut Vec<EnumerationWarning>,
+    ) -> SerialPortType {
+        let instance_id = match self.instance_id() {
+            Some(instance_id) => instance_id,
+            None => return SerialPortType::Unknown,
+        };
+        if instance_id.to_uppercase().starts_with("BTHENUM\\") {
+            return SerialPortType::BluetoothPort;
+        }
+
+        let parent_instance_id = self.parent_instance_id();
+        let mut info = match parse_usb_port_info(&instance_id, parent_instance_id.as_deref()) {
+            Some(info) => info,
+            None => return SerialPortType::Unknown,
+        };
+        if !details {
+            return SerialPortType::UsbPort(info);
+        }
+
+        let mut warn = |what: &str, error: Error| {
+            warnings.push(EnumerationWarning::new(
+                instance_id.as_str(),
+                format!("Failed to read the {}: {}", what, error),
+            ));
+        };
+        info.manufacturer = self.property(SPDRP_MFG).unwrap_or_else(|e| {
+            warn("manufacturer", e);
+            None
+        });
+        info.product = self.property(SPDRP_FRIENDLYNAME).unwrap_or_else(|e| {
+            warn("product name", e);
+            None
+        });
+        {
+            info.location = self.location_path().unwrap_or_else(|e| {
+                warn("location path", e);
+                None
+            });
+        }
+        {
+            info.bcd_device = self.bcd_device().unwrap_or_else(|e| {
+                warn("hardware IDs", e);
+                None
+            });
+        }
+        // The compatible IDs carry the class of the interface for interfaces of composite
+        // devices and the class of the whole device otherwise.
+        {
+            let class = self.usb_class().unwrap_or_else(|e| {
+                warn("compatible IDs", e);
+                None
+            });
+            if instance_id.contains("&MI_") {
+                info.interface_class = class;
+            } else {
+                info.device_class = class;
+            }
+        }
+        SerialPortType::UsbPort(info)
+    }
+
+    // Retrieves the first of the location paths of this device, which describes the chain of
+    // buses and hub ports leading to it. Returns None if the device has no location path.
+    fn location_path(&mut self) -> Result<Option<String>> {
+        Ok(self
+            .multi_sz_property(SPDRP_LOCATION_PATHS)?
+            .and_then(|paths| paths.into_iter().next()))
+    }
+
+    // Retrieves the USB device release number from the hardware IDs of this device. Returns None
+    // if none of them contains a revision.
+    fn bcd_device(&mut self) -> Result<Option<u16>> {
+        Ok(self
+            .multi_sz_property(SPDRP_HARDWAREID)?
+            .and_then(|hwids| hwids.iter().find_map(|hwid| parse_revision(hwid))))
+    }
+
+    // Retrieves the USB class codes from the compatible IDs of this device. Returns None if none
+    // of them contains the class codes.
+    fn usb_class(&mut self) -> Result<Option<UsbClass>> {
+        Ok(self
+            .multi_sz_property(SPDRP_COMPATIBLEIDS)?
+            .and_then(|ids| ids.iter().find_map(|id| parse_class(id))))
+    }
+
+    // Retrieves a device property consisting of multiple strings. Returns None if the property
+    // doesn't exist.
+    fn multi_sz_property(&mut self, property_id: DWORD) -> Result<Option<Vec<String>>> {
+        let mut value_type = 0;
+        let mut property_buf = [0u16; MAX_PATH];
+
+        let res = unsafe {
+            SetupDiGetDeviceRegistryPropertyW(
+                self.hdi,
+                &mut self.devinfo_data,
+                property_id,
+                &mut value_type,
+                property_buf.as_mut_ptr() as PBYTE,
+                (2 * property_buf.len()) as DWORD,
+                ptr::null_mut(),
+            )
+        };
+
+        if res == FALSE {
+            return missing_property(ERROR_INVALID_DATA);
+        }
+        if value_type != winapi::um::winnt::REG_MULTI_SZ {
+            return Ok(None);
+        }
+
+        // The strings are stored one after another, each of them null terminated. The list ends
+        // with an empty string.
+        Ok(Some(
+            property_buf
+                .split(|&c| c == 0)
+                .take_while(|s| !s.is_empty())
+                .map(String::from_utf16_lossy)
+                .collect(),
+        ))
+    }
+
+    // Retrieves the product description which the device reported to the bus driver. Returns None
+    // if the bus driver does not provide one.
+    fn bus_reported_description(&mut self) -> Result<Option<String>> {
+        use winapi::shared::devpkey::DEVPKEY_Device_BusReportedDeviceDesc;
+        use winapi::shared::devpropdef::DEVPROP_TYPE_STRING;
+
+        let mut property_type = 0;
+        let mut property_buf = [0u16; MAX_PATH];
+
+        let res = unsafe {
+            SetupDiGetDevicePropertyW(
+                self.hdi,
+                &mut self.devinfo_data,
+                &DEVPKEY_Device_BusReportedDeviceDesc,
+                &mut property_type,
+                property_buf.as_mut_ptr() as PBYTE,
+                (2 * property_buf.len()) as DWORD,
+                ptr::null_mut(),
+                0,
+            )
+        };
+
+        if res == FALSE {
+            return missing_property(ERROR_NOT_FOUND);
+        }
+        if property_type != DEVPROP_TYPE_STRING {
+            return Ok(None);
+        }
+
+        Ok(Some(from_utf16_lossy_trimmed(&property_buf)).filter(|s| !s.is_empty()))
     }
 
     // Retrieves a device property and returns it, if it exists. Returns None if the property
     // doesn't exist.
-    fn property(&mut self, property_id: DWORD) -> Option<String> {
+    fn property(&mut self, property_id: DWORD) -> Result<Option<String>> {
         let mut value_type = 0;
         let mut property_buf = [0u16; MAX_PATH];
 
@@ -409,17 +651,29 @@ impl PortDevice {
             )
         };
 
-        if res == FALSE || value_type != REG_SZ {
-            return None;
+        if res == FALSE {
+            return missing_property(ERROR_INVALID_DATA);
+        }
+        if value_type != REG_SZ {
+            return Ok(None);
         }
 
         // Using the unicode version of 'SetupDiGetDeviceRegistryProperty' seems to report the
         // entire mfg registry string. This typically includes some driver information that we should discard.
         // Example string: 'FTDI5.inf,%ftdi%;FTDI'
-        from_utf16_lossy_trimmed(&property_buf)
+        Ok(from_utf16_lossy_trimmed(&property_buf)
             .split(';')
             .last()
-            .map(str::to_string)
+            .map(str::to_string))
+    }
+}
+
+/// Handles the failure of querying a device property: If the last error is `not_found`, the
+/// property just doesn't exist. Any other error is a failure to read it.
+fn missing_property<T>(not_found: DWORD) -> Result<Option<T>> {
+    match unsafe { GetLastError() } {
+        error if error == not_found => Ok(None),
+        error => Err(io::Error::from_raw_os_error(error as i32).into()),
     }
 }
 
@@ -428,17 +682,32 @@ impl PortDevice {
 /// HKEY_LOCAL_MACHINE\HARDWARE\DEVICEMAP\SERIALCOMM
 ///
 /// port of https://learn.microsoft.com/en-us/windows/win32/sysinfo/enumerating-registry-subkeys
-fn get_registry_com_ports() -> HashSet<String> {
-    let mut ports_list = HashSet::new();
+///
+/// Failures to read the registry are recorded in `warnings`.
+fn get_registry_com_ports(warnings: &mut Vec<EnumerationWarning>) -> HashSet<String> {
+    const SERIALCOMM: &str = "HARDWARE\\DEVICEMAP\\SERIALCOMM";
 
-    let reg_key = as_utf16("HARDWARE\\DEVICEMAP\\SERIALCOMM");
+    let mut ports_list = HashSet::new();
+    let mut warn = |operation: &str, error: LSTATUS| {
+        let error = io::Error::from_raw_os_error(error);
+        warnings.push(EnumerationWarning::new(
+            format!("HKEY_LOCAL_MACHINE\\{}", SERIALCOMM),
+            format!("Failed to {}: {}", operation, error),
+        ));
+    };
+
+    let reg_key = as_utf16(SERIALCOMM);
     let key_ptr = reg_key.as_ptr();
     let mut ports_key = std::ptr::null_mut();
 
     // SAFETY: ffi, all inputs are correct
     let open_res =
         unsafe { RegOpenKeyExW(HKEY_LOCAL_MACHINE, key_ptr, 0, KEY_READ, &mut ports_key) };
-    if SUCCEEDED(open_res) {
+    // The key only exists while there are serial ports.
+    if open_res != ERROR_SUCCESS as LSTATUS && open_res != ERROR_FILE_NOT_FOUND as LSTATUS {
+        warn("open the registry key", open_res);
+    }
+    if open_res == ERROR_SUCCESS as LSTATUS {
         let mut class_name_buff = [0u16; MAX_PATH];
         let mut class_name_size = MAX_PATH as u32;
         let mut sub_key_count = 0;
@@ -469,7 +738,9 @@ fn get_registry_com_ports() -> HashSet<String> {
                 &mut last_write_time,
             )
         };
-        if SUCCEEDED(query_res) {
+        if query_res != ERROR_SUCCESS as LSTATUS {
+            warn("query the registry key", query_res);
+        } else {
             for idx in 0..num_key_values {
                 let mut val_name_buff = [0u16; MAX_PATH];
                 let mut val_name_size = MAX_PATH as u32;
@@ -491,8 +762,11 @@ fn get_registry_com_ports() -> HashSet<String> {
                         &mut byte_len,
                     )
                 };
-                if FAILED(res)
-                    || value_type != REG_SZ // only valid for text values
+                if res != ERROR_SUCCESS as LSTATUS {
+                    warn("read the registry values", res);
+                    break;
+                }
+                if value_type != REG_SZ // only valid for text values
                     || byte_len % 2 != 0 // out byte len should be a multiple of u16 size
                     || byte_len > buffer_byte_len
                 {
@@ -514,7 +788,22 @@ fn get_registry_com_ports() -> HashSet<String> {
 }
 
 /// List available serial ports on the system.
-pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
+pub fn available_ports(warnings: &mut Vec<EnumerationWarning>) -> Result<Vec<SerialPortInfo>> {
+    enumerate_ports(warnings, true)
+}
+
+/// Lists the serial ports along with the information contained in their instance ids, skipping
+/// the slow lookup of further device properties.
+pub fn available_ports_lossy(
+    warnings: &mut Vec<EnumerationWarning>,
+) -> Result<Vec<SerialPortInfo>> {
+    enumerate_ports(warnings, false)
+}
+
+fn enumerate_ports(
+    warnings: &mut Vec<EnumerationWarning>,
+    details: bool,
+) -> Result<Vec<SerialPortInfo>> {
     let mut ports = Vec::new();
     for guid in get_ports_guids()? {
         let port_devices = PortDevices::new(&guid);
@@ -525,6 +814,16 @@ pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
             }
 
             let port_name = port_device.name();
+            if port_name.is_empty() {
+                let device = port_device
+                    .instance_id()
+                    .unwrap_or_else(|| "Unknown device".to_string());
+                warnings.push(EnumerationWarning::new(
+                    device,
+                    "Failed to read the port name from the registry",
+                ));
+                continue;
+            }
 
             debug_assert!(
                 port_name.as_bytes().last().map_or(true, |c| *c != b'\0'),
@@ -537,14 +836,47 @@ pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
                 continue;
             }
 
+            // Warnings about the device name it by its instance id, if it can be determined.
+            let device = port_device
+                .instance_id()
+                .unwrap_or_else(|| port_name.clone());
             ports.push(SerialPortInfo {
                 port_name,
-                port_type: port_device.port_type(),
+                port_type: port_device.port_type(details, warnings),
+                friendly_name: if details {
+                    port_device
+                        .property(SPDRP_FRIENDLYNAME)
+                        .unwrap_or_else(|e| {
+                            warnings.push(EnumerationWarning::new(
+                                device.as_str(),
+                                format!("Failed to read the friendly name: {}", e),
+                            ));
+                            None
+                        })
+                } else {
+                    None
+                },
+                description: if details {
+                    port_device.bus_reported_description().unwrap_or_else(|e| {
+                        warnings.push(EnumerationWarning::new(
+                            device.as_str(),
+                            format!("Failed to read the description: {}", e),
+                        ));
+                        None
+                    })
+                } else {
+                    None
+                },
+                instance_id: port_device.instance_id(),
+                counterpart: None,
+                by_id: None,
+                by_path: None,
+                bluetooth: port_device.bluetooth_port_info(details),
             });
         }
     }
     // ports identified through the registry have no additional information
-    let mut raw_ports_set = get_registry_com_ports();
+    let mut raw_ports_set = get_registry_com_ports(warnings);
     if raw_ports_set.len() > ports.len() {
         // remove any duplicates. HashSet makes this relatively cheap
         for port in ports.iter() {
@@ -555,6 +887,13 @@ pub fn available_ports() -> Result<Vec<SerialPortInfo>> {
             ports.push(SerialPortInfo {
                 port_name: raw_port,
                 port_type: SerialPortType::Unknown,
+                friendly_name: None,
+                description: None,
+                instance_id: None,
+                counterpart: None,
+                by_id: None,
+                by_path: None,
+                bluetooth: None,
             })
         }
     }
@@ -727,7 +1066,6 @@ mod tests {
         assert_eq!(info.vid, 0x1D50);
         assert_eq!(info.pid, 0x6018);
         assert_eq!(info.serial_number, Some("85A12F01".to_string()));
-        #[cfg(feature = "usbportinfo-interface")]
         assert_eq!(info.interface, Some(2));
 
         let ftdi_serial_hwid = r"FTDIBUS\VID_0403+PID_6001+A702TB52A\0000";
@@ -736,7 +1074,6 @@ mod tests {
         assert_eq!(info.vid, 0x0403);
         assert_eq!(info.pid, 0x6001);
         assert_eq!(info.serial_number, Some("A702TB52A".to_string()));
-        #[cfg(feature = "usbportinfo-interface")]
         assert_eq!(info.interface, None);
 
         let pyboard_hwid = r"USB\VID_F055&PID_9802\385435603432";
@@ -745,7 +1082,6 @@ mod tests {
         assert_eq!(info.vid, 0xF055);
         assert_eq!(info.pid, 0x9802);
         assert_eq!(info.serial_number, Some("385435603432".to_string()));
-        #[cfg(feature = "usbportinfo-interface")]
         assert_eq!(info.interface, None);
 
         let unicode_serial = r"USB\VID_F055&PID_9802\3854356β03432&test";
@@ -760,4 +1096,52 @@ mod tests {
         let info = parse_usb_port_info(unicode_serial, None).unwrap();
         assert_eq!(info.serial_number.as_deref(), Some("3854356β"));
     }
+
+    #[test]
+    fn test_parsing_revision() {
+        assert_eq!(
+            parse_revision(r"USB\VID_0403&PID_6001&REV_0600"),
+            Some(0x0600)
+        );
+        assert_eq!(
+            parse_revision(r"USB\VID_1D50&PID_6018&REV_0100&MI_02"),
+            Some(0x0100)
+        );
+        assert_eq!(parse_revision(r"USB\VID_0403&PID_6001"), None);
+        assert_eq!(parse_revision(r"USB\VID_0403&PID_6001&REV_06"), None);
+    }
+
+    #[test]
+    fn test_parsing_class() {
+        assert_eq!(
+            parse_class(r"USB\Class_02&SubClass_02&Prot_01"),
+            Some(UsbClass {
+                class: 0x02,
+                subclass: 0x02,
+                protocol: 0x01
+            })
+        );
+        assert_eq!(parse_class(r"USB\Class_FF&SubClass_00"), None);
+        assert_eq!(parse_class(r"USB\COMPOSITE"), None);
+    }
+
+    #[test]
+    fn test_parsing_bluetooth_address() {
+        assert_eq!(
+            parse_bluetooth_address(
+                r"BTHENUM\{00001101-0000-1000-8000-00805F9B34FB}_LOCALMFG&0002\7&2A8C8C3F&0&001122aabbcc_C00000000"
+            ),
+            Some("00:11:22:AA:BB:CC".to_string())
+        );
+        assert_eq!(
+            parse_bluetooth_address(
+                r"BTHENUM\{00001101-0000-1000-8000-00805F9B34FB}_LOCALMFG&0000\7&2A8C8C3F&0&000000000000_00000001"
+            ),
+            None
+        );
+        assert_eq!(
+            parse_bluetooth_address(r"USB\VID_0403&PID_6001\A702TB52"),
+            None
+        );
+    }
 }
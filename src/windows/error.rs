@@ -13,13 +13,48 @@ use crate::{Error, ErrorKind};
 
 pub fn last_os_error() -> Error {
     let errno = errno();
+    Error::new(error_kind(errno), error_string(errno).trim())
+}
 
-    let kind = match errno {
-        ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND | ERROR_ACCESS_DENIED => ErrorKind::NoDevice,
+/// Returns the kind of error for a Windows error code
+fn error_kind(errno: u32) -> ErrorKind {
+    match errno {
+        ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND => ErrorKind::NoDevice,
+        _ if is_disconnect(errno) => ErrorKind::NoDevice,
+        // Opening a port which is open in another process fails with `ERROR_ACCESS_DENIED`.
+        ERROR_ACCESS_DENIED | ERROR_SHARING_VIOLATION | ERROR_PIPE_BUSY => ErrorKind::Busy,
+        ERROR_NOT_SUPPORTED | ERROR_INVALID_FUNCTION => ErrorKind::Unsupported,
+        ERROR_BROKEN_PIPE | ERROR_NO_DATA => ErrorKind::BrokenPipe,
         _ => ErrorKind::Io(io::ErrorKind::Other),
-    };
+    }
+}
+
+/// Returns the last error of a read or write operation
+///
+/// Errors indicating that the device has been disconnected are reported as `NoDevice`, all
+/// others as they are.
+pub fn last_io_error() -> io::Error {
+    let errno = errno();
+    if is_disconnect(errno) {
+        io::Error::from(Error::new(ErrorKind::NoDevice, error_string(errno).trim()))
+    } else {
+        io::Error::from_raw_os_error(errno as i32)
+    }
+}
 
-    Error::new(kind, error_string(errno).trim())
+/// Returns whether the error code indicates that the device has been disconnected
+///
+/// USB serial drivers don't agree on a single error code for this.
+fn is_disconnect(errno: u32) -> bool {
+    matches!(
+        errno,
+        ERROR_BAD_COMMAND | ERROR_GEN_FAILURE | ERROR_DEVICE_NOT_CONNECTED | ERROR_DEVICE_REMOVED
+    )
+}
+
+/// Returns whether opening a device failed because it is busy or not accessible yet
+pub(crate) fn is_busy(error: &Error) -> bool {
+    error.kind() == ErrorKind::Busy
 }
 
 // the rest of this module is borrowed from libstd
@@ -67,3 +102,22 @@ fn error_string(errnum: u32) -> String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kind() {
+        // A port opened by another process can't be opened again and reports access as denied.
+        assert_eq!(error_kind(ERROR_ACCESS_DENIED), ErrorKind::Busy);
+        assert_eq!(error_kind(ERROR_SHARING_VIOLATION), ErrorKind::Busy);
+        assert_eq!(error_kind(ERROR_FILE_NOT_FOUND), ErrorKind::NoDevice);
+        assert_eq!(error_kind(ERROR_DEVICE_REMOVED), ErrorKind::NoDevice);
+        assert_eq!(error_kind(ERROR_NOT_SUPPORTED), ErrorKind::Unsupported);
+        assert_eq!(
+            error_kind(ERROR_INVALID_HANDLE),
+            ErrorKind::Io(io::ErrorKind::Other)
+        );
+    }
+}
@@ -1,7 +1,15 @@
 pub use self::com::*;
 pub use self::enumerate::*;
+pub use self::pipe::*;
+
+pub(crate) use self::error::{is_busy, last_os_error};
+pub(crate) use self::pipe::is_pipe_path;
+pub(crate) use self::select::{select_readable, Waiter};
 
 mod com;
 mod dcb;
 mod enumerate;
 mod error;
+mod overlapped;
+mod pipe;
+mod select;
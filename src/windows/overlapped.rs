@@ -0,0 +1,170 @@
+//! Overlapped I/O on communications devices
+//!
+//! Ports get opened with `FILE_FLAG_OVERLAPPED`, so a read blocking on one handle does not hold
+//! up writes on the same device. Every operation waits for its own completion, so the
+//! `COMMTIMEOUTS` of the device still apply as for synchronous I/O.
+
+use std::sync::Mutex;
+use std::{fmt, io, mem, ptr};
+
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+use winapi::shared::winerror::{ERROR_IO_PENDING, ERROR_OPERATION_ABORTED};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::ioapiset::{CancelIoEx, GetOverlappedResult};
+use winapi::um::minwinbase::{LPOVERLAPPED, OVERLAPPED};
+use winapi::um::synchapi::{CreateEventW, ResetEvent, SetEvent, WaitForMultipleObjects};
+use winapi::um::winbase::{INFINITE, WAIT_OBJECT_0};
+use winapi::um::winnt::HANDLE;
+
+use crate::cancel::cancelled;
+use crate::CancelToken;
+
+/// An `OVERLAPPED` structure with its own event, reusable for one operation at a time
+pub(crate) struct Overlapped {
+    overlapped: OVERLAPPED,
+}
+
+impl Overlapped {
+    /// Creates the structure with a new manual-reset event, as required by
+    /// `GetOverlappedResult()`
+    pub(crate) fn new() -> io::Result<Overlapped> {
+        let event = unsafe { CreateEventW(ptr::null_mut(), TRUE, FALSE, ptr::null()) };
+        if event.is_null() {
+            return Err(super::error::last_io_error());
+        }
+
+        let mut overlapped: OVERLAPPED = unsafe { mem::zeroed() };
+        overlapped.hEvent = event;
+        Ok(Overlapped { overlapped })
+    }
+
+    /// Prepares the structure for a new operation and returns the pointer to pass to the
+    /// function starting it
+    pub(crate) fn start(&mut self) -> LPOVERLAPPED {
+        // All members but the event have to be zeroed before reusing the structure. The event
+        // gets reset by the function starting the operation.
+        let event = self.overlapped.hEvent;
+        self.overlapped = unsafe { mem::zeroed() };
+        self.overlapped.hEvent = event;
+        &mut self.overlapped
+    }
+
+    /// Waits for the operation on `handle` to complete and returns the number of bytes
+    /// transferred
+    ///
+    /// `started` is the result of the function starting the operation. Handles opened without
+    /// `FILE_FLAG_OVERLAPPED`, e.g. ones adopted with `from_raw_handle()`, complete the operation
+    /// right away, which is handled the same way.
+    ///
+    /// Cancelling any token in `cancel` aborts the operation with `CancelIoEx()`. Data
+    /// transferred before aborting is still reported, otherwise the wait fails with a
+    /// `Cancelled` error.
+    pub(crate) fn wait(
+        &mut self,
+        handle: HANDLE,
+        started: BOOL,
+        cancel: &[Option<&CancelToken>],
+    ) -> io::Result<DWORD> {
+        if started == 0 && unsafe { GetLastError() } != ERROR_IO_PENDING {
+            return Err(super::error::last_io_error());
+        }
+
+        let cancellable = cancel.iter().any(Option::is_some);
+        if started == 0 && cancellable && !self.is_complete_within(INFINITE, cancel) {
+            // The aborted operation still completes and has to be waited for below.
+            self.cancel(handle);
+        }
+
+        let mut len: DWORD = 0;
+        if unsafe { GetOverlappedResult(handle, &mut self.overlapped, &mut len, TRUE) } == 0 {
+            let aborted = unsafe { GetLastError() } == ERROR_OPERATION_ABORTED
+                && cancel.iter().flatten().any(|token| token.is_cancelled());
+            return match len {
+                _ if !aborted => Err(super::error::last_io_error()),
+                0 => Err(cancelled().into()),
+                len => Ok(len),
+            };
+        }
+        Ok(len)
+    }
+
+    /// Waits up to `timeout` milliseconds for the operation to complete without retrieving its
+    /// result, returning whether it completed
+    ///
+    /// Cancelling any token in `cancel` ends the wait early.
+    pub(crate) fn is_complete_within(
+        &self,
+        timeout: DWORD,
+        cancel: &[Option<&CancelToken>],
+    ) -> bool {
+        let mut events = [self.overlapped.hEvent; MAX_WAIT_EVENTS];
+        let mut count = 1;
+        for token in cancel.iter().flatten().take(MAX_WAIT_EVENTS - 1) {
+            events[count] = token.event();
+            count += 1;
+        }
+        // The lowest signaled index gets reported, so a completed operation wins over cancelling.
+        let result =
+            unsafe { WaitForMultipleObjects(count as DWORD, events.as_ptr(), FALSE, timeout) };
+        result == WAIT_OBJECT_0
+    }
+
+    /// Aborts the operation on `handle`, which still has to be waited for before reusing the
+    /// structure
+    pub(crate) fn cancel(&mut self, handle: HANDLE) {
+        unsafe { CancelIoEx(handle, &mut self.overlapped) };
+    }
+
+    /// Returns the event signaled when the operation completes
+    pub(crate) fn event(&self) -> HANDLE {
+        self.overlapped.hEvent
+    }
+
+    /// Signals the event without completing an operation, e.g. to report readiness right away
+    pub(crate) fn signal(&self) {
+        unsafe { SetEvent(self.overlapped.hEvent) };
+    }
+
+    /// Resets the event after a signal or a completed operation
+    pub(crate) fn reset(&self) {
+        unsafe { ResetEvent(self.overlapped.hEvent) };
+    }
+}
+
+/// The number of events waited for at most: the operation and up to two cancel tokens
+const MAX_WAIT_EVENTS: usize = 3;
+
+impl fmt::Debug for Overlapped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Overlapped")
+            .field("event", &self.overlapped.hEvent)
+            .finish()
+    }
+}
+
+impl Drop for Overlapped {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.overlapped.hEvent);
+        }
+    }
+}
+
+/// Runs `operation` with the `OVERLAPPED` structure cached in `cache`
+///
+/// Creating the event once instead of for every operation saves two system calls per read or
+/// write. Concurrent operations through shared references fall back to a new structure instead
+/// of waiting for the cached one.
+pub(crate) fn with_cached<T>(
+    cache: &Mutex<Option<Overlapped>>,
+    operation: impl FnOnce(&mut Overlapped) -> io::Result<T>,
+) -> io::Result<T> {
+    match cache.try_lock() {
+        Ok(mut cached) => match &mut *cached {
+            Some(overlapped) => operation(overlapped),
+            None => operation(cached.insert(Overlapped::new()?)),
+        },
+        Err(_) => operation(&mut Overlapped::new()?),
+    }
+}
@@ -0,0 +1,502 @@
+use std::fs::File;
+use std::os::windows::prelude::*;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::{io, ptr};
+
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+use winapi::shared::winerror::{
+    ERROR_BROKEN_PIPE, ERROR_IO_PENDING, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED,
+};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::fileapi::{CreateFileW, ReadFile, WriteFile, OPEN_EXISTING};
+use winapi::um::handleapi::{CloseHandle, DuplicateHandle, INVALID_HANDLE_VALUE};
+use winapi::um::minwinbase::LPOVERLAPPED;
+use winapi::um::namedpipeapi::{ConnectNamedPipe, CreateNamedPipeW, PeekNamedPipe, WaitNamedPipeW};
+use winapi::um::processthreadsapi::GetCurrentProcess;
+use winapi::um::winbase::{
+    FILE_FLAG_FIRST_PIPE_INSTANCE, FILE_FLAG_OVERLAPPED, PIPE_ACCESS_DUPLEX, PIPE_READMODE_BYTE,
+    PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+use winapi::um::winnt::{
+    DUPLICATE_SAME_ACCESS, FILE_ATTRIBUTE_NORMAL, GENERIC_READ, GENERIC_WRITE, HANDLE, LPVOID,
+};
+
+use super::overlapped::{self, Overlapped};
+use super::COMPort;
+use crate::{
+    ClearBuffer, DataBits, Error, ErrorKind, FlowControl, Parity, PortStats, Result, SerialPort,
+    SerialPortBuilder, StopBits,
+};
+
+/// The prefix of named pipe paths, which [`SerialPortBuilder::open`] connects to as emulated
+/// ports
+const PIPE_PREFIX: &str = r"\\.\pipe\";
+
+/// The size of the pipe buffers in each direction
+const PIPE_BUFFER_SIZE: DWORD = 4096;
+
+/// Returns whether `path` names a pipe to be opened with [`PipeSerialPort`]
+pub(crate) fn is_pipe_path(path: &str) -> bool {
+    path.get(..PIPE_PREFIX.len())
+        .map_or(false, |prefix| prefix.eq_ignore_ascii_case(PIPE_PREFIX))
+}
+
+fn to_wide(path: &str) -> Vec<u16> {
+    path.encode_utf16().chain(Some(0)).collect()
+}
+
+/// The device side of an emulated serial port
+///
+/// The server creates a named pipe, whose name has to start with `\\.\pipe\`. Applications
+/// connect to it with [`PipeSerialPort`], which behaves like a regular serial port. As
+/// [`SerialPortBuilder::open`] recognizes pipe names, applications opening a port by name can
+/// be pointed to the pipe without changes. Test code accepts these connections and plays the
+/// role of the device by reading and writing the returned files.
+///
+/// ```no_run
+/// use std::io::{Read, Write};
+/// use serialport::PipeSerialServer;
+///
+/// let server = PipeSerialServer::bind(r"\\.\pipe\fake-device").expect("Failed to bind");
+/// let mut port = serialport::new(r"\\.\pipe\fake-device", 9600)
+///     .open()
+///     .expect("Failed to connect");
+/// let mut device = server.accept().expect("Failed to accept");
+///
+/// port.write_all(b"AT\r").unwrap();
+/// let mut request = [0u8; 3];
+/// device.read_exact(&mut request).unwrap();
+/// device.write_all(b"OK\r\n").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PipeSerialServer {
+    name: String,
+    /// The pipe instance waiting for the next client
+    pending: Mutex<Option<File>>,
+}
+
+impl PipeSerialServer {
+    /// Creates the named pipe `name` and starts listening for connections
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidInput` if `name` does not start with `\\.\pipe\`.
+    /// * `Busy` if a pipe with this name already exists.
+    /// * `Io` for any other error while creating the pipe.
+    pub fn bind(name: impl AsRef<str>) -> Result<Self> {
+        let name = name.as_ref();
+        if !is_pipe_path(name) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("pipe names have to start with {}", PIPE_PREFIX),
+            ));
+        }
+
+        let first = create_instance(name, FILE_FLAG_FIRST_PIPE_INSTANCE)?;
+        Ok(PipeSerialServer {
+            name: name.to_string(),
+            pending: Mutex::new(Some(first)),
+        })
+    }
+
+    /// Returns the name to connect to
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Waits for a [`PipeSerialPort`] to connect and returns the device's end of the connection
+    pub fn accept(&self) -> Result<File> {
+        let pending = self
+            .pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        let instance = match pending {
+            Some(instance) => instance,
+            None => create_instance(&self.name, 0)?,
+        };
+
+        let connected =
+            unsafe { ConnectNamedPipe(instance.as_raw_handle() as HANDLE, ptr::null_mut()) };
+        // Clients connecting before this call are reported with `ERROR_PIPE_CONNECTED`.
+        if connected == 0 && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED {
+            return Err(super::error::last_os_error());
+        }
+
+        // Create the next instance right away, so clients don't find the pipe busy while test
+        // code is not waiting in `accept()`.
+        if let Ok(next) = create_instance(&self.name, 0) {
+            *self.pending.lock().unwrap_or_else(|e| e.into_inner()) = Some(next);
+        }
+        Ok(instance)
+    }
+}
+
+/// Creates a new instance of the named pipe `name` for the server side
+fn create_instance(name: &str, flags: DWORD) -> Result<File> {
+    let name = to_wide(name);
+    let handle = unsafe {
+        CreateNamedPipeW(
+            name.as_ptr(),
+            PIPE_ACCESS_DUPLEX | flags,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT,
+            PIPE_UNLIMITED_INSTANCES,
+            PIPE_BUFFER_SIZE,
+            PIPE_BUFFER_SIZE,
+            0,
+            ptr::null_mut(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(super::error::last_os_error());
+    }
+    Ok(unsafe { File::from_raw_handle(handle as RawHandle) })
+}
+
+/// The application side of an emulated serial port
+///
+/// See [`PipeSerialServer`] for providing the device side. Port settings are only recorded and
+/// reported back, they have no effect on the data transfer. The modem control lines report
+/// the device as present and ready.
+#[derive(Debug)]
+pub struct PipeSerialPort {
+    handle: HANDLE,
+    read_overlapped: Mutex<Option<Overlapped>>,
+    write_overlapped: Mutex<Option<Overlapped>>,
+    port_name: String,
+    baud_rate: u32,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+    timeout: Duration,
+    stats: PortStats,
+}
+
+unsafe impl Send for PipeSerialPort {}
+unsafe impl Sync for PipeSerialPort {}
+
+impl PipeSerialPort {
+    /// Connects to the [`PipeSerialServer`] with the builder's path as pipe name
+    ///
+    /// If all pipe instances are busy, this waits for one up to the builder's timeout.
+    ///
+    /// ## Errors
+    ///
+    /// * `NoDevice` if no server has created the pipe.
+    /// * `Busy` if no pipe instance became available in time.
+    /// * `Io` for any other error while connecting.
+    pub fn open(builder: &SerialPortBuilder) -> Result<Self> {
+        let name = to_wide(&builder.path);
+        let connect = || unsafe {
+            CreateFileW(
+                name.as_ptr(),
+                GENERIC_READ | GENERIC_WRITE,
+                0,
+                ptr::null_mut(),
+                OPEN_EXISTING,
+                FILE_ATTRIBUTE_NORMAL | FILE_FLAG_OVERLAPPED,
+                0 as HANDLE,
+            )
+        };
+
+        let mut handle = connect();
+        if handle == INVALID_HANDLE_VALUE
+            && unsafe { GetLastError() } == ERROR_PIPE_BUSY
+            && unsafe { WaitNamedPipeW(name.as_ptr(), COMPort::timeout_constant(builder.timeout)) }
+                != 0
+        {
+            handle = connect();
+        }
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(super::error::last_os_error());
+        }
+
+        Ok(PipeSerialPort {
+            handle,
+            read_overlapped: Mutex::new(None),
+            write_overlapped: Mutex::new(None),
+            port_name: builder.path.clone(),
+            baud_rate: builder.baud_rate,
+            data_bits: builder.data_bits,
+            flow_control: builder.flow_control,
+            parity: builder.parity,
+            stop_bits: builder.stop_bits,
+            timeout: builder.timeout,
+            stats: PortStats::default(),
+        })
+    }
+
+    /// Attempts to clone the `PipeSerialPort`
+    ///
+    /// Both instances share the connection, but each of them keeps its own settings.
+    pub fn try_clone_native(&self) -> Result<PipeSerialPort> {
+        let process = unsafe { GetCurrentProcess() };
+        let mut cloned: HANDLE = INVALID_HANDLE_VALUE;
+        let duplicated = unsafe {
+            DuplicateHandle(
+                process,
+                self.handle,
+                process,
+                &mut cloned,
+                0,
+                FALSE,
+                DUPLICATE_SAME_ACCESS,
+            )
+        };
+        if duplicated == 0 {
+            return Err(super::error::last_os_error());
+        }
+
+        Ok(PipeSerialPort {
+            handle: cloned,
+            read_overlapped: Mutex::new(None),
+            write_overlapped: Mutex::new(None),
+            port_name: self.port_name.clone(),
+            stats: PortStats::default(),
+            ..*self
+        })
+    }
+
+    /// Runs the overlapped operation started by `start`, aborting it after the port's timeout
+    fn transfer(
+        &self,
+        cache: &Mutex<Option<Overlapped>>,
+        start: impl FnOnce(LPOVERLAPPED) -> BOOL,
+    ) -> io::Result<usize> {
+        overlapped::with_cached(cache, |overlapped| {
+            let started = start(overlapped.start());
+            if started == 0 && unsafe { GetLastError() } != ERROR_IO_PENDING {
+                return Err(super::error::last_io_error());
+            }
+
+            let timeout = COMPort::timeout_constant(self.timeout);
+            let timed_out = started == 0 && !overlapped.is_complete_within(timeout, &[]);
+            if timed_out {
+                // The aborted operation still completes and has to be waited for below.
+                overlapped.cancel(self.handle);
+            }
+
+            match overlapped.wait(self.handle, TRUE, &[]) {
+                Ok(0) | Err(_) if timed_out => {
+                    Err(Error::new(ErrorKind::Timeout, "Operation timed out").into())
+                }
+                result => result.map(|len| len as usize),
+            }
+        })
+    }
+
+    fn read_handle(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.transfer(&self.read_overlapped, |overlapped| unsafe {
+            ReadFile(
+                self.handle,
+                buf.as_mut_ptr() as LPVOID,
+                buf.len() as DWORD,
+                ptr::null_mut(),
+                overlapped,
+            )
+        });
+        match result {
+            // The server closed its end, like an unplugged device.
+            Err(e) if e.raw_os_error() == Some(ERROR_BROKEN_PIPE as i32) => {
+                Err(Error::new(ErrorKind::NoDevice, "device has been disconnected").into())
+            }
+            result => result,
+        }
+    }
+
+    fn write_handle(&self, buf: &[u8]) -> io::Result<usize> {
+        self.transfer(&self.write_overlapped, |overlapped| unsafe {
+            WriteFile(
+                self.handle,
+                buf.as_ptr() as LPVOID,
+                buf.len() as DWORD,
+                ptr::null_mut(),
+                overlapped,
+            )
+        })
+    }
+
+    /// Returns the number of bytes waiting in the pipe, failing if the server has gone away
+    fn peek(&self) -> Result<u32> {
+        let mut available: DWORD = 0;
+        let peeked = unsafe {
+            PeekNamedPipe(
+                self.handle,
+                ptr::null_mut(),
+                0,
+                ptr::null_mut(),
+                &mut available,
+                ptr::null_mut(),
+            )
+        };
+        if peeked == 0 {
+            return Err(super::error::last_os_error());
+        }
+        Ok(available)
+    }
+}
+
+impl Drop for PipeSerialPort {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+impl AsRawHandle for PipeSerialPort {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.handle as RawHandle
+    }
+}
+
+impl io::Read for PipeSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.read_handle(buf);
+        self.stats.count_read(&result);
+        result
+    }
+}
+
+impl io::Write for PipeSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.write_handle(buf);
+        self.stats.count_write(&result);
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Writes complete once the data is in the pipe.
+        Ok(())
+    }
+}
+
+impl SerialPort for PipeSerialPort {
+    fn name(&self) -> Option<String> {
+        Some(self.port_name.clone())
+    }
+
+    fn baud_rate(&self) -> Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> Result<DataBits> {
+        Ok(self.data_bits)
+    }
+
+    fn flow_control(&self) -> Result<FlowControl> {
+        Ok(self.flow_control)
+    }
+
+    fn parity(&self) -> Result<Parity> {
+        Ok(self.parity)
+    }
+
+    fn stop_bits(&self) -> Result<StopBits> {
+        Ok(self.stop_bits)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> Result<()> {
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<()> {
+        self.data_bits = data_bits;
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> Result<()> {
+        self.flow_control = flow_control;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<()> {
+        self.parity = parity;
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<()> {
+        self.stop_bits = stop_bits;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> Result<u32> {
+        self.peek()
+    }
+
+    fn bytes_to_write(&self) -> Result<u32> {
+        // Written data is handed over to the server right away.
+        Ok(0)
+    }
+
+    fn stats(&self) -> Result<PortStats> {
+        Ok(self.stats)
+    }
+
+    fn is_connected(&self) -> Result<bool> {
+        Ok(self.peek().is_ok())
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> Result<()> {
+        if let ClearBuffer::Input | ClearBuffer::All = buffer_to_clear {
+            let mut buf = [0u8; 256];
+            let mut pending = self.bytes_to_read()? as usize;
+            while pending > 0 {
+                let n = self.read_handle(&mut buf[..pending.min(256)])?;
+                if n == 0 {
+                    break;
+                }
+                pending -= n;
+            }
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.try_clone_native()?))
+    }
+
+    fn set_break(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+//! Waiting for received data on several COM ports at once
+
+use std::mem::MaybeUninit;
+use std::time::Duration;
+
+use winapi::shared::minwindef::{DWORD, FALSE, TRUE};
+use winapi::shared::winerror::ERROR_IO_PENDING;
+use winapi::um::commapi::{ClearCommError, WaitCommEvent};
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::synchapi::WaitForMultipleObjects;
+use winapi::um::winnt::{HANDLE, MAXIMUM_WAIT_OBJECTS};
+
+use super::com::EV_RXCHAR;
+use super::overlapped::Overlapped;
+use crate::{Error, ErrorKind, Result};
+
+/// A port waited for by a `Selector` or a `Registration`, with the structure for its pending
+/// `WaitCommEvent()`
+///
+/// The system writes to the structure and the events until the wait is finished, so the waiter
+/// must not move in the meantime.
+#[derive(Debug)]
+pub(crate) struct Waiter {
+    handle: HANDLE,
+    overlapped: Overlapped,
+    /// The events reported by `WaitCommEvent()`
+    events: DWORD,
+    /// Whether a wait has been started but not finished yet
+    pending: bool,
+}
+
+impl Waiter {
+    pub(crate) fn new(handle: HANDLE) -> Result<Waiter> {
+        Ok(Waiter {
+            handle,
+            overlapped: Overlapped::new()?,
+            events: 0,
+            pending: false,
+        })
+    }
+
+    /// Starts waiting for received data, returning whether there already is some
+    pub(crate) fn start(&mut self) -> Result<bool> {
+        // Data received before starting to wait doesn't trigger the event.
+        let mut errors: DWORD = 0;
+        let mut status = MaybeUninit::uninit();
+        if unsafe { ClearCommError(self.handle, &mut errors, status.as_mut_ptr()) } != 0
+            && unsafe { status.assume_init() }.cbInQue > 0
+        {
+            return Ok(true);
+        }
+
+        self.events = 0;
+        let started =
+            unsafe { WaitCommEvent(self.handle, &mut self.events, self.overlapped.start()) };
+        if started != 0 {
+            return Ok(self.events & EV_RXCHAR != 0);
+        }
+        if unsafe { GetLastError() } != ERROR_IO_PENDING {
+            return Err(super::error::last_os_error());
+        }
+        self.pending = true;
+        Ok(false)
+    }
+
+    /// Finishes the started wait, aborting it if it is still pending, and returns whether data
+    /// has been received
+    pub(crate) fn finish(&mut self) -> bool {
+        if !self.pending {
+            return false;
+        }
+        self.pending = false;
+        // Aborting a completed wait has no effect. Checking the event first doesn't work, as it
+        // may have been signaled without completing the wait.
+        self.overlapped.cancel(self.handle);
+        let completed = self.overlapped.wait(self.handle, TRUE, &[]).is_ok();
+        completed && self.events & EV_RXCHAR != 0
+    }
+
+    /// Signals the event of the waiter without receiving data
+    pub(crate) fn signal(&self) {
+        self.overlapped.signal();
+    }
+
+    /// Resets the event of the waiter after finishing the wait
+    pub(crate) fn reset(&self) {
+        self.overlapped.reset();
+    }
+
+    /// Returns the event signaled when data has been received
+    pub(crate) fn event(&self) -> HANDLE {
+        self.overlapped.event()
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Waits up to `timeout` for any of `waiters` to receive data and returns the indices of the
+/// ready ones
+pub(crate) fn select_readable(waiters: &mut [Waiter], timeout: Duration) -> Result<Vec<usize>> {
+    if waiters.len() > MAXIMUM_WAIT_OBJECTS as usize {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("cannot wait for more than {} ports", MAXIMUM_WAIT_OBJECTS),
+        ));
+    }
+
+    let mut ready = Vec::new();
+    let mut pending = Vec::new();
+    let mut error = None;
+    for (index, waiter) in waiters.iter_mut().enumerate() {
+        match waiter.start() {
+            Ok(true) => ready.push(index),
+            Ok(false) => pending.push(index),
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    if ready.is_empty() && error.is_none() && !pending.is_empty() {
+        let events: Vec<HANDLE> = pending
+            .iter()
+            .map(|&index| waiters[index].event())
+            .collect();
+        let timeout = super::COMPort::timeout_constant(timeout);
+        unsafe { WaitForMultipleObjects(events.len() as DWORD, events.as_ptr(), FALSE, timeout) };
+    }
+
+    // All started waits have to be finished before returning, also after an error.
+    for index in pending {
+        if waiters[index].finish() {
+            ready.push(index);
+        }
+    }
+    if let Some(error) = error {
+        return Err(error);
+    }
+    if ready.is_empty() {
+        return Err(Error::new(ErrorKind::Timeout, "Operation timed out"));
+    }
+    ready.sort_unstable();
+    Ok(ready)
+}
@@ -0,0 +1,183 @@
+//! Writing frames to a serial port from a background thread with priority lanes.
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use crate::{Error, ErrorKind, Result, SerialPort};
+
+/// Priority class of a frame queued for a [`BackgroundWriter`]
+///
+/// Frames of a higher priority are written before any queued frame of a lower priority. A frame
+/// which is already being written is never interrupted, so an urgent frame preempts queued data
+/// at the next frame boundary.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WritePriority {
+    /// Urgent control traffic like emergency stop commands.
+    High,
+    /// Regular traffic.
+    Normal,
+    /// Bulk data which may be delayed arbitrarily by other traffic.
+    Low,
+}
+
+impl WritePriority {
+    const COUNT: usize = 3;
+
+    fn lane(self) -> usize {
+        match self {
+            WritePriority::High => 0,
+            WritePriority::Normal => 1,
+            WritePriority::Low => 2,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    lanes: [VecDeque<Vec<u8>>; WritePriority::COUNT],
+    error: Option<Error>,
+    shutdown: bool,
+}
+
+impl State {
+    fn next_frame(&mut self) -> Option<Vec<u8>> {
+        self.lanes.iter_mut().find_map(|lane| lane.pop_front())
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    condvar: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Writes frames to a serial port from a background thread
+///
+/// Frames are queued with [`send`](BackgroundWriter::send) and written in order of their
+/// [`WritePriority`]. Frames of the same priority are written in the order they were queued.
+///
+/// Dropping the writer waits for all queued frames to be written. If a write times out after
+/// that, the frame being written and all queued ones are discarded instead.
+///
+/// ```no_run
+/// use serialport::{BackgroundWriter, WritePriority};
+///
+/// let port = serialport::new("/dev/ttyUSB0", 115_200).open().expect("Failed to open port");
+/// let writer = BackgroundWriter::new(port);
+///
+/// writer.send(vec![0u8; 4096], WritePriority::Low).unwrap();
+/// writer.send(b"STOP\r\n".to_vec(), WritePriority::High).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct BackgroundWriter {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl BackgroundWriter {
+    /// Starts writing to `port` from a background thread
+    pub fn new(port: Box<dyn SerialPort>) -> Self {
+        let shared = Arc::new(Shared::default());
+        let thread_shared = shared.clone();
+        let thread = thread::spawn(move || write_loop(port, &thread_shared));
+
+        BackgroundWriter {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    /// Queues `frame` for being written with the given priority
+    ///
+    /// ## Errors
+    ///
+    /// Returns the error which occurred while writing a previously queued frame. The frames
+    /// which were queued at this point are discarded.
+    pub fn send(&self, frame: Vec<u8>, priority: WritePriority) -> Result<()> {
+        let mut state = self.shared.lock();
+        if let Some(error) = state.error.take() {
+            return Err(error);
+        }
+        if state.shutdown {
+            return Err(Error::new(
+                ErrorKind::BrokenPipe,
+                "background writer has stopped",
+            ));
+        }
+
+        state.lanes[priority.lane()].push_back(frame);
+        self.shared.condvar.notify_one();
+        Ok(())
+    }
+
+    /// Returns the number of frames waiting to be written
+    pub fn pending(&self) -> usize {
+        self.shared.lock().lanes.iter().map(VecDeque::len).sum()
+    }
+}
+
+impl Drop for BackgroundWriter {
+    fn drop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.condvar.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn write_loop(mut port: Box<dyn SerialPort>, shared: &Shared) {
+    loop {
+        let frame = {
+            let mut state = shared.lock();
+            loop {
+                if let Some(frame) = state.next_frame() {
+                    break frame;
+                }
+                if state.shutdown {
+                    return;
+                }
+                state = shared
+                    .condvar
+                    .wait(state)
+                    .unwrap_or_else(|e| e.into_inner());
+            }
+        };
+
+        if let Err(error) = write_frame(&mut port, &frame, shared) {
+            let mut state = shared.lock();
+            for lane in state.lanes.iter_mut() {
+                lane.clear();
+            }
+            state.error = Some(error);
+        }
+    }
+}
+
+/// Writes a complete frame. Timeouts only indicate that the receiving side is applying
+/// backpressure and are retried until the writer gets shut down.
+fn write_frame(port: &mut Box<dyn SerialPort>, mut frame: &[u8], shared: &Shared) -> Result<()> {
+    while !frame.is_empty() {
+        match port.write(frame) {
+            Ok(0) => {
+                return Err(Error::new(
+                    ErrorKind::Io(io::ErrorKind::WriteZero),
+                    "failed to write frame",
+                ))
+            }
+            Ok(n) => frame = &frame[n..],
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut && !shared.lock().shutdown => {}
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Tests for bridging serial ports to TCP connections.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use serialport::bridge::Bridge;
+use serialport::mock::MockPort;
+use serialport::SerialPort;
+
+fn exchange(client: &mut TcpStream, device: &mut MockPort) {
+    client.write_all(b"AT\r").unwrap();
+    let mut request = [0u8; 3];
+    device.read_exact(&mut request).unwrap();
+    assert_eq!(&request, b"AT\r");
+
+    device.write_all(b"OK\r\n").unwrap();
+    let mut response = [0u8; 4];
+    client.read_exact(&mut response).unwrap();
+    assert_eq!(&response, b"OK\r\n");
+}
+
+#[test]
+fn test_bridge_listen() {
+    let (port, mut device) = MockPort::pair();
+    device.set_timeout(Duration::from_secs(1)).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let mut bridge = Bridge::new(Box::new(port)).unwrap();
+    let stop = bridge.stop_handle();
+    let bridge = thread::spawn(move || bridge.listen(&listener));
+
+    // Clients are served one after another.
+    for _ in 0..2 {
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        exchange(&mut client, &mut device);
+    }
+
+    stop.stop();
+    bridge.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_bridge_connect_reconnects() {
+    let (port, mut device) = MockPort::pair();
+    device.set_timeout(Duration::from_secs(1)).unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let mut bridge = Bridge::new(Box::new(port))
+        .unwrap()
+        .reconnect_delay(Duration::from_millis(10));
+    let stop = bridge.stop_handle();
+    let bridge = thread::spawn(move || bridge.connect(address));
+
+    for _ in 0..2 {
+        let (mut server, _) = listener.accept().unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(1)))
+            .unwrap();
+        exchange(&mut server, &mut device);
+    }
+
+    stop.stop();
+    bridge.join().unwrap().unwrap();
+}
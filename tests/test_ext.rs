@@ -0,0 +1,146 @@
+//! Tests for the `SerialPortExt` trait.
+#![cfg(unix)]
+
+extern crate serialport;
+
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+
+use serialport::{
+    ErrorKind, PipeSerialPort, PipeSerialServer, SerialPort, SerialPortExt, StopBits, TTYPort,
+};
+
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+#[test]
+fn test_read_write_words() {
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    slave.write_u16_le(0x0102).unwrap();
+    slave.write_u16_be(0x0102).unwrap();
+    slave.write_u32_le(0x0102_0304).unwrap();
+    slave.write_u32_be(0x0102_0304).unwrap();
+
+    assert_eq!(master.read_u16_be(TIMEOUT).unwrap(), 0x0201);
+    assert_eq!(master.read_u16_le(TIMEOUT).unwrap(), 0x0201);
+    assert_eq!(master.read_u32_be(TIMEOUT).unwrap(), 0x0403_0201);
+    assert_eq!(master.read_u8_timeout(TIMEOUT).unwrap(), 0x01);
+    assert_eq!(master.read_u8_timeout(TIMEOUT).unwrap(), 0x02);
+    assert_eq!(master.read_u16_le(TIMEOUT).unwrap(), 0x0403);
+
+    // The port's timeout is left untouched.
+    assert_eq!(master.timeout(), Duration::from_millis(100));
+}
+
+#[test]
+fn test_read_exact_timeout_assembles_chunks() {
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let sender = thread::spawn(move || {
+        slave.write_all(&[1, 2]).unwrap();
+        thread::sleep(Duration::from_millis(50));
+        slave.write_all(&[3, 4]).unwrap();
+        slave
+    });
+
+    let mut buf = [0u8; 4];
+    master.read_exact_timeout(&mut buf, TIMEOUT).unwrap();
+    assert_eq!(buf, [1, 2, 3, 4]);
+
+    let _slave = sender.join().unwrap();
+}
+
+#[test]
+fn test_read_exact_timeout_times_out() {
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    slave.write_all(&[1]).unwrap();
+    let result = master.read_u16_le(Duration::from_millis(100));
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::Timeout);
+}
+
+#[test]
+fn test_write_words_timeout() {
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    slave.write_u16_le_timeout(0x0102, TIMEOUT).unwrap();
+    slave.write_u16_be_timeout(0x0102, TIMEOUT).unwrap();
+    slave.write_u32_le_timeout(0x0102_0304, TIMEOUT).unwrap();
+    slave.write_u32_be_timeout(0x0102_0304, TIMEOUT).unwrap();
+
+    let mut buf = [0u8; 12];
+    master.read_exact_timeout(&mut buf, TIMEOUT).unwrap();
+    assert_eq!(buf, [2, 1, 1, 2, 4, 3, 2, 1, 1, 2, 3, 4]);
+
+    // The port's timeout is left untouched.
+    assert_eq!(slave.timeout(), Duration::from_millis(100));
+}
+
+#[test]
+fn test_wait_for_carrier_with_carrier_present() {
+    let path = std::env::temp_dir().join(format!("serialport-carrier-{}", std::process::id()));
+    let _server = PipeSerialServer::bind(&path).unwrap();
+    let mut port = PipeSerialPort::open(&serialport::new(path.to_string_lossy(), 9600)).unwrap();
+
+    // Emulated ports always report a carrier.
+    port.wait_for_carrier(Duration::ZERO).unwrap();
+}
+
+#[test]
+#[cfg_attr(any(target_os = "ios", target_os = "macos"), ignore)]
+fn test_modify() {
+    #![allow(unused_variables)]
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Linux pseudo terminals always use eight data bits without parity, so these settings are not
+    // covered here.
+    slave
+        .modify(|settings| {
+            settings.baud_rate = 57600;
+            settings.stop_bits = StopBits::Two;
+            settings.timeout = TIMEOUT;
+        })
+        .expect("Unable to modify settings");
+    assert_eq!(slave.baud_rate().unwrap(), 57600);
+    assert_eq!(slave.stop_bits().unwrap(), StopBits::Two);
+    assert_eq!(slave.timeout(), TIMEOUT);
+}
+
+#[test]
+fn test_read_to_spare() {
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(TIMEOUT).unwrap();
+
+    let mut buf = Vec::with_capacity(6);
+    buf.push(0);
+    slave.write_all(&[1, 2, 3]).unwrap();
+    let mut len = 0;
+    while len < 3 {
+        len += master.read_to_spare(&mut buf).unwrap();
+    }
+    assert_eq!(buf, [0, 1, 2, 3]);
+
+    // Nothing gets read without spare capacity.
+    slave.write_all(&[4]).unwrap();
+    let mut empty = Vec::new();
+    assert_eq!(master.read_to_spare(&mut empty).unwrap(), 0);
+    master.read_to_spare(&mut buf).unwrap();
+    assert_eq!(buf, [0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_read_uninit_default() {
+    use std::mem::MaybeUninit;
+
+    let path = std::env::temp_dir().join(format!("serialport-uninit-{}", std::process::id()));
+    let server = PipeSerialServer::bind(&path).unwrap();
+    let mut port = PipeSerialPort::open(&serialport::new(path.to_string_lossy(), 9600)).unwrap();
+    let mut peer = server.accept().unwrap();
+
+    // Ports without native support read into the zeroed buffer.
+    peer.write_all(b"ping").unwrap();
+    let mut buf = [MaybeUninit::uninit(); 8];
+    let read = port.read_uninit(&mut buf).unwrap();
+    assert_eq!(read, b"ping");
+}
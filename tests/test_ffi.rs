@@ -0,0 +1,132 @@
+//! Tests for the C API.
+#![cfg(all(unix, feature = "ffi"))]
+
+use std::ffi::{CStr, CString};
+use std::io::Write;
+use std::ptr;
+
+use serialport::ffi::*;
+use serialport::{SerialPort, TTYPort};
+
+fn last_error() -> String {
+    unsafe { CStr::from_ptr(serialport_last_error()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[test]
+fn test_ffi_transfer() {
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let path = CString::new(slave.name().unwrap()).unwrap();
+
+    // A baud rate of 0 skips setting DTR, which pseudo terminals don't support.
+    let mut port = ptr::null_mut();
+    assert_eq!(
+        unsafe { serialport_open(path.as_ptr(), 0, &mut port) },
+        SERIALPORT_OK,
+        "{}",
+        last_error()
+    );
+    assert!(!port.is_null());
+
+    let mut config = SerialPortConfig {
+        baud_rate: 0,
+        data_bits: 0,
+        parity: 0,
+        stop_bits: 0,
+        flow_control: 0,
+        timeout_ms: 0,
+    };
+    assert_eq!(
+        unsafe { serialport_get_config(port, &mut config) },
+        SERIALPORT_OK
+    );
+    assert_eq!(config.data_bits, 8);
+    assert_eq!(config.stop_bits, 1);
+
+    config.baud_rate = 115_200;
+    config.timeout_ms = 50;
+    assert_eq!(
+        unsafe { serialport_set_config(port, &config) },
+        SERIALPORT_OK
+    );
+    let mut applied = config;
+    applied.baud_rate = 0;
+    assert_eq!(
+        unsafe { serialport_get_config(port, &mut applied) },
+        SERIALPORT_OK
+    );
+    assert_eq!(applied, config);
+
+    let mut buf = [0u8; 8];
+    let mut read = 0;
+    assert_eq!(
+        unsafe { serialport_read(port, buf.as_mut_ptr(), buf.len(), &mut read) },
+        SERIALPORT_ERROR_TIMED_OUT
+    );
+
+    master.write_all(b"ping").unwrap();
+    assert_eq!(
+        unsafe { serialport_read(port, buf.as_mut_ptr(), buf.len(), &mut read) },
+        SERIALPORT_OK
+    );
+    assert_eq!(&buf[..read], b"ping");
+
+    let mut written = 0;
+    assert_eq!(
+        unsafe { serialport_write(port, b"pong".as_ptr(), 4, &mut written) },
+        SERIALPORT_OK
+    );
+    assert_eq!(written, 4);
+
+    config.parity = 42;
+    assert_eq!(
+        unsafe { serialport_set_config(port, &config) },
+        SERIALPORT_ERROR_INVALID_INPUT
+    );
+    assert_eq!(last_error(), "invalid parity");
+
+    unsafe { serialport_close(port) };
+}
+
+#[test]
+fn test_ffi_errors() {
+    let path = CString::new("/dev/does-not-exist").unwrap();
+    let mut port = ptr::null_mut();
+    assert_ne!(
+        unsafe { serialport_open(path.as_ptr(), 9600, &mut port) },
+        SERIALPORT_OK
+    );
+    assert!(port.is_null());
+    assert!(!last_error().is_empty());
+
+    assert_eq!(
+        unsafe { serialport_flush(ptr::null_mut()) },
+        SERIALPORT_ERROR_INVALID_INPUT
+    );
+    assert_eq!(last_error(), "unexpected null pointer");
+
+    let name = unsafe { CStr::from_ptr(serialport_error_name(SERIALPORT_ERROR_TIMED_OUT)) };
+    assert_eq!(name.to_str().unwrap(), "operation timed out");
+}
+
+#[test]
+fn test_ffi_list_ports() {
+    let mut list = ptr::null_mut();
+    assert_eq!(unsafe { serialport_list_ports(&mut list) }, SERIALPORT_OK);
+
+    let ports = serialport::available_ports().unwrap();
+    let len = unsafe { serialport_list_len(list) };
+    assert_eq!(len, ports.len());
+    for (index, info) in ports.iter().enumerate() {
+        let name = unsafe { CStr::from_ptr(serialport_list_name(list, index)) };
+        assert_eq!(name.to_str().unwrap(), info.port_name);
+    }
+    assert!(unsafe { serialport_list_name(list, len) }.is_null());
+    assert_eq!(
+        unsafe { serialport_list_type(list, len) },
+        SERIALPORT_TYPE_UNKNOWN
+    );
+
+    unsafe { serialport_list_free(list) };
+}
@@ -0,0 +1,79 @@
+//! Tests for logging the traffic of serial ports.
+#![cfg(feature = "log")]
+
+use std::io::{Read, Write};
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serialport::logged::LoggedPort;
+use serialport::mock::MockPort;
+use serialport::SerialPort;
+
+struct TestLogger(Mutex<Vec<(Level, String)>>);
+
+impl Log for TestLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        assert_eq!(record.target(), "serialport::logged");
+        self.0
+            .lock()
+            .unwrap()
+            .push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+impl TestLogger {
+    fn take_messages(&self) -> Vec<(Level, String)> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+// Everything is tested in a single test as the logger is global.
+#[test]
+fn test_logged_port() {
+    let logger: &'static TestLogger = Box::leak(Box::new(TestLogger(Mutex::new(Vec::new()))));
+    log::set_logger(logger).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let (app, mut device) = MockPort::pair();
+    let mut port = LoggedPort::new(app);
+    port.write_all(b"AT\r").unwrap();
+    port.write_request_to_send(true).unwrap();
+    assert_eq!(
+        logger.take_messages(),
+        vec![
+            (
+                Level::Trace,
+                "mock0: wrote 3 bytes\n0000: 41 54 0d                                         AT."
+                    .to_string()
+            ),
+            (Level::Debug, "mock0: set RTS to true".to_string()),
+        ]
+    );
+
+    let mut port = port
+        .data_level(Level::Info)
+        .control_level(Level::Warn)
+        .redact(true);
+    device.write_all(b"secret").unwrap();
+    let mut buf = [0u8; 6];
+    port.read_exact(&mut buf).unwrap();
+    assert!(port.read_clear_to_send().unwrap());
+    assert!(port.read(&mut buf).is_err());
+    assert_eq!(
+        logger.take_messages(),
+        vec![
+            (Level::Info, "mock0: read 6 bytes (redacted)".to_string()),
+            (Level::Warn, "mock0: read CTS: true".to_string()),
+            (
+                Level::Info,
+                "mock0: read failed: Operation timed out".to_string()
+            ),
+        ]
+    );
+}
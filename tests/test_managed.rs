@@ -0,0 +1,123 @@
+//! Tests for the `ManagedPort` struct.
+#![cfg(unix)]
+
+extern crate serialport;
+
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use serialport::{ErrorKind, ManagedPort, PortState, SerialPort, Setting, TTYPort};
+
+#[test]
+fn test_managed_port_detects_hangup() {
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let slave_name = slave.name().expect("Pseudo terminal has no name");
+
+    // Pseudo terminals are indicated by a baud rate of 0.
+    let mut port =
+        ManagedPort::open(serialport::new(slave_name, 0).timeout(Duration::from_secs(1)))
+            .expect("Unable to open managed port");
+    assert_eq!(port.state(), PortState::Open);
+
+    master.write_all(b"ping").unwrap();
+    let mut buf = [0u8; 4];
+    port.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+
+    drop(master);
+    drop(slave);
+    assert!(port.read(&mut buf).is_err());
+    assert_eq!(port.state(), PortState::Disconnected);
+
+    // Once disconnected, operations fail right away.
+    let start = Instant::now();
+    assert_eq!(
+        port.bytes_to_read().unwrap_err().kind(),
+        ErrorKind::NoDevice
+    );
+    assert!(port.write(b"ping").is_err());
+    assert!(start.elapsed() < Duration::from_millis(500));
+}
+
+#[test]
+fn test_managed_port_not_open() {
+    let mut port = ManagedPort::new(serialport::new("/dev/null", 9600));
+    assert_eq!(port.state(), PortState::Configured);
+    assert_eq!(
+        port.set_baud_rate(19200).unwrap_err().kind(),
+        ErrorKind::NoDevice
+    );
+}
+
+#[test]
+fn test_managed_port_settings_lossy() {
+    let port = ManagedPort::new(serialport::new("/dev/null", 9600).timeout(Duration::from_secs(2)));
+    assert_eq!(port.try_settings().unwrap_err().kind(), ErrorKind::NoDevice);
+
+    // Only the timeout is known without opening the port.
+    let lossy = port.settings_lossy();
+    assert_eq!(lossy.settings.baud_rate, 0);
+    assert_eq!(lossy.settings.timeout, Duration::from_secs(2));
+    assert_eq!(
+        lossy.unknown,
+        vec![
+            Setting::BaudRate,
+            Setting::DataBits,
+            Setting::FlowControl,
+            Setting::Parity,
+            Setting::StopBits,
+            Setting::InterByteTimeout,
+        ]
+    );
+}
+
+#[test]
+fn test_managed_port_keeps_name_of_non_usb_port() {
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let slave_name = slave.name().expect("Pseudo terminal has no name");
+
+    let mut port = ManagedPort::new(serialport::new(slave_name.clone(), 0));
+    let renames = port.subscribe_renames();
+    port.recover().expect("Unable to open managed port");
+    port.recover().expect("Unable to reopen managed port");
+
+    // Pseudo terminals have no USB identity to follow.
+    assert_eq!(port.identity(), None);
+    assert_eq!(port.name(), Some(slave_name));
+    assert!(renames.try_recv().is_err());
+    drop(master);
+}
+
+#[test]
+fn test_managed_port_clone_shares_name() {
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let slave_name = slave.name().expect("Pseudo terminal has no name");
+
+    let port =
+        ManagedPort::open(serialport::new(slave_name.clone(), 0).timeout(Duration::from_secs(1)))
+            .expect("Unable to open managed port");
+    let mut clone = port.try_clone().expect("Unable to clone managed port");
+    assert_eq!(clone.name(), Some(slave_name));
+
+    master.write_all(b"ping").unwrap();
+    let mut buf = [0u8; 4];
+    clone.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+}
+
+#[test]
+fn test_managed_port_forwards_stats() {
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let slave_name = slave.name().expect("Pseudo terminal has no name");
+
+    let mut port =
+        ManagedPort::open(serialport::new(slave_name, 0).timeout(Duration::from_secs(1)))
+            .expect("Unable to open managed port");
+    port.write_all(b"ping").unwrap();
+    let mut buf = [0u8; 4];
+    master.read_exact(&mut buf).unwrap();
+
+    let stats = port.stats().unwrap();
+    assert_eq!(stats.bytes_written, 4);
+    assert_eq!(stats.bytes_read, 0);
+}
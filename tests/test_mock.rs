@@ -0,0 +1,274 @@
+//! Tests for the in-memory serial ports.
+
+use std::io::{ErrorKind as IoErrorKind, Read, Write};
+use std::thread;
+use std::time::Duration;
+
+use serialport::mock::{Fault, FaultyPort, MockPort, Script};
+use serialport::{CancelToken, ClearBuffer, ErrorKind, PortStats, SerialPort, SerialPortExt};
+
+#[test]
+fn test_mock_pair_round_trip() {
+    let (mut app, mut device) = MockPort::pair();
+    app.set_timeout(Duration::from_millis(100)).unwrap();
+    device.set_timeout(Duration::from_millis(100)).unwrap();
+
+    app.write_all(b"AT\r").unwrap();
+    assert_eq!(device.bytes_to_read().unwrap(), 3);
+    let mut request = [0u8; 3];
+    device.read_exact(&mut request).unwrap();
+    assert_eq!(&request, b"AT\r");
+
+    device.write_all(b"OK\r\n").unwrap();
+    let mut response = [0u8; 4];
+    app.read_exact(&mut response).unwrap();
+    assert_eq!(&response, b"OK\r\n");
+}
+
+#[test]
+fn test_mock_read_times_out() {
+    let mut port = MockPort::new();
+    port.set_timeout(Duration::from_millis(10)).unwrap();
+
+    let mut buf = [0u8; 1];
+    assert_eq!(
+        port.read(&mut buf).unwrap_err().kind(),
+        IoErrorKind::TimedOut
+    );
+}
+
+#[test]
+fn test_mock_read_wakes_up_on_data() {
+    let (mut app, device) = MockPort::pair();
+    app.set_timeout(Duration::from_secs(5)).unwrap();
+
+    let writer = thread::spawn(move || {
+        let mut device = device;
+        thread::sleep(Duration::from_millis(10));
+        device.write_all(&[0x42]).unwrap();
+    });
+
+    assert_eq!(app.read_u8_timeout(Duration::from_secs(5)).unwrap(), 0x42);
+    writer.join().unwrap();
+}
+
+#[test]
+fn test_mock_input_and_output() {
+    let mut port = MockPort::new();
+
+    port.push_input(b"hello");
+    port.clear(ClearBuffer::Input).unwrap();
+    assert_eq!(port.bytes_to_read().unwrap(), 0);
+
+    port.push_input(b"hello");
+    let mut buf = [0u8; 5];
+    port.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+
+    port.write_all(b"world").unwrap();
+    assert_eq!(port.take_output(), b"world");
+    assert!(port.take_output().is_empty());
+}
+
+#[test]
+fn test_mock_modem_lines() {
+    let (mut app, mut device) = MockPort::pair();
+    assert!(app.read_clear_to_send().unwrap());
+    assert!(app.read_data_set_ready().unwrap());
+    assert!(app.read_carrier_detect().unwrap());
+    assert!(!app.read_ring_indicator().unwrap());
+
+    device.write_request_to_send(false).unwrap();
+    device.write_data_terminal_ready(false).unwrap();
+    assert!(!device.request_to_send());
+    assert!(!device.data_terminal_ready());
+    assert!(!app.read_clear_to_send().unwrap());
+    assert!(!app.read_data_set_ready().unwrap());
+    assert!(!app.read_carrier_detect().unwrap());
+
+    app.set_ring_indicator(true);
+    assert!(app.read_ring_indicator().unwrap());
+
+    app.set_break().unwrap();
+    assert!(app.break_state());
+    app.clear_break().unwrap();
+    assert!(!app.break_state());
+}
+
+#[test]
+fn test_mock_clone_shares_buffers() {
+    let (mut app, mut device) = MockPort::pair();
+    let mut clone = app.try_clone().unwrap();
+    clone.set_baud_rate(115_200).unwrap();
+    assert_eq!(app.baud_rate().unwrap(), 9600);
+
+    clone.write_all(b"x").unwrap();
+    let mut buf = [0u8; 1];
+    device.read_exact(&mut buf).unwrap();
+    device.write_all(b"y").unwrap();
+    app.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"y");
+}
+
+#[test]
+fn test_faulty_port_injects_faults() {
+    let (app, mut device) = MockPort::pair();
+    let mut port = FaultyPort::new(app);
+    port.inject_write(0, Fault::Partial(2));
+    port.inject_write(1, Fault::Interrupted);
+
+    assert_eq!(port.write(b"hello").unwrap(), 2);
+    assert_eq!(
+        port.write(b"llo").unwrap_err().kind(),
+        IoErrorKind::Interrupted
+    );
+    port.write_all(b"llo").unwrap();
+    assert_eq!(port.writes(), 3);
+    let mut buf = [0u8; 5];
+    device.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+}
+
+#[test]
+fn test_faulty_port_disconnects() {
+    let port = MockPort::new();
+    port.push_input(b"abc");
+    let mut port = FaultyPort::new(port);
+    port.inject_read(1, Fault::Disconnect);
+
+    let mut buf = [0u8; 1];
+    assert_eq!(port.read(&mut buf).unwrap(), 1);
+    assert_eq!(
+        port.read(&mut buf).unwrap_err().kind(),
+        IoErrorKind::NotFound
+    );
+    assert_eq!(
+        port.read(&mut buf).unwrap_err().kind(),
+        IoErrorKind::NotFound
+    );
+    assert_eq!(
+        port.read_carrier_detect().unwrap_err().kind(),
+        ErrorKind::NoDevice
+    );
+    assert_eq!(port.reads(), 2);
+}
+
+#[test]
+fn test_faulty_port_forwards_to_inner_port() {
+    let port = MockPort::new();
+    port.push_input(b"abc");
+    let mut port = FaultyPort::new(port);
+    port.inject_read(0, Fault::Partial(1));
+
+    // Cancellable reads count as reads and get the injected faults.
+    let cancel = CancelToken::new().unwrap();
+    let mut buf = [0u8; 3];
+    assert_eq!(port.read_cancellable(&mut buf, &cancel).unwrap(), 1);
+    assert_eq!(port.reads(), 1);
+    assert!(port.is_connected().unwrap());
+    assert_eq!(port.stats().unwrap().bytes_read, 1);
+
+    port.disconnect();
+    assert!(!port.is_connected().unwrap());
+    assert_eq!(port.stats().unwrap_err().kind(), ErrorKind::NoDevice);
+    assert_eq!(
+        port.read_cancellable(&mut buf, &cancel).unwrap_err().kind(),
+        IoErrorKind::NotFound
+    );
+    assert_eq!(
+        port.wait_readable(Duration::ZERO).unwrap_err().kind(),
+        ErrorKind::NoDevice
+    );
+}
+
+#[test]
+fn test_script_replies_to_requests() {
+    let (mut app, device) = MockPort::pair();
+    let device = Script::new()
+        .expect(b"ATI\r")
+        .send(b"MockModem\r\n")
+        .expect(b"AT+CSQ\r")
+        .delay(Duration::from_millis(10))
+        .send(b"+CSQ: 20,0\r\n")
+        .spawn(device);
+
+    app.set_timeout(Duration::from_secs(1)).unwrap();
+    app.write_all(b"ATI\r").unwrap();
+    let mut response = [0u8; 11];
+    app.read_exact(&mut response).unwrap();
+    assert_eq!(&response, b"MockModem\r\n");
+
+    app.write_all(b"AT+CSQ\r").unwrap();
+    let mut response = [0u8; 12];
+    app.read_exact(&mut response).unwrap();
+    assert_eq!(&response, b"+CSQ: 20,0\r\n");
+
+    device.join().unwrap().unwrap();
+}
+
+#[test]
+fn test_script_reports_unexpected_data() {
+    let (mut app, device) = MockPort::pair();
+    let device = Script::new().expect(b"AT\r").send(b"OK\r\n").spawn(device);
+
+    app.write_all(b"XY\r").unwrap();
+    let err = device.join().unwrap().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Io(IoErrorKind::InvalidData));
+}
+
+#[test]
+fn test_script_times_out() {
+    let (_app, mut device) = MockPort::pair();
+    let err = Script::new()
+        .timeout(Duration::from_millis(10))
+        .expect(b"AT\r")
+        .run(&mut device)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Timeout);
+}
+
+#[test]
+fn test_mock_stats() {
+    let (mut app, mut device) = MockPort::pair();
+    app.set_timeout(Duration::from_millis(10)).unwrap();
+
+    app.write_all(b"AT\r").unwrap();
+    device.write_all(b"OK").unwrap();
+    let mut buf = [0u8; 4];
+    assert_eq!(app.read(&mut buf).unwrap(), 2);
+    assert!(app.read(&mut buf).is_err());
+
+    let stats = app.stats().unwrap();
+    assert_eq!(stats.bytes_written, 3);
+    assert_eq!(stats.writes, 1);
+    assert_eq!(stats.bytes_read, 2);
+    assert_eq!(stats.reads, 2);
+    assert_eq!(stats.timeouts, 1);
+    assert_eq!(stats.errors, 0);
+
+    let clone = app.try_clone_native().unwrap();
+    assert_eq!(clone.stats().unwrap(), PortStats::default());
+}
+
+#[test]
+fn test_mock_take_error() {
+    let (app, mut device) = MockPort::pair();
+    device.write_all(b"data").unwrap();
+    assert!(app.take_error().unwrap().is_none());
+}
+
+#[test]
+fn test_mock_to_builder() {
+    let mut port = MockPort::new();
+    port.set_baud_rate(115_200).unwrap();
+    port.set_parity(serialport::Parity::Odd).unwrap();
+    port.set_timeout(Duration::from_millis(50)).unwrap();
+
+    let builder = port.to_builder().unwrap();
+    assert_eq!(
+        builder,
+        serialport::new("mock", 115_200)
+            .parity(serialport::Parity::Odd)
+            .timeout(Duration::from_millis(50))
+    );
+}
@@ -0,0 +1,63 @@
+//! Tests for observing the activity of ports.
+#![cfg(unix)]
+
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+use serialport::{PortObserver, SerialPort, TTYPort};
+
+#[derive(Default)]
+struct Events(Mutex<Vec<String>>);
+
+impl Events {
+    fn push(&self, event: String) {
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl PortObserver for Events {
+    fn on_open(&self, _name: Option<&str>) {
+        self.push("open".to_string());
+    }
+
+    fn on_close(&self, _name: Option<&str>) {
+        self.push("close".to_string());
+    }
+
+    fn on_read(&self, _name: Option<&str>, data: &[u8]) {
+        self.push(format!("read {:?}", String::from_utf8_lossy(data)));
+    }
+
+    fn on_write(&self, _name: Option<&str>, data: &[u8]) {
+        self.push(format!("write {:?}", String::from_utf8_lossy(data)));
+    }
+}
+
+#[test]
+fn test_observing_ports() {
+    let events = Arc::new(Events::default());
+    let id = serialport::add_observer(events.clone());
+
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.write_all(b"ping").unwrap();
+    // Wait for all data to arrive for reading it at once.
+    while slave.bytes_to_read().unwrap() < 4 {
+        std::thread::yield_now();
+    }
+    let mut buf = [0u8; 4];
+    slave.read_exact(&mut buf).unwrap();
+    drop(master);
+    assert_eq!(
+        events.take(),
+        vec!["open", "open", "write \"ping\"", "read \"ping\"", "close"]
+    );
+
+    assert!(serialport::remove_observer(id));
+    assert!(!serialport::remove_observer(id));
+    drop(slave);
+    assert!(events.take().is_empty());
+}
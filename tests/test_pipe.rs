@@ -0,0 +1,104 @@
+//! Tests for the emulated serial ports.
+#![cfg(unix)]
+
+extern crate serialport;
+
+use std::io::{ErrorKind as IoErrorKind, Read, Write};
+use std::time::Duration;
+
+use serialport::{ClearBuffer, ErrorKind, PipeSerialPort, PipeSerialServer, SerialPort};
+
+fn socket_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("serialport-{}-{}", name, std::process::id()))
+}
+
+#[test]
+fn test_pipe_round_trip() {
+    let server = PipeSerialServer::bind(socket_path("round-trip")).unwrap();
+    let path = server.path().to_str().unwrap().to_string();
+    let mut port = PipeSerialPort::open(
+        &serialport::new(path.as_str(), 9600).timeout(Duration::from_millis(100)),
+    )
+    .unwrap();
+    let mut device = server.accept().unwrap();
+
+    port.write_all(b"AT\r").unwrap();
+    let mut request = [0u8; 3];
+    device.read_exact(&mut request).unwrap();
+    assert_eq!(&request, b"AT\r");
+
+    device.write_all(b"OK\r\n").unwrap();
+    let mut response = [0u8; 4];
+    port.read_exact(&mut response).unwrap();
+    assert_eq!(&response, b"OK\r\n");
+
+    assert_eq!(port.name(), Some(path));
+    assert_eq!(port.baud_rate().unwrap(), 9600);
+    port.set_baud_rate(115_200).unwrap();
+    assert_eq!(port.baud_rate().unwrap(), 115_200);
+}
+
+#[test]
+fn test_pipe_timeout_and_clear() {
+    let server = PipeSerialServer::bind(socket_path("timeout")).unwrap();
+    let path = server.path().to_str().unwrap().to_string();
+    let mut port =
+        PipeSerialPort::open(&serialport::new(path, 9600).timeout(Duration::from_millis(50)))
+            .unwrap();
+    let mut device = server.accept().unwrap();
+
+    let mut buf = [0u8; 4];
+    assert_eq!(
+        port.read(&mut buf).unwrap_err().kind(),
+        IoErrorKind::TimedOut
+    );
+
+    device.write_all(b"stale").unwrap();
+    // Give the data some time to arrive.
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(port.bytes_to_read().unwrap(), 5);
+    port.clear(ClearBuffer::Input).unwrap();
+    assert_eq!(port.bytes_to_read().unwrap(), 0);
+}
+
+#[test]
+fn test_pipe_device_closed() {
+    let server = PipeSerialServer::bind(socket_path("closed")).unwrap();
+    let path = server.path().to_str().unwrap().to_string();
+    let mut port =
+        PipeSerialPort::open(&serialport::new(path, 9600).timeout(Duration::from_millis(100)))
+            .unwrap();
+    let device = server.accept().unwrap();
+
+    // The device closing its end is reported like an unplugged device.
+    drop(device);
+    let mut buf = [0u8; 4];
+    assert_eq!(
+        port.read(&mut buf).unwrap_err().kind(),
+        IoErrorKind::NotFound
+    );
+}
+
+#[test]
+fn test_pipe_without_server() {
+    let error = PipeSerialPort::open(&serialport::new(
+        socket_path("missing").to_str().unwrap(),
+        9600,
+    ))
+    .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::NoDevice);
+}
+
+#[test]
+fn test_pipe_open_by_path() {
+    let server = PipeSerialServer::bind(socket_path("by-path")).unwrap();
+    let path = server.path().to_str().unwrap().to_string();
+    let mut port = serialport::new(path.as_str(), 9600).open().unwrap();
+    let mut device = server.accept().unwrap();
+
+    device.write_all(b"RING").unwrap();
+    let mut buf = [0u8; 4];
+    port.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"RING");
+    assert_eq!(port.name(), Some(path));
+}
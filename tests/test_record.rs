@@ -0,0 +1,206 @@
+//! Tests for recording the traffic of serial ports.
+
+use std::fs::File;
+use std::io::ErrorKind as IoErrorKind;
+use std::io::{Read, Write};
+use std::time::{Duration, Instant};
+
+use serialport::mock::MockPort;
+use serialport::record::{read_records, Event, Format, Record, RecordingPort, ReplayPort};
+use serialport::{CancelToken, ErrorKind, Parity, SerialPort};
+
+#[test]
+fn test_recording_traffic() {
+    let path = std::env::temp_dir().join(format!("serialport-record-{}", std::process::id()));
+    let (app, mut device) = MockPort::pair();
+    let mut port = RecordingPort::create(app, &path).unwrap();
+
+    port.set_baud_rate(115_200).unwrap();
+    port.set_parity(Parity::Even).unwrap();
+    port.write_request_to_send(true).unwrap();
+    port.write_all(b"AT\r").unwrap();
+    device.write_all(b"OK\r\n").unwrap();
+    device.set_clear_to_send(true);
+    let mut response = [0u8; 4];
+    port.read_exact(&mut response).unwrap();
+    assert!(port.read_clear_to_send().unwrap());
+    port.set_break().unwrap();
+    port.clear_break().unwrap();
+    port.finish().unwrap();
+
+    let records = read_records(File::open(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let events: Vec<Event> = records.iter().map(|record| record.event.clone()).collect();
+    assert_eq!(
+        events,
+        vec![
+            Event::BaudRate(115_200),
+            Event::Parity(Parity::Even),
+            Event::RequestToSend(true),
+            Event::Write(b"AT\r".to_vec()),
+            Event::Read(b"OK\r\n".to_vec()),
+            Event::ClearToSend(true),
+            Event::Break(true),
+            Event::Break(false),
+        ]
+    );
+    assert!(records
+        .windows(2)
+        .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+}
+
+#[test]
+fn test_recording_cancellable_traffic() {
+    let path =
+        std::env::temp_dir().join(format!("serialport-record-cancel-{}", std::process::id()));
+    let (app, mut device) = MockPort::pair();
+    let mut port = RecordingPort::create(app, &path).unwrap();
+    let cancel = CancelToken::new().unwrap();
+
+    assert_eq!(port.write_cancellable(b"AT\r", &cancel).unwrap(), 3);
+    port.flush_cancellable(&cancel).unwrap();
+    device.write_all(b"OK\r\n").unwrap();
+    let mut response = [0u8; 4];
+    assert_eq!(port.read_cancellable(&mut response, &cancel).unwrap(), 4);
+    port.finish().unwrap();
+
+    let records = read_records(File::open(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let events: Vec<Event> = records.iter().map(|record| record.event.clone()).collect();
+    assert_eq!(
+        events,
+        vec![
+            Event::Write(b"AT\r".to_vec()),
+            Event::Read(b"OK\r\n".to_vec())
+        ]
+    );
+}
+
+#[test]
+fn test_recording_skips_failed_operations() {
+    let path = std::env::temp_dir().join(format!("serialport-record-fail-{}", std::process::id()));
+    let mut port = RecordingPort::create(MockPort::new(), &path).unwrap();
+    port.set_timeout(Duration::from_millis(10)).unwrap();
+
+    let mut buf = [0u8; 1];
+    assert!(port.read(&mut buf).is_err());
+    port.finish().unwrap();
+
+    let records = read_records(File::open(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(records.is_empty());
+}
+
+#[test]
+fn test_record_text_round_trip() {
+    let record = Record {
+        timestamp: Duration::from_micros(1500),
+        event: Event::Write(b"AT\r".to_vec()),
+    };
+    assert_eq!(record.to_string(), "1500 write 41540d");
+    assert_eq!("1500 write 41540d".parse::<Record>().unwrap(), record);
+
+    let records = read_records(&b"# serialport recording\n\n0 dtr 1\n10 read \n"[..]).unwrap();
+    assert_eq!(records[0].event, Event::DataTerminalReady(true));
+    assert_eq!(records[1].event, Event::Read(Vec::new()));
+
+    for invalid in &["", "10", "x write 00", "10 write 0", "10 rts 2", "10 foo 1"] {
+        let error = invalid.parse::<Record>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+}
+
+#[test]
+fn test_replay_keeps_timing() {
+    let record = |millis, event| Record {
+        timestamp: Duration::from_millis(millis),
+        event,
+    };
+    let mut port = ReplayPort::new(vec![
+        record(0, Event::Write(b"AT\r".to_vec())),
+        record(20, Event::Read(b"OK".to_vec())),
+        record(30, Event::CarrierDetect(true)),
+        record(100, Event::Read(b"\r\n".to_vec())),
+    ]);
+    port.set_timeout(Duration::from_millis(500)).unwrap();
+    let start = Instant::now();
+
+    port.write_all(b"AT\r").unwrap();
+    let mut response = [0u8; 4];
+    port.read_exact(&mut response).unwrap();
+    assert_eq!(&response, b"OK\r\n");
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert!(port.read_carrier_detect().unwrap());
+    assert!(port.is_finished());
+
+    port.set_timeout(Duration::from_millis(10)).unwrap();
+    let error = port.read(&mut response).unwrap_err();
+    assert_eq!(error.kind(), IoErrorKind::TimedOut);
+}
+
+#[test]
+fn test_replay_times_out_before_data() {
+    let mut port = ReplayPort::new(vec![Record {
+        timestamp: Duration::from_secs(10),
+        event: Event::Read(b"late".to_vec()),
+    }]);
+    port.set_timeout(Duration::from_millis(10)).unwrap();
+
+    let mut buf = [0u8; 4];
+    let error = port.read(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), IoErrorKind::TimedOut);
+    assert_eq!(port.bytes_to_read().unwrap(), 0);
+    assert!(!port.is_finished());
+}
+
+/// Splits a pcapng file into the types and bodies of its blocks
+fn pcapng_blocks(mut file: &[u8]) -> Vec<(u32, &[u8])> {
+    let u32_at = |data: &[u8], i: usize| u32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+    let mut blocks = Vec::new();
+    while !file.is_empty() {
+        let len = u32_at(file, 4) as usize;
+        assert_eq!(len % 4, 0);
+        assert_eq!(u32_at(file, len - 4) as usize, len);
+        blocks.push((u32_at(file, 0), &file[8..len - 4]));
+        file = &file[len..];
+    }
+    blocks
+}
+
+#[test]
+fn test_recording_pcapng() {
+    let path =
+        std::env::temp_dir().join(format!("serialport-record-{}.pcapng", std::process::id()));
+    let (app, mut device) = MockPort::pair();
+    let mut port = RecordingPort::create_with_format(app, &path, Format::Pcapng).unwrap();
+
+    port.write_all(b"AT\r").unwrap();
+    device.write_all(b"OK").unwrap();
+    let mut response = [0u8; 2];
+    port.read_exact(&mut response).unwrap();
+    port.write_data_terminal_ready(true).unwrap();
+    port.finish().unwrap();
+
+    let file = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let blocks = pcapng_blocks(&file);
+    let types: Vec<u32> = blocks.iter().map(|(block_type, _)| *block_type).collect();
+    assert_eq!(types, vec![0x0A0D_0D0A, 1, 6, 6, 6]);
+    assert_eq!(&blocks[0].1[..4], &0x1A2B_3C4Du32.to_le_bytes());
+    // Link type USER0
+    assert_eq!(&blocks[1].1[..2], &147u16.to_le_bytes());
+
+    // Captured length, data and the epb_flags option with the direction
+    let write = blocks[2].1;
+    assert_eq!(&write[12..16], &3u32.to_le_bytes());
+    assert_eq!(&write[20..23], b"AT\r");
+    assert_eq!(&write[24..32], &[2, 0, 4, 0, 0b10, 0, 0, 0]);
+    let read = blocks[3].1;
+    assert_eq!(&read[20..22], b"OK");
+    assert_eq!(&read[24..32], &[2, 0, 4, 0, 0b01, 0, 0, 0]);
+    // Control line changes are carried in the comment of an empty packet
+    let dtr = blocks[4].1;
+    assert_eq!(&dtr[12..16], &0u32.to_le_bytes());
+    assert_eq!(&dtr[20..24], &[1, 0, 5, 0]);
+    assert_eq!(&dtr[24..29], b"dtr 1");
+}
@@ -0,0 +1,42 @@
+//! Tests for integrating ports into event loops with `Registration`.
+#![cfg(unix)]
+
+use std::io::{self, Read, Write};
+
+use serialport::{Interest, Registration, TTYPort};
+
+#[test]
+fn test_registration_reports_readiness() {
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let mut registration = Registration::new(&master).unwrap();
+    assert!(master.nonblocking());
+    registration
+        .arm(Interest::READABLE | Interest::WRITABLE)
+        .unwrap();
+    assert_eq!(
+        registration.interest(),
+        Interest::READABLE | Interest::WRITABLE
+    );
+
+    let ready = registration.clear().unwrap();
+    assert!(!ready.is_readable());
+    assert!(ready.is_writable());
+    assert!(registration.interest().is_empty());
+
+    registration.arm(Interest::READABLE).unwrap();
+    slave.write_all(b"ping").unwrap();
+    slave.flush().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert_eq!(registration.clear().unwrap(), Interest::READABLE);
+
+    // The registered port doesn't block once all data has been read.
+    let mut buf = [0u8; 8];
+    assert_eq!(registration.port().read(&mut buf).unwrap(), 4);
+    assert_eq!(&buf[..4], b"ping");
+    let error = registration.port().read(&mut buf).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+
+    drop(registration);
+    assert!(!master.nonblocking());
+}
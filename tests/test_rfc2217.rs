@@ -0,0 +1,84 @@
+//! Tests for the RFC 2217 client, talking to a minimal fake server.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serialport::{ErrorKind, Rfc2217Port, SerialPort};
+
+const IAC: u8 = 255;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const COM_PORT_OPTION: u8 = 44;
+
+/// Starts a server sending `greeting` after the client's negotiation and returning everything
+/// received from the client once it disconnects
+fn serve(greeting: Vec<u8>) -> (String, JoinHandle<Vec<u8>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let server = thread::spawn(move || {
+        let (mut stream, _): (TcpStream, _) = listener.accept().unwrap();
+        let mut negotiation = [0u8; 12];
+        stream.read_exact(&mut negotiation).unwrap();
+        stream.write_all(&greeting).unwrap();
+        let mut received = Vec::new();
+        let _ = stream.read_to_end(&mut received);
+        received
+    });
+    (address, server)
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[test]
+fn test_rfc2217_port() {
+    let greeting = [
+        // Accept the COM port option
+        &[IAC, 253, COM_PORT_OPTION][..],
+        // Notify CTS and CD being asserted
+        &[IAC, SB, COM_PORT_OPTION, 107, 0x90, IAC, SE],
+        // Data with an escaped IAC
+        &[b'O', b'K', IAC, IAC],
+    ];
+    let (address, server) = serve(greeting.concat());
+
+    let builder = serialport::new(&address, 115_200).timeout(Duration::from_secs(1));
+    let mut port = Rfc2217Port::open(&builder).unwrap();
+    let mut response = [0u8; 3];
+    port.read_exact(&mut response).unwrap();
+    assert_eq!(&response, b"OK\xff");
+    assert!(port.read_clear_to_send().unwrap());
+    assert!(!port.read_data_set_ready().unwrap());
+    assert!(port.read_carrier_detect().unwrap());
+
+    port.write_all(b"\xffA").unwrap();
+    port.set_break().unwrap();
+    drop(port);
+
+    let received = server.join().unwrap();
+    // Setting the baud rate of 115200 from the builder
+    assert!(contains(
+        &received,
+        &[IAC, SB, COM_PORT_OPTION, 1, 0x00, 0x01, 0xc2, 0x00, IAC, SE]
+    ));
+    assert!(contains(&received, &[IAC, IAC, b'A']));
+    assert!(contains(
+        &received,
+        &[IAC, SB, COM_PORT_OPTION, 5, 5, IAC, SE]
+    ));
+}
+
+#[test]
+fn test_rfc2217_port_refused() {
+    // Reject the COM port option
+    let (address, server) = serve(vec![IAC, 254, COM_PORT_OPTION]);
+
+    let error = Rfc2217Port::open(&serialport::new(&address, 9600)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::NoDevice);
+    server.join().unwrap();
+}
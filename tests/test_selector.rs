@@ -0,0 +1,54 @@
+//! Tests for waiting on several ports with `Selector`.
+#![cfg(unix)]
+
+use std::io::{Read, Write};
+use std::time::Duration;
+
+use serialport::{ErrorKind, Selector, TTYPort};
+
+#[test]
+fn test_selector_reports_readable_ports() {
+    let (first, mut first_device) = TTYPort::pair().expect("Unable to create ptty pair");
+    let (second, mut second_device) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let mut selector = Selector::new();
+    let first_key = selector.add(&first).unwrap();
+    let second_key = selector.add(&second).unwrap();
+    assert_eq!(selector.len(), 2);
+
+    let error = selector.select(Duration::from_millis(10)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Timeout);
+
+    second_device.write_all(b"ping").unwrap();
+    assert_eq!(
+        selector.select(Duration::from_secs(1)).unwrap(),
+        vec![second_key]
+    );
+    // Waiting doesn't consume the data.
+    let mut buf = [0u8; 4];
+    (&second).read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+
+    first_device.write_all(b"pong").unwrap();
+    second_device.write_all(b"pong").unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(
+        selector.select(Duration::from_secs(1)).unwrap(),
+        vec![first_key, second_key]
+    );
+
+    assert!(selector.remove(first_key));
+    assert!(!selector.remove(first_key));
+    assert_eq!(
+        selector.select(Duration::from_secs(1)).unwrap(),
+        vec![second_key]
+    );
+}
+
+#[test]
+fn test_selector_without_ports() {
+    let mut selector = Selector::new();
+    assert!(selector.is_empty());
+    let error = selector.select(Duration::from_millis(10)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
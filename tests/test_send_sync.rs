@@ -0,0 +1,78 @@
+//! Compile-time checks for the thread-safety guarantees of the public types.
+//!
+//! Removing `Send` or `Sync` from any of these types is a breaking change. These tests fail to
+//! compile if that happens by accident.
+
+extern crate serialport;
+
+use serialport::watcher::{PortEvent, PortWatcher, RemovalNotifier};
+use serialport::{
+    BackgroundWriter, Error, ManagedPort, PortFilter, PortState, ReadHalf, SerialPort,
+    SerialPortBuilder, SerialPortInfo, SharedPort, WriteHalf, WritePriority,
+};
+
+fn assert_send<T: Send + ?Sized>() {}
+fn assert_sync<T: Sync + ?Sized>() {}
+
+#[test]
+fn test_ports_are_send() {
+    assert_send::<dyn SerialPort>();
+    assert_send::<Box<dyn SerialPort>>();
+    assert_send::<ManagedPort>();
+    assert_send::<serialport::Rfc2217Port>();
+    assert_send::<serialport::mock::MockPort>();
+    assert_sync::<serialport::mock::MockPort>();
+    assert_send::<serialport::record::RecordingPort<serialport::mock::MockPort>>();
+    assert_sync::<serialport::record::RecordingPort<serialport::mock::MockPort>>();
+    assert_send::<serialport::record::ReplayPort>();
+    assert_sync::<serialport::record::ReplayPort>();
+
+    #[cfg(unix)]
+    {
+        assert_send::<serialport::TTYPort>();
+        assert_sync::<serialport::TTYPort>();
+        assert_send::<ReadHalf<serialport::TTYPort>>();
+        assert_send::<WriteHalf<serialport::TTYPort>>();
+        assert_send::<serialport::PipeSerialPort>();
+        assert_sync::<serialport::PipeSerialPort>();
+        assert_send::<serialport::PipeSerialServer>();
+        assert_sync::<serialport::PipeSerialServer>();
+    }
+
+    #[cfg(windows)]
+    {
+        assert_send::<serialport::COMPort>();
+        assert_sync::<serialport::COMPort>();
+        assert_send::<ReadHalf<serialport::COMPort>>();
+        assert_send::<WriteHalf<serialport::COMPort>>();
+    }
+}
+
+#[test]
+fn test_shared_types_are_send_and_sync() {
+    assert_send::<SharedPort>();
+    assert_sync::<SharedPort>();
+    assert_send::<BackgroundWriter>();
+    assert_sync::<BackgroundWriter>();
+    assert_send::<RemovalNotifier>();
+    assert_sync::<RemovalNotifier>();
+    assert_send::<PortWatcher>();
+}
+
+#[test]
+fn test_plain_data_is_send_and_sync() {
+    assert_send::<Error>();
+    assert_sync::<Error>();
+    assert_send::<SerialPortBuilder>();
+    assert_sync::<SerialPortBuilder>();
+    assert_send::<SerialPortInfo>();
+    assert_sync::<SerialPortInfo>();
+    assert_send::<PortFilter>();
+    assert_sync::<PortFilter>();
+    assert_send::<PortEvent>();
+    assert_sync::<PortEvent>();
+    assert_send::<PortState>();
+    assert_sync::<PortState>();
+    assert_send::<WritePriority>();
+    assert_sync::<WritePriority>();
+}
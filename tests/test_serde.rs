@@ -0,0 +1,60 @@
+//! Tests for serializing settings with serde.
+#![cfg(feature = "serde")]
+
+use std::time::Duration;
+
+use serialport::{
+    FlowControl, Parity, PortEnumeration, SerialPortBuilder, SerialPortInfo, SerialPortType,
+    StopBits,
+};
+
+#[test]
+fn test_builder_roundtrip() {
+    let builder = serialport::new("/dev/ttyUSB0", 115_200)
+        .parity(Parity::Even)
+        .stop_bits(StopBits::Two)
+        .flow_control(FlowControl::Hardware)
+        .timeout(Duration::from_millis(250))
+        .dtr_on_open(false);
+
+    let json = serde_json::to_string(&builder).unwrap();
+    let restored: SerialPortBuilder = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, builder);
+}
+
+#[test]
+fn test_builder_defaults() {
+    let json = r#"{ "path": "/dev/ttyUSB0", "baud_rate": 9600 }"#;
+    let builder: SerialPortBuilder = serde_json::from_str(json).unwrap();
+    assert_eq!(builder, serialport::new("/dev/ttyUSB0", 9600));
+
+    let json = r#"{ "path": "/dev/ttyUSB0", "baud_rate": 9600, "parity": "Odd" }"#;
+    let builder: SerialPortBuilder = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        builder,
+        serialport::new("/dev/ttyUSB0", 9600).parity(Parity::Odd)
+    );
+
+    // The path and the baud rate are required.
+    assert!(serde_json::from_str::<SerialPortBuilder>(r#"{ "baud_rate": 9600 }"#).is_err());
+    assert!(serde_json::from_str::<SerialPortBuilder>(r#"{ "path": "COM1" }"#).is_err());
+}
+
+#[test]
+fn test_enumeration_roundtrip() {
+    let enumeration = serialport::available_ports_with_warnings().expect("Failed to list ports");
+    let json = serde_json::to_string(&enumeration).unwrap();
+    let restored: PortEnumeration = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, enumeration);
+}
+
+#[test]
+fn test_port_info_json() {
+    let json = r#"{ "port_name": "/dev/ttyS0", "port_type": "PciPort" }"#;
+    let info: SerialPortInfo = serde_json::from_str(json).unwrap();
+    assert_eq!(info.port_name, "/dev/ttyS0");
+    assert_eq!(info.port_type, SerialPortType::PciPort);
+
+    let json = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["port_type"], "PciPort");
+}
@@ -15,6 +15,24 @@ fn test_listing_ports() {
     }
 }
 
+#[test]
+fn test_listing_ports_with_warnings() {
+    let enumeration = serialport::available_ports_with_warnings().expect("Failed to list ports");
+    assert_eq!(enumeration.ports, serialport::available_ports().unwrap());
+    for warning in enumeration.warnings {
+        println!("{}", warning);
+    }
+}
+
+#[test]
+fn test_listing_ports_lossy() {
+    let ports = serialport::available_ports().expect("Failed to list ports");
+    let names: Vec<_> = ports.iter().map(|p| &p.port_name).collect();
+    let lossy = serialport::available_ports_lossy().expect("Failed to list ports");
+    let lossy_names: Vec<_> = lossy.iter().map(|p| &p.port_name).collect();
+    assert_eq!(names, lossy_names);
+}
+
 #[rstest]
 #[cfg_attr(feature = "ignore-hardware-tests", ignore)]
 fn test_opening_found_ports(hw_config: HardwareConfig) {
@@ -70,3 +88,227 @@ fn test_duplicating_port_config(hw_config: HardwareConfig) {
     let _port1 = port1_config.open().unwrap();
     let _port1 = port2_config.open().unwrap();
 }
+
+#[test]
+fn test_ordering_port_info_naturally() {
+    fn port(name: &str) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: SerialPortType::Unknown,
+            friendly_name: None,
+            description: None,
+            instance_id: None,
+            counterpart: None,
+            by_id: None,
+            by_path: None,
+            bluetooth: None,
+        }
+    }
+
+    let mut ports: Vec<_> = [
+        "COM10",
+        "COM2",
+        "/dev/ttyUSB10",
+        "COM02",
+        "/dev/ttyACM0",
+        "/dev/ttyUSB2",
+        "COM1",
+    ]
+    .iter()
+    .map(|name| port(name))
+    .collect();
+    ports.sort();
+
+    let names: Vec<_> = ports.iter().map(|p| p.port_name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "/dev/ttyACM0",
+            "/dev/ttyUSB2",
+            "/dev/ttyUSB10",
+            "COM1",
+            "COM02",
+            "COM2",
+            "COM10"
+        ]
+    );
+}
+
+#[test]
+fn test_virtual_pair() {
+    use std::io::{Read, Write};
+
+    let (mut first, mut second) = serialport::virtual_pair().expect("Failed to create ports");
+    first.set_timeout(Duration::from_secs(1)).unwrap();
+    second.set_timeout(Duration::from_secs(1)).unwrap();
+
+    first.write_all(b"ping").unwrap();
+    let mut buf = [0u8; 4];
+    second.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+
+    second.write_all(b"pong").unwrap();
+    first.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"pong");
+}
+
+#[test]
+fn test_parse_mode() {
+    let builder = serialport::new("/dev/ttyUSB0", 9600);
+    assert_eq!(builder.clone().parse_mode("8N1").unwrap(), builder.clone());
+    assert_eq!(
+        builder.clone().parse_mode("115200").unwrap(),
+        builder.clone().baud_rate(115_200)
+    );
+    assert_eq!(
+        builder.clone().parse_mode("19200,5o2").unwrap(),
+        builder
+            .clone()
+            .baud_rate(19_200)
+            .data_bits(DataBits::Five)
+            .parity(Parity::Odd)
+            .stop_bits(StopBits::Two)
+    );
+
+    for mode in [
+        "",
+        "8N",
+        "9N1",
+        "8M1",
+        "8N3",
+        "fast,8N1",
+        "115200,",
+        "115200,8N1,",
+    ] {
+        let error = builder.clone().parse_mode(mode).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput, "{:?}", mode);
+    }
+}
+
+#[test]
+fn test_new_from_str() {
+    assert_eq!(
+        serialport::new_from_str("COM3:9600").unwrap(),
+        serialport::new("COM3", 9600)
+    );
+    assert_eq!(
+        serialport::new_from_str("/dev/ttyUSB0:115200,7E1").unwrap(),
+        serialport::new("/dev/ttyUSB0", 115_200)
+            .data_bits(DataBits::Seven)
+            .parity(Parity::Even)
+    );
+
+    for s in ["/dev/ttyUSB0", ":9600", "/dev/ttyUSB0:8N1", "/dev/ttyUSB0:"] {
+        let error = serialport::new_from_str(s).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput, "{:?}", s);
+    }
+}
+
+#[test]
+fn test_builder_getters() {
+    let builder = serialport::new("/dev/ttyUSB0", 9600);
+    assert_eq!(builder.get_path(), "/dev/ttyUSB0");
+    assert_eq!(builder.get_baud_rate(), 9600);
+    assert_eq!(builder.get_data_bits(), DataBits::Eight);
+    assert_eq!(builder.get_flow_control(), FlowControl::None);
+    assert_eq!(builder.get_parity(), Parity::None);
+    assert_eq!(builder.get_stop_bits(), StopBits::One);
+    assert_eq!(builder.get_timeout(), Duration::from_millis(0));
+    assert_eq!(builder.get_inter_byte_timeout(), None);
+    assert_eq!(builder.get_dtr_on_open(), Some(true));
+    assert_eq!(builder.get_strictness(), Strictness::Strict);
+
+    let builder = builder
+        .path("COM3")
+        .baud_rate(115_200)
+        .data_bits(DataBits::Seven)
+        .flow_control(FlowControl::Hardware)
+        .parity(Parity::Even)
+        .stop_bits(StopBits::Two)
+        .timeout(Duration::from_millis(10))
+        .inter_byte_timeout(Duration::from_millis(20))
+        .preserve_dtr_on_open()
+        .strictness(Strictness::Lenient);
+    assert_eq!(builder.get_path(), "COM3");
+    assert_eq!(builder.get_baud_rate(), 115_200);
+    assert_eq!(builder.get_data_bits(), DataBits::Seven);
+    assert_eq!(builder.get_flow_control(), FlowControl::Hardware);
+    assert_eq!(builder.get_parity(), Parity::Even);
+    assert_eq!(builder.get_stop_bits(), StopBits::Two);
+    assert_eq!(builder.get_timeout(), Duration::from_millis(10));
+    assert_eq!(
+        builder.get_inter_byte_timeout(),
+        Some(Duration::from_millis(20))
+    );
+    assert_eq!(builder.get_dtr_on_open(), None);
+    assert_eq!(builder.get_strictness(), Strictness::Lenient);
+}
+
+#[test]
+fn test_builder_validate() {
+    serialport::new("/dev/ttyUSB0", 9600).validate().unwrap();
+    serialport::new("COM3", 115_200)
+        .data_bits(DataBits::Five)
+        .validate()
+        .unwrap();
+
+    let invalid = [
+        serialport::new("", 9600),
+        serialport::new("/dev/ttyUSB0", 0),
+        serialport::new("/dev/ttyUSB0", 9600)
+            .data_bits(DataBits::Five)
+            .stop_bits(StopBits::Two),
+    ];
+    for builder in invalid {
+        let error = builder.validate().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput, "{:?}", builder);
+    }
+}
+
+#[test]
+fn test_open_with_retry_fails_fast() {
+    // Missing devices are not retried.
+    let start = std::time::Instant::now();
+    let result =
+        serialport::new("/dev/does-not-exist", 9600).open_with_retry(5, Duration::from_secs(10));
+    assert!(result.is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_error_context() {
+    let error = Error::new(ErrorKind::InvalidInput, "Invalid argument")
+        .with_context("setting baud rate", Some("/dev/ttyUSB0"))
+        .with_context("applying settings", None);
+    assert_eq!(error.operation(), Some("setting baud rate"));
+    assert_eq!(error.port(), Some("/dev/ttyUSB0"));
+    assert_eq!(
+        error.to_string(),
+        "/dev/ttyUSB0: setting baud rate: Invalid argument"
+    );
+
+    let restored = Error::from(std::io::Error::from(error));
+    assert_eq!(restored.port(), Some("/dev/ttyUSB0"));
+}
+
+#[test]
+fn test_error_io_round_trip() {
+    let error = Error::new(ErrorKind::NoDevice, "device has been disconnected");
+    let io_error = std::io::Error::from(error.clone());
+    assert_eq!(io_error.kind(), std::io::ErrorKind::NotFound);
+    assert_eq!(io_error.to_string(), error.to_string());
+
+    let restored = Error::from(io_error);
+    assert_eq!(restored.kind(), ErrorKind::NoDevice);
+    assert_eq!(restored.description, error.description);
+
+    let error = Error::from(std::io::Error::new(std::io::ErrorKind::TimedOut, "timeout"));
+    assert_eq!(error.kind(), ErrorKind::Timeout);
+    let io_error = std::io::Error::from(Error::new(ErrorKind::Timeout, "timeout"));
+    assert_eq!(io_error.kind(), std::io::ErrorKind::TimedOut);
+
+    let error = Error::from(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+    assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+    let io_error = std::io::Error::from(Error::new(ErrorKind::Unsupported, "unsupported"));
+    assert_eq!(io_error.kind(), std::io::ErrorKind::Unsupported);
+}
@@ -0,0 +1,51 @@
+//! Tests for the `SharedPort` struct.
+#![cfg(unix)]
+
+extern crate serialport;
+
+use std::io::{Read, Write};
+use std::thread;
+use std::time::Duration;
+
+use serialport::{ErrorKind, SerialPort, SharedPort, TTYPort};
+
+#[test]
+fn test_shared_port_transact() {
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_millis(1000)).unwrap();
+
+    let responder = thread::spawn(move || {
+        let mut request = [0u8; 3];
+        master.read_exact(&mut request).unwrap();
+        assert_eq!(&request, b"AT\r");
+        // Send the response in two parts to check that it gets reassembled.
+        master.write_all(b"O").unwrap();
+        thread::sleep(Duration::from_millis(50));
+        master.write_all(b"K\r\n").unwrap();
+        master
+    });
+
+    let shared = SharedPort::new(Box::new(slave));
+    let response = shared
+        .transact(
+            b"AT\r",
+            |data| data.ends_with(b"OK\r\n"),
+            Duration::from_millis(1000),
+        )
+        .expect("Transaction failed");
+    assert_eq!(response, b"OK\r\n");
+
+    // The port's original timeout gets restored after the transaction.
+    assert_eq!(shared.lock().timeout(), Duration::from_millis(100));
+
+    let _master = responder.join().unwrap();
+}
+
+#[test]
+fn test_shared_port_transact_timeout() {
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let shared = SharedPort::new(Box::new(slave));
+    let result = shared.transact(b"AT\r", |_| true, Duration::from_millis(100));
+    assert_eq!(result.unwrap_err().kind(), ErrorKind::Timeout);
+}
@@ -0,0 +1,48 @@
+//! Tests for splitting ports into reading and writing halves.
+#![cfg(unix)]
+
+extern crate serialport;
+
+use std::io::{Read, Write};
+use std::thread;
+use std::time::Duration;
+
+use serialport::{ErrorKind, SerialPort, TTYPort};
+
+#[test]
+fn test_split_port_across_threads() {
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_millis(1000)).unwrap();
+
+    slave.set_timeout(Duration::from_millis(1000)).unwrap();
+    let (mut reader, mut writer) = slave.split();
+    assert_eq!(reader.timeout(), Duration::from_millis(1000));
+    assert_eq!(reader.name(), writer.name());
+
+    // Block on reading in one thread while writing from another one.
+    let reading = thread::spawn(move || {
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+        reader
+    });
+    writer.write_all(b"pong").unwrap();
+    let mut buf = [0u8; 4];
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"pong");
+    master.write_all(b"ping").unwrap();
+    let reader = reading.join().unwrap();
+
+    let port = reader.reunite(writer).expect("Unable to reunite halves");
+    assert_eq!(port.timeout(), Duration::from_millis(1000));
+}
+
+#[test]
+fn test_split_reunite_mismatched_halves() {
+    let (first, second) = TTYPort::pair().expect("Unable to create ptty pair");
+    let (reader, _) = first.split();
+    let (_, writer) = second.split();
+
+    let error = reader.reunite(writer).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,22 @@
+//! Tests for gathering a support bundle.
+#![cfg(unix)]
+
+extern crate serialport;
+
+use serialport::{SerialPort, TTYPort};
+
+#[test]
+fn test_support_bundle_reports_open_ports() {
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let bundle = serialport::support_bundle(&[&master, &slave]);
+
+    assert_eq!(bundle.crate_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(bundle.platform.os, std::env::consts::OS);
+    assert!(bundle.platform.os_release.is_some());
+    assert_eq!(bundle.open_ports.len(), 2);
+    assert_eq!(bundle.open_ports[1].name, slave.name());
+    assert_eq!(bundle.open_ports[1].settings, slave.settings_lossy());
+    // Pseudo terminals don't count line errors.
+    assert!(bundle.open_ports[1].line_errors.is_none());
+}
@@ -51,3 +51,32 @@ fn test_try_clone_move() {
     // The thread should have already ended, but we'll make sure here anyways.
     loopback.join().unwrap();
 }
+
+// Test that native clones keep the platform-specific functionality
+#[test]
+fn test_try_clone_native() {
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let mut clone = master.try_clone_native().expect("Failed to clone");
+    assert_eq!(clone.name(), master.name());
+    assert_eq!(clone.exclusive(), master.exclusive());
+    clone.set_nonblocking(true).unwrap();
+    assert!(clone.nonblocking());
+
+    clone.write_all(b"native").unwrap();
+    let mut buffer = [0; 6];
+    slave.read_exact(&mut buffer).unwrap();
+    assert_eq!(&buffer, b"native");
+}
+
+// Test that clones share the settings applying to the device
+#[test]
+fn test_try_clone_shares_device_settings() {
+    let (mut master, _slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let clone = master.try_clone_native().expect("Failed to clone");
+    master.set_nonblocking(true).unwrap();
+    assert!(clone.nonblocking());
+    master.set_exclusive(false).unwrap();
+    assert!(!clone.exclusive());
+}
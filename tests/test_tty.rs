@@ -3,16 +3,18 @@
 
 extern crate serialport;
 
-use std::io::{Read, Write};
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
 use std::os::unix::prelude::*;
 use std::str;
 use std::time::Duration;
 
-use serialport::{SerialPort, TTYPort};
+use serialport::{
+    AccessMode, ErrorKind, FlowControl, SerialPort, ShareMode, StopBits, Strictness, TTYPort,
+};
 
 #[test]
 fn test_ttyport_pair() {
-    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
     let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
     master
         .set_timeout(Duration::from_millis(10))
@@ -70,7 +72,6 @@ fn test_ttyport_timeout() {
     let result_thread = result.clone();
 
     std::thread::spawn(move || {
-        // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
         let (mut master, _slave) = TTYPort::pair().expect("Unable to create ptty pair");
         master.set_timeout(Duration::new(1, 0)).unwrap();
 
@@ -113,6 +114,27 @@ fn test_osx_pty_pair() {
     assert_eq!(output_stream.recv().unwrap(), "12");
 }
 
+#[test]
+fn test_ttyport_pair_concurrently() {
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            std::thread::spawn(|| {
+                let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+                let name = slave.name().expect("Slave ptty has no name");
+                (master, slave, name)
+            })
+        })
+        .collect();
+    let pairs: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+
+    // Each slave gets a name of its own, and the ptys stay alive until the end of the test.
+    let names: HashSet<_> = pairs.iter().map(|(_, _, name)| name.clone()).collect();
+    assert_eq!(names.len(), pairs.len());
+    for (master, _, _) in &pairs {
+        assert_eq!(master.name(), None);
+    }
+}
+
 // On Mac this should work (in fact used to in b77768a) but now fails. It's not functionality that
 // should be required, and the ptys work otherwise. So going to just disable this test instead.
 #[test]
@@ -121,7 +143,6 @@ fn test_ttyport_set_standard_baud() {
     // `master` must be used here as Dropping it causes slave to be deleted by the OS.
     // TODO: Convert this to a statement-level attribute once
     //       https://github.com/rust-lang/rust/issues/15701 is on stable.
-    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
     #![allow(unused_variables)]
     let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
 
@@ -147,7 +168,6 @@ fn test_ttyport_set_nonstandard_baud() {
     // `master` must be used here as Dropping it causes slave to be deleted by the OS.
     // TODO: Convert this to a statement-level attribute once
     //       https://github.com/rust-lang/rust/issues/15701 is on stable.
-    // FIXME: Create a mutex across all tests for using `TTYPort::pair()` as it's not threadsafe
     #![allow(unused_variables)]
     let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
 
@@ -158,3 +178,697 @@ fn test_ttyport_set_nonstandard_baud() {
     slave.set_baud_rate(1_200_000).unwrap();
     assert_eq!(slave.baud_rate().unwrap(), 1_200_000);
 }
+
+#[test]
+fn test_ttyport_inter_byte_timeout() {
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave
+        .set_timeout(Duration::from_millis(1000))
+        .expect("Unable to set timeout on the slave");
+
+    assert_eq!(slave.inter_byte_timeout().unwrap(), None);
+    slave
+        .set_inter_byte_timeout(Some(Duration::from_millis(150)))
+        .expect("Unable to set inter-byte timeout on the slave");
+    // The resolution on POSIX systems is 100 ms and values get rounded up.
+    assert_eq!(
+        slave.inter_byte_timeout().unwrap(),
+        Some(Duration::from_millis(200))
+    );
+
+    master.write_all(b"abc").unwrap();
+
+    // Reading into a larger buffer returns the available data after the line went idle.
+    let mut buf = [0u8; 16];
+    let nbytes = slave.read(&mut buf).expect("Unable to read bytes.");
+    assert_eq!(&buf[..nbytes], b"abc");
+
+    slave
+        .set_inter_byte_timeout(None)
+        .expect("Unable to clear inter-byte timeout on the slave");
+    assert_eq!(slave.inter_byte_timeout().unwrap(), None);
+}
+
+#[test]
+#[cfg_attr(any(target_os = "ios", target_os = "macos"), ignore)]
+fn test_ttyport_apply_settings() {
+    // `master` must be used here as Dropping it causes slave to be deleted by the OS.
+    #![allow(unused_variables)]
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Linux pseudo terminals always use eight data bits without parity, so these settings are not
+    // covered here.
+    let settings = serialport::new("", 57600)
+        .stop_bits(StopBits::Two)
+        .flow_control(FlowControl::Hardware)
+        .timeout(Duration::from_millis(250))
+        .inter_byte_timeout(Duration::from_millis(300));
+    slave
+        .apply_settings(&settings)
+        .expect("Unable to apply settings");
+
+    assert_eq!(slave.baud_rate().unwrap(), 57600);
+    assert_eq!(slave.stop_bits().unwrap(), StopBits::Two);
+    assert_eq!(slave.flow_control().unwrap(), FlowControl::Hardware);
+    assert_eq!(slave.timeout(), Duration::from_millis(250));
+    assert_eq!(
+        slave.inter_byte_timeout().unwrap(),
+        Some(Duration::from_millis(300))
+    );
+
+    let snapshot = slave.try_settings().expect("Unable to read settings");
+    assert_eq!(snapshot.baud_rate, 57600);
+    assert_eq!(snapshot.stop_bits, StopBits::Two);
+    assert_eq!(snapshot.flow_control, FlowControl::Hardware);
+    assert_eq!(snapshot.timeout, Duration::from_millis(250));
+    assert_eq!(slave.settings_lossy().settings, snapshot);
+    assert!(slave.settings_lossy().unknown.is_empty());
+}
+
+#[test]
+fn test_ttyport_strictness() {
+    #![allow(unused_variables)]
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // Pseudo terminals don't support the modem control lines.
+    assert_eq!(slave.strictness(), Strictness::Strict);
+    assert_eq!(
+        slave.read_clear_to_send().unwrap_err().kind(),
+        ErrorKind::Unsupported
+    );
+
+    slave.set_strictness(Strictness::Lenient);
+    assert!(slave.read_clear_to_send().unwrap());
+    assert!(slave.read_data_set_ready().unwrap());
+    assert!(!slave.read_ring_indicator().unwrap());
+    assert!(slave.read_carrier_detect().unwrap());
+    slave.write_request_to_send(true).unwrap();
+    slave.write_data_terminal_ready(false).unwrap();
+}
+
+#[test]
+fn test_ttyport_try_from_raw_fd() {
+    #![allow(unused_variables)]
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    let fd = nix::unistd::dup(slave.as_raw_fd()).expect("Unable to duplicate fd");
+    nix::fcntl::fcntl(fd, nix::fcntl::F_SETFL(nix::fcntl::OFlag::O_NONBLOCK))
+        .expect("Unable to set O_NONBLOCK");
+    let mut port = unsafe { TTYPort::try_from_raw_fd(fd) }.expect("Unable to wrap fd");
+    assert!(port.nonblocking());
+
+    // Wrapping the file descriptor doesn't put the terminal into exclusive mode.
+    assert!(!port.exclusive());
+
+    // Reading returns right away instead of waiting for the timeout.
+    port.set_timeout(Duration::from_secs(10)).unwrap();
+    let mut buf = [0u8; 4];
+    assert_eq!(
+        port.read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+
+    port.set_nonblocking(false).unwrap();
+    assert!(!port.nonblocking());
+
+    let file = std::fs::File::open("/dev/null").unwrap();
+    let error = unsafe { TTYPort::try_from_raw_fd(file.as_raw_fd()) }.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+#[cfg_attr(any(target_os = "ios", target_os = "macos"), ignore)]
+fn test_ttyport_pair_with_settings() {
+    // Linux pseudo terminals always use eight data bits without parity, so these settings are not
+    // covered here.
+    let builder = serialport::new("", 19_200)
+        .flow_control(FlowControl::Hardware)
+        .stop_bits(StopBits::Two)
+        .timeout(Duration::from_millis(20))
+        .strictness(Strictness::Lenient);
+    let (mut master, mut slave) =
+        TTYPort::pair_with_settings(&builder).expect("Unable to create ptty pair");
+
+    assert_eq!(slave.baud_rate().unwrap(), 19_200);
+    assert_eq!(slave.flow_control().unwrap(), FlowControl::Hardware);
+    assert_eq!(slave.stop_bits().unwrap(), StopBits::Two);
+    assert_eq!(slave.strictness(), Strictness::Lenient);
+    assert_eq!(master.strictness(), Strictness::Lenient);
+    assert_eq!(slave.timeout(), Duration::from_millis(20));
+    assert_eq!(master.timeout(), Duration::from_millis(20));
+
+    // The master times out just like the slave.
+    let mut buf = [0u8; 4];
+    assert_eq!(
+        master.read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::TimedOut
+    );
+
+    // And fails right away in non-blocking mode.
+    master.set_nonblocking(true).unwrap();
+    master.set_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(
+        master.read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+
+    slave.write_all(b"data").unwrap();
+    slave.flush().unwrap();
+    master.set_nonblocking(false).unwrap();
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"data");
+}
+
+#[test]
+fn test_ttyport_open_timeout() {
+    #![allow(unused_variables)]
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // A baud rate of 0 skips setting DTR, which pseudo terminals don't support.
+    let port = serialport::new(slave.name().unwrap(), 0)
+        .open_timeout(Duration::from_secs(5))
+        .expect("Unable to open pty");
+    assert_eq!(port.name(), slave.name());
+
+    let error = serialport::new("/dev/does-not-exist", 0)
+        .open_timeout(Duration::from_secs(5))
+        .unwrap_err();
+    assert_ne!(error.kind(), ErrorKind::Timeout);
+}
+
+#[test]
+fn test_ttyport_hangup_reports_no_device() {
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_secs(1)).unwrap();
+    drop(master);
+
+    let mut buf = [0u8; 4];
+    let error = serialport::Error::from(slave.read(&mut buf).unwrap_err());
+    assert_eq!(error.kind(), ErrorKind::NoDevice);
+    let error = serialport::Error::from(slave.write(b"data").unwrap_err());
+    assert_eq!(error.kind(), ErrorKind::NoDevice);
+}
+
+#[test]
+fn test_ttyport_is_connected() {
+    let (master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    assert!(slave.is_connected().unwrap());
+    assert!(master.is_connected().unwrap());
+
+    drop(master);
+    assert!(!slave.is_connected().unwrap());
+}
+
+#[test]
+fn test_ttyport_errors_name_port() {
+    let error = TTYPort::open(&serialport::new("/dev/does-not-exist", 9600)).unwrap_err();
+    assert_eq!(error.port(), Some("/dev/does-not-exist"));
+    assert_eq!(error.operation(), Some("opening"));
+
+    let (master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_millis(10)).unwrap();
+    let mut buf = [0u8; 4];
+    let error = serialport::Error::from(slave.read(&mut buf).unwrap_err());
+    assert_eq!(error.kind(), ErrorKind::Timeout);
+    assert_eq!(error.port(), slave.name().as_deref());
+    assert_eq!(error.operation(), Some("reading"));
+    drop(master);
+}
+
+#[test]
+fn test_ttyport_open_non_exclusive() {
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+
+    // A baud rate of 0 skips setting DTR, which pseudo terminals don't support.
+    let builder = serialport::new(slave.name().unwrap(), 0);
+    assert_eq!(builder.get_share_mode(), ShareMode::Exclusive);
+    let port = TTYPort::open(&builder).expect("Unable to open pty");
+    assert!(port.exclusive());
+    drop(port);
+
+    let port = TTYPort::open(&builder.share_mode(ShareMode::Shared)).expect("Unable to open pty");
+    assert!(!port.exclusive());
+}
+
+#[test]
+fn test_ttyport_access_modes() {
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_millis(100)).unwrap();
+
+    // A baud rate of 0 skips setting DTR, which pseudo terminals don't support.
+    let builder = serialport::new(slave.name().unwrap(), 0).timeout(Duration::from_millis(100));
+    let mut reader = TTYPort::open(&builder.clone().access_mode(AccessMode::ReadOnly))
+        .expect("Unable to open pty for reading");
+    let mut writer = TTYPort::open(&builder.access_mode(AccessMode::WriteOnly))
+        .expect("Unable to open pty for writing");
+
+    assert!(reader.write(b"data").is_err());
+    master.write_all(b"data").unwrap();
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"data");
+
+    assert!(writer.read(&mut buf).is_err());
+    writer.write_all(b"back").unwrap();
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"back");
+}
+
+#[test]
+fn test_ttyport_skip_tty_checks() {
+    let file = std::env::temp_dir().join(format!("serialport-not-a-tty-{}", std::process::id()));
+    std::fs::write(&file, b"data").unwrap();
+    let builder = serialport::new(file.to_str().unwrap(), 9600).timeout(Duration::from_millis(10));
+
+    assert!(TTYPort::open(&builder).is_err());
+
+    let mut port = TTYPort::open(&builder.skip_tty_checks(true)).expect("Unable to open file");
+    assert!(!port.exclusive());
+    let mut buf = [0u8; 4];
+    port.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"data");
+    drop(port);
+    std::fs::remove_file(&file).unwrap();
+}
+
+#[test]
+#[cfg(feature = "io-safety")]
+fn test_ttyport_io_safety() {
+    use std::os::unix::io::{AsFd, OwnedFd};
+
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let raw_fd = slave.as_raw_fd();
+    assert_eq!(slave.as_fd().as_raw_fd(), raw_fd);
+
+    let fd = OwnedFd::from(slave);
+    assert_eq!(fd.as_raw_fd(), raw_fd);
+}
+
+#[test]
+#[cfg(feature = "io-safety")]
+fn test_ttyport_try_from_owned_fd() {
+    use std::os::unix::io::OwnedFd;
+
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let fd = OwnedFd::from(slave);
+    let raw_fd = fd.as_raw_fd();
+    let port = TTYPort::try_from(fd).expect("Unable to wrap fd");
+    assert_eq!(port.as_raw_fd(), raw_fd);
+
+    let file = OwnedFd::from(std::fs::File::open("/dev/null").unwrap());
+    let error = TTYPort::try_from(file).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_ttyport_from_raw_fd_with_settings() {
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let builder = serialport::new("inherited", 0)
+        .timeout(Duration::from_millis(250))
+        .flow_control(FlowControl::Hardware);
+
+    let fd = slave.into_raw_fd();
+    let port =
+        unsafe { TTYPort::from_raw_fd_with_settings(fd, &builder) }.expect("Unable to adopt fd");
+    assert_eq!(port.as_raw_fd(), fd);
+    assert_eq!(port.name().as_deref(), Some("inherited"));
+    assert_eq!(port.timeout(), Duration::from_millis(250));
+    assert_eq!(port.flow_control().unwrap(), FlowControl::Hardware);
+
+    // The caller keeps the file descriptor on failure.
+    let file = std::fs::File::open("/dev/null").unwrap();
+    let error =
+        unsafe { TTYPort::from_raw_fd_with_settings(file.as_raw_fd(), &builder) }.unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    assert_eq!(error.port(), Some("inherited"));
+    assert!(nix::unistd::isatty(file.as_raw_fd()).is_ok());
+}
+
+#[test]
+fn test_ttyport_from_raw_fd_with_settings_restores_terminal_on_failure() {
+    use nix::sys::termios::{tcgetattr, tcsetattr, LocalFlags, SetArg};
+
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let fd = slave.into_raw_fd();
+    let mut cooked = tcgetattr(fd).unwrap();
+    cooked.local_flags |= LocalFlags::ICANON | LocalFlags::ECHO;
+    tcsetattr(fd, SetArg::TCSANOW, &cooked).unwrap();
+
+    // Pseudo terminals reject setting DTR, which is only attempted for a non-zero baud rate.
+    let builder = serialport::new("inherited", 9600);
+    assert!(unsafe { TTYPort::from_raw_fd_with_settings(fd, &builder) }.is_err());
+
+    // The terminal is handed back neither in raw nor in exclusive mode.
+    let flags = tcgetattr(fd).unwrap().local_flags;
+    assert!(flags.contains(LocalFlags::ICANON | LocalFlags::ECHO));
+    let port = unsafe { TTYPort::try_from_raw_fd(fd) }.unwrap();
+    #[cfg(target_os = "linux")]
+    assert!(!port.exclusive());
+}
+
+#[test]
+fn test_ttyport_inheritable() {
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let name = slave.name().unwrap();
+
+    let port = TTYPort::open(&serialport::new(&name, 0)).expect("Unable to open");
+    assert!(!port.inheritable().unwrap());
+    port.set_inheritable(true).unwrap();
+    assert!(port.inheritable().unwrap());
+    port.set_inheritable(false).unwrap();
+    assert!(!port.inheritable().unwrap());
+
+    let port = TTYPort::open(&serialport::new(&name, 0).inheritable(true)).expect("Unable to open");
+    assert!(port.inheritable().unwrap());
+}
+
+#[test]
+fn test_ttyport_restore_settings() {
+    use nix::sys::termios::{tcgetattr, tcsetattr, LocalFlags, SetArg};
+
+    let (_master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    let is_canonical = || {
+        let termios = tcgetattr(slave.as_raw_fd()).unwrap();
+        termios.local_flags.contains(LocalFlags::ICANON)
+    };
+
+    // Put the terminal into canonical mode like a console would use it.
+    let mut termios = tcgetattr(slave.as_raw_fd()).unwrap();
+    termios.local_flags.insert(LocalFlags::ICANON);
+    tcsetattr(slave.as_raw_fd(), SetArg::TCSANOW, &termios).unwrap();
+
+    let builder = serialport::new(slave.name().unwrap(), 0).restore_settings(true);
+    let port = TTYPort::open(&builder).expect("Unable to open");
+    let clone = port.try_clone_native().expect("Unable to clone");
+    assert!(!is_canonical());
+
+    // The settings get restored once the last clone is closed.
+    drop(port);
+    assert!(!is_canonical());
+    drop(clone);
+    assert!(is_canonical());
+
+    // Also when the clones get closed concurrently.
+    let port = TTYPort::open(&builder).expect("Unable to open");
+    let clone = port.try_clone_native().expect("Unable to clone");
+    let dropper = std::thread::spawn(move || drop(clone));
+    drop(port);
+    dropper.join().unwrap();
+    assert!(is_canonical());
+}
+
+#[test]
+fn test_ttyport_shared_reference_io() {
+    use std::sync::Arc;
+    use std::thread;
+
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_millis(1000)).unwrap();
+    slave.set_timeout(Duration::from_millis(1000)).unwrap();
+    let master = Arc::new(master);
+
+    // Read and write through shared references from two threads at the same time.
+    let reader = Arc::clone(&master);
+    let reading = thread::spawn(move || {
+        let mut buf = [0u8; 4];
+        (&*reader).read_exact(&mut buf).unwrap();
+        buf
+    });
+    (&*master).write_all(b"pong").unwrap();
+    let mut buf = [0u8; 4];
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"pong");
+    slave.write_all(b"ping").unwrap();
+    assert_eq!(&reading.join().unwrap(), b"ping");
+
+    let stats = master.stats().unwrap();
+    assert_eq!(stats.bytes_read, 4);
+    assert_eq!(stats.bytes_written, 4);
+}
+
+#[test]
+fn test_ttyport_cancel_token() {
+    use std::thread;
+    use std::time::Instant;
+
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_secs(60)).unwrap();
+    slave.set_timeout(Duration::from_millis(1000)).unwrap();
+    let token = master.cancel_token().unwrap();
+    let clone = master.try_clone_native().unwrap();
+    assert!(!clone.cancel_token().unwrap().is_cancelled());
+
+    // A read blocking on another thread returns as soon as the token gets cancelled.
+    let started = Instant::now();
+    let reading = thread::spawn(move || {
+        let mut buf = [0u8; 4];
+        master.read(&mut buf).map(|_| master)
+    });
+    thread::sleep(Duration::from_millis(100));
+    token.cancel();
+    let error = reading.join().unwrap().unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert_eq!(serialport::Error::from(error).kind(), ErrorKind::Cancelled);
+
+    // Cancelling is sticky and applies to the clones of the port.
+    let mut buf = [0u8; 4];
+    let error = (&clone).read(&mut buf).unwrap_err();
+    assert_eq!(serialport::Error::from(error).kind(), ErrorKind::Cancelled);
+
+    token.reset();
+    slave.write_all(b"ping").unwrap();
+    (&clone).read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+}
+
+#[test]
+fn test_ttyport_read_cancellable() {
+    use serialport::CancelToken;
+    use std::thread;
+    use std::time::Instant;
+
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_secs(60)).unwrap();
+    slave.set_timeout(Duration::from_millis(1000)).unwrap();
+    let token = CancelToken::new().unwrap();
+
+    // The token passed to the read aborts it like the token of the port.
+    let started = Instant::now();
+    let cancel = token.clone();
+    let reading = thread::spawn(move || {
+        let mut buf = [0u8; 4];
+        let result = master.read_cancellable(&mut buf, &cancel);
+        (master, result)
+    });
+    thread::sleep(Duration::from_millis(100));
+    token.cancel();
+    let (_master, result) = reading.join().unwrap();
+    let error = result.unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert_eq!(serialport::Error::from(error).kind(), ErrorKind::Cancelled);
+
+    let error = slave.write_cancellable(b"ping", &token).unwrap_err();
+    assert_eq!(serialport::Error::from(error).kind(), ErrorKind::Cancelled);
+    let error = slave.flush_cancellable(&token).unwrap_err();
+    assert_eq!(serialport::Error::from(error).kind(), ErrorKind::Cancelled);
+
+    // Other operations of the port are not affected.
+    slave.write_all(b"ping").unwrap();
+    slave.flush().unwrap();
+    token.reset();
+    slave.write_cancellable(b"pong", &token).unwrap();
+    slave.flush_cancellable(&token).unwrap();
+}
+
+#[test]
+fn test_ttyport_wait_readable() {
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_millis(1000)).unwrap();
+
+    let error = master.wait_readable(Duration::from_millis(10)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Timeout);
+    master.wait_writable(Duration::from_millis(10)).unwrap();
+
+    // Waiting doesn't consume the data.
+    slave.write_all(b"ping").unwrap();
+    master.wait_readable(Duration::from_millis(1000)).unwrap();
+    master.wait_readable(Duration::from_millis(1000)).unwrap();
+    let mut buf = [0u8; 4];
+    master.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+}
+
+#[test]
+fn test_ttyport_retry_interrupted() {
+    catch_sigusr2();
+
+    let read_interrupted = |retry_interrupted: bool| {
+        let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+        master.set_retry_interrupted(retry_interrupted);
+        read_with_signal(master, &mut slave)
+    };
+
+    assert_eq!(read_interrupted(true).unwrap(), b"ping");
+    let error = read_interrupted(false).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_ttyport_wait_signal_mask() {
+    use serialport::SignalMask;
+
+    catch_sigusr2();
+
+    let mut mask = SignalMask::empty();
+    mask.add(nix::libc::SIGUSR2).unwrap();
+    assert!(mask.contains(nix::libc::SIGUSR2));
+    assert!(!mask.contains(nix::libc::SIGUSR1));
+    let error = mask.add(-1).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+    // The signal stays pending while waiting and doesn't interrupt the read.
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_retry_interrupted(false);
+    master.set_wait_signal_mask(Some(mask));
+    assert_eq!(master.wait_signal_mask(), Some(mask));
+    assert_eq!(read_with_signal(master, &mut slave).unwrap(), b"ping");
+}
+
+/// Catches `SIGUSR2` with a handler installed without `SA_RESTART`, so it interrupts blocking
+/// calls
+fn catch_sigusr2() {
+    use nix::sys::signal::{self, SaFlags, SigAction, SigHandler, SigSet, Signal};
+
+    extern "C" fn ignore(_: nix::libc::c_int) {}
+
+    let action = SigAction::new(
+        SigHandler::Handler(ignore),
+        SaFlags::empty(),
+        SigSet::empty(),
+    );
+    unsafe { signal::sigaction(Signal::SIGUSR2, &action) }.unwrap();
+}
+
+/// Reads from `master` on another thread, which gets `SIGUSR2` before `slave` sends data
+fn read_with_signal(mut master: TTYPort, slave: &mut TTYPort) -> io::Result<Vec<u8>> {
+    use std::sync::mpsc;
+    use std::thread;
+
+    master.set_timeout(Duration::from_millis(2000)).unwrap();
+    let (sender, receiver) = mpsc::channel();
+    let reading = thread::spawn(move || {
+        sender.send(unsafe { nix::libc::pthread_self() }).unwrap();
+        let mut buf = [0u8; 4];
+        let result = master.read(&mut buf).map(|len| buf[..len].to_vec());
+        (master, result)
+    });
+    let thread = receiver.recv().unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(
+        unsafe { nix::libc::pthread_kill(thread, nix::libc::SIGUSR2) },
+        0
+    );
+    thread::sleep(Duration::from_millis(100));
+    let _ = slave.write_all(b"ping");
+    let (_master, result) = reading.join().unwrap();
+    result
+}
+
+#[test]
+fn test_ttyport_read_modes() {
+    use nix::fcntl::{fcntl, OFlag, F_GETFL};
+    use nix::sys::termios::{tcgetattr, SpecialCharacterIndices};
+    use std::thread;
+
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    slave.set_timeout(Duration::from_millis(1000)).unwrap();
+
+    // The file descriptor stays in blocking mode for anyone borrowing it, with reads blocking
+    // until data is available.
+    let flags = OFlag::from_bits_truncate(fcntl(slave.as_raw_fd(), F_GETFL).unwrap());
+    assert!(!flags.contains(OFlag::O_NONBLOCK));
+    let termios = tcgetattr(slave.as_raw_fd()).unwrap();
+    assert_eq!(
+        termios.control_chars[SpecialCharacterIndices::VMIN as usize],
+        1
+    );
+
+    // Data received before reading is returned right away, later data after waiting for it.
+    master.write_all(b"ping").unwrap();
+    let mut buf = [0u8; 4];
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+    let writing = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(100));
+        master.write_all(b"pong").unwrap();
+        master
+    });
+    slave.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"pong");
+    let mut master = writing.join().unwrap();
+
+    // Without data, reads time out in blocking mode and would block in non-blocking mode.
+    slave.set_timeout(Duration::from_millis(100)).unwrap();
+    assert_eq!(
+        slave.read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::TimedOut
+    );
+    slave.set_nonblocking(true).unwrap();
+    assert_eq!(
+        slave.read(&mut buf).unwrap_err().kind(),
+        io::ErrorKind::WouldBlock
+    );
+    slave.set_nonblocking(false).unwrap();
+
+    // Reads keep ending after the inter-byte timeout with one set.
+    slave
+        .set_inter_byte_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+    master.write_all(b"ping").unwrap();
+    let mut buf = [0u8; 16];
+    assert_eq!(slave.read(&mut buf).unwrap(), 4);
+
+    // The file descriptor is handed out with reads blocking until data is available.
+    slave.set_inter_byte_timeout(None).unwrap();
+    let fd = slave.into_raw_fd();
+    let flags = OFlag::from_bits_truncate(fcntl(fd, F_GETFL).unwrap());
+    assert!(!flags.contains(OFlag::O_NONBLOCK));
+    let termios = tcgetattr(fd).unwrap();
+    assert_eq!(
+        termios.control_chars[SpecialCharacterIndices::VMIN as usize],
+        1
+    );
+    nix::unistd::close(fd).unwrap();
+}
+
+#[test]
+fn test_ttyport_vectored_io() {
+    use std::io::{IoSlice, IoSliceMut};
+
+    let (mut master, mut slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_millis(1000)).unwrap();
+
+    // The header and the payload get written with a single call.
+    let bufs = [
+        IoSlice::new(b"\x02"),
+        IoSlice::new(b""),
+        IoSlice::new(b"ping"),
+    ];
+    assert_eq!(slave.write_vectored(&bufs).unwrap(), 5);
+    let stats = slave.stats().unwrap();
+    assert_eq!((stats.writes, stats.bytes_written), (1, 5));
+
+    let mut header = [0u8; 1];
+    let mut payload = [0u8; 4];
+    let mut len = 0;
+    while len < 5 {
+        let mut bufs = [
+            IoSliceMut::new(&mut header[len.min(1)..]),
+            IoSliceMut::new(&mut payload[len.saturating_sub(1)..]),
+        ];
+        len += master.read_vectored(&mut bufs).unwrap();
+    }
+    assert_eq!(&header, b"\x02");
+    assert_eq!(&payload, b"ping");
+}
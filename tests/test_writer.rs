@@ -0,0 +1,72 @@
+//! Tests for the `BackgroundWriter` struct.
+#![cfg(unix)]
+
+extern crate serialport;
+
+use std::io::{ErrorKind, Read};
+use std::time::{Duration, Instant};
+
+use serialport::mock::{Fault, FaultyPort, MockPort};
+use serialport::{BackgroundWriter, SerialPort, TTYPort, WritePriority};
+
+const FRAME_SIZE: usize = 4096;
+const BULK_FRAMES: usize = 64;
+const URGENT_FRAME: &[u8] = b"STOP";
+const STALLED_WRITES: usize = 100_000;
+
+#[test]
+fn test_urgent_frame_preempts_bulk_data() {
+    let (mut master, slave) = TTYPort::pair().expect("Unable to create ptty pair");
+    master.set_timeout(Duration::from_millis(500)).unwrap();
+
+    let writer = BackgroundWriter::new(Box::new(slave));
+    for i in 0..BULK_FRAMES {
+        writer
+            .send(vec![b'a' + (i % 26) as u8; FRAME_SIZE], WritePriority::Low)
+            .unwrap();
+    }
+    writer
+        .send(URGENT_FRAME.to_vec(), WritePriority::High)
+        .unwrap();
+
+    let expected_len = BULK_FRAMES * FRAME_SIZE + URGENT_FRAME.len();
+    let mut received = Vec::with_capacity(expected_len);
+    let mut buffer = [0u8; 1024];
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while received.len() < expected_len && Instant::now() < deadline {
+        match master.read(&mut buffer) {
+            Ok(n) => received.extend_from_slice(&buffer[..n]),
+            Err(ref e) if e.kind() == ErrorKind::TimedOut => (),
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+    }
+    assert_eq!(received.len(), expected_len);
+
+    // The urgent frame has been written at a frame boundary and well before the end of the bulk
+    // data. Only the frames written until the pty buffer was full precede it.
+    let offset = received
+        .windows(URGENT_FRAME.len())
+        .position(|window| window == URGENT_FRAME)
+        .expect("urgent frame not received");
+    assert_eq!(offset % FRAME_SIZE, 0);
+    assert!(offset < (BULK_FRAMES - 1) * FRAME_SIZE);
+    assert_eq!(writer.pending(), 0);
+}
+
+#[test]
+fn test_drop_discards_frames_when_stalled() {
+    let (port, peer) = MockPort::pair();
+    let mut port = FaultyPort::new(port);
+    for i in 0..STALLED_WRITES {
+        port.inject_write(i, Fault::TimedOut);
+    }
+
+    // The receiving side never accepts the frame, so it gets discarded instead of being retried
+    // until the stall ends.
+    let writer = BackgroundWriter::new(Box::new(port));
+    writer
+        .send(URGENT_FRAME.to_vec(), WritePriority::High)
+        .unwrap();
+    drop(writer);
+    assert_eq!(peer.bytes_to_read().unwrap(), 0);
+}